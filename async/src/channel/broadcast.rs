@@ -0,0 +1,280 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::error::Error;
+use std::future::Future;
+use std::collections::VecDeque;
+use std::task::{Context, Poll};
+use std::io::{Error as IOError, Result as IOResult, ErrorKind};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use parking_lot::Mutex;
+
+use crate::rt::AsyncRuntime;
+use super::{Waiting, WaitQueue};
+
+/*
+* 构建指定容量的广播通道，并返回发送者和第一个接收者，发送的值会复制给发送时所有已订阅的接收者
+* 通道中最多保留容量数量的值，容量已满时发送会覆盖最旧的值，未接收到被覆盖的值的接收者会收到滞后错误
+* 等待的接收者通过指定运行时的任务唯一id挂起和唤醒
+*/
+pub fn channel<O: Default + 'static, T: Clone + Send + 'static>(rt: AsyncRuntime<O>, mut capacity: usize) -> (Sender<T>, Receiver<T>) {
+    if capacity == 0 {
+        //容量太小，则设置至少为1
+        capacity = 1;
+    }
+
+    let inner = Arc::new(InnerBroadcast {
+        buffer: Mutex::new(Buffer {
+            values: VecDeque::with_capacity(capacity),
+            head: 0,
+            receivers: 0,
+        }),
+        capacity,
+        senders: AtomicUsize::new(1),
+        waits: WaitQueue::new(rt),
+    });
+
+    let sender = Sender {
+        inner,
+    };
+    let receiver = sender.subscribe();
+
+    (sender, receiver)
+}
+
+/*
+* 广播通道的接收错误
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    Lagged(u64),    //接收过慢，未接收的值已被覆盖，值为跳过的值的数量，之后从通道中最旧的值继续接收
+    Closed,         //所有发送者已释放，且没有待接收的值
+}
+
+impl Display for RecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            RecvError::Lagged(skipped) => write!(f, "broadcast recv failed, reason: lagged {} values", skipped),
+            RecvError::Closed => write!(f, "broadcast recv failed, reason: all senders closed"),
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+impl From<RecvError> for IOError {
+    fn from(e: RecvError) -> Self {
+        match e {
+            RecvError::Lagged(_) => IOError::new(ErrorKind::Other, e),
+            RecvError::Closed => IOError::new(ErrorKind::BrokenPipe, e),
+        }
+    }
+}
+
+/*
+* 广播通道的值缓冲区
+*/
+struct Buffer<T: Clone + Send + 'static> {
+    values:     VecDeque<T>,    //保留的值
+    head:       u64,            //最旧的值的序号
+    receivers:  usize,          //接收者数量
+}
+
+/*
+* 内部广播通道
+*/
+struct InnerBroadcast<T: Clone + Send + 'static> {
+    buffer:     Mutex<Buffer<T>>,   //值缓冲区
+    capacity:   usize,              //容量
+    senders:    AtomicUsize,        //发送者数量
+    waits:      WaitQueue,          //接收者等待队列
+}
+
+unsafe impl<T: Clone + Send + 'static> Send for InnerBroadcast<T> {}
+unsafe impl<T: Clone + Send + 'static> Sync for InnerBroadcast<T> {}
+
+/*
+* 广播通道的发送者
+*/
+pub struct Sender<T: Clone + Send + 'static> {
+    inner:  Arc<InnerBroadcast<T>>,  //内部广播通道
+}
+
+unsafe impl<T: Clone + Send + 'static> Send for Sender<T> {}
+unsafe impl<T: Clone + Send + 'static> Sync for Sender<T> {}
+
+impl<T: Clone + Send + 'static> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner.senders.fetch_add(1, Ordering::SeqCst);
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.inner.senders.fetch_sub(1, Ordering::SeqCst) == 1 {
+            //最后一个发送者已释放，则唤醒所有接收者
+            self.inner.waits.wake_all();
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> Sender<T> {
+    //发送指定的值给所有已订阅的接收者，并唤醒它们，返回接收者数量，没有接收者则返回错误，容量已满则覆盖最旧的值
+    pub fn send(&self, value: T) -> IOResult<usize> {
+        let receivers = {
+            let mut buffer = self.inner.buffer.lock();
+            if buffer.receivers == 0 {
+                return Err(IOError::new(ErrorKind::BrokenPipe, "broadcast send failed, reason: no receiver"));
+            }
+
+            if buffer.values.len() >= self.inner.capacity {
+                buffer.values.pop_front();
+                buffer.head += 1;
+            }
+            buffer.values.push_back(value);
+            buffer.receivers
+        };
+
+        self.inner.waits.wake_all();
+        Ok(receivers)
+    }
+
+    //订阅广播通道，返回的接收者只会收到订阅后发送的值
+    pub fn subscribe(&self) -> Receiver<T> {
+        let next = {
+            let mut buffer = self.inner.buffer.lock();
+            buffer.receivers += 1;
+            buffer.head + buffer.values.len() as u64
+        };
+
+        Receiver {
+            inner: self.inner.clone(),
+            next,
+            waiting: None,
+        }
+    }
+
+    //获取当前接收者数量
+    pub fn receiver_count(&self) -> usize {
+        self.inner.buffer.lock().receivers
+    }
+
+    //获取通道容量
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+/*
+* 广播通道的接收者
+*/
+pub struct Receiver<T: Clone + Send + 'static> {
+    inner:      Arc<InnerBroadcast<T>>,  //内部广播通道
+    next:       u64,                        //下一个需要接收的值的序号
+    waiting:    Option<Waiting>,            //等待状态
+}
+
+unsafe impl<T: Clone + Send + 'static> Send for Receiver<T> {}
+unsafe impl<T: Clone + Send + 'static> Sync for Receiver<T> {}
+
+impl<T: Clone + Send + 'static> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.waits.cancel(&mut self.waiting);
+
+        //取消订阅
+        self.inner.buffer.lock().receivers -= 1;
+    }
+}
+
+/*
+* 广播通道接收者同步方法
+*/
+impl<T: Clone + Send + 'static> Receiver<T> {
+    //非阻塞接收值，没有值则返回空，接收过慢则返回滞后错误，所有发送者已释放且没有值则返回关闭错误
+    pub fn try_recv(&mut self) -> Result<Option<T>, RecvError> {
+        match self.take()? {
+            None if self.inner.senders.load(Ordering::SeqCst) == 0 => Err(RecvError::Closed),
+            r => Ok(r),
+        }
+    }
+
+    //获取待接收的值的数量，包括已被覆盖的值
+    pub fn len(&self) -> usize {
+        let buffer = self.inner.buffer.lock();
+        (buffer.head + buffer.values.len() as u64 - self.next) as usize
+    }
+
+    //接收下一个值，已被覆盖则跳到最旧的值，并返回滞后错误
+    fn take(&mut self) -> Result<Option<T>, RecvError> {
+        let buffer = self.inner.buffer.lock();
+        if self.next < buffer.head {
+            let skipped = buffer.head - self.next;
+            self.next = buffer.head;
+            return Err(RecvError::Lagged(skipped));
+        }
+
+        match buffer.values.get((self.next - buffer.head) as usize) {
+            None => Ok(None),
+            Some(value) => {
+                self.next += 1;
+                Ok(Some(value.clone()))
+            },
+        }
+    }
+}
+
+/*
+* 广播通道接收者异步方法
+*/
+impl<T: Clone + Send + 'static> Receiver<T> {
+    //接收值，没有值则挂起当前任务，直到有值或所有发送者已释放，接收过慢则返回滞后错误，之后可以继续接收
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        FutureRecv {
+            receiver: self,
+        }.await
+    }
+}
+
+/*
+* 接收异步任务
+*/
+struct FutureRecv<'a, T: Clone + Send + 'static> {
+    receiver:   &'a mut Receiver<T>, //接收者
+}
+
+impl<'a, T: Clone + Send + 'static> Future for FutureRecv<'a, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let receiver = &mut *(&mut self).receiver;
+        let inner = receiver.inner.clone();
+        let mut waiting = receiver.waiting.take();
+        let mut result = Ok(None);
+        let mut is_closed = false;
+        let is_pending = inner.waits.wait(cx, &mut waiting, || {
+            //在等待队列的锁内检查所有发送者是否已释放，或尝试接收值
+            is_closed = inner.senders.load(Ordering::SeqCst) == 0;
+            result = receiver.take();
+            if let Ok(None) = result {
+                return !is_closed;
+            }
+            false
+        });
+        receiver.waiting = waiting;
+        if is_pending {
+            //没有值，则等待发送者发送值
+            return Poll::Pending;
+        }
+
+        inner.waits.finish(&mut receiver.waiting);
+        match result {
+            Ok(Some(value)) => Poll::Ready(Ok(value)),
+            Ok(None) => Poll::Ready(Err(RecvError::Closed)),
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use std::task::{Waker, Context};
+
+use crate::lock::{NOTIFIED_ONE, Waiter, WaitQueue as LockWaitQueue, spin_lock::SpinLock};
+use crate::rt::{TaskId, AsyncRuntime, AsyncWakeup, AsyncTaskWaker};
+
+pub mod oneshot;
+pub mod mpsc;
+pub mod broadcast;
+
+/*
+* 通道的等待状态，包括等待队列中的等待者，和挂起当前任务的任务唯一id
+*/
+pub(crate) struct Waiting {
+    waiter:     Arc<Waiter>,    //等待者
+    task_id:    TaskId,         //挂起当前任务的任务唯一id
+}
+
+/*
+* 通道等待队列，与异步锁共享等待者和等待队列，挂起的任务通过运行时的任务唯一id唤醒
+* 等待者的唤醒器会调用运行时的wakeup，唤醒挂起在任务唯一id上的任务
+*/
+pub(crate) struct WaitQueue {
+    rt:     Arc<dyn AsyncWakeup>,       //挂起和唤醒任务的运行时
+    queue:  SpinLock<LockWaitQueue>,    //等待队列
+}
+
+unsafe impl Send for WaitQueue {}
+unsafe impl Sync for WaitQueue {}
+
+impl WaitQueue {
+    //构建指定运行时的等待队列
+    pub fn new<O: Default + 'static>(rt: AsyncRuntime<O>) -> Self {
+        WaitQueue {
+            rt: Arc::new(rt),
+            queue: SpinLock::new(LockWaitQueue::new()),
+        }
+    }
+
+    //在等待队列的锁内检查是否需要等待，需要则挂起当前任务并加入等待队列，返回是否已挂起
+    //因为唤醒也需要获取等待队列的锁，所以在锁内检查可以保证不会丢失唤醒
+    //当前任务已在等待队列中，则只更新挂起在任务唯一id上的唤醒器，不会重复加入等待队列
+    pub fn wait<F>(&self, cx: &Context<'_>, waiting: &mut Option<Waiting>, need_wait: F) -> bool
+        where F: FnOnce() -> bool {
+        let mut queue = self.queue.lock();
+        if !need_wait() {
+            return false;
+        }
+
+        if let Some(w) = waiting.as_ref() {
+            if !w.waiter.is_notified() {
+                //仍在等待队列中，因为唤醒需要获取等待队列的锁，所以更新时不会被唤醒
+                w.task_id.free_waker();
+                self.rt.pending(&w.task_id, cx.waker().clone());
+                return true;
+            }
+        }
+
+        let task_id = self.rt.alloc();
+        self.rt.pending(&task_id, cx.waker().clone());
+        let waiter = Waiter::new(0, AsyncTaskWaker::new(self.rt.clone(), task_id.clone()));
+        queue.push_back(waiter.clone());
+        *waiting = Some(Waiting {
+            waiter,
+            task_id,
+        });
+
+        true
+    }
+
+    //结束当前任务的等待，仍在等待队列中则移除
+    pub fn finish(&self, waiting: &mut Option<Waiting>) {
+        if let Some(w) = waiting.take() {
+            if !w.waiter.is_notified() {
+                self.queue.lock().remove(&w.waiter);
+            }
+        }
+    }
+
+    //取消当前任务的等待，仍在等待队列中则移除，已被单独唤醒但未处理则将唤醒传递给下一个等待的任务
+    pub fn cancel(&self, waiting: &mut Option<Waiting>) {
+        if let Some(w) = waiting.take() {
+            if !w.waiter.is_notified() {
+                if self.queue.lock().remove(&w.waiter) {
+                    return;
+                }
+            }
+
+            if w.waiter.status() == NOTIFIED_ONE {
+                self.wake_one();
+            }
+        }
+    }
+
+    //唤醒一个等待的任务
+    pub fn wake_one(&self) {
+        let waker = self.queue.lock().notify_one();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    //唤醒所有等待的任务
+    pub fn wake_all(&self) {
+        let wakers: Vec<Waker> = self.queue.lock().notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
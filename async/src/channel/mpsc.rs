@@ -0,0 +1,310 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::future::Future;
+use std::task::{Context, Poll};
+use std::io::{Error, Result, ErrorKind};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::lock::mpsc_deque::{Sender as DequeSender, Receiver as DequeReceiver, mpsc_deque};
+use crate::rt::AsyncRuntime;
+use super::{Waiting, WaitQueue};
+
+/*
+* 构建指定容量的有界多生产者单消费者通道，并返回发送者和接收者，等待的发送者和接收者通过指定运行时的任务唯一id挂起和唤醒
+*/
+pub fn channel<O: Default + 'static, T: Send + 'static>(rt: AsyncRuntime<O>, mut capacity: usize) -> (Sender<T>, Receiver<T>) {
+    if capacity == 0 {
+        //容量太小，则设置至少为1
+        capacity = 1;
+    }
+
+    let (producor, consumer) = mpsc_deque();
+    let inner = Arc::new(InnerChannel {
+        producor,
+        len: AtomicUsize::new(0),
+        capacity,
+        senders: AtomicUsize::new(1),
+        is_closed: AtomicBool::new(false),
+        recv_waits: WaitQueue::new(rt.clone()),
+        send_waits: WaitQueue::new(rt),
+    });
+
+    (Sender {
+        inner: inner.clone(),
+    },
+     Receiver {
+        inner,
+        consumer,
+        waiting: None,
+    })
+}
+
+/*
+* 内部有界通道
+*/
+struct InnerChannel<T: Send + 'static> {
+    producor:   DequeSender<T>,     //值队列生产者
+    len:        AtomicUsize,        //已占用的容量
+    capacity:   usize,              //容量
+    senders:    AtomicUsize,        //发送者数量
+    is_closed:  AtomicBool,         //接收者是否已释放
+    recv_waits: WaitQueue,       //接收者等待队列
+    send_waits: WaitQueue,       //发送者等待队列
+}
+
+unsafe impl<T: Send + 'static> Send for InnerChannel<T> {}
+unsafe impl<T: Send + 'static> Sync for InnerChannel<T> {}
+
+impl<T: Send + 'static> InnerChannel<T> {
+    //尝试占用一个容量，返回是否成功
+    fn try_reserve(&self) -> bool {
+        let mut len = self.len.load(Ordering::Relaxed);
+        loop {
+            if len >= self.capacity {
+                //容量已满
+                return false;
+            }
+
+            match self.len.compare_exchange_weak(len,
+                                                 len + 1,
+                                                 Ordering::Acquire,
+                                                 Ordering::Relaxed) {
+                Err(current) => {
+                    //已占用的容量不匹配，则更新后继续尝试
+                    len = current;
+                    continue;
+                },
+                Ok(_) => {
+                    return true;
+                },
+            }
+        }
+    }
+
+    //在已占用的容量上发送值，并唤醒接收者
+    fn send_reserved(&self, value: T) {
+        self.producor.send(value);
+        self.recv_waits.wake_one();
+    }
+}
+
+/*
+* 有界通道的发送者
+*/
+pub struct Sender<T: Send + 'static> {
+    inner:  Arc<InnerChannel<T>>,    //内部有界通道
+}
+
+unsafe impl<T: Send + 'static> Send for Sender<T> {}
+unsafe impl<T: Send + 'static> Sync for Sender<T> {}
+
+impl<T: Send + 'static> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner.senders.fetch_add(1, Ordering::SeqCst);
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.inner.senders.fetch_sub(1, Ordering::SeqCst) == 1 {
+            //最后一个发送者已释放，则唤醒接收者
+            self.inner.recv_waits.wake_all();
+        }
+    }
+}
+
+/*
+* 有界通道发送者同步方法
+*/
+impl<T: Send + 'static> Sender<T> {
+    //尝试发送指定的值，容量已满则返回WouldBlock错误，接收者已释放则返回BrokenPipe错误
+    pub fn try_send(&self, value: T) -> Result<()> {
+        if self.inner.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::new(ErrorKind::BrokenPipe, "mpsc send failed, reason: receiver closed"));
+        }
+
+        if !self.inner.try_reserve() {
+            return Err(Error::new(ErrorKind::WouldBlock, "mpsc send failed, reason: channel full"));
+        }
+
+        self.inner.send_reserved(value);
+        Ok(())
+    }
+
+    //检查接收者是否已释放
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed.load(Ordering::SeqCst)
+    }
+
+    //获取通道中待接收的值的数量
+    pub fn len(&self) -> usize {
+        self.inner.len.load(Ordering::Relaxed)
+    }
+
+    //获取通道容量
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+/*
+* 有界通道发送者异步方法
+*/
+impl<T: Send + 'static> Sender<T> {
+    //发送指定的值，容量已满则挂起当前任务，直到接收者接收值后有空闲容量，接收者已释放则返回错误
+    pub async fn send(&self, value: T) -> Result<()> {
+        FutureSend {
+            inner: self.inner.clone(),
+            value: Some(value),
+            waiting: None,
+        }.await
+    }
+}
+
+/*
+* 有界通道的接收者
+*/
+pub struct Receiver<T: Send + 'static> {
+    inner:      Arc<InnerChannel<T>>,    //内部有界通道
+    consumer:   DequeReceiver<T>,           //值队列消费者
+    waiting:    Option<Waiting>,            //等待状态
+}
+
+unsafe impl<T: Send + 'static> Send for Receiver<T> {}
+unsafe impl<T: Send + 'static> Sync for Receiver<T> {}
+
+impl<T: Send + 'static> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.recv_waits.cancel(&mut self.waiting);
+
+        //设置接收者已释放，并唤醒所有等待的发送者
+        self.inner.is_closed.store(true, Ordering::SeqCst);
+        self.inner.send_waits.wake_all();
+    }
+}
+
+/*
+* 有界通道接收者同步方法
+*/
+impl<T: Send + 'static> Receiver<T> {
+    //非阻塞接收值
+    pub fn try_recv(&mut self) -> Option<T> {
+        let value = self.consumer.try_recv();
+        if value.is_some() {
+            //接收成功，则释放占用的容量，并唤醒一个等待的发送者
+            self.inner.len.fetch_sub(1, Ordering::SeqCst);
+            self.inner.send_waits.wake_one();
+        }
+
+        value
+    }
+
+    //获取通道中待接收的值的数量
+    pub fn len(&self) -> usize {
+        self.inner.len.load(Ordering::Relaxed)
+    }
+}
+
+/*
+* 有界通道接收者异步方法
+*/
+impl<T: Send + 'static> Receiver<T> {
+    //接收值，没有值则挂起当前任务，直到有值或所有发送者已释放
+    pub async fn recv(&mut self) -> Result<T> {
+        FutureRecv {
+            receiver: self,
+        }.await
+    }
+}
+
+/*
+* 发送异步任务
+*/
+struct FutureSend<T: Send + 'static> {
+    inner:      Arc<InnerChannel<T>>,    //内部有界通道
+    value:      Option<T>,                  //需要发送的值
+    waiting:    Option<Waiting>,            //等待状态
+}
+
+impl<T: Send + 'static> Unpin for FutureSend<T> {}
+
+impl<T: Send + 'static> Drop for FutureSend<T> {
+    fn drop(&mut self) {
+        self.inner.send_waits.cancel(&mut self.waiting);
+    }
+}
+
+impl<T: Send + 'static> Future for FutureSend<T> {
+    type Output = Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = self.inner.clone();
+        let mut is_closed = false;
+        let mut is_reserved = false;
+        if inner.send_waits.wait(cx, &mut (&mut self).waiting, || {
+            //在等待队列的锁内检查接收者是否已释放，或尝试占用容量
+            is_closed = inner.is_closed.load(Ordering::SeqCst);
+            if !is_closed {
+                is_reserved = inner.try_reserve();
+            }
+            !is_closed && !is_reserved
+        }) {
+            //容量已满，则等待接收者释放容量
+            return Poll::Pending;
+        }
+
+        inner.send_waits.finish(&mut (&mut self).waiting);
+        if is_closed {
+            return Poll::Ready(Err(Error::new(ErrorKind::BrokenPipe, "mpsc send failed, reason: receiver closed")));
+        }
+
+        if let Some(value) = (&mut self).value.take() {
+            inner.send_reserved(value);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/*
+* 接收异步任务
+*/
+struct FutureRecv<'a, T: Send + 'static> {
+    receiver:   &'a mut Receiver<T>, //接收者
+}
+
+impl<'a, T: Send + 'static> Future for FutureRecv<'a, T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = self.receiver.inner.clone();
+        let receiver = &mut *(&mut self).receiver;
+        let consumer = &mut receiver.consumer;
+        let mut value = None;
+        let mut is_closed = false;
+        if inner.recv_waits.wait(cx, &mut receiver.waiting, || {
+            //在等待队列的锁内检查所有发送者是否已释放，或尝试接收值
+            is_closed = inner.senders.load(Ordering::SeqCst) == 0;
+            value = consumer.try_recv();
+            value.is_none() && !is_closed
+        }) {
+            //没有值，则等待发送者发送值
+            return Poll::Pending;
+        }
+
+        inner.recv_waits.finish(&mut receiver.waiting);
+        match value {
+            Some(value) => {
+                //接收成功，则释放占用的容量，并唤醒一个等待的发送者
+                inner.len.fetch_sub(1, Ordering::SeqCst);
+                inner.send_waits.wake_one();
+                Poll::Ready(Ok(value))
+            },
+            None => {
+                Poll::Ready(Err(Error::new(ErrorKind::BrokenPipe, "mpsc recv failed, reason: all senders closed")))
+            },
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::future::Future;
+use std::task::{Context, Poll};
+use std::io::{Error, Result, ErrorKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use parking_lot::Mutex;
+
+use crate::rt::AsyncRuntime;
+use super::{Waiting, WaitQueue};
+
+/*
+* 构建单次通道，并返回发送者和接收者，接收者通过指定运行时的任务唯一id挂起和唤醒
+*/
+pub fn channel<O: Default + 'static, T: Send + 'static>(rt: AsyncRuntime<O>) -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(InnerOneshot {
+        value: Mutex::new(None),
+        is_closed: AtomicBool::new(false),
+        waits: WaitQueue::new(rt),
+    });
+
+    (Sender {
+        inner: inner.clone(),
+    },
+     Receiver {
+        inner,
+        waiting: None,
+    })
+}
+
+/*
+* 内部单次通道
+*/
+struct InnerOneshot<T: Send + 'static> {
+    value:      Mutex<Option<T>>,   //发送的值
+    is_closed:  AtomicBool,         //发送者或接收者是否已释放
+    waits:      WaitQueue,       //接收者等待队列
+}
+
+unsafe impl<T: Send + 'static> Send for InnerOneshot<T> {}
+unsafe impl<T: Send + 'static> Sync for InnerOneshot<T> {}
+
+/*
+* 单次通道的发送者
+*/
+pub struct Sender<T: Send + 'static> {
+    inner:  Arc<InnerOneshot<T>>,    //内部单次通道
+}
+
+unsafe impl<T: Send + 'static> Send for Sender<T> {}
+unsafe impl<T: Send + 'static> Sync for Sender<T> {}
+
+impl<T: Send + 'static> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if !self.inner.is_closed.swap(true, Ordering::SeqCst) {
+            //接收者未释放，则唤醒接收者，接收者会获取已发送的值，未发送值则获取错误原因
+            self.inner.waits.wake_all();
+        }
+    }
+}
+
+impl<T: Send + 'static> Sender<T> {
+    //发送指定的值，并唤醒接收者，接收者已释放则返回错误
+    pub fn send(self, value: T) -> Result<()> {
+        if self.inner.is_closed.load(Ordering::SeqCst) {
+            //接收者已释放，则立即返回错误原因
+            return Err(Error::new(ErrorKind::BrokenPipe, "oneshot send failed, reason: receiver closed"));
+        }
+
+        *self.inner.value.lock() = Some(value);
+        Ok(())
+    }
+
+    //检查接收者是否已释放
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed.load(Ordering::SeqCst)
+    }
+}
+
+/*
+* 单次通道的接收者，等待接收者即等待发送者发送的值
+*/
+pub struct Receiver<T: Send + 'static> {
+    inner:      Arc<InnerOneshot<T>>,    //内部单次通道
+    waiting:    Option<Waiting>,            //等待状态
+}
+
+unsafe impl<T: Send + 'static> Send for Receiver<T> {}
+unsafe impl<T: Send + 'static> Sync for Receiver<T> {}
+
+impl<T: Send + 'static> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.waits.cancel(&mut self.waiting);
+        self.inner.is_closed.store(true, Ordering::SeqCst);
+    }
+}
+
+impl<T: Send + 'static> Future for Receiver<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = self.inner.clone();
+        let mut value = None;
+        let mut is_closed = false;
+        if inner.waits.wait(cx, &mut (&mut self).waiting, || {
+            //在等待队列的锁内检查发送者是否已释放或是否已发送值，因为发送者在释放前发送值，所以需要先检查是否已释放
+            is_closed = inner.is_closed.load(Ordering::SeqCst);
+            value = inner.value.lock().take();
+            value.is_none() && !is_closed
+        }) {
+            return Poll::Pending;
+        }
+
+        inner.waits.finish(&mut (&mut self).waiting);
+        match value {
+            Some(value) => Poll::Ready(Ok(value)),
+            None => Poll::Ready(Err(Error::new(ErrorKind::BrokenPipe, "oneshot recv failed, reason: sender closed"))),
+        }
+    }
+}
+
+impl<T: Send + 'static> Receiver<T> {
+    //非阻塞接收值
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.inner.value.lock().take()
+    }
+}
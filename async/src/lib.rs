@@ -3,8 +3,13 @@ extern crate crossbeam_channel;
 extern crate parking_lot;
 extern crate log;
 extern crate local_timer;
+#[cfg(feature = "apm_collect")]
+extern crate atom;
+#[cfg(feature = "apm_collect")]
+extern crate apm;
 
 pub mod lock;
+pub mod channel;
 pub mod rt;
 pub mod task;
 pub mod local_queue;
@@ -0,0 +1,136 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::future::Future;
+use std::task::{Context, Poll};
+
+use super::{Waiter, WaitQueue, spin_lock::SpinLock};
+
+/*
+* 异步屏障等待结果
+*/
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    //检查当前任务是否是本轮最后到达屏障的任务，每轮只有一个任务是领导者
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+/*
+* 异步屏障，等待指定数量的任务都到达屏障后，同时唤醒这些任务，可以重复使用
+*/
+pub struct Barrier {
+    inner:  Arc<SpinLock<BarrierState>>,    //异步屏障状态
+    size:   usize,                          //每轮需要到达屏障的任务数量
+}
+
+unsafe impl Send for Barrier {}
+unsafe impl Sync for Barrier {}
+
+/*
+* 异步屏障同步方法
+*/
+impl Barrier {
+    //构建指定任务数量的异步屏障
+    pub fn new(mut size: usize) -> Self {
+        if size == 0 {
+            //任务数量太小，则设置至少为1
+            size = 1;
+        }
+
+        Barrier {
+            inner: Arc::new(SpinLock::new(BarrierState {
+                count: 0,
+                generation: 0,
+                waits: WaitQueue::new(),
+            })),
+            size,
+        }
+    }
+}
+
+/*
+* 异步屏障异步方法
+*/
+impl Barrier {
+    //到达屏障，并等待本轮所有任务都到达屏障
+    pub async fn wait(&self) -> BarrierWaitResult {
+        FutureBarrier {
+            inner: self.inner.clone(),
+            size: self.size,
+            waiter: None,
+        }.await
+    }
+}
+
+/*
+* 异步屏障状态
+*/
+struct BarrierState {
+    count:      usize,      //本轮已到达屏障的任务数量
+    generation: usize,      //当前轮次
+    waits:      WaitQueue,  //本轮等待的任务队列
+}
+
+/*
+* 等待屏障异步任务
+*/
+struct FutureBarrier {
+    inner:  Arc<SpinLock<BarrierState>>,    //异步屏障状态
+    size:   usize,                          //每轮需要到达屏障的任务数量
+    waiter: Option<Arc<Waiter>>,            //等待者
+}
+
+impl Drop for FutureBarrier {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            //已在等待屏障时被释放
+            let mut state = self.inner.lock();
+            if state.generation == waiter.value() && state.waits.remove(&waiter) {
+                //本轮还未结束，则从等待队列中移除，并减少已到达屏障的任务数量
+                state.count -= 1;
+            }
+        }
+    }
+}
+
+impl Future for FutureBarrier {
+    type Output = BarrierWaitResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(waiter) = (&self).waiter.as_ref() {
+            //已在等待屏障，则检查本轮是否已结束
+            if !waiter.update_waker(cx.waker()) {
+                return Poll::Pending;
+            }
+
+            (&mut self).waiter = None;
+            return Poll::Ready(BarrierWaitResult(false));
+        }
+
+        let size = (&self).size;
+        let mut state = (&self).inner.lock();
+        state.count += 1;
+        if state.count >= size {
+            //本轮所有任务都已到达屏障，则开始下一轮，并唤醒本轮所有等待的任务
+            state.count = 0;
+            state.generation = state.generation.wrapping_add(1);
+            let wakers = state.waits.notify_all();
+            drop(state);
+
+            for waker in wakers {
+                waker.wake();
+            }
+            return Poll::Ready(BarrierWaitResult(true));
+        }
+
+        //本轮还有任务未到达屏障，则加入等待队列尾
+        let waiter = Waiter::new(state.generation, cx.waker().clone());
+        state.waits.push_back(waiter.clone());
+        drop(state);
+        (&mut self).waiter = Some(waiter);
+
+        Poll::Pending
+    }
+}
@@ -0,0 +1,113 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::future::Future;
+use std::task::{Context, Poll};
+
+use super::{NOTIFIED_ONE, Waiter, WaitQueue, spin_lock::SpinLock, mutex_lock::MutexGuard};
+
+/*
+* 异步条件变量，与异步互斥锁配合使用，等待时会释放异步互斥锁，被唤醒后会重新获取异步互斥锁
+*/
+pub struct Condvar {
+    inner:  Arc<SpinLock<WaitQueue>>,   //等待条件的任务队列
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+/*
+* 异步条件变量同步方法
+*/
+impl Condvar {
+    //构建异步条件变量
+    pub fn new() -> Self {
+        Condvar {
+            inner: Arc::new(SpinLock::new(WaitQueue::new())),
+        }
+    }
+
+    //唤醒一个等待条件的任务，没有等待的任务则忽略
+    pub fn notify_one(&self) {
+        notify_one(&self.inner);
+    }
+
+    //唤醒所有等待条件的任务
+    pub fn notify_all(&self) {
+        let wakers = self.inner.lock().notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/*
+* 异步条件变量异步方法
+*/
+impl Condvar {
+    //释放指定的异步互斥锁守护者，并等待条件，被唤醒后重新获取异步互斥锁，并返回新的守护者
+    pub async fn wait<T>(&self, guard: MutexGuard<T>) -> MutexGuard<T> {
+        let mutex = guard.to_mutex();
+        FutureCondvar {
+            inner: self.inner.clone(),
+            guard: Some(guard),
+            waiter: None,
+        }.await;
+
+        mutex.lock().await
+    }
+}
+
+//唤醒一个等待条件的任务
+fn notify_one(inner: &SpinLock<WaitQueue>) {
+    let waker = inner.lock().notify_one();
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/*
+* 等待条件异步任务
+*/
+struct FutureCondvar<T> {
+    inner:  Arc<SpinLock<WaitQueue>>,   //等待条件的任务队列
+    guard:  Option<MutexGuard<T>>,      //等待前持有的异步互斥锁守护者
+    waiter: Option<Arc<Waiter>>,        //等待者
+}
+
+impl<T> Drop for FutureCondvar<T> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            //已在等待条件时被释放
+            if waiter.status() == NOTIFIED_ONE {
+                //已单独被唤醒但未处理，则将唤醒转交给其它等待的任务
+                notify_one(&self.inner);
+            } else {
+                self.inner.lock().remove(&waiter);
+            }
+        }
+    }
+}
+
+impl<T> Future for FutureCondvar<T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(waiter) = (&self).waiter.as_ref() {
+            //已在等待条件，则检查是否已被唤醒
+            if !waiter.update_waker(cx.waker()) {
+                return Poll::Pending;
+            }
+
+            (&mut self).waiter = None;
+            return Poll::Ready(());
+        }
+
+        //先加入等待队列尾，再释放异步互斥锁，保证释放异步互斥锁后的唤醒不会丢失
+        let waiter = Waiter::new(0, cx.waker().clone());
+        (&self).inner.lock().push_back(waiter.clone());
+        (&mut self).waiter = Some(waiter);
+        (&mut self).guard = None;
+
+        Poll::Pending
+    }
+}
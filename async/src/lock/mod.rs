@@ -1,4 +1,8 @@
-use std::sync::atomic::spin_loop_hint;
+use std::sync::Arc;
+use std::task::Waker;
+use std::time::Duration;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU8, Ordering, spin_loop_hint};
 
 pub mod mpmc_deque;
 pub mod mpsc_deque;
@@ -6,6 +10,12 @@ pub mod steal_deque;
 pub mod spin_lock;
 pub mod mutex_lock;
 pub mod rw_lock;
+pub mod semaphore;
+pub mod notify;
+pub mod barrier;
+pub mod condvar;
+
+use spin_lock::SpinLock;
 
 /*
 * 根据指定值进行自旋，返回下次自旋的值
@@ -23,4 +33,157 @@ pub(crate) fn spin(mut len: u32) -> u32 {
     }
 
     len + 1
+}
+
+/*
+* 将指定时长转换为定时器使用的毫秒数，不足1毫秒的部分向上取整
+*/
+#[inline]
+pub(crate) fn duration_to_millis(duration: Duration) -> usize {
+    let millis = duration.as_millis();
+    if duration.subsec_nanos() % 1_000_000 > 0 {
+        millis as usize + 1
+    } else {
+        millis as usize
+    }
+}
+
+/*
+* 等待者状态
+*/
+pub(crate) const WAITING: u8 = 0;       //等待中
+pub(crate) const NOTIFIED_ONE: u8 = 1;  //被单独唤醒
+pub(crate) const NOTIFIED_ALL: u8 = 2;  //被全部唤醒
+pub(crate) const CANCELED: u8 = 3;      //已取消等待
+
+/*
+* 异步等待者
+*/
+pub(crate) struct Waiter {
+    status: AtomicU8,                   //等待状态
+    value:  usize,                      //等待的值
+    waker:  SpinLock<Option<Waker>>,    //等待任务的唤醒器
+}
+
+impl Waiter {
+    //构建指定等待值和唤醒器的等待者
+    pub fn new(value: usize, waker: Waker) -> Arc<Self> {
+        Arc::new(Waiter {
+            status: AtomicU8::new(WAITING),
+            value,
+            waker: SpinLock::new(Some(waker)),
+        })
+    }
+
+    //获取等待的值
+    #[inline(always)]
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    //获取等待状态
+    #[inline(always)]
+    pub fn status(&self) -> u8 {
+        self.status.load(Ordering::Acquire)
+    }
+
+    //检查是否已被唤醒，已取消等待也视为已被唤醒
+    #[inline(always)]
+    pub fn is_notified(&self) -> bool {
+        self.status() != WAITING
+    }
+
+    //更新等待任务的唤醒器，返回更新后是否已被唤醒，防止在更新唤醒器前已被唤醒
+    pub fn update_waker(&self, waker: &Waker) -> bool {
+        {
+            let mut current = self.waker.lock();
+            match current.as_ref() {
+                Some(w) if w.will_wake(waker) => (),
+                _ => *current = Some(waker.clone()),
+            }
+        }
+
+        self.is_notified()
+    }
+
+    //尝试单独唤醒等待者，并返回等待任务的唤醒器，已被唤醒或已取消等待则返回空，用于无外部锁保护的等待队列
+    pub fn try_notify_one(&self) -> Option<Waker> {
+        if self.status.compare_exchange(WAITING,
+                                        NOTIFIED_ONE,
+                                        Ordering::AcqRel,
+                                        Ordering::Acquire).is_err() {
+            return None;
+        }
+
+        self.waker.lock().take()
+    }
+
+    //取消等待，返回是否在被唤醒前取消，返回失败则表示已被唤醒，调用者需要将唤醒传递给其它等待者
+    pub fn cancel(&self) -> bool {
+        if self.status.compare_exchange(WAITING,
+                                        CANCELED,
+                                        Ordering::AcqRel,
+                                        Ordering::Acquire).is_err() {
+            return false;
+        }
+
+        self.waker.lock().take();
+        true
+    }
+
+    //设置唤醒状态，并返回等待任务的唤醒器
+    fn notify(&self, status: u8) -> Option<Waker> {
+        self.status.store(status, Ordering::Release);
+        self.waker.lock().take()
+    }
+}
+
+/*
+* 异步等待队列，异步锁和通道共享，需要在外部锁的保护下访问，返回的唤醒器应在外部锁释放后唤醒
+*/
+pub(crate) struct WaitQueue(VecDeque<Arc<Waiter>>);
+
+impl WaitQueue {
+    //构建异步等待队列
+    pub fn new() -> Self {
+        WaitQueue(VecDeque::new())
+    }
+
+    //检查等待队列是否为空
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    //加入等待队列尾
+    #[inline(always)]
+    pub fn push_back(&mut self, waiter: Arc<Waiter>) {
+        self.0.push_back(waiter);
+    }
+
+    //获取等待队列头
+    #[inline(always)]
+    pub fn front(&self) -> Option<&Arc<Waiter>> {
+        self.0.front()
+    }
+
+    //单独唤醒等待队列头的等待者，返回它的唤醒器，等待队列为空则返回空
+    pub fn notify_one(&mut self) -> Option<Waker> {
+        self.0.pop_front().and_then(|waiter| waiter.notify(NOTIFIED_ONE))
+    }
+
+    //唤醒所有等待者，返回它们的唤醒器
+    pub fn notify_all(&mut self) -> Vec<Waker> {
+        self.0.drain(..).filter_map(|waiter| waiter.notify(NOTIFIED_ALL)).collect()
+    }
+
+    //从等待队列中移除指定的等待者，返回是否成功
+    pub fn remove(&mut self, waiter: &Arc<Waiter>) -> bool {
+        if let Some(index) = self.0.iter().position(|w| Arc::ptr_eq(w, waiter)) {
+            self.0.remove(index);
+            return true;
+        }
+
+        false
+    }
 }
\ No newline at end of file
@@ -1,12 +1,15 @@
 use std::pin::Pin;
 use std::sync::Arc;
 use std::future::Future;
+use std::io::Result;
+use std::time::Duration;
 use std::cell::UnsafeCell;
 use std::ops::{Deref, DerefMut};
-use std::task::{Waker, Context, Poll};
+use std::task::{Context, Poll};
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use super::{spin, mpsc_deque::{Sender, Receiver, mpsc_deque}};
+use crate::rt::{AsyncRuntime, timeout};
+use super::{spin, duration_to_millis, Waiter, WaitQueue, spin_lock::SpinLock, mpsc_deque::{Sender, Receiver, mpsc_deque}};
 
 /*
 * 异步互斥锁守护者
@@ -33,22 +36,38 @@ impl<T> DerefMut for MutexGuard<T> {
     }
 }
 
+impl<T> MutexGuard<T> {
+    //获取守护者所属的异步互斥锁
+    pub(crate) fn to_mutex(&self) -> Mutex<T> {
+        Mutex {
+            inner: self.guarder.clone(),
+        }
+    }
+}
+
 impl<T> Drop for MutexGuard<T> {
     fn drop(&mut self) {
+        if self.guarder.fair.is_some() {
+            //公平模式，则直接将锁交给等待队列头的任务
+            self.guarder.unlock_fair();
+            return;
+        }
+
         unsafe {
             let consumer = &mut *self.guarder.consumer;
-            //因为互斥锁保证了，同一时间只有一个线程可以获取到锁，所以可以使用不精确的检查接收队列是否为空的检查
-            if consumer.try_is_empty() {
-                self.guarder.unlock();
-            } else {
-                if let Some(waker) = consumer.try_recv() {
+            //因为互斥锁保证了，同一时间只有一个线程可以获取到锁，所以可以不加锁的从接收队列中接收
+            while let Some(waiter) = consumer.try_recv() {
+                if let Some(waker) = waiter.try_notify_one() {
                     //有异步任务等待异步互斥锁释放，则解锁并唤醒此任务
                     self.guarder.unlock();
                     waker.wake();
-                } else {
-                    self.guarder.unlock();
+                    return;
                 }
+
+                //等待者已取消等待，则继续唤醒下一个等待者
             }
+
+            self.guarder.unlock();
         }
     }
 }
@@ -69,11 +88,35 @@ unsafe impl<T> Sync for Mutex<T> {}
 impl<T> Mutex<T> {
     //构建异步互斥锁
     pub fn new(v: T) -> Self {
+        Self::with_fair(v, false)
+    }
+
+    //构建公平的异步互斥锁，等待锁的任务严格按先进先出的顺序获取锁
+    pub fn new_fair(v: T) -> Self {
+        Self::with_fair(v, true)
+    }
+
+    //检查是否是公平的异步互斥锁
+    pub fn is_fair(&self) -> bool {
+        self.inner.fair.is_some()
+    }
+
+    //构建指定是否公平的异步互斥锁
+    fn with_fair(v: T, is_fair: bool) -> Self {
         let (producor, consumer) = mpsc_deque();
+        let fair = if is_fair {
+            Some(SpinLock::new(FairMutexState {
+                is_locked: false,
+                waits: WaitQueue::new(),
+            }))
+        } else {
+            None
+        };
         let inner = Arc::new(InnerMutex {
             producor,
             consumer: Box::into_raw(Box::new(consumer)),
             lock_status: AtomicBool::new(false),
+            fair,
             inner: UnsafeCell::new(v),
         });
 
@@ -89,20 +132,34 @@ impl<T> Mutex<T> {
 impl<T> Mutex<T> {
     //获取异步互斥锁
     pub async fn lock(&self) -> MutexGuard<T> {
-        FutureMutex {
-            inner: self.inner.clone(),
-        }.await
+        FutureMutex::new(self.inner.clone()).await
+    }
+}
+
+impl<T: 'static> Mutex<T> {
+    //在指定时间内获取异步互斥锁，超时则放弃获取，并返回超时错误，需要指定运行时有本地定时器，定时器精度为毫秒，不足1毫秒的部分向上取整
+    pub async fn try_lock_for<O: Default + 'static>(&self, rt: AsyncRuntime<O>, duration: Duration) -> Result<MutexGuard<T>> {
+        timeout(rt, duration_to_millis(duration), FutureMutex::new(self.inner.clone())).await
     }
 }
 
+/*
+* 公平模式的异步互斥锁状态
+*/
+struct FairMutexState {
+    is_locked:  bool,       //是否已锁
+    waits:      WaitQueue,  //锁等待队列
+}
+
 /*
 * 内部异步互斥锁
 */
 struct InnerMutex<T> {
-    producor:       Sender<Waker>,          //锁等待队列生产者
-    consumer:       *mut Receiver<Waker>,   //锁等待队列消费者
-    lock_status:    AtomicBool,             //异步互斥锁状态
-    inner:          UnsafeCell<T>,          //异步互斥锁内容
+    producor:       Sender<Arc<Waiter>>,                //锁等待队列生产者
+    consumer:       *mut Receiver<Arc<Waiter>>,         //锁等待队列消费者
+    lock_status:    AtomicBool,                         //异步互斥锁状态
+    fair:           Option<SpinLock<FairMutexState>>,   //公平模式的异步互斥锁状态，非公平模式则为空
+    inner:          UnsafeCell<T>,                      //异步互斥锁内容
 }
 
 unsafe impl<T> Send for InnerMutex<T> {}
@@ -111,8 +168,8 @@ unsafe impl<T> Sync for InnerMutex<T> {}
 impl<T> InnerMutex<T> {
     //加入等待队列
     #[inline(always)]
-    pub fn push(&self, waker: Waker) {
-        self.producor.send(waker);
+    pub fn push(&self, waiter: Arc<Waiter>) {
+        self.producor.send(waiter);
     }
 
     //尝试获取异步互斥锁，返回是否成功
@@ -130,24 +187,86 @@ impl<T> InnerMutex<T> {
     pub fn unlock(&self) {
         self.lock_status.store(false, Ordering::Relaxed);
     }
+
+    //公平模式下解锁异步互斥锁，有等待的任务则不解锁，并直接将锁交给等待队列头的任务
+    pub fn unlock_fair(&self) {
+        let waker = {
+            let mut state = self.fair.as_ref().unwrap().lock();
+            if state.waits.is_empty() {
+                //没有等待的任务，则解锁
+                state.is_locked = false;
+                return;
+            }
+
+            state.waits.notify_one()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    //公平模式下取消等待锁，已获取到锁则立即解锁
+    pub fn cancel_fair(&self, waiter: &Arc<Waiter>) {
+        if waiter.is_notified() {
+            //已获取到锁，则解锁
+            self.unlock_fair();
+        } else {
+            self.fair.as_ref().unwrap().lock().waits.remove(waiter);
+        }
+    }
 }
 
 /*
 * 互斥锁异步任务
 */
 struct FutureMutex<T> {
-    inner:  Arc<InnerMutex<T>>,  //内部锁
+    inner:      Arc<InnerMutex<T>>,     //内部锁
+    waiter:     Option<Arc<Waiter>>,    //等待者
+}
+
+impl<T> Drop for FutureMutex<T> {
+    fn drop(&mut self) {
+        let waiter = if let Some(waiter) = self.waiter.take() {
+            waiter
+        } else {
+            return;
+        };
+
+        if self.inner.fair.is_some() {
+            //公平模式下已在等待锁时被释放
+            self.inner.cancel_fair(&waiter);
+        } else if !waiter.cancel() {
+            //非公平模式下已被唤醒后被释放，已消耗了解锁时的唤醒，则尝试获取锁后立即解锁，以唤醒其它等待的任务
+            if self.inner.lock_status.compare_exchange(false,
+                                                       true,
+                                                       Ordering::Acquire,
+                                                       Ordering::Relaxed).is_ok() {
+                drop(MutexGuard {
+                    guarder: self.inner.clone(),
+                });
+            }
+        }
+    }
 }
 
 impl<T> Future for FutureMutex<T> {
     type Output = MutexGuard<T>;
 
     //抢占式的获取互斥锁
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if (&self).inner.fair.is_some() {
+            //公平模式
+            return self.poll_fair(cx);
+        }
+
         //尝试获取异步互斥锁
         for spin_len in 1..10 {
             if (&self).inner.try_lock() {
-                //获取异步互斥锁成功，则返回异步互斥锁守护者
+                //获取异步互斥锁成功，如果还在锁等待队列中则取消等待，并返回异步互斥锁守护者
+                if let Some(waiter) = (&mut self).waiter.take() {
+                    waiter.cancel();
+                }
                 return Poll::Ready(MutexGuard {
                     guarder: (&self).inner.clone()
                 });
@@ -158,8 +277,59 @@ impl<T> Future for FutureMutex<T> {
             }
         }
 
+        if let Some(waiter) = (&self).waiter.as_ref() {
+            //已在锁等待队列中，则只更新唤醒器
+            if !waiter.update_waker(cx.waker()) {
+                return Poll::Pending;
+            }
+        }
+
         //尝试获取异步互斥锁失败，则加入锁等待队列
-        (&self).inner.push(cx.waker().clone());
+        let waiter = Waiter::new(0, cx.waker().clone());
+        (&self).inner.push(waiter.clone());
+        (&mut self).waiter = Some(waiter);
+        Poll::Pending
+    }
+}
+
+impl<T> FutureMutex<T> {
+    //构建互斥锁异步任务
+    fn new(inner: Arc<InnerMutex<T>>) -> Self {
+        FutureMutex {
+            inner,
+            waiter: None,
+        }
+    }
+
+    //按先进先出的顺序获取互斥锁
+    fn poll_fair(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<MutexGuard<T>> {
+        if let Some(waiter) = (&self).waiter.as_ref() {
+            //已在等待锁，则检查是否已获取到锁
+            if !waiter.update_waker(cx.waker()) {
+                return Poll::Pending;
+            }
+
+            (&mut self).waiter = None;
+            return Poll::Ready(MutexGuard {
+                guarder: (&self).inner.clone()
+            });
+        }
+
+        let mut state = (&self).inner.fair.as_ref().unwrap().lock();
+        if !state.is_locked && state.waits.is_empty() {
+            //未锁，且没有等待的任务，则立即获取锁
+            state.is_locked = true;
+            return Poll::Ready(MutexGuard {
+                guarder: (&self).inner.clone()
+            });
+        }
+
+        //已锁，则加入锁等待队列尾
+        let waiter = Waiter::new(0, cx.waker().clone());
+        state.waits.push_back(waiter.clone());
+        drop(state);
+        (&mut self).waiter = Some(waiter);
+
         Poll::Pending
     }
 }
@@ -0,0 +1,135 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::future::Future;
+use std::task::{Context, Poll};
+
+use super::{NOTIFIED_ONE, Waiter, WaitQueue, spin_lock::SpinLock};
+
+/*
+* 异步通知，用于唤醒等待通知的任务
+*/
+pub struct Notify {
+    inner:  Arc<SpinLock<NotifyState>>, //异步通知状态
+}
+
+unsafe impl Send for Notify {}
+unsafe impl Sync for Notify {}
+
+/*
+* 异步通知同步方法
+*/
+impl Notify {
+    //构建异步通知
+    pub fn new() -> Self {
+        Notify {
+            inner: Arc::new(SpinLock::new(NotifyState {
+                permit: false,
+                waits: WaitQueue::new(),
+            })),
+        }
+    }
+
+    //唤醒一个等待通知的任务，没有等待的任务则保存通知，下一个等待通知的任务会立即获取到通知
+    pub fn notify_one(&self) {
+        notify_one(&self.inner);
+    }
+
+    //唤醒所有当前等待通知的任务，不会保存通知
+    pub fn notify_waiters(&self) {
+        let wakers = self.inner.lock().waits.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/*
+* 异步通知异步方法
+*/
+impl Notify {
+    //等待通知
+    pub async fn notified(&self) {
+        FutureNotified {
+            inner: self.inner.clone(),
+            waiter: None,
+        }.await
+    }
+}
+
+/*
+* 异步通知状态
+*/
+struct NotifyState {
+    permit: bool,       //是否有保存的通知
+    waits:  WaitQueue,  //等待通知的任务队列
+}
+
+//唤醒一个等待通知的任务，没有等待的任务则保存通知
+fn notify_one(inner: &SpinLock<NotifyState>) {
+    let waker = {
+        let mut state = inner.lock();
+        match state.waits.notify_one() {
+            None => {
+                //没有等待的任务，则保存通知
+                state.permit = true;
+                return;
+            },
+            Some(waker) => waker,
+        }
+    };
+
+    waker.wake();
+}
+
+/*
+* 等待通知异步任务
+*/
+struct FutureNotified {
+    inner:  Arc<SpinLock<NotifyState>>, //异步通知状态
+    waiter: Option<Arc<Waiter>>,        //等待者
+}
+
+impl Drop for FutureNotified {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            //已在等待通知时被释放
+            if waiter.status() == NOTIFIED_ONE {
+                //已单独获取到通知但未处理，则将通知转交给其它等待的任务
+                notify_one(&self.inner);
+            } else {
+                self.inner.lock().waits.remove(&waiter);
+            }
+        }
+    }
+}
+
+impl Future for FutureNotified {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(waiter) = (&self).waiter.as_ref() {
+            //已在等待通知，则检查是否已获取到通知
+            if !waiter.update_waker(cx.waker()) {
+                return Poll::Pending;
+            }
+
+            (&mut self).waiter = None;
+            return Poll::Ready(());
+        }
+
+        let mut state = (&self).inner.lock();
+        if state.permit {
+            //有保存的通知，则立即获取通知
+            state.permit = false;
+            return Poll::Ready(());
+        }
+
+        //没有通知，则加入等待队列尾
+        let waiter = Waiter::new(0, cx.waker().clone());
+        state.waits.push_back(waiter.clone());
+        drop(state);
+        (&mut self).waiter = Some(waiter);
+
+        Poll::Pending
+    }
+}
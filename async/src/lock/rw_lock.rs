@@ -1,12 +1,20 @@
 use std::pin::Pin;
 use std::sync::Arc;
 use std::future::Future;
+use std::io::Result;
+use std::ptr;
+use std::time::Duration;
 use std::cell::UnsafeCell;
+use std::mem::ManuallyDrop;
 use std::ops::{Deref, DerefMut};
 use std::task::{Waker, Context, Poll};
-use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
 
-use super::{spin, mpsc_deque::{Sender, Receiver, mpsc_deque}};
+use crate::rt::{AsyncRuntime, timeout};
+use super::{spin, duration_to_millis, Waiter, WaitQueue,
+            spin_lock::SpinLock,
+            mutex_lock::{Mutex, MutexGuard},
+            mpsc_deque::{Sender, Receiver, mpsc_deque}};
 
 /*
 * 读写锁状态
@@ -16,6 +24,12 @@ const EXCLUSIVE: isize = -1;        //独占
 const UNLOCKED: isize = 0;          //未锁
 const SHARED_ONCE: isize = 1;       //唯一共享
 
+/*
+* 公平模式的读写锁等待类型
+*/
+const WAIT_SHARED: usize = 0;       //等待共享锁
+const WAIT_EXCLUSIVE: usize = 1;    //等待独占锁
+
 /*
 * 异步读锁守护者
 */
@@ -35,25 +49,46 @@ impl<T> Deref for RwLockReadGuard<T> {
 
 impl<T> Drop for RwLockReadGuard<T> {
     fn drop(&mut self) {
-        if self.guarder.free_shared() {
-            //准备解锁共享锁
-            unsafe {
-                let exclusive_consumer = &mut *self.guarder.exclusive_consumer;
-                //因为准备解锁共享锁状态保证了，同一时间只有一个线程可以获取到锁，所以可以使用不精确的检查接收队列是否为空的检查
-                if !exclusive_consumer.try_is_empty() {
-                    if let Some(waker) = exclusive_consumer.try_recv() {
-                        //有异步独占锁任务等待异步共享锁释放，则唤醒此独占锁任务
-                        self.guarder.unlock_shared();
-                        waker.wake();
-                        return;
-                    }
-                }
-                self.guarder.unlock_shared();
-            }
+        self.guarder.release_shared();
+    }
+}
+
+impl<T> RwLockReadGuard<T> {
+    //获取内部锁，且不释放异步共享锁
+    fn into_inner(self) -> Arc<InnerRwLock<T>> {
+        let guard = ManuallyDrop::new(self);
+        unsafe {
+            ptr::read(&guard.guarder)
         }
     }
 }
 
+/*
+* 异步可升级读锁守护者，同一时间只允许一个可升级读锁，可以与其它读锁共存
+*/
+pub struct RwLockUpgradableReadGuard<T> {
+    guard:  RwLockReadGuard<T>,     //读锁守护者
+    token:  MutexGuard<()>,         //可升级令牌，需要在读锁释放后释放
+}
+
+impl<T> Deref for RwLockUpgradableReadGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.deref()
+    }
+}
+
+impl<T> RwLockUpgradableReadGuard<T> {
+    //升级为异步写锁，会等待其它读锁释放，因为升级期间一直持有读锁，所以不会有其它写锁获取到锁
+    pub async fn upgrade(self) -> RwLockWriteGuard<T> {
+        FutureUpgrade {
+            guard: Some(self),
+            waiter: None,
+        }.await
+    }
+}
+
 /*
 * 异步写锁守护者
 */
@@ -81,29 +116,7 @@ impl<T> DerefMut for RwLockWriteGuard<T> {
 
 impl<T> Drop for RwLockWriteGuard<T> {
     fn drop(&mut self) {
-        unsafe {
-            let shared_producor = &self.guarder.shared_producor;
-            let exclusive_consumer = &mut *self.guarder.exclusive_consumer;
-            //因为独占锁保证了，同一时间只有一个线程可以获取到锁，所以可以使用不精确的检查接收队列是否为空的检查
-            if !shared_producor.try_is_empty() {
-                //有异步共享锁任务等待异步独占锁释放，则唤醒所有共享锁任务
-                let wakers = shared_producor.take();
-                self.guarder.unlock_exclusive();
-                for waker in wakers {
-                    waker.wake();
-                }
-                return;
-            } else if !exclusive_consumer.try_is_empty() {
-                //有异步独占锁任务等待当前异步独占锁释放，则唤醒此独占锁任务
-                if let Some(waker) = exclusive_consumer.try_recv() {
-                    //有异步独占锁任务等待异步共享锁释放，则唤醒此独占锁任务
-                    self.guarder.unlock_exclusive();
-                    waker.wake();
-                    return;
-                }
-            }
-            self.guarder.unlock_exclusive();
-        }
+        self.guarder.release_exclusive();
     }
 }
 
@@ -123,14 +136,43 @@ unsafe impl<T> Sync for RwLock<T> {}
 impl<T> RwLock<T> {
     //构建异步读写锁
     pub fn new(v: T) -> Self {
+        Self::with_fair(v, false)
+    }
+
+    //构建公平的异步读写锁，等待锁的任务严格按先进先出的顺序获取锁，有写锁在等待时后到的读锁也会等待，以防止写锁饥饿
+    pub fn new_fair(v: T) -> Self {
+        Self::with_fair(v, true)
+    }
+
+    //检查是否是公平的异步读写锁
+    pub fn is_fair(&self) -> bool {
+        self.inner.fair.is_some()
+    }
+
+    //构建指定是否公平的异步读写锁
+    fn with_fair(v: T, is_fair: bool) -> Self {
         let (shared_producor, shared_consumer) = mpsc_deque();
         let (exclusive_producor, exclusive_consumer) = mpsc_deque();
+        let (fair, upgradable) = if is_fair {
+            (Some(SpinLock::new(FairRwLockState {
+                readers: 0,
+                is_writing: false,
+                upgrading: WaitQueue::new(),
+                waits: WaitQueue::new(),
+            })), Mutex::new_fair(()))
+        } else {
+            (None, Mutex::new(()))
+        };
         let inner = Arc::new(InnerRwLock {
             shared_producor,
             shared_consumer: Box::into_raw(Box::new(shared_consumer)),
             exclusive_producor,
             exclusive_consumer: Box::into_raw(Box::new(exclusive_consumer)),
             lock_status: AtomicIsize::new(UNLOCKED),
+            is_upgrading: AtomicBool::new(false),
+            upgrade_waker: SpinLock::new(None),
+            fair,
+            upgradable,
             inner: UnsafeCell::new(v),
         });
 
@@ -146,29 +188,106 @@ impl<T> RwLock<T> {
 impl<T> RwLock<T> {
     //获取异步读锁
     pub async fn read(&self) -> RwLockReadGuard<T> {
-        FutureShared {
-            inner: self.inner.clone(),
-        }.await
+        FutureShared::new(self.inner.clone()).await
+    }
+
+    //获取异步可升级读锁
+    pub async fn upgradable_read(&self) -> RwLockUpgradableReadGuard<T> {
+        let token = self.inner.upgradable.lock().await;
+        let guard = FutureShared::new(self.inner.clone()).await;
+
+        RwLockUpgradableReadGuard {
+            guard,
+            token,
+        }
     }
 
     //获取异步写锁
     pub async fn write(&self) -> RwLockWriteGuard<T> {
-        FutureExclusive {
-            inner: self.inner.clone(),
-        }.await
+        FutureExclusive::new(self.inner.clone()).await
+    }
+}
+
+impl<T: 'static> RwLock<T> {
+    //在指定时间内获取异步读锁，超时则放弃获取，并返回超时错误，需要指定运行时有本地定时器，定时器精度为毫秒，不足1毫秒的部分向上取整
+    pub async fn try_read_for<O: Default + 'static>(&self, rt: AsyncRuntime<O>, duration: Duration) -> Result<RwLockReadGuard<T>> {
+        timeout(rt, duration_to_millis(duration), FutureShared::new(self.inner.clone())).await
+    }
+
+    //在指定时间内获取异步写锁，超时则放弃获取，并返回超时错误，需要指定运行时有本地定时器，定时器精度为毫秒，不足1毫秒的部分向上取整
+    pub async fn try_write_for<O: Default + 'static>(&self, rt: AsyncRuntime<O>, duration: Duration) -> Result<RwLockWriteGuard<T>> {
+        timeout(rt, duration_to_millis(duration), FutureExclusive::new(self.inner.clone())).await
     }
 }
 
+/*
+* 公平模式的异步读写锁状态
+*/
+struct FairRwLockState {
+    readers:    usize,      //持有共享锁的数量
+    is_writing: bool,       //是否已独占
+    upgrading:  WaitQueue,  //等待升级的可升级读锁
+    waits:      WaitQueue,  //锁等待队列
+}
+
+//公平模式下按先进先出的顺序将锁交给等待队列头的任务，直到遇到无法获取锁的任务，返回需要唤醒的任务的唤醒器
+fn grant_fair(state: &mut FairRwLockState) -> Vec<Waker> {
+    let mut wakers = Vec::new();
+    if !state.upgrading.is_empty() {
+        //有可升级读锁在等待升级，则只允许在其它共享锁都释放后升级
+        if state.readers == 1 {
+            state.readers = 0;
+            state.is_writing = true;
+            if let Some(waker) = state.upgrading.notify_one() {
+                wakers.push(waker);
+            }
+        }
+
+        return wakers;
+    }
+
+    while let Some(waiter) = state.waits.front() {
+        if waiter.value() == WAIT_SHARED {
+            if state.is_writing {
+                //已独占
+                break;
+            }
+
+            state.readers += 1;
+            if let Some(waker) = state.waits.notify_one() {
+                wakers.push(waker);
+            }
+        } else {
+            if state.is_writing || state.readers > 0 {
+                //已独占或已共享
+                break;
+            }
+
+            state.is_writing = true;
+            if let Some(waker) = state.waits.notify_one() {
+                wakers.push(waker);
+            }
+            break;
+        }
+    }
+
+    wakers
+}
+
 /*
 * 内部异步读写锁
 */
 struct InnerRwLock<T> {
-    shared_producor:    Sender<Waker>,          //共享等待队列生产者
-    shared_consumer:    *mut Receiver<Waker>,   //共享等待队列消费者
-    exclusive_producor: Sender<Waker>,          //独占等待队列生产者
-    exclusive_consumer: *mut Receiver<Waker>,   //独占等待队列消费者
-    lock_status:        AtomicIsize,            //异步读写锁状态
-    inner:              UnsafeCell<T>,          //异步读写锁内容
+    shared_producor:    Sender<Arc<Waiter>>,                //共享等待队列生产者
+    shared_consumer:    *mut Receiver<Arc<Waiter>>,         //共享等待队列消费者
+    exclusive_producor: Sender<Arc<Waiter>>,                //独占等待队列生产者
+    exclusive_consumer: *mut Receiver<Arc<Waiter>>,         //独占等待队列消费者
+    lock_status:        AtomicIsize,                        //异步读写锁状态
+    is_upgrading:       AtomicBool,                         //非公平模式下是否有可升级读锁在等待升级
+    upgrade_waker:      SpinLock<Option<Waker>>,            //非公平模式下等待升级的可升级读锁的唤醒器
+    fair:               Option<SpinLock<FairRwLockState>>,  //公平模式的异步读写锁状态，非公平模式则为空
+    upgradable:         Mutex<()>,                          //可升级令牌，保证同一时间只有一个可升级读锁
+    inner:              UnsafeCell<T>,                      //异步读写锁内容
 }
 
 unsafe impl<T> Send for InnerRwLock<T> {}
@@ -177,14 +296,14 @@ unsafe impl<T> Sync for InnerRwLock<T> {}
 impl<T> InnerRwLock<T> {
     //加入共享等待队列
     #[inline(always)]
-    pub fn push_shared(&self, waker: Waker) {
-        self.shared_producor.send(waker);
+    pub fn push_shared(&self, waiter: Arc<Waiter>) {
+        self.shared_producor.send(waiter);
     }
 
     //加入独占等待队列
     #[inline(always)]
-    pub fn push_exclusive(&self, waker: Waker) {
-        self.exclusive_producor.send(waker);
+    pub fn push_exclusive(&self, waiter: Arc<Waiter>) {
+        self.exclusive_producor.send(waiter);
     }
 
     //获取当前异步读写锁状态
@@ -280,24 +399,184 @@ impl<T> InnerRwLock<T> {
     pub fn unlock_exclusive(&self) {
         self.lock_status.store(UNLOCKED, Ordering::Relaxed);
     }
+
+    //释放异步共享锁，并唤醒等待的任务
+    pub fn release_shared(&self) {
+        if let Some(fair) = self.fair.as_ref() {
+            //公平模式
+            let wakers = {
+                let mut state = fair.lock();
+                state.readers -= 1;
+                grant_fair(&mut state)
+            };
+
+            for waker in wakers {
+                waker.wake();
+            }
+            return;
+        }
+
+        if !self.free_shared() {
+            //未准备解锁共享锁
+            if self.is_upgrading.load(Ordering::SeqCst) && self.get_lock_status() == SHARED_ONCE {
+                //只剩下等待升级的可升级读锁，则唤醒它
+                if let Some(waker) = self.upgrade_waker.lock().take() {
+                    waker.wake();
+                }
+            }
+        } else {
+            //准备解锁共享锁
+            unsafe {
+                let exclusive_consumer = &mut *self.exclusive_consumer;
+                //因为准备解锁共享锁状态保证了，同一时间只有一个线程可以获取到锁，所以可以不加锁的从接收队列中接收
+                while let Some(waiter) = exclusive_consumer.try_recv() {
+                    if let Some(waker) = waiter.try_notify_one() {
+                        //有异步独占锁任务等待异步共享锁释放，则唤醒此独占锁任务
+                        self.unlock_shared();
+                        waker.wake();
+                        return;
+                    }
+
+                    //等待者已取消等待，则继续唤醒下一个等待者
+                }
+                self.unlock_shared();
+            }
+        }
+    }
+
+    //释放异步独占锁，并唤醒等待的任务
+    pub fn release_exclusive(&self) {
+        if let Some(fair) = self.fair.as_ref() {
+            //公平模式
+            let wakers = {
+                let mut state = fair.lock();
+                state.is_writing = false;
+                grant_fair(&mut state)
+            };
+
+            for waker in wakers {
+                waker.wake();
+            }
+            return;
+        }
+
+        unsafe {
+            let shared_producor = &self.shared_producor;
+            let exclusive_consumer = &mut *self.exclusive_consumer;
+            //因为独占锁保证了，同一时间只有一个线程可以获取到锁，所以可以使用不精确的检查接收队列是否为空的检查
+            if !shared_producor.try_is_empty() {
+                //有异步共享锁任务等待异步独占锁释放，则唤醒所有未取消等待的共享锁任务
+                let wakers: Vec<Waker> = shared_producor
+                    .take()
+                    .into_iter()
+                    .filter_map(|waiter| waiter.try_notify_one())
+                    .collect();
+                if !wakers.is_empty() {
+                    self.unlock_exclusive();
+                    for waker in wakers {
+                        waker.wake();
+                    }
+                    return;
+                }
+            }
+
+            while let Some(waiter) = exclusive_consumer.try_recv() {
+                if let Some(waker) = waiter.try_notify_one() {
+                    //有异步独占锁任务等待当前异步独占锁释放，则唤醒此独占锁任务
+                    self.unlock_exclusive();
+                    waker.wake();
+                    return;
+                }
+
+                //等待者已取消等待，则继续唤醒下一个等待者
+            }
+            self.unlock_exclusive();
+        }
+    }
+
+    //非公平模式下将已消耗的唤醒传递给其它等待的任务，当前未锁则尝试获取独占锁后立即释放，已锁则由持有锁的任务在释放时唤醒
+    pub fn pass_wakeup(&self) {
+        if self.lock_status.compare_exchange(UNLOCKED,
+                                             EXCLUSIVE,
+                                             Ordering::Acquire,
+                                             Ordering::Relaxed).is_ok() {
+            self.release_exclusive();
+        }
+    }
+
+    //公平模式下取消等待锁，已获取到锁则立即释放锁
+    pub fn cancel_fair(&self, waiter: &Arc<Waiter>) {
+        let wakers = {
+            let mut state = self.fair.as_ref().unwrap().lock();
+            if waiter.is_notified() {
+                //已获取到锁，则释放锁
+                if waiter.value() == WAIT_SHARED {
+                    state.readers -= 1;
+                } else {
+                    state.is_writing = false;
+                }
+            } else {
+                //未获取到锁，则从等待队列中移除
+                state.waits.remove(waiter);
+            }
+            grant_fair(&mut state)
+        };
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
 }
 
 /*
 * 共享锁异步任务
 */
 struct FutureShared<T> {
-    inner:  Arc<InnerRwLock<T>>,  //内部锁
+    inner:  Arc<InnerRwLock<T>>,    //内部锁
+    waiter: Option<Arc<Waiter>>,    //等待者
+}
+
+impl<T> Drop for FutureShared<T> {
+    fn drop(&mut self) {
+        let waiter = if let Some(waiter) = self.waiter.take() {
+            waiter
+        } else {
+            return;
+        };
+
+        if self.inner.fair.is_some() {
+            //公平模式下已在等待锁时被释放
+            self.inner.cancel_fair(&waiter);
+        } else if !waiter.cancel() {
+            //非公平模式下已被唤醒后被释放，已消耗了解锁时的唤醒，则尝试获取独占锁后立即释放，以唤醒其它等待的任务
+            self.inner.pass_wakeup();
+        }
+    }
 }
 
 impl<T> Future for FutureShared<T> {
     type Output = RwLockReadGuard<T>;
 
     //抢占式的获取共享锁
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if (&self).inner.fair.is_some() {
+            //公平模式
+            let inner = (&self).inner.clone();
+            if poll_fair(&inner, &mut (&mut self).waiter, cx, WAIT_SHARED) {
+                return Poll::Ready(RwLockReadGuard {
+                    guarder: inner,
+                });
+            }
+            return Poll::Pending;
+        }
+
         //尝试获取异步共享锁
         for spin_len in 1..10 {
             if (&self).inner.try_lock_shared() {
-                //获取异步共享锁成功，则返回异步读锁守护者
+                //获取异步共享锁成功，如果还在共享等待队列中则取消等待，并返回异步读锁守护者
+                if let Some(waiter) = (&mut self).waiter.take() {
+                    waiter.cancel();
+                }
                 return Poll::Ready(RwLockReadGuard {
                     guarder: (&self).inner.clone()
                 });
@@ -308,28 +587,80 @@ impl<T> Future for FutureShared<T> {
             }
         }
 
-        //尝试获取异步共享锁失败，则加入共享等待队列，并立即解锁等待队列的共享锁
-        (&self).inner.push_shared(cx.waker().clone());
+        if let Some(waiter) = (&self).waiter.as_ref() {
+            //已在共享等待队列中，则只更新唤醒器
+            if !waiter.update_waker(cx.waker()) {
+                return Poll::Pending;
+            }
+        }
+
+        //尝试获取异步共享锁失败，则加入共享等待队列
+        let waiter = Waiter::new(WAIT_SHARED, cx.waker().clone());
+        (&self).inner.push_shared(waiter.clone());
+        (&mut self).waiter = Some(waiter);
         Poll::Pending
     }
 }
 
+impl<T> FutureShared<T> {
+    //构建共享锁异步任务
+    fn new(inner: Arc<InnerRwLock<T>>) -> Self {
+        FutureShared {
+            inner,
+            waiter: None,
+        }
+    }
+}
+
 /*
 * 独占锁异步任务
 */
 struct FutureExclusive<T> {
-    inner:  Arc<InnerRwLock<T>>,  //内部锁
+    inner:      Arc<InnerRwLock<T>>,    //内部锁
+    waiter:     Option<Arc<Waiter>>,    //等待者
+}
+
+impl<T> Drop for FutureExclusive<T> {
+    fn drop(&mut self) {
+        let waiter = if let Some(waiter) = self.waiter.take() {
+            waiter
+        } else {
+            return;
+        };
+
+        if self.inner.fair.is_some() {
+            //公平模式下已在等待锁时被释放
+            self.inner.cancel_fair(&waiter);
+        } else if !waiter.cancel() {
+            //非公平模式下已被唤醒后被释放，已消耗了解锁时的唤醒，则尝试获取独占锁后立即释放，以唤醒其它等待的任务
+            self.inner.pass_wakeup();
+        }
+    }
 }
 
 impl<T> Future for FutureExclusive<T> {
     type Output = RwLockWriteGuard<T>;
 
     //抢占式的获取独占锁
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if (&self).inner.fair.is_some() {
+            //公平模式
+            let inner = (&self).inner.clone();
+            if poll_fair(&inner, &mut (&mut self).waiter, cx, WAIT_EXCLUSIVE) {
+                return Poll::Ready(RwLockWriteGuard {
+                    guarder: inner,
+                });
+            }
+            return Poll::Pending;
+        }
+
         //尝试获取异步独占锁
         for spin_len in 1..5 {
             if (&self).inner.try_lock_exclusive() {
-                //获取异步独占锁成功，则返回异步写锁守护者
+                //获取异步独占锁成功，如果还在独占等待队列中则取消等待，并返回异步写锁守护者
+                if let Some(waiter) = (&mut self).waiter.take() {
+                    waiter.cancel();
+                }
                 return Poll::Ready(RwLockWriteGuard {
                     guarder: (&self).inner.clone()
                 });
@@ -340,8 +671,163 @@ impl<T> Future for FutureExclusive<T> {
             }
         }
 
+        if let Some(waiter) = (&self).waiter.as_ref() {
+            //已在独占等待队列中，则只更新唤醒器
+            if !waiter.update_waker(cx.waker()) {
+                return Poll::Pending;
+            }
+        }
+
         //尝试获取异步独占锁失败，则加入独占等待队列
-        (&self).inner.push_exclusive(cx.waker().clone());
+        let waiter = Waiter::new(WAIT_EXCLUSIVE, cx.waker().clone());
+        (&self).inner.push_exclusive(waiter.clone());
+        (&mut self).waiter = Some(waiter);
         Poll::Pending
     }
+}
+
+impl<T> FutureExclusive<T> {
+    //构建独占锁异步任务
+    fn new(inner: Arc<InnerRwLock<T>>) -> Self {
+        FutureExclusive {
+            inner,
+            waiter: None,
+        }
+    }
+}
+
+//公平模式下按先进先出的顺序获取指定类型的锁，返回是否成功
+fn poll_fair<T>(inner: &Arc<InnerRwLock<T>>,
+                waiter: &mut Option<Arc<Waiter>>,
+                cx: &mut Context<'_>,
+                wait: usize) -> bool {
+    if let Some(w) = waiter.as_ref() {
+        //已在等待锁，则检查是否已获取到锁
+        if !w.update_waker(cx.waker()) {
+            return false;
+        }
+
+        *waiter = None;
+        return true;
+    }
+
+    let mut state = inner.fair.as_ref().unwrap().lock();
+    if state.waits.is_empty() && state.upgrading.is_empty() && !state.is_writing {
+        //没有等待的任务，且未独占
+        if wait == WAIT_SHARED {
+            //立即获取共享锁
+            state.readers += 1;
+            return true;
+        } else if state.readers == 0 {
+            //未共享，则立即获取独占锁
+            state.is_writing = true;
+            return true;
+        }
+    }
+
+    //无法获取锁，则加入锁等待队列尾
+    let w = Waiter::new(wait, cx.waker().clone());
+    state.waits.push_back(w.clone());
+    *waiter = Some(w);
+
+    false
+}
+
+/*
+* 升级锁异步任务
+*/
+struct FutureUpgrade<T> {
+    guard:  Option<RwLockUpgradableReadGuard<T>>,   //可升级读锁守护者
+    waiter: Option<Arc<Waiter>>,                    //公平模式下的等待者
+}
+
+impl<T> Drop for FutureUpgrade<T> {
+    fn drop(&mut self) {
+        let guard = if let Some(guard) = self.guard.take() {
+            guard
+        } else {
+            return;
+        };
+        let inner = guard.guard.guarder.clone();
+
+        if let Some(waiter) = self.waiter.take() {
+            //公平模式下已在等待升级时被释放
+            let is_upgraded = {
+                let mut state = inner.fair.as_ref().unwrap().lock();
+                if !waiter.is_notified() {
+                    state.upgrading.remove(&waiter);
+                    false
+                } else {
+                    true
+                }
+            };
+
+            if is_upgraded {
+                //已升级，则释放异步独占锁
+                let RwLockUpgradableReadGuard { guard, token } = guard;
+                guard.into_inner();
+                inner.release_exclusive();
+                drop(token);
+            }
+        } else if inner.fair.is_none() {
+            //非公平模式下已在等待升级时被释放，则取消升级
+            inner.upgrade_waker.lock().take();
+            inner.is_upgrading.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<T> Future for FutureUpgrade<T> {
+    type Output = RwLockWriteGuard<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = (&self).guard.as_ref().unwrap().guard.guarder.clone();
+        if let Some(fair) = inner.fair.as_ref() {
+            //公平模式
+            if let Some(waiter) = (&self).waiter.as_ref() {
+                //已在等待升级，则检查是否已升级
+                if !waiter.update_waker(cx.waker()) {
+                    return Poll::Pending;
+                }
+
+                (&mut self).waiter = None;
+            } else {
+                let mut state = fair.lock();
+                if state.readers == 1 {
+                    //只剩下当前可升级读锁，则立即升级
+                    state.readers = 0;
+                    state.is_writing = true;
+                } else {
+                    //还有其它读锁，则等待其它读锁释放
+                    let waiter = Waiter::new(WAIT_EXCLUSIVE, cx.waker().clone());
+                    state.upgrading.push_back(waiter.clone());
+                    drop(state);
+                    (&mut self).waiter = Some(waiter);
+                    return Poll::Pending;
+                }
+            }
+        } else {
+            //非公平模式，先设置唤醒器，再尝试升级，防止其它读锁在设置唤醒器前释放
+            *inner.upgrade_waker.lock() = Some(cx.waker().clone());
+            inner.is_upgrading.store(true, Ordering::SeqCst);
+            if inner.lock_status.compare_exchange(SHARED_ONCE,
+                                                  EXCLUSIVE,
+                                                  Ordering::Acquire,
+                                                  Ordering::Relaxed).is_err() {
+                //还有其它读锁，则等待其它读锁释放
+                return Poll::Pending;
+            }
+
+            inner.is_upgrading.store(false, Ordering::SeqCst);
+            inner.upgrade_waker.lock().take();
+        }
+
+        //已升级，则将共享锁转换为独占锁，并释放可升级令牌
+        let RwLockUpgradableReadGuard { guard, token } = (&mut self).guard.take().unwrap();
+        let guarder = guard.into_inner();
+        drop(token);
+        Poll::Ready(RwLockWriteGuard {
+            guarder,
+        })
+    }
 }
\ No newline at end of file
@@ -0,0 +1,241 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::future::Future;
+use std::task::{Waker, Context, Poll};
+use std::io::{Error, Result, ErrorKind};
+
+use super::{Waiter, WaitQueue, spin_lock::SpinLock};
+
+/*
+* 异步信号量许可，持有信号量的引用，可以跨任务移动，释放时归还许可
+*/
+pub struct SemaphorePermit {
+    inner:      Arc<InnerSemaphore>,    //内部信号量
+    permits:    usize,                  //持有的许可数量
+}
+
+unsafe impl Send for SemaphorePermit {}
+unsafe impl Sync for SemaphorePermit {}
+
+impl Drop for SemaphorePermit {
+    fn drop(&mut self) {
+        if self.permits > 0 {
+            self.inner.release(self.permits);
+        }
+    }
+}
+
+impl SemaphorePermit {
+    //获取持有的许可数量
+    pub fn permits(&self) -> usize {
+        self.permits
+    }
+
+    //放弃持有的许可，放弃后许可不会归还给信号量，信号量的许可总数也会减少，注意已在等待且需要的许可数量超过减少后许可总数的任务将无法被唤醒
+    pub fn forget(mut self) {
+        self.inner.state.lock().total -= self.permits;
+        self.permits = 0;
+    }
+}
+
+/*
+* 异步计数信号量，等待许可的任务按先进先出的顺序获取许可
+*/
+pub struct Semaphore {
+    inner:  Arc<InnerSemaphore>,    //内部信号量
+}
+
+unsafe impl Send for Semaphore {}
+unsafe impl Sync for Semaphore {}
+
+/*
+* 异步信号量同步方法
+*/
+impl Semaphore {
+    //构建指定许可数量的异步信号量
+    pub fn new(permits: usize) -> Self {
+        let inner = Arc::new(InnerSemaphore {
+            state: SpinLock::new(SemaphoreState {
+                permits,
+                total: permits,
+                waits: WaitQueue::new(),
+            }),
+        });
+
+        Semaphore {
+            inner,
+        }
+    }
+
+    //获取当前可用的许可数量
+    pub fn available_permits(&self) -> usize {
+        self.inner.state.lock().permits
+    }
+
+    //获取许可总数，包括已被获取的许可
+    pub fn total_permits(&self) -> usize {
+        self.inner.state.lock().total
+    }
+
+    //增加指定数量的许可，并唤醒可以获取许可的等待任务
+    pub fn add_permits(&self, permits: usize) {
+        self.inner.state.lock().total += permits;
+        self.inner.release(permits);
+    }
+
+    //尝试获取指定数量的许可，失败则立即返回空
+    pub fn try_acquire(&self, permits: usize) -> Option<SemaphorePermit> {
+        let mut state = self.inner.state.lock();
+        if state.waits.is_empty() && state.permits >= permits {
+            //没有等待的任务，且许可足够
+            state.permits -= permits;
+            return Some(SemaphorePermit {
+                inner: self.inner.clone(),
+                permits,
+            });
+        }
+
+        None
+    }
+}
+
+/*
+* 异步信号量异步方法
+*/
+impl Semaphore {
+    //获取指定数量的许可，许可不足则挂起当前任务，直到其它任务归还许可，需要的许可数量超过许可总数则立即返回错误原因
+    pub async fn acquire(&self, permits: usize) -> Result<SemaphorePermit> {
+        let total = self.total_permits();
+        if permits > total {
+            //需要的许可永远不可能满足，如果加入等待队列则会阻塞之后所有等待的任务
+            return Err(Error::new(ErrorKind::InvalidInput, format!("acquire semaphore failed, permits: {:?}, total: {:?}, reason: permits exceed total", permits, total)));
+        }
+
+        Ok(FutureAcquire {
+            inner: self.inner.clone(),
+            permits,
+            waiter: None,
+        }.await)
+    }
+}
+
+/*
+* 内部异步信号量状态
+*/
+struct SemaphoreState {
+    permits:    usize,      //可用的许可数量
+    total:      usize,      //许可总数
+    waits:      WaitQueue,  //等待许可的任务队列
+}
+
+/*
+* 内部异步信号量
+*/
+struct InnerSemaphore {
+    state:  SpinLock<SemaphoreState>,   //异步信号量状态
+}
+
+unsafe impl Send for InnerSemaphore {}
+unsafe impl Sync for InnerSemaphore {}
+
+impl InnerSemaphore {
+    //归还指定数量的许可，并唤醒可以获取许可的等待任务
+    fn release(&self, permits: usize) {
+        let wakers = {
+            let mut state = self.state.lock();
+            state.permits += permits;
+            grant(&mut state)
+        };
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+//按先进先出的顺序将可用的许可直接交给等待任务，返回需要唤醒的等待任务的唤醒器
+fn grant(state: &mut SemaphoreState) -> Vec<Waker> {
+    let mut wakers = Vec::new();
+    loop {
+        let permits = match state.waits.front() {
+            Some(waiter) if waiter.value() <= state.permits => waiter.value(),
+            _ => break, //没有等待任务，或等待队列头的任务需要的许可不足
+        };
+
+        state.permits -= permits;
+        if let Some(waker) = state.waits.notify_one() {
+            wakers.push(waker);
+        }
+    }
+
+    wakers
+}
+
+/*
+* 获取许可异步任务
+*/
+struct FutureAcquire {
+    inner:      Arc<InnerSemaphore>,    //内部信号量
+    permits:    usize,                  //需要获取的许可数量
+    waiter:     Option<Arc<Waiter>>,    //等待者
+}
+
+impl Drop for FutureAcquire {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            //已在等待许可时被释放
+            let wakers = {
+                let mut state = self.inner.state.lock();
+                if waiter.is_notified() {
+                    //已获取到许可，则归还许可
+                    state.permits += self.permits;
+                } else {
+                    //未获取到许可，则从等待队列中移除
+                    state.waits.remove(&waiter);
+                }
+                grant(&mut state)
+            };
+
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Future for FutureAcquire {
+    type Output = SemaphorePermit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(waiter) = (&self).waiter.as_ref() {
+            //已在等待许可，则检查是否已获取到许可
+            if !waiter.update_waker(cx.waker()) {
+                return Poll::Pending;
+            }
+
+            (&mut self).waiter = None;
+            return Poll::Ready(SemaphorePermit {
+                inner: (&self).inner.clone(),
+                permits: (&self).permits,
+            });
+        }
+
+        let mut state = (&self).inner.state.lock();
+        if state.waits.is_empty() && state.permits >= (&self).permits {
+            //没有等待的任务，且许可足够，则立即获取许可
+            state.permits -= (&self).permits;
+            return Poll::Ready(SemaphorePermit {
+                inner: (&self).inner.clone(),
+                permits: (&self).permits,
+            });
+        }
+
+        //许可不足，则加入等待队列尾
+        let waiter = Waiter::new((&self).permits, cx.waker().clone());
+        state.waits.push_back(waiter.clone());
+        drop(state);
+        (&mut self).waiter = Some(waiter);
+
+        Poll::Pending
+    }
+}
@@ -2,7 +2,10 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::cell::RefCell;
 use std::future::Future;
-use std::task::{Context, Poll};
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+use std::task::{Waker, Context, Poll};
 use std::io::{Error, Result, ErrorKind};
 use std::fmt::{Debug, Formatter, Result as FmtResult};
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
@@ -10,8 +13,9 @@ use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
 pub mod single_thread;
 pub mod multi_thread;
 
-use futures::{future::BoxFuture, FutureExt};
+use futures::{future::BoxFuture, task::{ArcWake, waker}, stream::{Stream, StreamExt}, FutureExt};
 use crossbeam_channel::{Sender, Receiver, unbounded};
+use parking_lot::Mutex;
 
 use local_timer::LocalTimer;
 
@@ -32,12 +36,45 @@ pub fn alloc_rt_uid() -> usize {
     RUNTIME_UID_GEN.fetch_add(1, Ordering::Relaxed)
 }
 
+/*
+* 异步运行时状态
+*/
+pub(crate) const RUNTIME_RUNNING: usize = 0;    //运行中
+pub(crate) const RUNTIME_DRAINING: usize = 1;   //关闭中，不再接受新的任务，等待已派发的任务完成
+pub(crate) const RUNTIME_STOPPED: usize = 2;    //已关闭
+
+/*
+* 异步运行时关闭报告
+*/
+#[derive(Debug, Clone)]
+pub struct ShutdownReport {
+    pub completed:  usize,      //关闭期间完成的任务数量
+    pub dropped:    usize,      //到达期限后仍未完成，而被释放的任务数量
+    pub joined:     usize,      //已结束的运行时线程数量
+    pub elapsed:    Duration,   //关闭耗时
+}
+
 /*
 * 异步任务唯一id
 */
 #[derive(Clone)]
 pub struct TaskId(Arc<AtomicUsize>);
 
+impl TaskId {
+    //获取任务唯一id的键，同一个任务唯一id的所有副本的键相同
+    pub(crate) fn key(&self) -> usize {
+        &*self.0 as *const AtomicUsize as usize
+    }
+
+    //释放挂起任务时设置的唤醒器，但不唤醒
+    pub(crate) fn free_waker(&self) {
+        let ptr = self.0.swap(0, Ordering::SeqCst);
+        if ptr != 0 {
+            unsafe { drop(Box::from_raw(ptr as *mut Waker)); }
+        }
+    }
+}
+
 impl Debug for TaskId {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "TaskId[inner = {}]", self.0.load(Ordering::Relaxed))
@@ -64,6 +101,123 @@ impl<O: Default + 'static> Clone for AsyncRuntime<O> {
     }
 }
 
+impl<O: Default + 'static> AsyncRuntime<O> {
+    //获取运行时的定时任务生产者和定时任务取消者，没有本地定时器则返回空
+    fn timer_producor(&self) -> Option<(Sender<(usize, TaskId)>, Sender<TaskId>)> {
+        match self {
+            AsyncRuntime::Single(rt) => rt.timer_producor(),
+            AsyncRuntime::Multi(rt) => rt.timer_producor(),
+        }
+    }
+
+    //在运行时的本地定时器上设置指定超时的定时器，到期后通过定时器状态唤醒等待的任务
+    fn set_timer(&self, producor: &(Sender<(usize, TaskId)>, Sender<TaskId>), timeout: usize) -> Arc<TimerState> {
+        let task_id = match self {
+            AsyncRuntime::Single(rt) => rt.alloc(),
+            AsyncRuntime::Multi(rt) => rt.alloc(),
+        };
+        let state = Arc::new(TimerState {
+            is_expired: AtomicBool::new(false),
+            is_cancel: AtomicBool::new(false),
+            waker: Mutex::new(None),
+            canceller: producor.1.clone(),
+            task_id: task_id.clone(),
+        });
+
+        //将定时器状态作为唤醒器绑定到定时任务，以保证取消后到期的定时任务不会唤醒等待的任务
+        let timer_waker = waker(state.clone());
+        match self {
+            AsyncRuntime::Single(rt) => {
+                let _: Poll<()> = rt.pending(&task_id, timer_waker);
+            },
+            AsyncRuntime::Multi(rt) => {
+                let _: Poll<()> = rt.pending(&task_id, timer_waker);
+            },
+        }
+        let _ = producor.0.send((timeout, task_id));
+
+        state
+    }
+}
+
+/*
+* 通过任务唯一id挂起和唤醒任务，用于在不区分运行时类型的地方使用运行时的唤醒方式
+*/
+pub(crate) trait AsyncWakeup: Send + Sync + 'static {
+    //分配任务唯一id
+    fn alloc(&self) -> TaskId;
+
+    //将唤醒器挂起在指定的任务唯一id上
+    fn pending(&self, task_id: &TaskId, waker: Waker);
+
+    //唤醒挂起在指定的任务唯一id上的唤醒器
+    fn wakeup(&self, task_id: &TaskId);
+}
+
+impl<O: Default + 'static> AsyncWakeup for AsyncRuntime<O> {
+    fn alloc(&self) -> TaskId {
+        match self {
+            AsyncRuntime::Single(rt) => rt.alloc(),
+            AsyncRuntime::Multi(rt) => rt.alloc(),
+        }
+    }
+
+    fn pending(&self, task_id: &TaskId, waker: Waker) {
+        match self {
+            AsyncRuntime::Single(rt) => {
+                let _: Poll<()> = rt.pending(task_id, waker);
+            },
+            AsyncRuntime::Multi(rt) => {
+                let _: Poll<()> = rt.pending(task_id, waker);
+            },
+        }
+    }
+
+    fn wakeup(&self, task_id: &TaskId) {
+        match self {
+            AsyncRuntime::Single(rt) => rt.wakeup(task_id),
+            AsyncRuntime::Multi(rt) => rt.wakeup(task_id),
+        }
+    }
+}
+
+/*
+* 通过运行时唤醒的唤醒器，被唤醒时调用运行时的wakeup唤醒挂起在任务唯一id上的任务，释放时释放未被唤醒的唤醒器
+*/
+pub(crate) struct AsyncTaskWaker {
+    rt:         Arc<dyn AsyncWakeup>,   //挂起任务的运行时
+    task_id:    TaskId,                 //挂起任务的任务唯一id
+    is_woken:   AtomicBool,             //是否已唤醒
+}
+
+impl ArcWake for AsyncTaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        if arc_self.is_woken.swap(true, Ordering::SeqCst) {
+            //已唤醒，则忽略
+            return;
+        }
+
+        arc_self.rt.wakeup(&arc_self.task_id);
+    }
+}
+
+impl Drop for AsyncTaskWaker {
+    fn drop(&mut self) {
+        self.task_id.free_waker();
+    }
+}
+
+impl AsyncTaskWaker {
+    //构建通过运行时唤醒的唤醒器
+    pub fn new(rt: Arc<dyn AsyncWakeup>, task_id: TaskId) -> Waker {
+        waker(Arc::new(AsyncTaskWaker {
+            rt,
+            task_id,
+            is_woken: AtomicBool::new(false),
+        }))
+    }
+}
+
 /*
 * 异步值
 */
@@ -186,13 +340,172 @@ impl<V: Send + 'static> Clone for AsyncWaitResult<V> {
     }
 }
 
+/*
+* 异步任务句柄的共享状态
+*/
+struct JoinInner<V: Send + 'static> {
+    is_abort:   AtomicBool,                 //是否已中止
+    result:     Mutex<Option<Result<V>>>,   //异步任务的结果
+    task_waker: Mutex<Option<Waker>>,       //被派发的异步任务的唤醒器
+    join_waker: Mutex<Option<Waker>>,       //等待异步任务结果的唤醒器
+}
+
+impl<V: Send + 'static> JoinInner<V> {
+    //设置异步任务的结果，并唤醒等待结果的任务，已设置结果则忽略
+    fn finish(&self, result: Result<V>) {
+        {
+            let mut r = self.result.lock();
+            if r.is_some() {
+                //已设置结果，则忽略
+                return;
+            }
+            *r = Some(result);
+        }
+
+        if let Some(waker) = self.join_waker.lock().take() {
+            waker.wake();
+        }
+    }
+}
+
+/*
+* 异步任务句柄，用于等待被派发的异步任务的返回值，或中止被派发的异步任务
+*/
+pub struct JoinHandle<V: Send + 'static>(Arc<JoinInner<V>>);
+
+unsafe impl<V: Send + 'static> Send for JoinHandle<V> {}
+unsafe impl<V: Send + 'static> Sync for JoinHandle<V> {}
+
+impl<V: Send + 'static> Future for JoinHandle<V> {
+    type Output = Result<V>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.0.result.lock().take() {
+            //异步任务已完成或已中止，则返回
+            return Poll::Ready(result);
+        }
+
+        //异步任务未完成，则设置唤醒器，并再次检查异步任务的结果，防止在设置唤醒器前任务已完成
+        *self.0.join_waker.lock() = Some(cx.waker().clone());
+        if let Some(result) = self.0.result.lock().take() {
+            return Poll::Ready(result);
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<V: Send + 'static> JoinHandle<V> {
+    //中止被派发的异步任务，被派发的异步任务会在下次被轮询时释放，并以取消错误完成当前句柄
+    pub fn abort(&self) {
+        if self.0.is_abort.swap(true, Ordering::SeqCst) {
+            //已中止，则忽略
+            return;
+        }
+
+        if let Some(waker) = self.0.task_waker.lock().take() {
+            //被派发的异步任务已挂起，则唤醒它，以保证它尽快被释放
+            waker.wake();
+        }
+    }
+
+    //检查被派发的异步任务是否已完成或已中止
+    pub fn is_finished(&self) -> bool {
+        self.0.result.lock().is_some()
+    }
+}
+
+/*
+* 可中止的异步任务，用于包装被派发的异步任务，并将返回值传递给异步任务句柄
+*/
+pub struct JoinTask<O: Default + 'static, V: Send + 'static> {
+    inner:  Arc<JoinInner<V>>,                  //共享状态
+    future: Option<BoxFuture<'static, V>>,      //被派发的异步任务
+    marker: PhantomData<O>,
+}
+
+unsafe impl<O: Default + 'static, V: Send + 'static> Send for JoinTask<O, V> {}
+unsafe impl<O: Default + 'static, V: Send + 'static> Sync for JoinTask<O, V> {}
+impl<O: Default + 'static, V: Send + 'static> Unpin for JoinTask<O, V> {}
+
+impl<O: Default + 'static, V: Send + 'static> Future for JoinTask<O, V> {
+    type Output = O;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        //先设置唤醒器，再检查是否已中止，以保证中止时可以唤醒当前任务
+        {
+            let mut task_waker = self.inner.task_waker.lock();
+            match task_waker.as_ref() {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => *task_waker = Some(cx.waker().clone()),
+            }
+        }
+
+        if self.inner.is_abort.load(Ordering::SeqCst) {
+            //已中止，则立即释放被派发的异步任务，并以取消错误完成异步任务句柄
+            (&mut self).future.take();
+            self.inner.task_waker.lock().take();
+            self.inner.finish(Err(Error::new(ErrorKind::Interrupted, "async task cancelled")));
+            return Poll::Ready(Default::default());
+        }
+
+        let r = match (&mut self).future.as_mut() {
+            None => return Poll::Ready(Default::default()),
+            Some(future) => future.as_mut().poll(cx),
+        };
+
+        match r {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(value) => {
+                //已完成，则释放被派发的异步任务，并完成异步任务句柄
+                (&mut self).future.take();
+                self.inner.task_waker.lock().take();
+                self.inner.finish(Ok(value));
+                Poll::Ready(Default::default())
+            },
+        }
+    }
+}
+
+impl<O: Default + 'static, V: Send + 'static> Drop for JoinTask<O, V> {
+    //未完成时被释放，例如运行时关闭时释放了挂起的任务，则以取消错误完成异步任务句柄，防止句柄永远等待
+    fn drop(&mut self) {
+        if self.future.take().is_some() {
+            self.inner.task_waker.lock().take();
+            self.inner.finish(Err(Error::new(ErrorKind::Interrupted, "async task cancelled")));
+        }
+    }
+}
+
+impl<O: Default + 'static, V: Send + 'static> JoinTask<O, V> {
+    //构建可中止的异步任务和对应的异步任务句柄
+    pub fn new<F>(future: F) -> (Self, JoinHandle<V>)
+        where F: Future<Output = V> + Send + 'static {
+        let inner = Arc::new(JoinInner {
+            is_abort: AtomicBool::new(false),
+            result: Mutex::new(None),
+            task_waker: Mutex::new(None),
+            join_waker: Mutex::new(None),
+        });
+
+        (JoinTask {
+            inner: inner.clone(),
+            future: Some(Box::new(future).boxed()),
+            marker: PhantomData,
+        }, JoinHandle(inner))
+    }
+}
+
 /*
 * 异步任务本地定时器
 */
 pub struct AsyncTaskTimer {
-    producor:   Sender<(usize, TaskId)>,            //定时任务生产者
-    consumer:   Receiver<(usize, TaskId)>,          //定时任务消费者
-    timer:      Arc<RefCell<LocalTimer<TaskId>>>,   //定时器
+    producor:   Sender<(usize, TaskId)>,                //定时任务生产者
+    consumer:   Receiver<(usize, TaskId)>,              //定时任务消费者
+    canceller:  Sender<TaskId>,                         //定时任务取消者
+    cancels:    Receiver<TaskId>,                       //需要取消的定时任务
+    timer:      Arc<RefCell<LocalTimer<TaskId>>>,       //定时器
+    refs:       Arc<RefCell<HashMap<usize, usize>>>,    //未到期的定时任务的任务唯一id的键和定时器引用
 }
 
 unsafe impl Send for AsyncTaskTimer {}
@@ -202,10 +515,14 @@ impl AsyncTaskTimer {
     //构建异步任务本地定时器
     pub fn new() -> Self {
         let (producor, consumer) = unbounded();
+        let (canceller, cancels) = unbounded();
         AsyncTaskTimer {
             producor,
             consumer,
+            canceller,
+            cancels,
             timer: Arc::new(RefCell::new(LocalTimer::new())),
+            refs: Arc::new(RefCell::new(HashMap::new())),
         }
     }
 
@@ -214,21 +531,60 @@ impl AsyncTaskTimer {
         self.producor.clone()
     }
 
+    //获取定时任务取消者，取消的定时任务会在下次消费时从定时器中移除
+    pub fn get_canceller(&self) -> Sender<TaskId> {
+        self.canceller.clone()
+    }
+
+    //获取未到期的定时任务数量
+    pub fn len(&self) -> usize {
+        self.refs.borrow().len()
+    }
+
     //设置定时器
     pub fn set_timer(&self, task_id: TaskId, timeout: usize) -> usize {
-        self.timer.borrow_mut().set_timeout(task_id, timeout)
+        let key = task_id.key();
+        let timer_ref = self.timer.borrow_mut().set_timeout(task_id, timeout);
+        self.refs.borrow_mut().insert(key, timer_ref);
+        timer_ref
     }
 
     //取消定时器
     pub fn cancel_timer(&self, timer_ref: usize) -> Option<TaskId> {
-        self.timer.borrow_mut().cancel(timer_ref)
+        let task_id = self.timer.borrow_mut().cancel(timer_ref);
+        if let Some(task_id) = &task_id {
+            self.refs.borrow_mut().remove(&task_id.key());
+        }
+        task_id
     }
 
-    //消费所有定时任务，返回定时任务数量
+    //消费所有定时任务，并移除所有已取消的定时任务，返回定时任务数量
     pub fn consume(&self) -> usize {
+        //先获取已取消的定时任务，因为定时任务总是在取消前设置，所以之后一定可以消费到已取消的定时任务
+        let mut cancels = HashSet::new();
+        for task_id in self.cancels.try_iter() {
+            let timer_ref = self.refs.borrow().get(&task_id.key()).cloned();
+            match timer_ref {
+                Some(timer_ref) => {
+                    if let Some(task_id) = self.cancel_timer(timer_ref) {
+                        task_id.free_waker();
+                    }
+                },
+                None => {
+                    cancels.insert(task_id.key());
+                },
+            }
+        }
+
         let mut len = 0;
         let timer_tasks = self.consumer.try_iter().collect::<Vec<(usize, TaskId)>>();
         for (timeout, task_id) in timer_tasks {
+            if cancels.remove(&task_id.key()) {
+                //已取消，则不再设置
+                task_id.free_waker();
+                continue;
+            }
+
             self.set_timer(task_id, timeout);
             len += 1;
         }
@@ -238,12 +594,18 @@ impl AsyncTaskTimer {
 
     //轮询定时器，返回过期任务
     pub fn poll(&self) -> Vec<TaskId> {
-        self.timer.borrow_mut().poll()
+        let expired = self.timer.borrow_mut().poll();
+        let mut refs = self.refs.borrow_mut();
+        for task_id in &expired {
+            refs.remove(&task_id.key());
+        }
+        expired
     }
 
     //清空定时器
     pub fn clear(&self) {
         self.timer.borrow_mut().clear();
+        self.refs.borrow_mut().clear();
     }
 }
 
@@ -305,6 +667,304 @@ impl<O: Default + 'static> AsyncWaitTimeout<O> {
     }
 }
 
+/*
+* 定时器状态，作为定时任务的唤醒器
+*/
+struct TimerState {
+    is_expired: AtomicBool,             //是否已到期
+    is_cancel:  AtomicBool,             //是否已取消
+    waker:      Mutex<Option<Waker>>,   //等待定时器到期的任务的唤醒器
+    canceller:  Sender<TaskId>,         //本地定时器的定时任务取消者
+    task_id:    TaskId,                 //定时任务的任务唯一id
+}
+
+impl ArcWake for TimerState {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.is_expired.store(true, Ordering::SeqCst);
+        if arc_self.is_cancel.load(Ordering::SeqCst) {
+            //已取消，则忽略
+            return;
+        }
+
+        if let Some(waker) = arc_self.waker.lock().take() {
+            waker.wake();
+        }
+    }
+}
+
+impl TimerState {
+    //检查定时器是否已到期，未到期则更新等待定时器到期的任务的唤醒器
+    fn poll_expired(&self, cx: &Context<'_>) -> bool {
+        if self.is_expired.load(Ordering::SeqCst) {
+            return true;
+        }
+
+        //先设置唤醒器，再次检查是否已到期，防止在设置唤醒器前已到期
+        *self.waker.lock() = Some(cx.waker().clone());
+        self.is_expired.load(Ordering::SeqCst)
+    }
+
+    //取消定时器，取消后到期也不会唤醒等待的任务，未到期则从本地定时器中移除
+    fn cancel(&self) {
+        if self.is_cancel.swap(true, Ordering::SeqCst) {
+            //已取消，则忽略
+            return;
+        }
+        self.waker.lock().take();
+
+        if !self.is_expired.load(Ordering::SeqCst) {
+            let _ = self.canceller.send(self.task_id.clone());
+        }
+    }
+}
+
+/*
+* 休眠指定时间
+*/
+pub struct AsyncSleep<O: Default + 'static> {
+    rt:         AsyncRuntime<O>,            //当前运行时
+    timeout:    usize,                      //休眠时长，单位ms
+    timer:      Option<Arc<TimerState>>,    //定时器状态
+}
+
+unsafe impl<O: Default + 'static> Send for AsyncSleep<O> {}
+unsafe impl<O: Default + 'static> Sync for AsyncSleep<O> {}
+
+impl<O: Default + 'static> Future for AsyncSleep<O> {
+    type Output = Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(timer) = (&self).timer.as_ref() {
+            //已设置定时器，则检查是否已到期
+            if timer.poll_expired(cx) {
+                return Poll::Ready(Ok(()));
+            }
+            return Poll::Pending;
+        }
+
+        match (&self).rt.timer_producor() {
+            None => {
+                //没有本地定时器，则立即返回错误原因
+                Poll::Ready(Err(Error::new(ErrorKind::Other, "async sleep failed, reason: local timer not exist")))
+            },
+            Some(producor) => {
+                //有本地定时器，则设置定时器
+                let timer = (&self).rt.set_timer(&producor, (&self).timeout);
+                let r = if timer.poll_expired(cx) {
+                    Poll::Ready(Ok(()))
+                } else {
+                    Poll::Pending
+                };
+                (&mut self).timer = Some(timer);
+                r
+            },
+        }
+    }
+}
+
+impl<O: Default + 'static> Drop for AsyncSleep<O> {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.cancel();
+        }
+    }
+}
+
+impl<O: Default + 'static> AsyncSleep<O> {
+    //构建休眠指定时间的方法
+    pub fn new(rt: AsyncRuntime<O>, timeout: usize) -> Self {
+        AsyncSleep {
+            rt,
+            timeout,
+            timer: None,
+        }
+    }
+}
+
+/*
+* 定时间隔流，每间隔指定时间产生一次值
+*/
+pub struct AsyncInterval<O: Default + 'static> {
+    rt:         AsyncRuntime<O>,            //当前运行时
+    period:     Duration,                   //间隔时长
+    deadline:   Instant,                    //下次到期时间
+    timer:      Option<Arc<TimerState>>,    //定时器状态
+}
+
+unsafe impl<O: Default + 'static> Send for AsyncInterval<O> {}
+unsafe impl<O: Default + 'static> Sync for AsyncInterval<O> {}
+
+impl<O: Default + 'static> Stream for AsyncInterval<O> {
+    type Item = Result<()>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(timer) = (&self).timer.as_ref() {
+            //已设置定时器，则检查是否已到期
+            if !timer.poll_expired(cx) {
+                return Poll::Pending;
+            }
+
+            //已到期，则计算下次到期时间
+            (&mut self).timer = None;
+            let period = (&self).period;
+            (&mut self).deadline += period;
+            return Poll::Ready(Some(Ok(())));
+        }
+
+        let now = Instant::now();
+        if now >= (&self).deadline {
+            //已错过到期时间，则立即产生值，并从当前时间开始计算下次到期时间
+            let period = (&self).period;
+            (&mut self).deadline = now + period;
+            return Poll::Ready(Some(Ok(())));
+        }
+
+        let timeout = ((&self).deadline - now).as_millis() as usize;
+        match (&self).rt.timer_producor() {
+            None => {
+                //没有本地定时器，则立即返回错误原因
+                Poll::Ready(Some(Err(Error::new(ErrorKind::Other, "async interval failed, reason: local timer not exist"))))
+            },
+            Some(producor) => {
+                //有本地定时器，则设置到下次到期时间的定时器
+                let timer = (&self).rt.set_timer(&producor, timeout);
+                if timer.poll_expired(cx) {
+                    let period = (&self).period;
+                    (&mut self).deadline += period;
+                    return Poll::Ready(Some(Ok(())));
+                }
+                (&mut self).timer = Some(timer);
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<O: Default + 'static> Drop for AsyncInterval<O> {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.cancel();
+        }
+    }
+}
+
+impl<O: Default + 'static> AsyncInterval<O> {
+    //构建指定间隔时长的定时间隔流，第一次值在间隔时长后产生
+    pub fn new(rt: AsyncRuntime<O>, period: usize) -> Self {
+        let period = Duration::from_millis(period as u64);
+        AsyncInterval {
+            rt,
+            period,
+            deadline: Instant::now() + period,
+            timer: None,
+        }
+    }
+
+    //等待下一次到期，没有本地定时器则返回错误原因
+    pub async fn tick(&mut self) -> Result<()> {
+        match self.next().await {
+            None => Err(Error::new(ErrorKind::Other, "async interval failed, reason: interval closed")),
+            Some(r) => r,
+        }
+    }
+}
+
+/*
+* 带超时的异步任务，在指定时间内未完成则返回超时错误
+*/
+pub struct AsyncTimeout<O: Default + 'static, V: Send + 'static> {
+    rt:         AsyncRuntime<O>,                //当前运行时
+    timeout:    usize,                          //超时时长，单位ms
+    future:     Option<BoxFuture<'static, V>>,  //需要等待的异步任务
+    timer:      Option<Arc<TimerState>>,        //定时器状态
+}
+
+unsafe impl<O: Default + 'static, V: Send + 'static> Send for AsyncTimeout<O, V> {}
+unsafe impl<O: Default + 'static, V: Send + 'static> Sync for AsyncTimeout<O, V> {}
+
+impl<O: Default + 'static, V: Send + 'static> Future for AsyncTimeout<O, V> {
+    type Output = Result<V>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let r = match (&mut self).future.as_mut() {
+            None => return Poll::Ready(Err(Error::new(ErrorKind::NotFound, "invalid future"))),
+            Some(future) => future.as_mut().poll(cx),
+        };
+
+        if let Poll::Ready(value) = r {
+            //异步任务已完成，则取消定时器，并返回值
+            (&mut self).future = None;
+            if let Some(timer) = (&mut self).timer.take() {
+                timer.cancel();
+            }
+            return Poll::Ready(Ok(value));
+        }
+
+        if (&self).timer.is_none() {
+            //还未设置定时器，则设置定时器
+            match (&self).rt.timer_producor() {
+                None => {
+                    //没有本地定时器，则立即返回错误原因
+                    (&mut self).future = None;
+                    return Poll::Ready(Err(Error::new(ErrorKind::Other, "async timeout failed, reason: local timer not exist")));
+                },
+                Some(producor) => {
+                    let timer = (&self).rt.set_timer(&producor, (&self).timeout);
+                    (&mut self).timer = Some(timer);
+                },
+            }
+        }
+
+        if (&self).timer.as_ref().unwrap().poll_expired(cx) {
+            //已超时，则释放异步任务，并返回超时错误
+            (&mut self).future = None;
+            (&mut self).timer = None;
+            return Poll::Ready(Err(Error::new(ErrorKind::TimedOut, format!("async timeout, timeout: {:?}ms", (&self).timeout))));
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<O: Default + 'static, V: Send + 'static> Drop for AsyncTimeout<O, V> {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.cancel();
+        }
+    }
+}
+
+impl<O: Default + 'static, V: Send + 'static> AsyncTimeout<O, V> {
+    //构建带超时的异步任务
+    pub fn new<F>(rt: AsyncRuntime<O>, timeout: usize, future: F) -> Self
+        where F: Future<Output = V> + Send + 'static {
+        AsyncTimeout {
+            rt,
+            timeout,
+            future: Some(Box::new(future).boxed()),
+            timer: None,
+        }
+    }
+}
+
+//挂起当前任务，等待指定运行时的本地定时器休眠指定的时间后唤醒当前任务，单位ms，没有本地定时器则返回错误原因
+pub fn sleep<O: Default + 'static>(rt: AsyncRuntime<O>, timeout: usize) -> AsyncSleep<O> {
+    AsyncSleep::new(rt, timeout)
+}
+
+//构建指定运行时的本地定时器驱动的定时间隔流，单位ms
+pub fn interval<O: Default + 'static>(rt: AsyncRuntime<O>, period: usize) -> AsyncInterval<O> {
+    AsyncInterval::new(rt, period)
+}
+
+//等待指定的异步任务，在指定时间内未完成则返回超时错误，单位ms
+pub fn timeout<O, V, F>(rt: AsyncRuntime<O>, timeout: usize, future: F) -> AsyncTimeout<O, V>
+    where O: Default + 'static,
+          V: Send + 'static,
+          F: Future<Output = V> + Send + 'static {
+    AsyncTimeout::new(rt, timeout, future)
+}
+
 /*
 * 等待异步任务执行完成
 */
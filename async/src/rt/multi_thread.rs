@@ -1,20 +1,27 @@
 use std::thread;
 use std::sync::Arc;
-use std::io::Result;
+use std::io::{Error, Result, ErrorKind};
 use std::future::Future;
-use std::time::Duration;
 use std::thread::Builder;
 use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::time::{Instant, Duration};
 use std::task::{Waker, Context, Poll};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use crossbeam_channel::unbounded;
+use crossbeam_channel::{Sender, unbounded};
 use parking_lot::{Mutex, Condvar};
 use futures::{future::{FutureExt, BoxFuture}, task::{ArcWake, waker_ref}, TryFuture};
+use log::warn;
+
+#[cfg(feature = "apm_collect")]
+use atom::Atom;
+#[cfg(feature = "apm_collect")]
+use apm::counter::{GLOBAL_PREF_COLLECT, PrefCounter, PrefTimer};
 
 use crate::{AsyncTask,
             lock::steal_deque::{Sender as StealSent, Receiver as StealRecv, steal_deque}};
-use super::{TaskId, AsyncRuntime, AsyncTaskTimer, AsyncWaitTimeout, AsyncWait, AsyncWaitAny, AsyncMap, alloc_rt_uid};
+use super::{TaskId, JoinTask, JoinHandle, AsyncRuntime, AsyncTaskTimer, AsyncWaitTimeout, AsyncSleep, AsyncInterval, AsyncTimeout, AsyncWait, AsyncWaitAny, AsyncMap, ShutdownReport, RUNTIME_RUNNING, RUNTIME_DRAINING, RUNTIME_STOPPED, alloc_rt_uid};
 
 /*
 * 线程唯一id
@@ -38,6 +45,11 @@ unsafe impl<O: Default + 'static> Sync for MultiTask<O> {}
 
 impl<O: Default + 'static> ArcWake for MultiTask<O> {
     fn wake_by_ref(arc_self: &Arc<Self>) {
+        if arc_self.queue.is_stopped() {
+            //任务所在运行时已关闭，则忽略唤醒
+            return;
+        }
+
         let origin_thread = arc_self.queue.id;
         if let Err(_) = THREAD_LOCAL_ID.try_with(move |id| {
             unsafe {
@@ -96,6 +108,7 @@ pub struct MultiTasks<O: Default + 'static> {
     producer:       StealSent<Arc<MultiTask<O>>>,   //任务生产者
     worker_waker:   Arc<(Mutex<bool>, Condvar)>,    //工作者唤醒器
     recv_counter:   Arc<AtomicUsize>,               //接收队列计数器
+    status:         Arc<AtomicUsize>,               //运行时状态
 }
 
 unsafe impl<O: Default + 'static> Send for MultiTasks<O> {}
@@ -109,6 +122,7 @@ impl<O: Default + 'static> Clone for MultiTasks<O> {
             producer: self.producer.clone(),
             worker_waker: self.worker_waker.clone(),
             recv_counter: self.recv_counter.clone(),
+            status: self.status.clone(),
         }
     }
 }
@@ -120,6 +134,12 @@ impl<O: Default + 'static> MultiTasks<O> {
         self.producer.len() + self.consumer.len()
     }
 
+    //检查任务队列所在运行时是否已关闭
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.status.load(Ordering::Relaxed) == RUNTIME_STOPPED
+    }
+
     //尝试向多线程任务队列尾推入指定的任务
     pub fn try_push_back(&self, task: Arc<MultiTask<O>>) -> Option<Arc<MultiTask<O>>> {
         if let Some(task) = self.producer.try_send(1, task) {
@@ -190,6 +210,12 @@ pub struct MultiTaskRuntime<O: Default + 'static>(Arc<(
     Arc<Vec<Arc<MultiTasks<O>>>>,                   //异步任务队列
     Arc<AtomicUsize>,                               //所有待处理任务数量，只包括所有接收队列的任务数量
     Option<AsyncTaskTimer>,                         //本地定时器
+    Option<Arc<RuntimeStats>>,                      //运行时统计
+    Arc<AtomicUsize>,                               //运行时状态
+    AtomicUsize,                                    //已派发但未完成的任务数量
+    Mutex<Vec<thread::JoinHandle<()>>>,             //运行时线程句柄
+    Vec<Mutex<HashMap<usize, Arc<MultiTask<O>>>>>,  //每个工作者已派发但未完成的任务表，用于在关闭时释放挂起的任务
+    AtomicUsize,                                    //已完成的任务数量
 )>);
 
 unsafe impl<O: Default + 'static> Send for MultiTaskRuntime<O> {}
@@ -229,16 +255,84 @@ impl<O: Default + 'static> MultiTaskRuntime<O> {
         len
     }
 
+    //检查当前运行时是否已开始关闭
+    pub fn is_shutdown(&self) -> bool {
+        (self.0).6.load(Ordering::Relaxed) != RUNTIME_RUNNING
+    }
+
+    //获取当前运行时已派发但未完成的任务数量
+    pub fn alive_len(&self) -> usize {
+        (self.0).7.load(Ordering::Relaxed)
+    }
+
+    //检查当前运行时是否有本地定时器
+    pub fn is_enable_timer(&self) -> bool {
+        (self.0).4.is_some()
+    }
+
+    //检查当前运行时是否开启了运行时统计
+    pub fn is_enable_stats(&self) -> bool {
+        (self.0).5.is_some()
+    }
+
+    //获取当前运行时的统计快照，未开启运行时统计则返回空
+    pub fn stats(&self) -> Option<MultiTaskStats> {
+        let stats = (self.0).5.as_ref()?;
+
+        let mut workers = Vec::with_capacity(stats.workers.len());
+        for (index, worker) in stats.workers.iter().enumerate() {
+            //任务队列长度在读取时计算
+            let queue_len = (self.0).2[index].len();
+            let pending = worker.pending.lock()
+                .values()
+                .map(|(task_id, poll_count)| (task_id.clone(), *poll_count))
+                .collect();
+
+            workers.push(MultiWorkerStats {
+                index,
+                queue_len,
+                poll_count: worker.poll_count.get(),
+                complete_count: worker.complete_count.get(),
+                steal_count: worker.steal_count.get(),
+                steal_task_count: worker.steal_task_count.get(),
+                poll_time: Duration::from_micros(worker.poll_time.get() as u64),
+                max_poll_time: Duration::from_micros(worker.max_poll_time.get() as u64),
+                pending,
+            });
+        }
+
+        Some(MultiTaskStats {
+            spawn_count: stats.spawn_count.get(),
+            complete_count: workers.iter().map(|worker| worker.complete_count).sum(),
+            poll_count: workers.iter().map(|worker| worker.poll_count).sum(),
+            workers,
+        })
+    }
+
     //分配异步任务的唯一id
     pub fn alloc(&self) -> TaskId {
         TaskId(Arc::new(AtomicUsize::new(0)))
     }
 
-    //派发一个指定的异步任务到异步多线程运行时
+    //派发一个指定的异步任务到异步多线程运行时，运行时关闭中时只允许当前运行时的任务派发，以保证已派发的任务可以继续派发辅助任务
     pub fn spawn<F>(&self, task_id: TaskId, future: F) -> Result<()>
         where F: Future<Output = O> + Send + 'static {
+        (self.0).7.fetch_add(1, Ordering::SeqCst);
+        match (self.0).6.load(Ordering::SeqCst) {
+            RUNTIME_RUNNING => (),
+            RUNTIME_DRAINING if self.is_worker_thread() => (),
+            _ => {
+                //运行时已开始关闭，则不再接受外部派发的任务
+                (self.0).7.fetch_sub(1, Ordering::SeqCst);
+                return Err(Error::new(ErrorKind::Other, "multi thread runtime already shutdown"));
+            },
+        }
+
         let queues = &(self.0).2;
         let queues_len = queues.len();
+        if let Some(stats) = (self.0).5.as_ref() {
+            stats.spawn_count.sum(1);
+        }
 
         let _ = THREAD_LOCAL_ID.try_with(move |id| {
             let thread_id = unsafe { *id.get() };
@@ -246,6 +340,7 @@ impl<O: Default + 'static> MultiTaskRuntime<O> {
                 //当前派发线程，是当前运行时线程，则派发任务到当前运行时线程的任务队列
                 let queue = &queues[(thread_id & 0xff) - 1];
                 let task = Arc::new(MultiTask::new(task_id, queue.clone(), Some(Box::new(future).boxed())));
+                self.register(&task);
 
                 if let Some(last_task) = queue.try_push_back(task) {
                     //尝试当前队列发送缓冲区尾推送失败，则更换到当前队列的接收队列尾
@@ -257,15 +352,15 @@ impl<O: Default + 'static> MultiTaskRuntime<O> {
                 let mut index: usize = (self.0).1.fetch_add(1, Ordering::Relaxed) % (self.0).2.len(); //随机选择一个线程的队列
                 let queue = &queues[index];
                 let mut task = Arc::new(MultiTask::new(task_id, queue.clone(), Some(Box::new(future).boxed())));
+                self.register(&task);
 
                 loop {
                     if let Some(last_task) = queue.try_push_back_notify(task) {
-                        //尝试当前队列推送失败，则更换到其它队列
-                        task = last_task;
+                        //尝试当前队列推送失败，则更换到其它队列，任务已在任务表中，所以需要用新的任务替换
                         index += 1;
-                        if let Some(r) = Arc::get_mut(&mut task) {
-                            r.queue = queues[m - index % queues_len].clone();
-                        }
+                        task = Arc::new(MultiTask::new(last_task.uid.clone(), queues[m - index % queues_len].clone(), last_task.get_inner()));
+                        self.unregister(&last_task);
+                        self.register(&task);
                         continue;
                     } else {
                         //尝试当前队列推送成功，则立即退出
@@ -278,6 +373,16 @@ impl<O: Default + 'static> MultiTaskRuntime<O> {
         Ok(())
     }
 
+    //派发一个指定的异步任务到异步多线程运行时，并返回用于等待异步任务返回值或中止异步任务的句柄
+    pub fn spawn_with_handle<V, F>(&self, task_id: TaskId, future: F) -> Result<JoinHandle<V>>
+        where V: Send + 'static,
+              F: Future<Output = V> + Send + 'static {
+        let (task, handle) = JoinTask::<O, V>::new(future);
+        self.spawn(task_id, task)?;
+
+        Ok(handle)
+    }
+
     //挂起指定唯一id的异步任务
     pub fn pending<Output>(&self, task_id: &TaskId, waker: Waker) -> Poll<Output> {
         task_id.0.store(Box::into_raw(Box::new(waker)) as usize, Ordering::Relaxed);
@@ -286,7 +391,8 @@ impl<O: Default + 'static> MultiTaskRuntime<O> {
 
     //唤醒执行指定唯一id的异步任务
     pub fn wakeup(&self, task_id: &TaskId) {
-        match task_id.0.load(Ordering::Relaxed) {
+        //取出唤醒器后清空，防止重复唤醒或释放时再次释放唤醒器
+        match task_id.0.swap(0, Ordering::SeqCst) {
             0 => panic!("Multi runtime wakeup task failed, reason: task id not exist"),
             ptr => {
                 unsafe {
@@ -297,6 +403,102 @@ impl<O: Default + 'static> MultiTaskRuntime<O> {
         }
     }
 
+    //关闭当前运行时，不再接受新的任务，并在指定期限内等待已派发的任务完成，到达期限后释放未完成的任务，并等待所有运行时线程结束，单位ms
+    pub fn shutdown(&self, deadline: u64) -> Result<ShutdownReport> {
+        let rt_uid = (self.0).0;
+        if self.is_worker_thread() {
+            //在当前运行时的工作者线程中关闭，则立即返回错误原因
+            return Err(Error::new(ErrorKind::Other, "shutdown multi thread runtime failed, reason: shutdown in worker thread"));
+        }
+
+        if (self.0).6.compare_exchange(RUNTIME_RUNNING,
+                                       RUNTIME_DRAINING,
+                                       Ordering::SeqCst,
+                                       Ordering::SeqCst).is_err() {
+            //已开始关闭，则立即返回错误原因
+            return Err(Error::new(ErrorKind::Other, "shutdown multi thread runtime failed, reason: already shutdown"));
+        }
+
+        //在指定期限内等待已派发的任务完成
+        let start = Instant::now();
+        let limit = start + Duration::from_millis(deadline);
+        let completed = (self.0).10.load(Ordering::SeqCst);
+        while (self.0).7.load(Ordering::SeqCst) > 0 && Instant::now() < limit {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        //设置运行时为已关闭，并唤醒所有休眠的工作者
+        (self.0).6.store(RUNTIME_STOPPED, Ordering::SeqCst);
+        for queue in (self.0).2.iter() {
+            let (lock, cvar) = &*queue.worker_waker;
+            let mut status = lock.lock();
+            *status = true;
+            cvar.notify_one();
+        }
+
+        //等待所有运行时线程结束
+        let handles: Vec<thread::JoinHandle<()>> = (self.0).8.lock().drain(..).collect();
+        let mut joined = 0;
+        for handle in handles {
+            if let Err(e) = handle.join() {
+                warn!("Multi runtime shutdown join thread failed, runtime: {:?}, reason: {:?}", rt_uid, e);
+                continue;
+            }
+            joined += 1;
+        }
+
+        //所有运行时线程已结束，则释放所有未完成的任务，包括在任务队列中的任务和挂起的任务
+        let tasks: Vec<Arc<MultiTask<O>>> = (self.0).9
+            .iter()
+            .flat_map(|tasks| tasks.lock().drain().map(|(_, task)| task).collect::<Vec<_>>())
+            .collect();
+        let mut dropped = 0;
+        for task in tasks {
+            if let Some(future) = task.get_inner() {
+                //释放任务的内部任务，释放时唤醒其它任务会被忽略
+                drop(future);
+                (self.0).7.fetch_sub(1, Ordering::SeqCst);
+                dropped += 1;
+            }
+        }
+
+        Ok(ShutdownReport {
+            completed: (self.0).10.load(Ordering::SeqCst) - completed,
+            dropped,
+            joined,
+            elapsed: start.elapsed(),
+        })
+    }
+
+    //检查当前线程是否是当前运行时的工作者线程
+    fn is_worker_thread(&self) -> bool {
+        let rt_uid = (self.0).0;
+        if let Ok(true) = THREAD_LOCAL_ID.try_with(move |id| {
+            rt_uid == (unsafe { *id.get() } >> 8 & 0xff)
+        }) {
+            return true;
+        }
+
+        false
+    }
+
+    //将指定任务加入任务所在工作者的未完成的任务表，每个工作者的任务表独立加锁，工作者之间不会竞争
+    #[inline]
+    fn register(&self, task: &Arc<MultiTask<O>>) {
+        (self.0).9[(task.queue.id & 0xff) - 1].lock().insert(registry_key(task), task.clone());
+    }
+
+    //将指定任务从任务所在工作者的未完成的任务表中移除
+    #[inline]
+    fn unregister(&self, task: &Arc<MultiTask<O>>) {
+        (self.0).9[(task.queue.id & 0xff) - 1].lock().remove(&registry_key(task));
+    }
+
+    //获取本地定时器的定时任务生产者和定时任务取消者
+    pub(crate) fn timer_producor(&self) -> Option<(Sender<(usize, TaskId)>, Sender<TaskId>)> {
+        (self.0).4.as_ref().map(|timer| (timer.get_producor(), timer.get_canceller()))
+    }
+
     //构建用于派发多个异步任务到指定运行时的映射
     pub fn map<V: Send + 'static>(&self) -> AsyncMap<O, V> {
         let (producor, consumer) = unbounded();
@@ -325,6 +527,23 @@ impl<O: Default + 'static> MultiTaskRuntime<O> {
         }
     }
 
+    //挂起当前多线程运行时的当前任务，由本地定时器休眠指定的时间后唤醒当前任务，单位ms
+    pub fn sleep(&self, timeout: usize) -> AsyncSleep<O> {
+        AsyncSleep::new(AsyncRuntime::Multi(self.clone()), timeout)
+    }
+
+    //构建由当前多线程运行时的本地定时器驱动的定时间隔流，单位ms
+    pub fn interval(&self, period: usize) -> AsyncInterval<O> {
+        AsyncInterval::new(AsyncRuntime::Multi(self.clone()), period)
+    }
+
+    //等待指定的异步任务，在指定时间内未完成则释放此异步任务，并返回超时错误，单位ms
+    pub fn timeout<V, F>(&self, timeout: usize, future: F) -> AsyncTimeout<O, V>
+        where V: Send + 'static,
+              F: Future<Output = V> + Send + 'static {
+        AsyncTimeout::new(AsyncRuntime::Multi(self.clone()), timeout, future)
+    }
+
     //挂起当前多线程运行时的当前任务，并在指定的其它运行时上派发一个指定的异步任务，等待其它运行时上的异步任务完成后，唤醒当前运行时的当前任务，并返回其它运行时上的异步任务的值
     pub async fn wait<R, V, F>(&self, rt: AsyncRuntime<R>, future: F) -> Result<V>
         where R: Default + 'static,
@@ -357,7 +576,17 @@ unsafe impl<O: Default + 'static> Sync for MultiTaskPool<O> {}
 
 impl<O: Default + 'static> MultiTaskPool<O> {
     //构建指定线程名前缀、线程数量、线程栈大小、线程空闲时最长休眠时间和是否使用本地定时器的多线程任务池
-    pub fn new(prefix: String, mut size: usize, stack_size: usize, timeout: u64, interval: Option<u64>) -> Self {
+    pub fn new(prefix: String, size: usize, stack_size: usize, timeout: u64, interval: Option<u64>) -> Self {
+        Self::with_stats(prefix, size, stack_size, timeout, interval, false)
+    }
+
+    //构建开启了运行时统计的多线程任务池，运行时统计的计数器会以线程名前缀和运行时唯一id命名，开启apm_collect特性时会同时导出到全局并发性能采集
+    pub fn new_with_stats(prefix: String, size: usize, stack_size: usize, timeout: u64, interval: Option<u64>) -> Self {
+        Self::with_stats(prefix, size, stack_size, timeout, interval, true)
+    }
+
+    //构建指定是否开启运行时统计的多线程任务池
+    fn with_stats(prefix: String, mut size: usize, stack_size: usize, timeout: u64, interval: Option<u64>, is_stats: bool) -> Self {
         if size == 0 {
             //如果线程太少，则设置至少1个线程
             size = 1;
@@ -375,6 +604,7 @@ impl<O: Default + 'static> MultiTaskPool<O> {
         let rt_uid = alloc_rt_uid();
         let mut queues = Vec::with_capacity(size);
         let counter = Arc::new(AtomicUsize::new(0));
+        let status = Arc::new(AtomicUsize::new(RUNTIME_RUNNING));
         for index in 0..size {
             let (producer, consumer) = steal_deque();
             let worker_waker = Arc::new((Mutex::new(false), Condvar::new()));
@@ -384,6 +614,7 @@ impl<O: Default + 'static> MultiTaskPool<O> {
                 producer,
                 worker_waker,
                 recv_counter: counter.clone(),
+                status: status.clone(),
             });
             queues.push(queue);
         }
@@ -398,6 +629,13 @@ impl<O: Default + 'static> MultiTaskPool<O> {
             (None, None)
         };
 
+        //构建运行时统计
+        let stats = if is_stats {
+            Some(Arc::new(RuntimeStats::new(&prefix, rt_uid, size)))
+        } else {
+            None
+        };
+
         //构建多线程任务运行时
         let runtime = MultiTaskRuntime(Arc::new((
             rt_uid,
@@ -405,6 +643,12 @@ impl<O: Default + 'static> MultiTaskPool<O> {
             Arc::new(queues),
             counter,
             timer,
+            stats,
+            status,
+            AtomicUsize::new(0),
+            Mutex::new(Vec::with_capacity(size + 1)),
+            (0..size).map(|_| Mutex::new(HashMap::new())).collect(),
+            AtomicUsize::new(0),
         )));
 
         MultiTaskPool {
@@ -427,9 +671,11 @@ impl<O: Default + 'static> MultiTaskPool<O> {
             //启动本地定时器
             let runtime = self.runtime.clone();
             let interval = self.interval.unwrap();
-            builer.spawn(move || {
+            if let Ok(handle) = builer.spawn(move || {
                 timer_loop(runtime, interval);
-            });
+            }) {
+                (self.runtime.0).8.lock().push(handle);
+            }
         }
 
         //启动工作线程
@@ -437,9 +683,11 @@ impl<O: Default + 'static> MultiTaskPool<O> {
             let builder = self.builders.remove(0);
             let runtime = self.runtime.clone();
             let timeout = self.timeout;
-            let _ = builder.spawn(move || {
+            if let Ok(handle) = builder.spawn(move || {
                 work_loop(runtime, index, enable_steal, timeout);
-            });
+            }) {
+                (self.runtime.0).8.lock().push(handle);
+            }
         }
 
         self.runtime
@@ -448,7 +696,7 @@ impl<O: Default + 'static> MultiTaskPool<O> {
 
 //定时器循环
 fn timer_loop<O: Default + 'static>(runtime: MultiTaskRuntime<O>, interval: u64) {
-    loop {
+    while (runtime.0).6.load(Ordering::Relaxed) != RUNTIME_STOPPED {
         //设置新的定时任务，并唤醒已过期的定时任务
         (runtime.0).4.as_ref().unwrap().consume();
         for expired in &(runtime.0).4.as_ref().unwrap().poll() {
@@ -482,6 +730,12 @@ fn work_loop<O: Default + 'static>(runtime: MultiTaskRuntime<O>,
 
     let counter = &(runtime.0).3;
     loop {
+        if queue.is_stopped() {
+            //运行时已关闭，则清空当前工作者队列，并结束工作，任务的内部任务由关闭运行时的线程统一释放
+            while let Some(_) = queue.consumer.try_recv(counter) {}
+            break;
+        }
+
         match queue.consumer.try_recv(counter) {
             None => {
                 //当前没有任务
@@ -545,6 +799,7 @@ fn try_steal_task<O: Default + 'static>(runtime: &MultiTaskRuntime<O>, queue: &A
                 if buf.len() > 0 {
                     // println!("!!!!!!{:?} steal sent buf ok from {:?}, len: {:?}", queue.id, index + 1, buf.len());
                     //再次确认发送缓冲区不为空，则将窃取的任务加入发送缓冲区尾部，并立即结束本次窃取
+                    record_steal(runtime, ignore_index, buf.len());
                     queue.producer.append(&mut buf);
                     return true;
                 }
@@ -558,6 +813,7 @@ fn try_steal_task<O: Default + 'static>(runtime: &MultiTaskRuntime<O>, queue: &A
                 if deque.len() > 0 {
                     // println!("!!!!!!{:?} steal recv deque ok from {:?}, len: {:?}", queue.id, index + 1, deque.len());
                     //再次确认接收队列不为空，则将窃取的任务加入发送缓冲区尾部，并立即结束本次窃取
+                    record_steal(runtime, ignore_index, deque.len());
                     queue.producer.append(&mut deque.into());
                     return true;
                 }
@@ -577,7 +833,14 @@ fn run_task<O: Default + 'static>(runtime: &MultiTaskRuntime<O>, queue: &Arc<Mul
     if task.queue.id != queue.id {
         if let Some(future) = task.get_inner() {
             //当前任务是窃取的任务，则替换此任务的id和队列
+            if let Some(stats) = (runtime.0).5.as_ref() {
+                //窃取的任务不再挂起在原工作者上
+                stats.workers[(task.queue.id & 0xff) - 1].pending.lock().remove(&task_key(&task.uid));
+            }
+            let stolen = task;
             task = Arc::new(MultiTask::new(runtime.alloc(), queue.clone(), Some(future)));
+            runtime.unregister(&stolen);
+            runtime.register(&task);
         } else {
             //窃取的任务的内部任务还未恢复，则将此任务放回发送缓冲区尾
             let _ = queue.push_back(task);
@@ -588,9 +851,274 @@ fn run_task<O: Default + 'static>(runtime: &MultiTaskRuntime<O>, queue: &Arc<Mul
     let waker = waker_ref(&task);
     let mut context = Context::from_waker(&*waker);
     if let Some(mut future) = task.get_inner() {
+        if let Some(stats) = (runtime.0).5.as_ref() {
+            //已开启运行时统计，则统计本次轮询
+            let worker = &stats.workers[(queue.id & 0xff) - 1];
+            let start = worker.poll_time.start();
+            let r = future.as_mut().poll(&mut context);
+            worker.record_poll(&task.uid, start, r.is_pending());
+            if r.is_pending() {
+                //当前未准备好，则恢复异步任务，以保证异步服务后续访问异步任务和异步任务不被提前释放
+                task.set_inner(Some(future));
+            } else {
+                complete_task(runtime, &task);
+            }
+            return;
+        }
+
         if let Poll::Pending = future.as_mut().poll(&mut context) {
             //当前未准备好，则恢复异步任务，以保证异步服务后续访问异步任务和异步任务不被提前释放
             task.set_inner(Some(future));
+        } else {
+            complete_task(runtime, &task);
         }
     }
 }
+
+//完成指定的任务，从未完成的任务表中移除，并减少未完成的任务数量
+fn complete_task<O: Default + 'static>(runtime: &MultiTaskRuntime<O>, task: &Arc<MultiTask<O>>) {
+    runtime.unregister(task);
+    (runtime.0).10.fetch_add(1, Ordering::SeqCst);
+    (runtime.0).7.fetch_sub(1, Ordering::SeqCst);
+}
+
+//记录指定工作者的窃取
+fn record_steal<O: Default + 'static>(runtime: &MultiTaskRuntime<O>, index: usize, len: usize) {
+    if let Some(stats) = (runtime.0).5.as_ref() {
+        let worker = &stats.workers[index];
+        worker.steal_count.sum(1);
+        worker.steal_task_count.sum(len);
+    }
+}
+
+//获取指定任务唯一id在挂起任务表中的键
+#[inline]
+fn task_key(task_id: &TaskId) -> usize {
+    &*task_id.0 as *const AtomicUsize as usize
+}
+
+//获取指定任务在未完成的任务表中的键
+#[inline]
+fn registry_key<O: Default + 'static>(task: &Arc<MultiTask<O>>) -> usize {
+    &**task as *const MultiTask<O> as usize
+}
+
+/*
+* 多线程运行时统计
+*/
+struct RuntimeStats {
+    spawn_count:    StatsCounter,       //已派发的任务数量
+    workers:        Vec<WorkerStats>,   //所有工作者的统计
+}
+
+impl RuntimeStats {
+    //构建指定线程名前缀、运行时唯一id和工作者数量的运行时统计，相同线程名前缀的运行时不会共享计数器
+    fn new(prefix: &str, rt_uid: usize, size: usize) -> Self {
+        let prefix = prefix.to_string() + "#" + rt_uid.to_string().as_str();
+        let spawn_count = StatsCounter::new(&prefix, "spawn_count");
+
+        let mut workers = Vec::with_capacity(size);
+        for index in 0..size {
+            let worker_prefix = prefix.to_string() + "-" + index.to_string().as_str();
+            workers.push(WorkerStats {
+                poll_count: StatsCounter::new(&worker_prefix, "poll_count"),
+                complete_count: StatsCounter::new(&worker_prefix, "complete_count"),
+                steal_count: StatsCounter::new(&worker_prefix, "steal_count"),
+                steal_task_count: StatsCounter::new(&worker_prefix, "steal_task_count"),
+                poll_time: StatsTimer::new(&worker_prefix, "poll_time"),
+                max_poll_time: StatsCounter::new(&worker_prefix, "max_poll_time"),
+                pending: Mutex::new(HashMap::new()),
+            });
+        }
+
+        RuntimeStats {
+            spawn_count,
+            workers,
+        }
+    }
+}
+
+/*
+* 运行时统计计数器，统计值总是保存在本地，开启apm_collect特性时同时导出到全局并发性能采集的动态计数器
+*/
+struct StatsCounter {
+    count:  AtomicUsize,            //计数
+    #[cfg(feature = "apm_collect")]
+    pref:   Option<PrefCounter>,    //导出的动态计数器，全局并发性能采集已满则为空
+}
+
+impl StatsCounter {
+    //构建指定前缀和名称的计数器
+    #[allow(unused_variables)]
+    fn new(prefix: &str, name: &str) -> Self {
+        StatsCounter {
+            count: AtomicUsize::new(0),
+            #[cfg(feature = "apm_collect")]
+            pref: export_counter(prefix, name),
+        }
+    }
+
+    //获取
+    fn get(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    //设置
+    fn set(&self, count: usize) {
+        self.count.store(count, Ordering::Relaxed);
+
+        #[cfg(feature = "apm_collect")]
+        if let Some(pref) = &self.pref {
+            pref.set(count);
+        }
+    }
+
+    //累加
+    fn sum(&self, count: usize) {
+        self.count.fetch_add(count, Ordering::Relaxed);
+
+        #[cfg(feature = "apm_collect")]
+        if let Some(pref) = &self.pref {
+            pref.sum(count);
+        }
+    }
+}
+
+/*
+* 运行时统计计时器，单位us，统计值总是保存在本地，开启apm_collect特性时同时导出到全局并发性能采集的动态计时器
+*/
+struct StatsTimer {
+    time:   AtomicUsize,        //累计耗时
+    #[cfg(feature = "apm_collect")]
+    pref:   Option<PrefTimer>,  //导出的动态计时器，全局并发性能采集已满则为空
+}
+
+impl StatsTimer {
+    //构建指定前缀和名称的计时器
+    #[allow(unused_variables)]
+    fn new(prefix: &str, name: &str) -> Self {
+        StatsTimer {
+            time: AtomicUsize::new(0),
+            #[cfg(feature = "apm_collect")]
+            pref: export_timer(prefix, name),
+        }
+    }
+
+    //获取
+    fn get(&self) -> usize {
+        self.time.load(Ordering::Relaxed)
+    }
+
+    //开始计时
+    fn start(&self) -> Instant {
+        Instant::now()
+    }
+
+    //计时
+    fn timing(&self, start: Instant) {
+        self.time.fetch_add((Instant::now() - start).as_micros() as usize, Ordering::Relaxed);
+
+        #[cfg(feature = "apm_collect")]
+        if let Some(pref) = &self.pref {
+            pref.timing(start);
+        }
+    }
+}
+
+//在全局并发性能采集上构建指定前缀和名称的动态计数器，已满则只在本地统计
+#[cfg(feature = "apm_collect")]
+fn export_counter(prefix: &str, name: &str) -> Option<PrefCounter> {
+    let counter = GLOBAL_PREF_COLLECT.new_dynamic_counter(Atom::from(prefix.to_string() + "_" + name), 0);
+    if counter.is_none() {
+        warn!("Multi runtime export stats failed, prefix: {:?}, name: {:?}, reason: pref collect full", prefix, name);
+    }
+    counter
+}
+
+//在全局并发性能采集上构建指定前缀和名称的动态计时器，已满则只在本地统计
+#[cfg(feature = "apm_collect")]
+fn export_timer(prefix: &str, name: &str) -> Option<PrefTimer> {
+    let timer = GLOBAL_PREF_COLLECT.new_dynamic_timer(Atom::from(prefix.to_string() + "_" + name), 0);
+    if timer.is_none() {
+        warn!("Multi runtime export stats failed, prefix: {:?}, name: {:?}, reason: pref collect full", prefix, name);
+    }
+    timer
+}
+
+/*
+* 多线程工作者统计，除挂起任务表以外，只由工作者线程更新
+*/
+struct WorkerStats {
+    poll_count:         StatsCounter,                       //已轮询的次数
+    complete_count:     StatsCounter,                       //已完成的任务数量
+    steal_count:        StatsCounter,                       //成功窃取的次数
+    steal_task_count:   StatsCounter,                       //窃取的任务数量
+    poll_time:          StatsTimer,                         //轮询总耗时，单位us
+    max_poll_time:      StatsCounter,                       //单次轮询的最长耗时，单位us
+    pending:            Mutex<HashMap<usize, (TaskId, usize)>>, //挂起的任务唯一id和已轮询次数
+}
+
+impl WorkerStats {
+    //记录一次轮询
+    fn record_poll(&self, task_id: &TaskId, start: Instant, is_pending: bool) {
+        let time = (Instant::now() - start).as_micros() as usize;
+        self.poll_time.timing(start);
+        if time > self.max_poll_time.get() {
+            self.max_poll_time.set(time);
+        }
+        self.poll_count.sum(1);
+
+        let key = task_key(task_id);
+        if is_pending {
+            //任务挂起，则更新挂起任务的轮询次数
+            self.pending.lock().entry(key).or_insert_with(|| (task_id.clone(), 0)).1 += 1;
+        } else {
+            //任务完成，则从挂起任务表中移除
+            self.complete_count.sum(1);
+            self.pending.lock().remove(&key);
+        }
+    }
+}
+
+/*
+* 多线程运行时统计快照
+*/
+#[derive(Debug, Clone)]
+pub struct MultiTaskStats {
+    pub spawn_count:    usize,                  //已派发的任务数量
+    pub complete_count: usize,                  //已完成的任务数量
+    pub poll_count:     usize,                  //已轮询的次数
+    pub workers:        Vec<MultiWorkerStats>,  //所有工作者的统计快照
+}
+
+impl MultiTaskStats {
+    //获取平均每个任务的轮询次数
+    pub fn polls_per_task(&self) -> f64 {
+        if self.spawn_count == 0 {
+            return 0.0;
+        }
+
+        self.poll_count as f64 / self.spawn_count as f64
+    }
+
+    //获取所有工作者当前挂起的任务数量
+    pub fn pending_len(&self) -> usize {
+        self.workers.iter().map(|worker| worker.pending.len()).sum()
+    }
+}
+
+/*
+* 多线程工作者统计快照
+*/
+#[derive(Debug, Clone)]
+pub struct MultiWorkerStats {
+    pub index:              usize,                  //工作者序号
+    pub queue_len:          usize,                  //任务队列长度，不精确
+    pub poll_count:         usize,                  //已轮询的次数
+    pub complete_count:     usize,                  //已完成的任务数量
+    pub steal_count:        usize,                  //成功窃取的次数，只有允许窃取时才会窃取
+    pub steal_task_count:   usize,                  //窃取的任务数量
+    pub poll_time:          Duration,               //轮询总耗时
+    pub max_poll_time:      Duration,               //单次轮询的最长耗时
+    pub pending:            Vec<(TaskId, usize)>,   //当前挂起的任务唯一id和已轮询次数
+}
@@ -1,17 +1,47 @@
+use std::thread;
 use std::sync::Arc;
 use std::future::Future;
-use std::cell::UnsafeCell;
+use std::cell::{Cell, UnsafeCell};
+use std::collections::HashMap;
+use std::time::{Instant, Duration};
 use std::task::{Waker, Context, Poll};
 use std::io::{Error, Result, ErrorKind};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use crossbeam_channel::{Sender, Receiver, unbounded};
+use parking_lot::Mutex;
 use futures::{future::{FutureExt, BoxFuture}, task::{ArcWake, waker_ref}};
 
 use crate::AsyncTask;
-use super::{TaskId, AsyncRuntime, AsyncTaskTimer, AsyncWaitTimeout, AsyncWait, AsyncWaitAny, AsyncMap, alloc_rt_uid};
+use super::{TaskId, JoinTask, JoinHandle, AsyncRuntime, AsyncTaskTimer, AsyncWaitTimeout, AsyncSleep, AsyncInterval, AsyncTimeout, AsyncWait, AsyncWaitAny, AsyncMap, ShutdownReport, RUNTIME_RUNNING, RUNTIME_DRAINING, RUNTIME_STOPPED, alloc_rt_uid};
 use crate::rt::AsyncWaitResult;
 
+/*
+* 当前线程正在运行的单线程运行时唯一id，没有运行则为0
+*/
+thread_local! {
+    static THREAD_LOCAL_RUNNING_RT: Cell<usize> = Cell::new(0);
+}
+
+/*
+* 当前线程正在运行的单线程运行时的守护者，释放时恢复之前正在运行的运行时唯一id，任务执行时恐慌也会恢复
+*/
+struct RunningGuard(usize);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        let last = self.0;
+        let _ = THREAD_LOCAL_RUNNING_RT.try_with(move |running| running.set(last));
+    }
+}
+
+impl RunningGuard {
+    //标记当前线程正在运行指定唯一id的运行时，并返回守护者
+    fn new(rt_uid: usize) -> Self {
+        RunningGuard(THREAD_LOCAL_RUNNING_RT.with(move |running| running.replace(rt_uid)))
+    }
+}
+
 /*
 * 单线程任务
 */
@@ -26,6 +56,11 @@ unsafe impl<O: Default + 'static> Sync for SingleTask<O> {}
 
 impl<O: Default + 'static> ArcWake for SingleTask<O> {
     fn wake_by_ref(arc_self: &Arc<Self>) {
+        if arc_self.queue.status.load(Ordering::Relaxed) == RUNTIME_STOPPED {
+            //任务所在运行时已关闭，则忽略唤醒
+            return;
+        }
+
         let _ = arc_self.queue.push_back(arc_self.clone());
     }
 }
@@ -65,6 +100,7 @@ pub struct SingleTasks<O: Default + 'static> {
     id:             usize,                          //绑定的线程唯一id
     consumer:       Receiver<Arc<SingleTask<O>>>,   //任务消费者
     producer:       Sender<Arc<SingleTask<O>>>,     //任务生产者
+    status:         Arc<AtomicUsize>,               //运行时状态
 }
 
 unsafe impl<O: Default + 'static> Send for SingleTasks<O> {}
@@ -76,6 +112,7 @@ impl<O: Default + 'static> Clone for SingleTasks<O> {
             id: self.id,
             consumer: self.consumer.clone(),
             producer: self.producer.clone(),
+            status: self.status.clone(),
         }
     }
 }
@@ -104,6 +141,10 @@ pub struct SingleTaskRuntime<O: Default + 'static>(Arc<(
     usize,                                              //运行时唯一id
     Arc<SingleTasks<O>>,                                //异步任务队列
     AsyncTaskTimer,                                     //本地定时器
+    Arc<AtomicUsize>,                                   //运行时状态
+    AtomicUsize,                                        //已派发但未完成的任务数量
+    Mutex<HashMap<usize, Arc<SingleTask<O>>>>,          //已派发但未完成的任务表，用于在关闭时释放挂起的任务
+    AtomicUsize,                                        //已完成的任务数量
 )>);
 
 unsafe impl<O: Default + 'static> Send for SingleTaskRuntime<O> {}
@@ -134,23 +175,58 @@ impl<O: Default + 'static> SingleTaskRuntime<O> {
         (self.0).1.len()
     }
 
+    //检查当前运行时是否已开始关闭
+    pub fn is_shutdown(&self) -> bool {
+        (self.0).3.load(Ordering::Relaxed) != RUNTIME_RUNNING
+    }
+
+    //获取当前运行时已派发但未完成的任务数量
+    pub fn alive_len(&self) -> usize {
+        (self.0).4.load(Ordering::Relaxed)
+    }
+
     //分配异步任务的唯一id
     pub fn alloc(&self) -> TaskId {
         TaskId(Arc::new(AtomicUsize::new(0)))
     }
 
-    //派发一个指定的异步任务到异步单线程运行时
+    //派发一个指定的异步任务到异步单线程运行时，运行时关闭中时只允许当前运行时的任务派发，以保证已派发的任务可以继续派发辅助任务
     pub fn spawn<F>(&self, task_id: TaskId, future: F) -> Result<()>
         where F: Future<Output = O> + Send + 'static {
+        (self.0).4.fetch_add(1, Ordering::SeqCst);
+        match (self.0).3.load(Ordering::SeqCst) {
+            RUNTIME_RUNNING => (),
+            RUNTIME_DRAINING if self.is_running_thread() => (),
+            _ => {
+                //运行时已开始关闭，则不再接受外部派发的任务
+                (self.0).4.fetch_sub(1, Ordering::SeqCst);
+                return Err(Error::new(ErrorKind::Other, "single thread runtime already shutdown"));
+            },
+        }
+
         let queue = (self.0).1.clone();
         let boxed = Box::new(future).boxed();
-        if let Err(e) = (self.0).1.push_back(Arc::new(SingleTask::new(task_id, queue, Some(boxed)))) {
+        let task = Arc::new(SingleTask::new(task_id, queue, Some(boxed)));
+        self.register(&task);
+        if let Err(e) = (self.0).1.push_back(task.clone()) {
+            self.unregister(&task);
+            (self.0).4.fetch_sub(1, Ordering::SeqCst);
             return Err(Error::new(ErrorKind::Other, e));
         }
 
         Ok(())
     }
 
+    //派发一个指定的异步任务到异步单线程运行时，并返回用于等待异步任务返回值或中止异步任务的句柄
+    pub fn spawn_with_handle<V, F>(&self, task_id: TaskId, future: F) -> Result<JoinHandle<V>>
+        where V: Send + 'static,
+              F: Future<Output = V> + Send + 'static {
+        let (task, handle) = JoinTask::<O, V>::new(future);
+        self.spawn(task_id, task)?;
+
+        Ok(handle)
+    }
+
     //挂起指定唯一id的异步任务
     pub fn pending<Output>(&self, task_id: &TaskId, waker: Waker) -> Poll<Output> {
         task_id.0.store(Box::into_raw(Box::new(waker)) as usize, Ordering::Relaxed);
@@ -159,7 +235,8 @@ impl<O: Default + 'static> SingleTaskRuntime<O> {
 
     //唤醒指定唯一id的异步任务
     pub fn wakeup(&self, task_id: &TaskId) {
-        match task_id.0.load(Ordering::Relaxed) {
+        //取出唤醒器后清空，防止重复唤醒或释放时再次释放唤醒器
+        match task_id.0.swap(0, Ordering::SeqCst) {
             0 => panic!("Single runtime wakeup task failed, reason: task id not exist"),
             ptr => {
                 unsafe {
@@ -170,6 +247,35 @@ impl<O: Default + 'static> SingleTaskRuntime<O> {
         }
     }
 
+    //获取本地定时器的定时任务生产者和定时任务取消者
+    pub(crate) fn timer_producor(&self) -> Option<(Sender<(usize, TaskId)>, Sender<TaskId>)> {
+        Some(((self.0).2.get_producor(), (self.0).2.get_canceller()))
+    }
+
+    //检查当前线程是否正在运行当前运行时
+    fn is_running_thread(&self) -> bool {
+        let rt_uid = (self.0).0;
+        if let Ok(true) = THREAD_LOCAL_RUNNING_RT.try_with(move |running| {
+            running.get() == rt_uid
+        }) {
+            return true;
+        }
+
+        false
+    }
+
+    //将指定任务加入未完成的任务表
+    #[inline]
+    fn register(&self, task: &Arc<SingleTask<O>>) {
+        (self.0).5.lock().insert(registry_key(task), task.clone());
+    }
+
+    //将指定任务从未完成的任务表中移除
+    #[inline]
+    fn unregister(&self, task: &Arc<SingleTask<O>>) {
+        (self.0).5.lock().remove(&registry_key(task));
+    }
+
     //构建用于派发多个异步任务到指定运行时的映射
     pub fn map<V: Send + 'static>(&self) -> AsyncMap<O, V> {
         let (producor, consumer) = unbounded();
@@ -192,6 +298,23 @@ impl<O: Default + 'static> SingleTaskRuntime<O> {
         AsyncWaitTimeout::new(AsyncRuntime::Single(self.clone()), (self.0).2.get_producor(), timeout).await
     }
 
+    //挂起当前单线程运行时的当前任务，由本地定时器休眠指定的时间后唤醒当前任务，单位ms
+    pub fn sleep(&self, timeout: usize) -> AsyncSleep<O> {
+        AsyncSleep::new(AsyncRuntime::Single(self.clone()), timeout)
+    }
+
+    //构建由当前单线程运行时的本地定时器驱动的定时间隔流，单位ms
+    pub fn interval(&self, period: usize) -> AsyncInterval<O> {
+        AsyncInterval::new(AsyncRuntime::Single(self.clone()), period)
+    }
+
+    //等待指定的异步任务，在指定时间内未完成则释放此异步任务，并返回超时错误，单位ms
+    pub fn timeout<V, F>(&self, timeout: usize, future: F) -> AsyncTimeout<O, V>
+        where V: Send + 'static,
+              F: Future<Output = V> + Send + 'static {
+        AsyncTimeout::new(AsyncRuntime::Single(self.clone()), timeout, future)
+    }
+
     //挂起当前单线程运行时的当前任务，并在指定的其它运行时上派发一个指定的异步任务，等待其它运行时上的异步任务完成后，唤醒当前运行时的当前任务，并返回其它运行时上的异步任务的值
     pub async fn wait<R, V, F>(&self, rt: AsyncRuntime<R>, future: F) -> Result<V>
         where R: Default + 'static,
@@ -225,10 +348,12 @@ impl<O: Default + 'static> SingleTaskRunner<O> {
         //构建单线程任务队列
         let rt_uid = alloc_rt_uid();
         let (producer, consumer) = unbounded();
+        let status = Arc::new(AtomicUsize::new(RUNTIME_RUNNING));
         let queue = Arc::new(SingleTasks {
             id: (rt_uid << 8) & 0xffff | 1,
             consumer,
             producer,
+            status: status.clone(),
         });
 
         //构建本地定时器
@@ -239,6 +364,10 @@ impl<O: Default + 'static> SingleTaskRunner<O> {
             rt_uid,
             queue,
             timer,
+            status,
+            AtomicUsize::new(0),
+            Mutex::new(HashMap::new()),
+            AtomicUsize::new(0),
         )));
 
         SingleTaskRunner {
@@ -257,6 +386,11 @@ impl<O: Default + 'static> SingleTaskRunner<O> {
         Some(self.runtime.clone())
     }
 
+    //获取本地定时器中未到期的定时任务数量，需要在运行执行器的线程中调用
+    pub fn timer_len(&self) -> usize {
+        (self.runtime.0).2.len()
+    }
+
     //运行一次单线程异步任务执行器，返回当前任务队列中任务的数量
     pub fn run_once(&self) -> Result<usize> {
         if !self.is_running.load(Ordering::Relaxed) {
@@ -264,29 +398,105 @@ impl<O: Default + 'static> SingleTaskRunner<O> {
             return Err(Error::new(ErrorKind::Other, "single thread runtime not running"));
         }
 
+        if (self.runtime.0).3.load(Ordering::Relaxed) == RUNTIME_STOPPED {
+            //已关闭，则返回错误原因
+            return Err(Error::new(ErrorKind::Other, "single thread runtime already shutdown"));
+        }
+
         //设置新的定时任务，并唤醒已过期的定时任务
         (self.runtime.0).2.consume();
         for expired in &(self.runtime.0).2.poll() {
             self.runtime.wakeup(expired);
         }
 
-        //执行异步任务
+        //执行异步任务，执行期间标记当前线程正在运行当前运行时
+        let rt_uid = (self.runtime.0).0;
+        let _guard = RunningGuard::new(rt_uid);
         for task in (self.runtime.0).1.consumer.try_iter() {
-            run_task(task);
+            run_task(&self.runtime, task);
         }
 
         Ok((self.runtime.0).1.consumer.len())
     }
+
+    //关闭单线程异步任务执行器，不再接受新的任务，并在指定期限内继续运行已派发的任务，到达期限后释放未完成的任务，单位ms，需要在运行执行器的线程中调用
+    pub fn shutdown(&self, deadline: u64) -> Result<ShutdownReport> {
+        if !self.is_running.load(Ordering::Relaxed) {
+            //未启动，则返回错误原因
+            return Err(Error::new(ErrorKind::Other, "single thread runtime not running"));
+        }
+
+        if (self.runtime.0).3.compare_exchange(RUNTIME_RUNNING,
+                                               RUNTIME_DRAINING,
+                                               Ordering::SeqCst,
+                                               Ordering::SeqCst).is_err() {
+            //已开始关闭，则立即返回错误原因
+            return Err(Error::new(ErrorKind::Other, "shutdown single thread runtime failed, reason: already shutdown"));
+        }
+
+        //在指定期限内继续运行已派发的任务
+        let start = Instant::now();
+        let limit = start + Duration::from_millis(deadline);
+        let completed = (self.runtime.0).6.load(Ordering::SeqCst);
+        while (self.runtime.0).4.load(Ordering::SeqCst) > 0 && Instant::now() < limit {
+            if self.run_once()? == 0 {
+                //当前没有可运行的任务，则休眠后继续
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        let dropped = self.release();
+        Ok(ShutdownReport {
+            completed: (self.runtime.0).6.load(Ordering::SeqCst) - completed,
+            dropped,
+            joined: 0,
+            elapsed: start.elapsed(),
+        })
+    }
+
+    //设置运行时为已关闭，并释放所有未完成的任务，包括在任务队列中的任务和挂起的任务，释放时唤醒其它任务会被忽略，返回释放的任务数量
+    fn release(&self) -> usize {
+        (self.runtime.0).3.store(RUNTIME_STOPPED, Ordering::SeqCst);
+        for _ in (self.runtime.0).1.consumer.try_iter() {}
+        let tasks: Vec<Arc<SingleTask<O>>> = (self.runtime.0).5.lock().drain().map(|(_, task)| task).collect();
+        let mut dropped = 0;
+        for task in tasks {
+            if let Some(future) = task.get_inner() {
+                drop(future);
+                (self.runtime.0).4.fetch_sub(1, Ordering::SeqCst);
+                dropped += 1;
+            }
+        }
+        dropped
+    }
+}
+
+impl<O: Default + 'static> Drop for SingleTaskRunner<O> {
+    //释放执行器后运行时不会再运行，则释放所有未完成的任务，任务和唤醒器之间的循环引用也会随之断开
+    fn drop(&mut self) {
+        self.release();
+    }
 }
 
 //执行异步任务
-fn run_task<O: Default + 'static>(task: Arc<SingleTask<O>>) {
+fn run_task<O: Default + 'static>(runtime: &SingleTaskRuntime<O>, task: Arc<SingleTask<O>>) {
     let waker = waker_ref(&task);
     let mut context = Context::from_waker(&*waker);
     if let Some(mut future) = task.get_inner() {
         if let Poll::Pending = future.as_mut().poll(&mut context) {
             //当前未准备好，则恢复异步任务，以保证异步服务后续访问异步任务和异步任务不被提前释放
             task.set_inner(Some(future));
+        } else {
+            //任务已完成，则从未完成的任务表中移除，并减少未完成的任务数量
+            runtime.unregister(&task);
+            (runtime.0).6.fetch_add(1, Ordering::SeqCst);
+            (runtime.0).4.fetch_sub(1, Ordering::SeqCst);
         }
     }
+}
+
+//获取指定任务在未完成的任务表中的键
+#[inline]
+fn registry_key<O: Default + 'static>(task: &Arc<SingleTask<O>>) -> usize {
+    &**task as *const SingleTask<O> as usize
 }
\ No newline at end of file
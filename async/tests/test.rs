@@ -17,6 +17,7 @@ use std::sync::Arc;
 use std::future::Future;
 use std::collections::HashMap;
 use std::time::{Instant, Duration};
+use std::io::ErrorKind;
 use std::cell::{UnsafeCell, RefCell};
 use std::task::{Context, Poll, Waker};
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, AtomicU64, AtomicUsize, Ordering};
@@ -30,14 +31,23 @@ use dashmap::DashMap;
 use rand::prelude::*;
 use future_parking_lot::{mutex::{Mutex as FutureMutex, FutureLockable}, rwlock::{RwLock as FutureRwLock, FutureReadable, FutureWriteable}};
 use tokio::runtime::Builder as TokioRtBuilder;
+#[cfg(feature = "apm_collect")]
+use atom::Atom;
+#[cfg(feature = "apm_collect")]
+use apm::counter::GLOBAL_PREF_COLLECT;
 
 use r#async::{AsyncTask, AsyncExecutorResult, AsyncExecutor, AsyncSpawner,
               lock::{mpmc_deque::MpmcDeque,
                      mpsc_deque::mpsc_deque,
                      spin_lock::SpinLock,
                      mutex_lock::Mutex,
-                     rw_lock::RwLock},
-              rt::{TaskId, AsyncRuntime, AsyncValue,
+                     rw_lock::{RwLock, RwLockUpgradableReadGuard},
+                     semaphore::Semaphore,
+                     barrier::Barrier,
+                     notify::Notify,
+                     condvar::Condvar},
+              channel::{oneshot, mpsc, broadcast},
+              rt::{TaskId, AsyncRuntime, AsyncValue, JoinHandle,
                    single_thread::{SingleTask, SingleTaskRuntime, SingleTaskRunner},
                    multi_thread::{MultiTask, MultiTasks, MultiTaskRuntime, MultiTaskPool}},
               local_queue::{LocalQueueSpawner, LocalQueue}, task::LocalTask};
@@ -3270,4 +3280,797 @@ fn test_async_wait_all() {
     }
 
     thread::sleep(Duration::from_millis(100000000));
+}
+
+#[test]
+fn test_join_handle() {
+    let runner = SingleTaskRunner::new();
+    let rt = runner.startup().unwrap();
+
+    thread::spawn(move || {
+        loop {
+            if let Err(e) = runner.run_once() {
+                println!("!!!!!!run failed, reason: {:?}", e);
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    let pool = MultiTaskPool::<()>::new("AsyncRuntime0".to_string(), 8, 1024 * 1024, 10, None);
+    let rt0 = pool.startup(false);
+
+    //等待异步任务的返回值
+    let handle: JoinHandle<String> = rt0.spawn_with_handle(rt0.alloc(), async move {
+        "Hello World".to_string()
+    }).unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let r = handle.await;
+        println!("!!!!!!join ok, r: {:?}", r);
+        assert_eq!(r.unwrap(), "Hello World".to_string());
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    //中止一个永远不会完成的异步任务
+    let handle: JoinHandle<usize> = rt.spawn_with_handle(rt.alloc(), futures::future::pending()).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    handle.abort();
+    let counter_copy = counter.clone();
+    rt0.spawn(rt0.alloc(), async move {
+        let r = handle.await;
+        println!("!!!!!!abort ok, r: {:?}", r);
+        assert!(r.is_err());
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    thread::sleep(Duration::from_millis(1000));
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_join_handle_dropped() {
+    let pool = MultiTaskPool::<()>::new("AsyncJoinDropped".to_string(), 2, 1024 * 1024, 10, None);
+    let rt0 = pool.startup(false);
+
+    //关闭运行时会释放挂起的任务，等待中的句柄以取消错误完成
+    let runner = SingleTaskRunner::<()>::new();
+    let rt = runner.startup().unwrap();
+    let handle: JoinHandle<usize> = rt.spawn_with_handle(rt.alloc(), futures::future::pending()).unwrap();
+    runner.run_once().unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter_copy = counter.clone();
+    rt0.spawn(rt0.alloc(), async move {
+        let r = handle.await;
+        println!("!!!!!!shutdown dropped, r: {:?}", r);
+        assert_eq!(r.unwrap_err().kind(), ErrorKind::Interrupted);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+    runner.shutdown(100).unwrap();
+    drop(runner);
+    drop(rt);
+
+    //未关闭就释放执行器，挂起的任务随执行器释放，等待中的句柄以取消错误完成
+    let runner = SingleTaskRunner::<()>::new();
+    let rt = runner.startup().unwrap();
+    let handle: JoinHandle<usize> = rt.spawn_with_handle(rt.alloc(), futures::future::pending()).unwrap();
+    runner.run_once().unwrap();
+    let counter_copy = counter.clone();
+    rt0.spawn(rt0.alloc(), async move {
+        let r = handle.await;
+        println!("!!!!!!runtime dropped, r: {:?}", r);
+        assert_eq!(r.unwrap_err().kind(), ErrorKind::Interrupted);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+    thread::sleep(Duration::from_millis(100));
+    drop(runner);
+    drop(rt);
+
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_async_sleep_interval_timeout() {
+    let runner = SingleTaskRunner::new();
+    let rt = runner.startup().unwrap();
+
+    thread::spawn(move || {
+        loop {
+            if let Err(e) = runner.run_once() {
+                println!("!!!!!!run failed, reason: {:?}", e);
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    let pool = MultiTaskPool::<()>::new("AsyncRuntime0".to_string(), 8, 1024 * 1024, 10, Some(10));
+    let rt0 = pool.startup(false);
+
+    let counter = Arc::new(AtomicUsize::new(0));
+    let rt_copy = rt.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let start = Instant::now();
+        rt_copy.sleep(300).await.unwrap();
+        println!("!!!!!!sleep ok, time: {:?}", Instant::now() - start);
+
+        let start = Instant::now();
+        let mut interval = rt_copy.interval(100);
+        for index in 0..5 {
+            interval.tick().await.unwrap();
+            println!("!!!!!!interval ok, index: {:?}, time: {:?}", index, Instant::now() - start);
+        }
+
+        let r = rt_copy.timeout(300, futures::future::pending::<()>()).await;
+        println!("!!!!!!timeout ok, r: {:?}", r);
+        assert!(r.is_err());
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    let rt0_copy = rt0.clone();
+    let counter_copy = counter.clone();
+    rt0.spawn(rt0.alloc(), async move {
+        let rt0_clone = rt0_copy.clone();
+        let r = rt0_copy.timeout(1000, async move {
+            rt0_clone.sleep(300).await.unwrap();
+            "Hello World".to_string()
+        }).await;
+        println!("!!!!!!timeout ok, r: {:?}", r);
+        assert_eq!(r.unwrap(), "Hello World".to_string());
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    thread::sleep(Duration::from_millis(3000));
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_async_timer_cancel() {
+    //没有本地定时器的运行时，休眠和定时间隔都返回错误
+    let pool = MultiTaskPool::<()>::new("AsyncNoTimer".to_string(), 2, 1024 * 1024, 10, None);
+    let rt0 = pool.startup(false);
+    let counter = Arc::new(AtomicUsize::new(0));
+    let rt0_copy = rt0.clone();
+    let counter_copy = counter.clone();
+    rt0.spawn(rt0.alloc(), async move {
+        assert!(rt0_copy.sleep(100).await.is_err());
+        assert!(rt0_copy.interval(100).tick().await.is_err());
+        assert!(rt0_copy.timeout(100, async move {}).await.is_ok());
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    //已完成的带超时的异步任务，会从本地定时器中移除未到期的定时器
+    let runner = SingleTaskRunner::<()>::new();
+    let rt = runner.startup().unwrap();
+    let rt_copy = rt.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let rt_clone = rt_copy.clone();
+        let r = rt_copy.timeout(60000, async move {
+            rt_clone.sleep(10).await
+        }).await;
+        assert!(r.unwrap().is_ok());
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    }).unwrap();
+
+    let start = Instant::now();
+    while counter.load(Ordering::Relaxed) < 2 && start.elapsed() < Duration::from_millis(3000) {
+        runner.run_once().unwrap();
+        thread::sleep(Duration::from_millis(1));
+    }
+    runner.run_once().unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+    assert_eq!(runner.timer_len(), 0);
+}
+
+#[test]
+fn test_async_channel() {
+    let runner = SingleTaskRunner::new();
+    let rt = runner.startup().unwrap();
+
+    thread::spawn(move || {
+        loop {
+            if let Err(e) = runner.run_once() {
+                println!("!!!!!!run failed, reason: {:?}", e);
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    let pool = MultiTaskPool::<()>::new("AsyncRuntime0".to_string(), 8, 1024 * 1024, 10, None);
+    let rt0 = pool.startup(false);
+
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    //单次通道
+    let (sender, receiver) = oneshot::channel(AsyncRuntime::Single(rt.clone()));
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let r = receiver.await;
+        println!("!!!!!!oneshot recv ok, r: {:?}", r);
+        assert_eq!(r.unwrap(), "Hello World".to_string());
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+    rt0.spawn(rt0.alloc(), async move {
+        sender.send("Hello World".to_string()).unwrap();
+    });
+
+    //有界多生产者单消费者通道
+    let (sender, mut receiver) = mpsc::channel(AsyncRuntime::Single(rt.clone()), 10);
+    for index in 0..10 {
+        let sender_copy = sender.clone();
+        rt0.spawn(rt0.alloc(), async move {
+            for value in 0..100 {
+                sender_copy.send(index * 100 + value).await.unwrap();
+            }
+        });
+    }
+    drop(sender);
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let mut count = 0;
+        while let Ok(_) = receiver.recv().await {
+            count += 1;
+        }
+        println!("!!!!!!mpsc recv finish, count: {:?}", count);
+        assert_eq!(count, 1000);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    //广播通道
+    let (sender, mut receiver0) = broadcast::channel(AsyncRuntime::Multi(rt0.clone()), 100);
+    let mut receiver1 = sender.subscribe();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let mut sum = 0;
+        while let Ok(value) = receiver0.recv().await {
+            sum += value;
+        }
+        println!("!!!!!!broadcast recv finish, sum: {:?}", sum);
+        assert_eq!(sum, 4950);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+    let counter_copy = counter.clone();
+    rt0.spawn(rt0.alloc(), async move {
+        let mut sum = 0;
+        while let Ok(value) = receiver1.recv().await {
+            sum += value;
+        }
+        println!("!!!!!!broadcast recv finish, sum: {:?}", sum);
+        assert_eq!(sum, 4950);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+    for value in 0..100 {
+        sender.send(value).unwrap();
+    }
+    drop(sender);
+
+    let start = Instant::now();
+    while counter.load(Ordering::Relaxed) < 4 && start.elapsed() < Duration::from_millis(10000) {
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 4);
+
+    //手动运行的单线程运行时，挂起的接收者被多次轮询后，仍只在发送后被唤醒
+    let runner = SingleTaskRunner::<()>::new();
+    let rt = runner.startup().unwrap();
+    let (sender, mut receiver) = mpsc::channel(AsyncRuntime::Single(rt.clone()), 1);
+    let (notify_sender, notify_receiver) = oneshot::channel(AsyncRuntime::Single(rt.clone()));
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        assert_eq!(notify_receiver.await.unwrap(), 1);
+
+        //在等待接收时多次被其它异步任务唤醒，接收者会被多次轮询
+        let mut count = 0;
+        let yield_times = futures::future::poll_fn(move |cx| {
+            if count < 3 {
+                count += 1;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            Poll::Ready(())
+        });
+        let (r, _) = futures::future::join(receiver.recv(), yield_times).await;
+        assert_eq!(r.unwrap(), 2);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    }).unwrap();
+    runner.run_once().unwrap();
+    runner.run_once().unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+    notify_sender.send(1).unwrap();
+    for _ in 0..5 {
+        runner.run_once().unwrap();
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+    assert_eq!(runner.run_once().unwrap(), 0);
+
+    sender.try_send(2).unwrap();
+    runner.run_once().unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), 1);
+
+    //广播通道容量已满时覆盖最旧的值，接收过慢的接收者收到滞后错误后继续接收
+    let (sender, mut receiver) = broadcast::channel(AsyncRuntime::Single(rt.clone()), 4);
+    for value in 0..10 {
+        assert_eq!(sender.send(value).unwrap(), 1);
+    }
+    assert_eq!(receiver.len(), 10);
+    assert_eq!(receiver.try_recv(), Err(broadcast::RecvError::Lagged(6)));
+    for value in 6..8 {
+        assert_eq!(receiver.try_recv(), Ok(Some(value)));
+    }
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        assert_eq!(receiver.recv().await, Ok(8));
+        assert_eq!(receiver.recv().await, Ok(9));
+        assert_eq!(receiver.recv().await, Err(broadcast::RecvError::Lagged(3)));
+        for value in 13..17 {
+            assert_eq!(receiver.recv().await, Ok(value));
+        }
+        assert_eq!(receiver.recv().await, Err(broadcast::RecvError::Closed));
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    }).unwrap();
+    runner.run_once().unwrap();
+    for value in 10..17 {
+        sender.send(value).unwrap();
+    }
+    runner.run_once().unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), 1);
+    drop(sender);
+    runner.run_once().unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_async_sync_primitives() {
+    let pool = MultiTaskPool::<()>::new("AsyncRuntime0".to_string(), 8, 1024 * 1024, 10, None);
+    let rt = pool.startup(false);
+
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    //异步信号量
+    let semaphore = Arc::new(Semaphore::new(2));
+    let running = Arc::new(AtomicUsize::new(0));
+    let finished = Arc::new(AtomicUsize::new(0));
+    for _ in 0..10 {
+        let semaphore_copy = semaphore.clone();
+        let running_copy = running.clone();
+        let finished_copy = finished.clone();
+        rt.spawn(rt.alloc(), async move {
+            let _permit = semaphore_copy.acquire(1).await.unwrap();
+            let current = running_copy.fetch_add(1, Ordering::SeqCst) + 1;
+            assert!(current <= 2);
+            thread::sleep(Duration::from_millis(10));
+            running_copy.fetch_sub(1, Ordering::SeqCst);
+            finished_copy.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    let semaphore_copy = semaphore.clone();
+    let finished_copy = finished.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        //需要的许可超过许可总数，则立即返回错误
+        assert!(semaphore_copy.acquire(3).await.is_err());
+        let permit = semaphore_copy.acquire(2).await.unwrap();
+        println!("!!!!!!semaphore acquire ok, permits: {:?}, finished: {:?}", permit.permits(), finished_copy.load(Ordering::SeqCst));
+        assert!(semaphore_copy.try_acquire(1).is_none());
+        drop(permit);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    //异步屏障
+    let barrier = Arc::new(Barrier::new(5));
+    let leaders = Arc::new(AtomicUsize::new(0));
+    for _ in 0..10 {
+        let barrier_copy = barrier.clone();
+        let leaders_copy = leaders.clone();
+        let counter_copy = counter.clone();
+        rt.spawn(rt.alloc(), async move {
+            if barrier_copy.wait().await.is_leader() {
+                leaders_copy.fetch_add(1, Ordering::SeqCst);
+            }
+            counter_copy.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    //异步通知
+    let notify = Arc::new(Notify::new());
+    notify.notify_one();
+    let notify_copy = notify.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        //已保存通知，则立即获取通知
+        notify_copy.notified().await;
+        println!("!!!!!!notified ok");
+        notify_copy.notified().await;
+        println!("!!!!!!notified again ok");
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+    thread::sleep(Duration::from_millis(1000));
+    notify.notify_one();
+
+    //异步条件变量
+    let shared = Arc::new((Mutex::new(Vec::new()), Condvar::new()));
+    let shared_copy = shared.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let (mutex, condvar) = &*shared_copy;
+        let mut sum = 0;
+        let mut count = 0;
+        let mut guard = mutex.lock().await;
+        while count < 100 {
+            while let Some(value) = guard.pop() {
+                sum += value;
+                count += 1;
+            }
+            if count < 100 {
+                guard = condvar.wait(guard).await;
+            }
+        }
+        println!("!!!!!!condvar wait finish, sum: {:?}", sum);
+        assert_eq!(sum, 4950);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+    for value in 0..100 {
+        let shared_copy = shared.clone();
+        rt.spawn(rt.alloc(), async move {
+            let (mutex, condvar) = &*shared_copy;
+            mutex.lock().await.push(value);
+            condvar.notify_one();
+        });
+    }
+
+    thread::sleep(Duration::from_millis(10000));
+    assert_eq!(semaphore.available_permits(), 2);
+    assert_eq!(leaders.load(Ordering::SeqCst), 2);
+    assert_eq!(counter.load(Ordering::Relaxed), 13);
+}
+
+#[test]
+fn test_async_lock_fair_timeout() {
+    let runner = SingleTaskRunner::new();
+    let rt = runner.startup().unwrap();
+
+    thread::spawn(move || {
+        loop {
+            if let Err(e) = runner.run_once() {
+                println!("!!!!!!run failed, reason: {:?}", e);
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    //公平的异步互斥锁，等待锁的任务按先进先出的顺序获取锁，超时的任务会放弃获取锁
+    let mutex = Arc::new(Mutex::new_fair(Vec::new()));
+    assert!(mutex.is_fair());
+    let rt_copy = rt.clone();
+    let mutex_copy = mutex.clone();
+    rt.spawn(rt.alloc(), async move {
+        let guard = mutex_copy.lock().await;
+        rt_copy.sleep(500).await.unwrap();
+        drop(guard);
+    });
+    thread::sleep(Duration::from_millis(100));
+    for index in 0..5 {
+        let mutex_copy = mutex.clone();
+        rt.spawn(rt.alloc(), async move {
+            mutex_copy.lock().await.push(index);
+        });
+    }
+    let rt_copy = rt.clone();
+    let mutex_copy = mutex.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let r = mutex_copy.try_lock_for(AsyncRuntime::Single(rt_copy.clone()), Duration::from_millis(100)).await;
+        println!("!!!!!!try lock for ok, r: {:?}", r.is_ok());
+        assert!(r.is_err());
+
+        let r = mutex_copy.try_lock_for(AsyncRuntime::Single(rt_copy.clone()), Duration::from_millis(2000)).await;
+        println!("!!!!!!try lock for ok, r: {:?}", r.as_ref().map(|guard| (&**guard).clone()));
+        assert_eq!(&*r.unwrap(), &vec![0, 1, 2, 3, 4]);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    //公平的异步读写锁，有写锁在等待时后到的读锁也会等待
+    let rwlock = Arc::new(RwLock::new_fair(Vec::new()));
+    let rt_copy = rt.clone();
+    let rwlock_copy = rwlock.clone();
+    rt.spawn(rt.alloc(), async move {
+        let guard = rwlock_copy.read().await;
+        rt_copy.sleep(300).await.unwrap();
+        drop(guard);
+    });
+    thread::sleep(Duration::from_millis(100));
+    let rwlock_copy = rwlock.clone();
+    rt.spawn(rt.alloc(), async move {
+        rwlock_copy.write().await.push("write");
+    });
+    let rwlock_copy = rwlock.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let guard = rwlock_copy.read().await;
+        println!("!!!!!!fair read ok, value: {:?}", &*guard);
+        assert_eq!(&*guard, &vec!["write"]);
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    //可升级的异步读锁，升级期间不会有其它写锁获取到锁
+    let rwlock = Arc::new(RwLock::new(0));
+    let rt_copy = rt.clone();
+    let rwlock_copy = rwlock.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let guard = rwlock_copy.upgradable_read().await;
+        assert_eq!(*rwlock_copy.read().await, 0);
+        rt_copy.sleep(300).await.unwrap();
+        let value = *guard;
+        let mut guard = RwLockUpgradableReadGuard::upgrade(guard).await;
+        *guard = value + 1;
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+    thread::sleep(Duration::from_millis(100));
+    let rt_copy = rt.clone();
+    let rwlock_copy = rwlock.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        let r = rwlock_copy.try_write_for(AsyncRuntime::Single(rt_copy.clone()), Duration::from_millis(100)).await;
+        assert!(r.is_err());
+
+        let mut guard = rwlock_copy.write().await;
+        println!("!!!!!!upgradable write ok, value: {:?}", *guard);
+        assert_eq!(*guard, 1);
+        *guard += 1;
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    //非公平的异步互斥锁，超时放弃获取锁的任务不会吞掉解锁时的唤醒
+    let mutex = Arc::new(Mutex::new(0));
+    let rt_copy = rt.clone();
+    let mutex_copy = mutex.clone();
+    rt.spawn(rt.alloc(), async move {
+        let guard = mutex_copy.lock().await;
+        rt_copy.sleep(300).await.unwrap();
+        drop(guard);
+    });
+    thread::sleep(Duration::from_millis(100));
+    let rt_copy = rt.clone();
+    let mutex_copy = mutex.clone();
+    rt.spawn(rt.alloc(), async move {
+        let r = mutex_copy.try_lock_for(AsyncRuntime::Single(rt_copy.clone()), Duration::from_millis(50)).await;
+        assert!(r.is_err());
+    });
+    thread::sleep(Duration::from_millis(20));
+    let mutex_copy = mutex.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        *mutex_copy.lock().await += 1;
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    //非公平的异步读写锁，超时放弃获取写锁的任务不会吞掉释放读锁时的唤醒
+    let rwlock = Arc::new(RwLock::new(0));
+    let rt_copy = rt.clone();
+    let rwlock_copy = rwlock.clone();
+    rt.spawn(rt.alloc(), async move {
+        let guard = rwlock_copy.read().await;
+        rt_copy.sleep(300).await.unwrap();
+        drop(guard);
+    });
+    thread::sleep(Duration::from_millis(100));
+    let rt_copy = rt.clone();
+    let rwlock_copy = rwlock.clone();
+    rt.spawn(rt.alloc(), async move {
+        let r = rwlock_copy.try_write_for(AsyncRuntime::Single(rt_copy.clone()), Duration::from_millis(50)).await;
+        assert!(r.is_err());
+    });
+    thread::sleep(Duration::from_millis(20));
+    let rwlock_copy = rwlock.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        *rwlock_copy.write().await += 1;
+        counter_copy.fetch_add(1, Ordering::Relaxed);
+    });
+
+    thread::sleep(Duration::from_millis(3000));
+    assert_eq!(counter.load(Ordering::Relaxed), 6);
+}
+
+#[test]
+fn test_multi_runtime_stats() {
+    let pool = MultiTaskPool::<()>::new_with_stats("AsyncStats".to_string(), 4, 1024 * 1024, 10, None);
+    let rt = pool.startup(true);
+    assert!(rt.is_enable_stats());
+
+    //派发大量任务，其中部分任务会一直挂起
+    let mut values = Vec::new();
+    for index in 0..1000 {
+        if index % 100 == 0 {
+            let value = AsyncValue::<(), ()>::new(AsyncRuntime::Multi(rt.clone()));
+            let value_copy = value.clone();
+            rt.spawn(rt.alloc(), async move {
+                value_copy.await;
+            });
+            values.push(value);
+        } else {
+            rt.spawn(rt.alloc(), async move {
+                thread::sleep(Duration::from_micros(100));
+            });
+        }
+    }
+    thread::sleep(Duration::from_millis(3000));
+
+    let stats = rt.stats().unwrap();
+    println!("!!!!!!stats: spawn: {:?}, complete: {:?}, poll: {:?}, polls per task: {:?}",
+             stats.spawn_count,
+             stats.complete_count,
+             stats.poll_count,
+             stats.polls_per_task());
+    for worker in &stats.workers {
+        println!("!!!!!!worker {:?}: queue_len: {:?}, poll: {:?}, complete: {:?}, steal: {:?}/{:?}, max poll time: {:?}, pending: {:?}",
+                 worker.index,
+                 worker.queue_len,
+                 worker.poll_count,
+                 worker.complete_count,
+                 worker.steal_count,
+                 worker.steal_task_count,
+                 worker.max_poll_time,
+                 worker.pending.len());
+    }
+    assert_eq!(stats.spawn_count, 1000);
+    assert_eq!(stats.complete_count, 990);
+    assert_eq!(stats.pending_len(), 10);
+    #[cfg(feature = "apm_collect")]
+    assert_eq!(GLOBAL_PREF_COLLECT.new_dynamic_counter(Atom::from(format!("AsyncStats#{}_spawn_count", rt.get_id())), 0).unwrap().get(), 1000);
+
+    //相同线程名前缀的任务池不会共享计数器
+    let other_pool = MultiTaskPool::<()>::new_with_stats("AsyncStats".to_string(), 1, 1024 * 1024, 10, None);
+    let other_rt = other_pool.startup(true);
+    other_rt.spawn(other_rt.alloc(), async move {}).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(other_rt.stats().unwrap().spawn_count, 1);
+    assert_eq!(rt.stats().unwrap().spawn_count, 1000);
+
+    //唤醒所有挂起的任务
+    for value in values {
+        value.set(());
+    }
+    thread::sleep(Duration::from_millis(1000));
+
+    let stats = rt.stats().unwrap();
+    assert_eq!(stats.complete_count, 1000);
+    assert_eq!(stats.pending_len(), 0);
+}
+
+#[test]
+fn test_runtime_shutdown() {
+    //释放时计数的守护者，用于检查任务是否已被释放
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    //关闭多线程运行时
+    let pool = MultiTaskPool::<()>::new("AsyncShutdown".to_string(), 4, 1024 * 1024, 10, Some(1));
+    let rt = pool.startup(true);
+
+    let counter = Arc::new(AtomicUsize::new(0));
+    let released = Arc::new(AtomicUsize::new(0));
+    for _ in 0..100 {
+        let rt_copy = rt.clone();
+        let counter_copy = counter.clone();
+        rt.spawn(rt.alloc(), async move {
+            rt_copy.sleep(100).await.unwrap();
+            counter_copy.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    //关闭中的任务可以继续通过运行时派发辅助任务
+    let rt_copy = rt.clone();
+    let counter_copy = counter.clone();
+    rt.spawn(rt.alloc(), async move {
+        rt_copy.sleep(100).await.unwrap();
+        let r = rt_copy.wait(AsyncRuntime::Multi(rt_copy.clone()), async move {
+            Ok(1)
+        }).await;
+        counter_copy.fetch_add(r.unwrap(), Ordering::Relaxed);
+    });
+    for _ in 0..5 {
+        //挂起在定时器上的任务
+        let rt_copy = rt.clone();
+        let guard = DropCounter(released.clone());
+        rt.spawn(rt.alloc(), async move {
+            let _guard = guard;
+            rt_copy.sleep(60000).await.unwrap();
+        });
+    }
+    //挂起在不会被唤醒的任务唯一id上的任务
+    let rt_copy = rt.clone();
+    let guard = DropCounter(released.clone());
+    rt.spawn(rt.alloc(), async move {
+        let _guard = guard;
+        let task_id = rt_copy.alloc();
+        futures::future::poll_fn(|cx| rt_copy.pending::<()>(&task_id, cx.waker().clone())).await;
+    });
+
+    let report = rt.shutdown(1000).unwrap();
+    println!("!!!!!!multi runtime shutdown, report: {:?}", report);
+    assert!(rt.is_shutdown());
+    assert_eq!(counter.load(Ordering::Relaxed), 101);
+    assert!(report.completed > 101);
+    assert_eq!(report.dropped, 6);
+    assert_eq!(released.load(Ordering::SeqCst), 6);
+    assert_eq!(rt.alive_len(), 0);
+    assert_eq!(report.joined, 5);
+    assert!(rt.spawn(rt.alloc(), async move {}).is_err());
+    assert!(rt.shutdown(1000).is_err());
+
+    //关闭单线程运行时
+    let runner = SingleTaskRunner::<()>::new();
+    let rt = runner.startup().unwrap();
+
+    let counter = Arc::new(AtomicUsize::new(0));
+    for _ in 0..10 {
+        let rt_copy = rt.clone();
+        let counter_copy = counter.clone();
+        rt.spawn(rt.alloc(), async move {
+            rt_copy.sleep(10).await.unwrap();
+            counter_copy.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    let released = Arc::new(AtomicUsize::new(0));
+    let rt_copy = rt.clone();
+    let guard = DropCounter(released.clone());
+    rt.spawn(rt.alloc(), async move {
+        let _guard = guard;
+        rt_copy.sleep(60000).await.unwrap();
+    });
+    let rt_copy = rt.clone();
+    let guard = DropCounter(released.clone());
+    rt.spawn(rt.alloc(), async move {
+        let _guard = guard;
+        let task_id = rt_copy.alloc();
+        futures::future::poll_fn(|cx| rt_copy.pending::<()>(&task_id, cx.waker().clone())).await;
+    });
+
+    let report = runner.shutdown(500).unwrap();
+    println!("!!!!!!single runtime shutdown, report: {:?}", report);
+    assert!(rt.is_shutdown());
+    assert_eq!(counter.load(Ordering::Relaxed), 10);
+    assert_eq!(report.completed, 10);
+    assert_eq!(report.dropped, 2);
+    assert_eq!(released.load(Ordering::SeqCst), 2);
+    assert_eq!(rt.alive_len(), 0);
+    assert!(rt.spawn(rt.alloc(), async move {}).is_err());
+    assert!(runner.run_once().is_err());
+
+    //任务恐慌后，当前线程不再被认为正在运行单线程运行时，所以不能向关闭中的运行时派发任务
+    let runner = SingleTaskRunner::<()>::new();
+    let rt = runner.startup().unwrap();
+    rt.spawn(rt.alloc(), async move {
+        panic!("single runtime task panic");
+    }).unwrap();
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| runner.run_once())).is_err());
+
+    let rt_copy = rt.clone();
+    rt.spawn(rt.alloc(), async move {
+        let task_id = rt_copy.alloc();
+        futures::future::poll_fn(|cx| rt_copy.pending::<()>(&task_id, cx.waker().clone())).await;
+    }).unwrap();
+    let shutdown = thread::spawn(move || runner.shutdown(500));
+    thread::sleep(Duration::from_millis(100));
+    assert!(rt.spawn(rt.alloc(), async move {}).is_err());
+    let report = shutdown.join().unwrap().unwrap();
+    assert_eq!(report.dropped, 1);
 }
\ No newline at end of file
@@ -0,0 +1,294 @@
+use std::sync::Arc;
+use std::io::{Result, Error, ErrorKind};
+
+use futures::stream::{Stream, unfold};
+
+use crate::file::{AsyncFile, WriteOptions};
+
+/*
+* 默认的缓冲区大小
+*/
+const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/*
+* 异步带缓冲的文件读取器，从指定位置开始顺序读取文件
+*/
+pub struct AsyncBufReader<O: Default + 'static> {
+    file:       AsyncFile<O>,   //文件
+    pos:        u64,            //下次填充缓冲区时的文件指针位置
+    buf:        Vec<u8>,        //读缓冲
+    buf_pos:    usize,          //读缓冲中已消耗的字节数
+    capacity:   usize,          //每次填充缓冲区时读取的最大字节数
+    is_eof:     bool,           //是否已读到文件尾
+}
+
+unsafe impl<O: Default + 'static> Send for AsyncBufReader<O> {}
+unsafe impl<O: Default + 'static> Sync for AsyncBufReader<O> {}
+
+/*
+* 异步带缓冲的文件读取器同步方法
+*/
+impl<O: Default + 'static> AsyncBufReader<O> {
+    //构建从文件头开始读取的异步带缓冲的文件读取器
+    pub fn new(file: AsyncFile<O>) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, file)
+    }
+
+    //构建指定缓冲区大小的，从文件头开始读取的异步带缓冲的文件读取器
+    pub fn with_capacity(mut capacity: usize, file: AsyncFile<O>) -> Self {
+        if capacity == 0 {
+            //缓冲区太小，则设置至少1个字节
+            capacity = 1;
+        }
+
+        AsyncBufReader {
+            file,
+            pos: 0,
+            buf: Vec::new(),
+            buf_pos: 0,
+            capacity,
+            is_eof: false,
+        }
+    }
+
+    //获取文件
+    pub fn get_ref(&self) -> &AsyncFile<O> {
+        &self.file
+    }
+
+    //获取下一个未读取字节的文件指针位置
+    pub fn pos(&self) -> u64 {
+        self.pos - (self.buf.len() - self.buf_pos) as u64
+    }
+
+    //设置下一个读取字节的文件指针位置，会丢弃缓冲区中未读取的字节
+    pub fn seek(&mut self, pos: u64) {
+        self.pos = pos;
+        self.buf.clear();
+        self.buf_pos = 0;
+        self.is_eof = false;
+    }
+
+    //获取缓冲区中未读取的字节
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.buf_pos..]
+    }
+
+    //消耗缓冲区中指定数量的字节
+    pub fn consume(&mut self, len: usize) {
+        self.buf_pos = (self.buf_pos + len).min(self.buf.len());
+    }
+}
+
+/*
+* 异步带缓冲的文件读取器异步方法
+*/
+impl<O: Default + 'static> AsyncBufReader<O> {
+    //缓冲区已全部消耗时异步填充缓冲区，并返回缓冲区中未读取的字节，返回空表示已读到文件尾
+    pub async fn fill_buf(&mut self) -> Result<&[u8]> {
+        if self.buf_pos >= self.buf.len() && !self.is_eof {
+            //缓冲区已全部消耗，且未读到文件尾，则继续读取文件
+            let bin = self.file.read(self.pos, self.capacity).await?;
+            if bin.is_empty() {
+                self.is_eof = true;
+            }
+            self.pos += bin.len() as u64;
+            self.buf = bin;
+            self.buf_pos = 0;
+        }
+
+        Ok(&self.buf[self.buf_pos..])
+    }
+
+    //异步读取最多指定数量的字节，返回空表示已读到文件尾
+    pub async fn read(&mut self, len: usize) -> Result<Vec<u8>> {
+        let available = self.fill_buf().await?;
+        let len = len.min(available.len());
+        let bin = available[..len].to_vec();
+        self.consume(len);
+
+        Ok(bin)
+    }
+
+    //异步读取字节，直到读到指定的分隔字节或文件尾，读取的字节包括分隔字节，并追加到指定的缓冲区，返回读取的字节数
+    pub async fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> Result<usize> {
+        let mut len = 0;
+        loop {
+            let (is_finish, used) = {
+                let available = self.fill_buf().await?;
+                match available.iter().position(|b| *b == byte) {
+                    Some(index) => {
+                        //已读到分隔字节
+                        buf.extend_from_slice(&available[..=index]);
+                        (true, index + 1)
+                    },
+                    None => {
+                        //未读到分隔字节，则读取缓冲区中的所有字节，缓冲区为空表示已读到文件尾
+                        buf.extend_from_slice(available);
+                        (available.is_empty(), available.len())
+                    },
+                }
+            };
+            self.consume(used);
+            len += used;
+
+            if is_finish {
+                return Ok(len);
+            }
+        }
+    }
+
+    //异步读取一行，读取的行包括换行符，并追加到指定的字符串，返回读取的字节数，返回0表示已读到文件尾
+    pub async fn read_line(&mut self, line: &mut String) -> Result<usize> {
+        let mut buf = Vec::new();
+        let len = self.read_until(b'\n', &mut buf).await?;
+        match String::from_utf8(buf) {
+            Err(e) => Err(Error::new(ErrorKind::InvalidData, format!("read line failed, pos: {:?}, reason: {:?}", self.pos(), e))),
+            Ok(str) => {
+                line.push_str(&str);
+                Ok(len)
+            },
+        }
+    }
+
+    //转换为按行读取的异步流，返回的行不包括换行符
+    pub fn lines(self) -> impl Stream<Item = Result<String>> {
+        unfold(self, |mut reader| async move {
+            let mut line = String::new();
+            match reader.read_line(&mut line).await {
+                Err(e) => Some((Err(e), reader)),
+                Ok(0) => None, //已读到文件尾，则流结束
+                Ok(_) => {
+                    if line.ends_with('\n') {
+                        line.pop();
+                        if line.ends_with('\r') {
+                            line.pop();
+                        }
+                    }
+                    Some((Ok(line), reader))
+                },
+            }
+        })
+    }
+
+    //转换为按块读取的异步流，每块最多为缓冲区大小
+    pub fn chunks(self) -> impl Stream<Item = Result<Vec<u8>>> {
+        unfold(self, |mut reader| async move {
+            let capacity = reader.capacity;
+            match reader.read(capacity).await {
+                Err(e) => Some((Err(e), reader)),
+                Ok(bin) if bin.is_empty() => None, //已读到文件尾，则流结束
+                Ok(bin) => Some((Ok(bin), reader)),
+            }
+        })
+    }
+}
+
+/*
+* 异步带缓冲的文件写入器，从指定位置开始顺序写入文件，会合并多次小的写入，需要在释放前调用flush，否则缓冲区中的字节会丢失
+*/
+pub struct AsyncBufWriter<O: Default + 'static> {
+    file:       AsyncFile<O>,   //文件
+    pos:        u64,            //下次写入文件时的文件指针位置
+    buf:        Vec<u8>,        //写缓冲
+    capacity:   usize,          //缓冲区大小
+    options:    WriteOptions,   //刷新时的写文件选项
+    is_dirty:   bool,           //是否有已写入文件但未按写文件选项同步的字节
+}
+
+unsafe impl<O: Default + 'static> Send for AsyncBufWriter<O> {}
+unsafe impl<O: Default + 'static> Sync for AsyncBufWriter<O> {}
+
+/*
+* 异步带缓冲的文件写入器同步方法
+*/
+impl<O: Default + 'static> AsyncBufWriter<O> {
+    //构建从指定位置开始写入的异步带缓冲的文件写入器，刷新时根据写文件选项同步文件
+    pub fn new(file: AsyncFile<O>, pos: u64, options: WriteOptions) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, file, pos, options)
+    }
+
+    //构建指定缓冲区大小的，从指定位置开始写入的异步带缓冲的文件写入器
+    pub fn with_capacity(mut capacity: usize, file: AsyncFile<O>, pos: u64, options: WriteOptions) -> Self {
+        if capacity == 0 {
+            //缓冲区太小，则设置至少1个字节
+            capacity = 1;
+        }
+
+        AsyncBufWriter {
+            file,
+            pos,
+            buf: Vec::with_capacity(capacity),
+            capacity,
+            options,
+            is_dirty: false,
+        }
+    }
+
+    //获取文件
+    pub fn get_ref(&self) -> &AsyncFile<O> {
+        &self.file
+    }
+
+    //获取下一个写入字节的文件指针位置
+    pub fn pos(&self) -> u64 {
+        self.pos + self.buf.len() as u64
+    }
+
+    //获取缓冲区中未写入文件的字节
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+/*
+* 异步带缓冲的文件写入器异步方法
+*/
+impl<O: Default + 'static> AsyncBufWriter<O> {
+    //异步写入指定的字节，缓冲区已满时将缓冲区写入文件，但不同步文件，返回写入的字节数
+    pub async fn write(&mut self, bin: &[u8]) -> Result<usize> {
+        if self.buf.len() + bin.len() > self.capacity {
+            //缓冲区将满，则先将缓冲区写入文件
+            self.write_buf(WriteOptions::None).await?;
+        }
+
+        if bin.len() >= self.capacity {
+            //写入的字节不小于缓冲区，则直接写入文件
+            let len = self.file.write(self.pos, Arc::new(bin.to_vec()), WriteOptions::None).await?;
+            self.pos += len as u64;
+            self.is_dirty = true;
+        } else {
+            self.buf.extend_from_slice(bin);
+        }
+
+        Ok(bin.len())
+    }
+
+    //异步将缓冲区写入文件，并根据写文件选项同步文件
+    pub async fn flush(&mut self) -> Result<()> {
+        if self.buf.is_empty() && !self.is_dirty {
+            //没有需要写入或同步的字节，则立即返回
+            return Ok(());
+        }
+
+        let options = self.options.clone();
+        self.write_buf(options).await?;
+        self.is_dirty = false;
+
+        Ok(())
+    }
+
+    //异步将缓冲区以指定的写文件选项写入文件，写入失败则保留缓冲区
+    async fn write_buf(&mut self, options: WriteOptions) -> Result<()> {
+        let len = self.file.write(self.pos, Arc::new(self.buf.clone()), options.clone()).await?;
+        self.buf.clear();
+        self.pos += len as u64;
+        if let WriteOptions::None = options {
+            if len > 0 {
+                self.is_dirty = true;
+            }
+        }
+
+        Ok(())
+    }
+}
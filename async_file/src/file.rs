@@ -1,24 +1,38 @@
 use std::pin::Pin;
+use std::process;
 use std::sync::Arc;
+use std::ffi::OsString;
 use std::time::Duration;
 use std::future::Future;
 use std::cell::{RefCell, Ref};
 use std::path::{Path, PathBuf};
+use std::collections::VecDeque;
 #[cfg(any(unix))]
-use std::os::unix::fs::FileExt;
+use std::os::unix::fs::{FileExt, symlink as unix_symlink};
 #[cfg(any(windows))]
-use std::os::windows::fs::FileExt;
+use std::os::windows::fs::{FileExt, symlink_file, symlink_dir};
 use std::task::{Context, Poll, Waker};
 use std::fmt::{Debug, Formatter, Result as FmtResult};
-use std::fs::{File, OpenOptions, Metadata,
+use std::fs::{File, OpenOptions, Metadata, Permissions, ReadDir, DirEntry,
               rename as sync_rename,
               create_dir_all as sync_create_dir_all,
               remove_file as sync_remove_file,
               copy as sync_copy,
-              remove_dir as sync_remove_dir};
+              remove_dir as sync_remove_dir,
+              remove_dir_all as sync_remove_dir_all,
+              read_dir as sync_read_dir,
+              metadata as sync_metadata,
+              hard_link as sync_hard_link,
+              read_link as sync_read_link,
+              set_permissions as sync_set_permissions};
 use std::io::{Seek, Write, Result, SeekFrom, Error, ErrorKind};
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(any(unix))]
+use std::{mem, os::unix::io::AsRawFd};
 
 use parking_lot::RwLock;
+use log::warn;
+use futures::stream::Stream;
 use r#async::rt::multi_thread::{MultiTaskPool, MultiTaskRuntime};
 
 /*
@@ -448,154 +462,1239 @@ pub async fn remove_file<P, O>(runtime: MultiTaskRuntime<O>, path: P) -> Result<
 }
 
 /*
-* 文件选项
+* 异步获取指定路径的元信息的结果
 */
-pub enum AsyncFileOptions {
-    OnlyRead,
-    OnlyWrite,
-    OnlyAppend,
-    ReadAppend,
-    ReadWrite,
-    TruncateWrite,
+#[derive(Clone)]
+struct MetadataResult(Arc<RefCell<Option<Result<Metadata>>>>);
+
+unsafe impl Send for MetadataResult {}
+unsafe impl Sync for MetadataResult {}
+
+/*
+* 异步获取指定路径的元信息
+*/
+struct AsyncMetadata<P: AsRef<Path> + Send + 'static, O: Default + 'static> {
+    runtime:    MultiTaskRuntime<O>,    //异步运行时
+    path:       P,                      //文件或目录路径
+    result:     MetadataResult,         //获取元信息结果
+}
+
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Send for AsyncMetadata<P, O> {}
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Sync for AsyncMetadata<P, O> {}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Future for AsyncMetadata<P, O> {
+    type Output = Result<Metadata>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.as_ref().result.0.borrow_mut().take() {
+            //已获取指定路径的元信息，则返回
+            return Poll::Ready(result);
+        }
+
+        //异步获取指定路径的元信息
+        let task_id = self.as_ref().runtime.alloc();
+        let task_id_copy = task_id.clone();
+        let runtime = self.as_ref().runtime.clone();
+        let path = self.as_ref().path.as_ref().to_path_buf();
+        let result = self.as_ref().result.clone();
+        let task = async move {
+            match sync_metadata(path) {
+                Err(e) => {
+                    //异步获取元信息失败，则设置等待异步获取元信息的任务的值
+                    *result.0.borrow_mut() = Some(Err(e));
+                },
+                Ok(meta) => {
+                    //异步获取元信息成功，则设置等待异步获取元信息的任务的值
+                    *result.0.borrow_mut() = Some(Ok(meta));
+                },
+            }
+
+            //唤醒等待异步获取元信息的任务
+            runtime.wakeup(&task_id_copy);
+
+            //返回当前异步任务的默认值
+            Default::default()
+        };
+
+        //挂起当前任务，并返回值未就绪
+        self.as_ref().runtime.pending::<()>(&task_id, cx.waker().clone());
+        if let Err(e) = self.as_ref().runtime.spawn(task_id, task) {
+            //派发异步获取元信息的任务失败，则立即返回错误原因
+            return Poll::Ready(Err(Error::new(ErrorKind::Other, format!("Async Metadata Error, path: {:?}, reason: {:?}", self.as_ref().path.as_ref(), e))));
+        }
+        Poll::Pending
+    }
+}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> AsyncMetadata<P, O> {
+    //构建异步获取指定路径的元信息的方法
+    pub fn new(runtime: MultiTaskRuntime<O>, path: P) -> Self {
+        AsyncMetadata {
+            runtime,
+            path,
+            result: MetadataResult(Arc::new(RefCell::new(None))), //设置初始值
+        }
+    }
 }
 
 /*
-* 写文件选项
+* 异步获取文件或目录的元信息，会跟随符号链接
 */
-#[derive(Debug, Clone)]
-pub enum WriteOptions {
-    None,
-    Flush,
-    Sync(bool),
-    SyncAll(bool),
+pub async fn metadata<P, O>(runtime: MultiTaskRuntime<O>, path: P) -> Result<Metadata>
+    where P: AsRef<Path> + Send + 'static, O: Default + 'static {
+    AsyncMetadata::new(runtime, path).await
 }
 
 /*
-* 异步内部文件
+* 异步创建指定链接的结果
 */
-struct InnerFile<O: Default + 'static> {
-    runtime:        MultiTaskRuntime<O>,
-    path:           PathBuf,
-    inner:          RwLock<File>,
+#[derive(Clone)]
+struct LinkResult(Arc<RefCell<Option<Result<()>>>>);
+
+unsafe impl Send for LinkResult {}
+unsafe impl Sync for LinkResult {}
+
+/*
+* 异步创建指定的符号链接或硬链接
+*/
+struct AsyncLink<P: AsRef<Path> + Send + 'static, O: Default + 'static> {
+    runtime:    MultiTaskRuntime<O>,    //异步运行时
+    src:        P,                      //源文件或目录路径
+    dst:        P,                      //链接路径
+    is_symlink: bool,                   //是否是符号链接
+    result:     LinkResult,             //创建链接结果
 }
 
-impl<O: Default + 'static> Debug for InnerFile<O> {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "AsyncFile[file = {:?}]", self.path)
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Send for AsyncLink<P, O> {}
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Sync for AsyncLink<P, O> {}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Future for AsyncLink<P, O> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.as_ref().result.0.borrow_mut().take() {
+            //已创建指定链接，则返回
+            return Poll::Ready(result);
+        }
+
+        //异步创建指定链接
+        let task_id = self.as_ref().runtime.alloc();
+        let task_id_copy = task_id.clone();
+        let runtime = self.as_ref().runtime.clone();
+        let src = self.as_ref().src.as_ref().to_path_buf();
+        let dst = self.as_ref().dst.as_ref().to_path_buf();
+        let is_symlink = self.as_ref().is_symlink;
+        let result = self.as_ref().result.clone();
+        let task = async move {
+            let r = if is_symlink {
+                sync_symlink(src, dst)
+            } else {
+                sync_hard_link(src, dst)
+            };
+
+            match r {
+                Err(e) => {
+                    //异步创建链接失败，则设置等待异步创建链接的任务的值
+                    *result.0.borrow_mut() = Some(Err(e));
+                },
+                Ok(_) => {
+                    //异步创建链接成功，则设置等待异步创建链接的任务的值
+                    *result.0.borrow_mut() = Some(Ok(()));
+                },
+            }
+
+            //唤醒等待异步创建链接的任务
+            runtime.wakeup(&task_id_copy);
+
+            //返回当前异步任务的默认值
+            Default::default()
+        };
+
+        //挂起当前任务，并返回值未就绪
+        self.as_ref().runtime.pending::<()>(&task_id, cx.waker().clone());
+        if let Err(e) = self.as_ref().runtime.spawn(task_id, task) {
+            //派发异步创建链接的任务失败，则立即返回错误原因
+            return Poll::Ready(Err(Error::new(ErrorKind::Other, format!("Async Link Error, src: {:?}, dst: {:?}, symlink: {:?}, reason: {:?}", self.as_ref().src.as_ref(), self.as_ref().dst.as_ref(), self.as_ref().is_symlink, e))));
+        }
+        Poll::Pending
+    }
+}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> AsyncLink<P, O> {
+    //构建异步创建指定链接的方法
+    pub fn new(runtime: MultiTaskRuntime<O>, src: P, dst: P, is_symlink: bool) -> Self {
+        AsyncLink {
+            runtime,
+            src,
+            dst,
+            is_symlink,
+            result: LinkResult(Arc::new(RefCell::new(None))), //设置初始值
+        }
     }
 }
 
 /*
-* 异步文件
+* 异步创建指向源文件或目录的符号链接
 */
-#[derive(Debug)]
-pub struct AsyncFile<O: Default + 'static>(Arc<InnerFile<O>>);
+pub async fn symlink<P, O>(runtime: MultiTaskRuntime<O>, src: P, dst: P) -> Result<()>
+    where P: AsRef<Path> + Send + 'static, O: Default + 'static {
+    AsyncLink::new(runtime, src, dst, true).await
+}
 
-unsafe impl<O: Default + 'static> Send for AsyncFile<O> {}
-unsafe impl<O: Default + 'static> Sync for AsyncFile<O> {}
+/*
+* 异步创建指向源文件的硬链接
+*/
+pub async fn hard_link<P, O>(runtime: MultiTaskRuntime<O>, src: P, dst: P) -> Result<()>
+    where P: AsRef<Path> + Send + 'static, O: Default + 'static {
+    AsyncLink::new(runtime, src, dst, false).await
+}
 
-impl<O: Default + 'static> Clone for AsyncFile<O> {
-    fn clone(&self) -> Self {
-        AsyncFile(self.0.clone())
+//创建符号链接
+#[cfg(any(unix))]
+fn sync_symlink(src: PathBuf, dst: PathBuf) -> Result<()> {
+    unix_symlink(src, dst)
+}
+
+//创建符号链接，需要根据源路径是否是目录，选择创建文件或目录的符号链接
+#[cfg(any(windows))]
+fn sync_symlink(src: PathBuf, dst: PathBuf) -> Result<()> {
+    if src.is_dir() {
+        symlink_dir(src, dst)
+    } else {
+        symlink_file(src, dst)
     }
 }
 
 /*
-* 异步文件的同步方法
+* 异步设置指定路径的权限的结果
 */
-impl<O: Default + 'static> AsyncFile<O> {
-    //检查是否是符号链接
-    pub fn is_symlink(&self) -> bool {
-        self.0.inner.read().metadata().ok().unwrap().file_type().is_symlink()
-    }
+#[derive(Clone)]
+struct SetPermissionsResult(Arc<RefCell<Option<Result<()>>>>);
 
-    //检查是否是文件
-    pub fn is_file(&self) -> bool {
-        self.0.inner.read().metadata().ok().unwrap().file_type().is_file()
-    }
+unsafe impl Send for SetPermissionsResult {}
+unsafe impl Sync for SetPermissionsResult {}
 
-    //检查文件是否只读
-    pub fn is_only_read(&self) -> bool {
-        self.0.inner.read().metadata().ok().unwrap().permissions().readonly()
-    }
+/*
+* 异步设置指定路径的权限
+*/
+struct AsyncSetPermissions<P: AsRef<Path> + Send + 'static, O: Default + 'static> {
+    runtime:    MultiTaskRuntime<O>,    //异步运行时
+    path:       P,                      //文件或目录路径
+    perm:       Permissions,            //权限
+    result:     SetPermissionsResult,   //设置权限结果
+}
 
-    //获取文件大小
-    pub fn get_size(&self) -> u64 {
-        self.0.inner.read().metadata().ok().unwrap().len()
-    }
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Send for AsyncSetPermissions<P, O> {}
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Sync for AsyncSetPermissions<P, O> {}
 
-    //获取文件修改时间
-    pub fn get_modified_time(&self) -> Result<Duration> {
-        match self.0.inner.read().metadata().ok().unwrap().modified() {
-            Err(e) => Err(Error::new(ErrorKind::Other, e)),
-            Ok(time) => {
-                match time.elapsed() {
-                    Err(e) => Err(Error::new(ErrorKind::Other, e)),
-                    Ok(duration) => Ok(duration),
-                }
-            },
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Future for AsyncSetPermissions<P, O> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.as_ref().result.0.borrow_mut().take() {
+            //已设置指定路径的权限，则返回
+            return Poll::Ready(result);
         }
-    }
 
-    //获取文件访问时间
-    pub fn get_accessed_time(&self) -> Result<Duration> {
-        match self.0.inner.read().metadata().ok().unwrap().accessed() {
-            Err(e) => Err(Error::new(ErrorKind::Other, e)),
-            Ok(time) => {
-                match time.elapsed() {
-                    Err(e) => Err(Error::new(ErrorKind::Other, e)),
-                    Ok(duration) => Ok(duration),
-                }
-            },
+        //异步设置指定路径的权限
+        let task_id = self.as_ref().runtime.alloc();
+        let task_id_copy = task_id.clone();
+        let runtime = self.as_ref().runtime.clone();
+        let path = self.as_ref().path.as_ref().to_path_buf();
+        let perm = self.as_ref().perm.clone();
+        let result = self.as_ref().result.clone();
+        let task = async move {
+            match sync_set_permissions(path, perm) {
+                Err(e) => {
+                    //异步设置权限失败，则设置等待异步设置权限的任务的值
+                    *result.0.borrow_mut() = Some(Err(e));
+                },
+                Ok(_) => {
+                    //异步设置权限成功，则设置等待异步设置权限的任务的值
+                    *result.0.borrow_mut() = Some(Ok(()));
+                },
+            }
+
+            //唤醒等待异步设置权限的任务
+            runtime.wakeup(&task_id_copy);
+
+            //返回当前异步任务的默认值
+            Default::default()
+        };
+
+        //挂起当前任务，并返回值未就绪
+        self.as_ref().runtime.pending::<()>(&task_id, cx.waker().clone());
+        if let Err(e) = self.as_ref().runtime.spawn(task_id, task) {
+            //派发异步设置权限的任务失败，则立即返回错误原因
+            return Poll::Ready(Err(Error::new(ErrorKind::Other, format!("Async Set Permissions Error, path: {:?}, reason: {:?}", self.as_ref().path.as_ref(), e))));
         }
+        Poll::Pending
     }
+}
 
-    //获取文件创建时间
-    pub fn get_created_time(&self) -> Result<Duration> {
-        match self.0.inner.read().metadata().ok().unwrap().created() {
-            Err(e) => Err(Error::new(ErrorKind::Other, e)),
-            Ok(time) => {
-                match time.elapsed() {
-                    Err(e) => Err(Error::new(ErrorKind::Other, e)),
-                    Ok(duration) => Ok(duration),
-                }
-            },
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> AsyncSetPermissions<P, O> {
+    //构建异步设置指定路径的权限的方法
+    pub fn new(runtime: MultiTaskRuntime<O>, path: P, perm: Permissions) -> Self {
+        AsyncSetPermissions {
+            runtime,
+            path,
+            perm,
+            result: SetPermissionsResult(Arc::new(RefCell::new(None))), //设置初始值
         }
     }
 }
 
 /*
-* 异步文件的异步方法
+* 异步设置文件或目录的权限
 */
-impl<O: Default + 'static> AsyncFile<O> {
-    //以指定方式异步打开指定的文件
-    pub async fn open<P>(runtime: MultiTaskRuntime<O>,
-                         path: P,
-                         options: AsyncFileOptions) -> Result<Self>
-        where P: AsRef<Path> + Send + 'static {
-        AsyncOpenFile::new(runtime, path, options).await
-    }
+pub async fn set_permissions<P, O>(runtime: MultiTaskRuntime<O>, path: P, perm: Permissions) -> Result<()>
+    where P: AsRef<Path> + Send + 'static, O: Default + 'static {
+    AsyncSetPermissions::new(runtime, path, perm).await
+}
 
-    //从指定位置开始异步读指定字节
-    pub async fn read(&self, pos: u64, len: usize) -> Result<Vec<u8>> {
-        if len == 0 {
-            //无效的字节数，则立即返回
-            return Ok(Vec::with_capacity(0));
-        }
+/*
+* 异步递归移除指定的目录的结果
+*/
+#[derive(Clone)]
+struct RemoveDirAllResult(Arc<RefCell<Option<Result<()>>>>);
 
-        let mut buf = Vec::with_capacity(len);
-        unsafe { buf.set_len(len); }
-        AsyncReadFile::new(self.0.runtime.clone(), buf, 0, self.clone(), pos, len, 0).await
-    }
+unsafe impl Send for RemoveDirAllResult {}
+unsafe impl Sync for RemoveDirAllResult {}
 
-    //从指定位置开始异步写指定字节
-    pub async fn write(&self, pos: u64, buf: Arc<Vec<u8>>, options: WriteOptions) -> Result<usize> {
-        if buf.len() == 0 {
-            //无效的字节数，则立即返回
-            return Ok(0);
-        }
+/*
+* 异步递归移除指定的目录
+*/
+struct AsyncRemoveDirAll<P: AsRef<Path> + Send + 'static, O: Default + 'static> {
+    runtime:    MultiTaskRuntime<O>,    //异步运行时
+    path:       P,                      //目录路径
+    result:     RemoveDirAllResult,     //递归移除目录结果
+}
 
-        AsyncWriteFile::new(self.0.runtime.clone(), buf, 0, self.clone(), pos, options, 0).await
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Send for AsyncRemoveDirAll<P, O> {}
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Sync for AsyncRemoveDirAll<P, O> {}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Future for AsyncRemoveDirAll<P, O> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.as_ref().result.0.borrow_mut().take() {
+            //已递归移除指定目录，则返回
+            return Poll::Ready(result);
+        }
+
+        //异步递归移除指定目录
+        let task_id = self.as_ref().runtime.alloc();
+        let task_id_copy = task_id.clone();
+        let runtime = self.as_ref().runtime.clone();
+        let path = self.as_ref().path.as_ref().to_path_buf();
+        let result = self.as_ref().result.clone();
+        let task = async move {
+            match sync_remove_dir_all(path) {
+                Err(e) => {
+                    //异步递归移除目录失败，则设置等待异步递归移除目录的任务的值
+                    *result.0.borrow_mut() = Some(Err(e));
+                },
+                Ok(_) => {
+                    //异步递归移除目录成功，则设置等待异步递归移除目录的任务的值
+                    *result.0.borrow_mut() = Some(Ok(()));
+                },
+            }
+
+            //唤醒等待异步递归移除目录的任务
+            runtime.wakeup(&task_id_copy);
+
+            //返回当前异步任务的默认值
+            Default::default()
+        };
+
+        //挂起当前任务，并返回值未就绪
+        self.as_ref().runtime.pending::<()>(&task_id, cx.waker().clone());
+        if let Err(e) = self.as_ref().runtime.spawn(task_id, task) {
+            //派发异步递归移除目录的任务失败，则立即返回错误原因
+            return Poll::Ready(Err(Error::new(ErrorKind::Other, format!("Async Remove Dir All Error, dir: {:?}, reason: {:?}", self.as_ref().path.as_ref(), e))));
+        }
+        Poll::Pending
+    }
+}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> AsyncRemoveDirAll<P, O> {
+    //构建异步递归移除指定目录的方法
+    pub fn new(runtime: MultiTaskRuntime<O>, path: P) -> Self {
+        AsyncRemoveDirAll {
+            runtime,
+            path,
+            result: RemoveDirAllResult(Arc::new(RefCell::new(None))), //设置初始值
+        }
+    }
+}
+
+/*
+* 异步递归移除目录及目录下的所有文件和子目录，不会跟随符号链接
+*/
+pub async fn remove_dir_all<P, O>(runtime: MultiTaskRuntime<O>, path: P) -> Result<()>
+    where P: AsRef<Path> + Send + 'static, O: Default + 'static {
+    AsyncRemoveDirAll::new(runtime, path).await
+}
+
+/*
+* 异步递归复制指定的目录的结果
+*/
+#[derive(Clone)]
+struct CopyDirResult(Arc<RefCell<Option<Result<u64>>>>);
+
+unsafe impl Send for CopyDirResult {}
+unsafe impl Sync for CopyDirResult {}
+
+/*
+* 异步递归复制指定的目录
+*/
+struct AsyncCopyDir<P: AsRef<Path> + Send + 'static, O: Default + 'static> {
+    runtime:    MultiTaskRuntime<O>,    //异步运行时
+    from:       P,                      //源目录路径
+    to:         P,                      //目标目录路径
+    result:     CopyDirResult,          //递归复制目录结果
+}
+
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Send for AsyncCopyDir<P, O> {}
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Sync for AsyncCopyDir<P, O> {}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Future for AsyncCopyDir<P, O> {
+    type Output = Result<u64>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.as_ref().result.0.borrow_mut().take() {
+            //已递归复制指定目录，则返回
+            return Poll::Ready(result);
+        }
+
+        //异步递归复制指定目录
+        let task_id = self.as_ref().runtime.alloc();
+        let task_id_copy = task_id.clone();
+        let runtime = self.as_ref().runtime.clone();
+        let from = self.as_ref().from.as_ref().to_path_buf();
+        let to = self.as_ref().to.as_ref().to_path_buf();
+        let result = self.as_ref().result.clone();
+        let task = async move {
+            match sync_copy_dir(from, to) {
+                Err(e) => {
+                    //异步递归复制目录失败，则设置等待异步递归复制目录的任务的值
+                    *result.0.borrow_mut() = Some(Err(e));
+                },
+                Ok(len) => {
+                    //异步递归复制目录成功，则设置等待异步递归复制目录的任务的值
+                    *result.0.borrow_mut() = Some(Ok(len));
+                },
+            }
+
+            //唤醒等待异步递归复制目录的任务
+            runtime.wakeup(&task_id_copy);
+
+            //返回当前异步任务的默认值
+            Default::default()
+        };
+
+        //挂起当前任务，并返回值未就绪
+        self.as_ref().runtime.pending::<()>(&task_id, cx.waker().clone());
+        if let Err(e) = self.as_ref().runtime.spawn(task_id, task) {
+            //派发异步递归复制目录的任务失败，则立即返回错误原因
+            return Poll::Ready(Err(Error::new(ErrorKind::Other, format!("Async Copy Dir Error, from: {:?}, to: {:?}, reason: {:?}", self.as_ref().from.as_ref(), self.as_ref().to.as_ref(), e))));
+        }
+        Poll::Pending
+    }
+}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> AsyncCopyDir<P, O> {
+    //构建异步递归复制指定目录的方法
+    pub fn new(runtime: MultiTaskRuntime<O>, from: P, to: P) -> Self {
+        AsyncCopyDir {
+            runtime,
+            from,
+            to,
+            result: CopyDirResult(Arc::new(RefCell::new(None))), //设置初始值
+        }
+    }
+}
+
+/*
+* 异步递归复制目录及目录下的所有文件和子目录，目标目录不存在则创建，符号链接会被复制为符号链接，返回复制的总字节数
+*/
+pub async fn copy_dir<P, O>(runtime: MultiTaskRuntime<O>, from: P, to: P) -> Result<u64>
+    where P: AsRef<Path> + Send + 'static, O: Default + 'static {
+    AsyncCopyDir::new(runtime, from, to).await
+}
+
+//递归复制目录，返回复制的总字节数
+fn sync_copy_dir(from: PathBuf, to: PathBuf) -> Result<u64> {
+    let from = from.canonicalize()?;
+    if canonicalize_nearest(&to)?.starts_with(&from) {
+        //目标目录是源目录或源目录的子目录，则在创建目标目录前立即返回错误原因，以避免无限递归复制
+        return Err(Error::new(ErrorKind::InvalidInput, format!("copy dir into itself, from: {:?}, to: {:?}", from, to)));
+    }
+
+    sync_create_dir_all(&to)?;
+    copy_dir_entries(&from, &to)
+}
+
+//规范化指定路径，路径不存在则规范化最近的已存在的祖先路径，并拼接剩余的路径
+fn canonicalize_nearest(path: &Path) -> Result<PathBuf> {
+    let mut names = Vec::new();
+    let mut current = path;
+    loop {
+        match current.canonicalize() {
+            Ok(mut canonical) => {
+                for name in names.iter().rev() {
+                    canonical.push(name);
+                }
+                return Ok(canonical);
+            },
+            Err(e) => {
+                match (current.parent(), current.file_name()) {
+                    (Some(parent), Some(name)) => {
+                        //当前路径不存在，则继续规范化父路径
+                        names.push(name.to_os_string());
+                        current = if parent.as_os_str().is_empty() {
+                            Path::new(".")
+                        } else {
+                            parent
+                        };
+                    },
+                    _ => return Err(e),
+                }
+            },
+        }
+    }
+}
+
+//递归复制目录下的所有文件和子目录，符号链接会被复制为指向相同目标的符号链接，不会跟随符号链接复制
+fn copy_dir_entries(from: &Path, to: &Path) -> Result<u64> {
+    let mut len = 0;
+    for entry in sync_read_dir(from)? {
+        let entry = entry?;
+        let to_path = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            //是符号链接，则复制符号链接
+            copy_symlink(&entry.path(), to_path)?;
+        } else if file_type.is_dir() {
+            //是子目录，则创建目标子目录后继续递归复制
+            sync_create_dir_all(&to_path)?;
+            len += copy_dir_entries(&entry.path(), &to_path)?;
+        } else {
+            len += sync_copy(entry.path(), to_path)?;
+        }
+    }
+
+    Ok(len)
+}
+
+//复制符号链接
+#[cfg(any(unix))]
+fn copy_symlink(src: &Path, dst: PathBuf) -> Result<()> {
+    unix_symlink(sync_read_link(src)?, dst)
+}
+
+//复制符号链接，需要根据符号链接指向的是否是目录，选择创建文件或目录的符号链接
+#[cfg(any(windows))]
+fn copy_symlink(src: &Path, dst: PathBuf) -> Result<()> {
+    let target = sync_read_link(src)?;
+    if src.is_dir() {
+        symlink_dir(target, dst)
+    } else {
+        symlink_file(target, dst)
+    }
+}
+
+/*
+* 异步打开指定的目录的结果
+*/
+struct OpenDirResult<O: Default + 'static>(Arc<RefCell<Option<Result<AsyncReadDir<O>>>>>);
+
+unsafe impl<O: Default + 'static> Send for OpenDirResult<O> {}
+unsafe impl<O: Default + 'static> Sync for OpenDirResult<O> {}
+
+impl<O: Default + 'static> Clone for OpenDirResult<O> {
+    fn clone(&self) -> Self {
+        OpenDirResult(self.0.clone())
+    }
+}
+
+/*
+* 异步打开指定的目录
+*/
+struct AsyncOpenDir<P: AsRef<Path> + Send + 'static, O: Default + 'static> {
+    runtime:    MultiTaskRuntime<O>,    //异步运行时
+    path:       P,                      //目录路径
+    recursive:  bool,                   //是否递归遍历子目录
+    result:     OpenDirResult<O>,       //打开目录结果
+}
+
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Send for AsyncOpenDir<P, O> {}
+unsafe impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Sync for AsyncOpenDir<P, O> {}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> Future for AsyncOpenDir<P, O> {
+    type Output = Result<AsyncReadDir<O>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.as_ref().result.0.borrow_mut().take() {
+            //已打开指定目录，则返回
+            return Poll::Ready(result);
+        }
+
+        //异步打开指定目录
+        let task_id = self.as_ref().runtime.alloc();
+        let task_id_copy = task_id.clone();
+        let runtime = self.as_ref().runtime.clone();
+        let path = self.as_ref().path.as_ref().to_path_buf();
+        let recursive = self.as_ref().recursive;
+        let result = self.as_ref().result.clone();
+        let task = async move {
+            match sync_read_dir(path) {
+                Err(e) => {
+                    //打开目录失败，则设置等待异步打开目录的任务的值
+                    *result.0.borrow_mut() = Some(Err(e));
+                },
+                Ok(dir) => {
+                    //打开目录成功，则设置等待异步打开目录的任务的值
+                    *result.0.borrow_mut() = Some(Ok(AsyncReadDir {
+                        runtime: runtime.clone(),
+                        recursive,
+                        dirs: Some(vec![dir]),
+                        buf: VecDeque::new(),
+                        result: ReadDirResult(Arc::new(RefCell::new(None))),
+                    }));
+                },
+            }
+
+            //唤醒等待异步打开目录的任务
+            runtime.wakeup(&task_id_copy);
+
+            //返回当前异步任务的默认值
+            Default::default()
+        };
+
+        //挂起当前任务，并返回值未就绪
+        self.as_ref().runtime.pending::<()>(&task_id, cx.waker().clone());
+        if let Err(e) = self.as_ref().runtime.spawn(task_id, task) {
+            //派发异步打开目录的任务失败，则立即返回错误原因
+            return Poll::Ready(Err(Error::new(ErrorKind::Other, format!("Async Open Dir Error, dir: {:?}, reason: {:?}", self.as_ref().path.as_ref(), e))));
+        }
+        Poll::Pending
+    }
+}
+
+impl<P: AsRef<Path> + Send + 'static, O: Default + 'static> AsyncOpenDir<P, O> {
+    //构建异步打开指定目录的方法
+    pub fn new(runtime: MultiTaskRuntime<O>, path: P, recursive: bool) -> Self {
+        AsyncOpenDir {
+            runtime,
+            path,
+            recursive,
+            result: OpenDirResult(Arc::new(RefCell::new(None))), //设置初始值
+        }
+    }
+}
+
+/*
+* 异步打开目录，并返回目录项的异步流，只包括目录下的直接目录项
+*/
+pub async fn read_dir<P, O>(runtime: MultiTaskRuntime<O>, path: P) -> Result<AsyncReadDir<O>>
+    where P: AsRef<Path> + Send + 'static, O: Default + 'static {
+    AsyncOpenDir::new(runtime, path, false).await
+}
+
+/*
+* 异步打开目录，并返回深度优先递归遍历所有目录项的异步流，不会跟随符号链接
+*/
+pub async fn walk_dir<P, O>(runtime: MultiTaskRuntime<O>, path: P) -> Result<AsyncReadDir<O>>
+    where P: AsRef<Path> + Send + 'static, O: Default + 'static {
+    AsyncOpenDir::new(runtime, path, true).await
+}
+
+/*
+* 每次异步读取的最大目录项数量
+*/
+const READ_DIR_BATCH_SIZE: usize = 64;
+
+/*
+* 异步批量读取目录项的结果
+*/
+#[derive(Clone)]
+struct ReadDirResult(Arc<RefCell<Option<(Vec<ReadDir>, VecDeque<Result<DirEntry>>)>>>);
+
+unsafe impl Send for ReadDirResult {}
+unsafe impl Sync for ReadDirResult {}
+
+/*
+* 目录项的异步流，每次在运行时上批量读取目录项，并逐个返回
+*/
+pub struct AsyncReadDir<O: Default + 'static> {
+    runtime:    MultiTaskRuntime<O>,                //异步运行时
+    recursive:  bool,                               //是否递归遍历子目录
+    dirs:       Option<Vec<ReadDir>>,               //待读取的目录栈，正在批量读取时为空
+    buf:        VecDeque<Result<DirEntry>>,         //已读取但未返回的目录项
+    result:     ReadDirResult,                      //批量读取目录项结果
+}
+
+unsafe impl<O: Default + 'static> Send for AsyncReadDir<O> {}
+unsafe impl<O: Default + 'static> Sync for AsyncReadDir<O> {}
+
+impl<O: Default + 'static> Stream for AsyncReadDir<O> {
+    type Item = Result<DirEntry>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(entry) = this.buf.pop_front() {
+            //有已读取的目录项，则立即返回
+            return Poll::Ready(Some(entry));
+        }
+
+        if let Some((dirs, buf)) = this.result.0.borrow_mut().take() {
+            //已批量读取目录项，则恢复待读取的目录栈
+            this.dirs = Some(dirs);
+            this.buf = buf;
+        }
+        if let Some(entry) = this.buf.pop_front() {
+            return Poll::Ready(Some(entry));
+        }
+
+        let dirs = match this.dirs.take() {
+            None => {
+                //正在批量读取目录项，则继续等待
+                return Poll::Pending;
+            },
+            Some(dirs) if dirs.is_empty() => {
+                //所有目录已读取完成，则流结束
+                this.dirs = Some(dirs);
+                return Poll::Ready(None);
+            },
+            Some(dirs) => dirs,
+        };
+
+        //异步批量读取目录项
+        let task_id = this.runtime.alloc();
+        let task_id_copy = task_id.clone();
+        let runtime = this.runtime.clone();
+        let recursive = this.recursive;
+        let result = this.result.clone();
+        let task = async move {
+            //批量读取目录项，并设置等待异步批量读取目录项的任务的值
+            *result.0.borrow_mut() = Some(read_dir_batch(dirs, recursive));
+
+            //唤醒等待异步批量读取目录项的任务
+            runtime.wakeup(&task_id_copy);
+
+            //返回当前异步任务的默认值
+            Default::default()
+        };
+
+        //挂起当前任务，并返回值未就绪
+        this.runtime.pending::<()>(&task_id, cx.waker().clone());
+        if let Err(e) = this.runtime.spawn(task_id, task) {
+            //派发异步批量读取目录项的任务失败，则立即返回错误原因，并结束流
+            this.dirs = Some(Vec::new());
+            return Poll::Ready(Some(Err(Error::new(ErrorKind::Other, format!("Async Read Dir Error, reason: {:?}", e)))));
+        }
+        Poll::Pending
+    }
+}
+
+//从目录栈顶开始批量读取目录项，递归遍历时会将子目录压入目录栈，返回剩余的目录栈和已读取的目录项
+fn read_dir_batch(mut dirs: Vec<ReadDir>, recursive: bool) -> (Vec<ReadDir>, VecDeque<Result<DirEntry>>) {
+    let mut buf = VecDeque::with_capacity(READ_DIR_BATCH_SIZE);
+    while buf.len() < READ_DIR_BATCH_SIZE {
+        let next = match dirs.last_mut() {
+            None => break, //所有目录已读取完成
+            Some(dir) => dir.next(),
+        };
+
+        match next {
+            None => {
+                //栈顶目录已读取完成，则弹出
+                dirs.pop();
+            },
+            Some(Err(e)) => {
+                buf.push_back(Err(e));
+            },
+            Some(Ok(entry)) => {
+                if recursive {
+                    if let Ok(true) = entry.file_type().map(|file_type| file_type.is_dir()) {
+                        //递归遍历时是子目录，则打开子目录并压入目录栈，打开失败则返回错误原因
+                        match sync_read_dir(entry.path()) {
+                            Err(e) => {
+                                buf.push_back(Ok(entry));
+                                buf.push_back(Err(e));
+                                continue;
+                            },
+                            Ok(dir) => dirs.push(dir),
+                        }
+                    }
+                }
+                buf.push_back(Ok(entry));
+            },
+        }
+    }
+
+    (dirs, buf)
+}
+
+/*
+* 文件选项
+*/
+pub enum AsyncFileOptions {
+    OnlyRead,
+    OnlyWrite,
+    OnlyAppend,
+    ReadAppend,
+    ReadWrite,
+    TruncateWrite,
+}
+
+/*
+* 写文件选项
+*/
+#[derive(Debug, Clone)]
+pub enum WriteOptions {
+    None,
+    Flush,
+    Sync(bool),
+    SyncAll(bool),
+}
+
+/*
+* 文件建议锁类型
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileLockKind {
+    Shared,     //共享锁
+    Exclusive,  //独占锁
+}
+
+/*
+* 异步内部文件
+*/
+struct InnerFile<O: Default + 'static> {
+    runtime:        MultiTaskRuntime<O>,
+    path:           PathBuf,
+    inner:          RwLock<File>,
+}
+
+impl<O: Default + 'static> Debug for InnerFile<O> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "AsyncFile[file = {:?}]", self.path)
+    }
+}
+
+/*
+* 异步文件
+*/
+#[derive(Debug)]
+pub struct AsyncFile<O: Default + 'static>(Arc<InnerFile<O>>);
+
+unsafe impl<O: Default + 'static> Send for AsyncFile<O> {}
+unsafe impl<O: Default + 'static> Sync for AsyncFile<O> {}
+
+impl<O: Default + 'static> Clone for AsyncFile<O> {
+    fn clone(&self) -> Self {
+        AsyncFile(self.0.clone())
+    }
+}
+
+/*
+* 异步文件的同步方法
+*/
+impl<O: Default + 'static> AsyncFile<O> {
+    //检查是否是符号链接
+    pub fn is_symlink(&self) -> bool {
+        self.0.inner.read().metadata().ok().unwrap().file_type().is_symlink()
+    }
+
+    //检查是否是文件
+    pub fn is_file(&self) -> bool {
+        self.0.inner.read().metadata().ok().unwrap().file_type().is_file()
+    }
+
+    //检查文件是否只读
+    pub fn is_only_read(&self) -> bool {
+        self.0.inner.read().metadata().ok().unwrap().permissions().readonly()
+    }
+
+    //获取文件大小
+    pub fn get_size(&self) -> u64 {
+        self.0.inner.read().metadata().ok().unwrap().len()
+    }
+
+    //获取文件修改时间
+    pub fn get_modified_time(&self) -> Result<Duration> {
+        match self.0.inner.read().metadata().ok().unwrap().modified() {
+            Err(e) => Err(Error::new(ErrorKind::Other, e)),
+            Ok(time) => {
+                match time.elapsed() {
+                    Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                    Ok(duration) => Ok(duration),
+                }
+            },
+        }
+    }
+
+    //获取文件访问时间
+    pub fn get_accessed_time(&self) -> Result<Duration> {
+        match self.0.inner.read().metadata().ok().unwrap().accessed() {
+            Err(e) => Err(Error::new(ErrorKind::Other, e)),
+            Ok(time) => {
+                match time.elapsed() {
+                    Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                    Ok(duration) => Ok(duration),
+                }
+            },
+        }
+    }
+
+    //获取文件创建时间
+    pub fn get_created_time(&self) -> Result<Duration> {
+        match self.0.inner.read().metadata().ok().unwrap().created() {
+            Err(e) => Err(Error::new(ErrorKind::Other, e)),
+            Ok(time) => {
+                match time.elapsed() {
+                    Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                    Ok(duration) => Ok(duration),
+                }
+            },
+        }
+    }
+
+    //尝试获取整个文件的建议锁，已被其它文件句柄锁住则立即返回false，建议锁只约束同样使用建议锁的文件句柄
+    pub fn try_lock(&self, kind: FileLockKind) -> Result<bool> {
+        lock_file(&self.0.inner.read(), kind, None)
+    }
+
+    //释放整个文件的建议锁
+    pub fn unlock(&self) -> Result<()> {
+        unlock_file(&self.0.inner.read(), None)
+    }
+
+    //尝试获取文件指定范围的建议锁，已被其它进程锁住则立即返回false，范围长度为0表示锁住从起始位置到文件尾，范围锁属于进程，同一进程内不互斥，进程关闭该文件的任意文件句柄都会释放这些范围锁
+    pub fn try_lock_range(&self, kind: FileLockKind, start: u64, len: u64) -> Result<bool> {
+        lock_file(&self.0.inner.read(), kind, Some((start, len)))
+    }
+
+    //释放文件指定范围的建议锁
+    pub fn unlock_range(&self, start: u64, len: u64) -> Result<()> {
+        unlock_file(&self.0.inner.read(), Some((start, len)))
+    }
+}
+
+/*
+* 异步文件的异步方法
+*/
+impl<O: Default + 'static> AsyncFile<O> {
+    //以指定方式异步打开指定的文件
+    pub async fn open<P>(runtime: MultiTaskRuntime<O>,
+                         path: P,
+                         options: AsyncFileOptions) -> Result<Self>
+        where P: AsRef<Path> + Send + 'static {
+        AsyncOpenFile::new(runtime, path, options).await
+    }
+
+    //从指定位置开始异步读指定字节
+    pub async fn read(&self, pos: u64, len: usize) -> Result<Vec<u8>> {
+        if len == 0 {
+            //无效的字节数，则立即返回
+            return Ok(Vec::with_capacity(0));
+        }
+
+        let mut buf = Vec::with_capacity(len);
+        unsafe { buf.set_len(len); }
+        AsyncReadFile::new(self.0.runtime.clone(), buf, 0, self.clone(), pos, len, 0).await
+    }
+
+    //从指定位置开始异步写指定字节，写入的字节为空时只根据写文件选项同步文件
+    pub async fn write(&self, pos: u64, buf: Arc<Vec<u8>>, options: WriteOptions) -> Result<usize> {
+        if buf.len() == 0 {
+            if let WriteOptions::None = options {
+                //无效的字节数，且不需要同步文件，则立即返回
+                return Ok(0);
+            }
+        }
+
+        AsyncWriteFile::new(self.0.runtime.clone(), buf, 0, self.clone(), pos, options, 0).await
+    }
+
+    //异步获取整个文件的建议锁，已被其它文件句柄锁住则异步休眠后重试，运行时没有本地定时器则在文件运行时上阻塞等待
+    pub async fn lock(&self, kind: FileLockKind) -> Result<()> {
+        self.lock_wait(kind, None).await
+    }
+
+    //异步获取文件指定范围的建议锁，已被其它进程锁住则异步休眠后重试，范围长度为0表示锁住从起始位置到文件尾
+    //注意范围锁是fcntl记录锁，属于进程而不属于文件句柄，同一进程内不互斥，且进程关闭该文件的任意文件句柄时，会释放进程在该文件上的所有范围锁
+    pub async fn lock_range(&self, kind: FileLockKind, start: u64, len: u64) -> Result<()> {
+        self.lock_wait(kind, Some((start, len))).await
+    }
+
+    //异步原子的替换指定文件的内容，先写入同目录下的临时文件并同步，再重命名为指定文件，保证崩溃时指定文件只会是旧内容或新内容
+    //重命名成功后新内容已提交，此时同步所在目录失败只记录警告并返回成功，只是重命名在崩溃后可能丢失
+    pub async fn atomic_write<P>(runtime: MultiTaskRuntime<O>,
+                                 path: P,
+                                 buf: Arc<Vec<u8>>) -> Result<()>
+        where P: AsRef<Path> + Send + 'static {
+        let path = path.as_ref().to_path_buf();
+        let temp_path = atomic_temp_path(&path)?;
+
+        //写入临时文件，并同步临时文件的数据和元信息
+        let file = AsyncFile::open(runtime.clone(), temp_path.clone(), AsyncFileOptions::TruncateWrite).await?;
+        let r = file.write(0, buf, WriteOptions::SyncAll(true)).await;
+        drop(file);
+        if let Err(e) = r {
+            //写入临时文件失败，则移除临时文件
+            let _ = remove_file(runtime, temp_path).await;
+            return Err(e);
+        }
+
+        //将临时文件重命名为指定文件
+        if let Err(e) = rename(runtime.clone(), temp_path.clone(), path.clone()).await {
+            //重命名失败，则移除临时文件
+            let _ = remove_file(runtime, temp_path).await;
+            return Err(e);
+        }
+
+        //同步指定文件所在的目录，保证重命名在崩溃后依然有效，新内容已提交，所以同步失败不返回错误
+        if let Err(e) = sync_parent_dir(&path) {
+            warn!("Atomic write sync parent dir failed, path: {:?}, reason: {:?}", path, e);
+        }
+
+        Ok(())
+    }
+
+    //以非阻塞方式尝试获取建议锁，已被锁住则释放文件句柄的读锁，并由运行时的本地定时器异步休眠后重试
+    //运行时没有本地定时器，则在文件运行时上阻塞的获取建议锁，不会在等待建议锁的任务所在的工作者上同步休眠
+    async fn lock_wait(&self, kind: FileLockKind, range: Option<(u64, u64)>) -> Result<()> {
+        if !self.0.runtime.is_enable_timer() {
+            if lock_file(&self.0.inner.read(), kind, range)? {
+                return Ok(());
+            }
+
+            return AsyncLockFile::new(self.0.runtime.clone(), self.clone(), kind, range).await;
+        }
+
+        loop {
+            if lock_file(&self.0.inner.read(), kind, range)? {
+                return Ok(());
+            }
+
+            self.0.runtime.sleep(LOCK_RETRY_INTERVAL).await?;
+        }
+    }
+}
+
+/*
+* 异步获取建议锁失败时的重试间隔，单位毫秒
+*/
+const LOCK_RETRY_INTERVAL: usize = 10;
+
+/*
+* 原子写使用的临时文件唯一id生成器
+*/
+static ATOMIC_TEMP_UID_GEN: AtomicUsize = AtomicUsize::new(0);
+
+//获取原子写指定文件时使用的同目录下的临时文件路径
+fn atomic_temp_path(path: &Path) -> Result<PathBuf> {
+    match path.file_name() {
+        None => Err(Error::new(ErrorKind::InvalidInput, format!("atomic write failed, path: {:?}, reason: invalid file name", path))),
+        Some(name) => {
+            let mut temp_name = OsString::from(".");
+            temp_name.push(name);
+            temp_name.push(format!(".{}.{}.tmp", process::id(), ATOMIC_TEMP_UID_GEN.fetch_add(1, Ordering::Relaxed)));
+            Ok(path.with_file_name(temp_name))
+        },
+    }
+}
+
+//同步指定文件所在的目录
+#[cfg(any(unix))]
+fn sync_parent_dir(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(parent) if parent.as_os_str().len() > 0 => File::open(parent)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+//同步指定文件所在的目录，当前平台不支持打开目录，则忽略
+#[cfg(any(windows))]
+fn sync_parent_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+//获取文件的建议锁，不会阻塞，已被锁住则返回false
+#[cfg(any(unix))]
+fn lock_file(file: &File, kind: FileLockKind, range: Option<(u64, u64)>) -> Result<bool> {
+    try_lock_file(file, kind, range, false)
+}
+
+//获取文件的建议锁，已被锁住则阻塞当前线程，直到获取建议锁
+#[cfg(any(unix))]
+fn lock_file_wait(file: &File, kind: FileLockKind, range: Option<(u64, u64)>) -> Result<()> {
+    try_lock_file(file, kind, range, true).map(|_| ())
+}
+
+//获取文件的建议锁，指定范围则使用fcntl范围锁，否则使用flock整个文件锁，不阻塞时已被锁住则返回false
+#[cfg(any(unix))]
+fn try_lock_file(file: &File, kind: FileLockKind, range: Option<(u64, u64)>, is_block: bool) -> Result<bool> {
+    loop {
+        let r = match range {
+            None => {
+                let operation = match kind {
+                    FileLockKind::Shared => libc::LOCK_SH,
+                    FileLockKind::Exclusive => libc::LOCK_EX,
+                };
+                let operation = if is_block {
+                    operation
+                } else {
+                    operation | libc::LOCK_NB
+                };
+                unsafe { libc::flock(file.as_raw_fd(), operation) }
+            },
+            Some((start, len)) => {
+                let mut lock: libc::flock = unsafe { mem::zeroed() };
+                lock.l_type = match kind {
+                    FileLockKind::Shared => libc::F_RDLCK as libc::c_short,
+                    FileLockKind::Exclusive => libc::F_WRLCK as libc::c_short,
+                };
+                lock.l_whence = libc::SEEK_SET as libc::c_short;
+                lock.l_start = start as libc::off_t;
+                lock.l_len = len as libc::off_t;
+                let cmd = if is_block {
+                    libc::F_SETLKW
+                } else {
+                    libc::F_SETLK
+                };
+                unsafe { libc::fcntl(file.as_raw_fd(), cmd, &lock) }
+            },
+        };
+        if r == 0 {
+            return Ok(true);
+        }
+
+        let e = Error::last_os_error();
+        match e.raw_os_error() {
+            Some(libc::EINTR) => continue, //被信号中断，则重试
+            Some(libc::EWOULDBLOCK) | Some(libc::EACCES) => return Ok(false), //已被锁住
+            _ => return Err(e),
+        }
+    }
+}
+
+//释放文件的建议锁
+#[cfg(any(unix))]
+fn unlock_file(file: &File, range: Option<(u64, u64)>) -> Result<()> {
+    let r = match range {
+        None => unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_UN) },
+        Some((start, len)) => {
+            let mut lock: libc::flock = unsafe { mem::zeroed() };
+            lock.l_type = libc::F_UNLCK as libc::c_short;
+            lock.l_whence = libc::SEEK_SET as libc::c_short;
+            lock.l_start = start as libc::off_t;
+            lock.l_len = len as libc::off_t;
+            unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &lock) }
+        },
+    };
+    if r != 0 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+//获取文件的建议锁，当前平台不支持
+#[cfg(any(windows))]
+fn lock_file(_file: &File, _kind: FileLockKind, _range: Option<(u64, u64)>) -> Result<bool> {
+    Err(Error::new(ErrorKind::Other, "lock file failed, reason: unsupported platform"))
+}
+
+//阻塞的获取文件的建议锁，当前平台不支持
+#[cfg(any(windows))]
+fn lock_file_wait(_file: &File, _kind: FileLockKind, _range: Option<(u64, u64)>) -> Result<()> {
+    Err(Error::new(ErrorKind::Other, "lock file failed, reason: unsupported platform"))
+}
+
+//释放文件的建议锁，当前平台不支持
+#[cfg(any(windows))]
+fn unlock_file(_file: &File, _range: Option<(u64, u64)>) -> Result<()> {
+    Err(Error::new(ErrorKind::Other, "unlock file failed, reason: unsupported platform"))
+}
+
+/*
+* 异步阻塞的获取建议锁的结果
+*/
+#[derive(Clone)]
+struct LockFileResult(Arc<RefCell<Option<Result<()>>>>);
+
+unsafe impl Send for LockFileResult {}
+unsafe impl Sync for LockFileResult {}
+
+/*
+* 在文件运行时上阻塞的获取建议锁，用于运行时没有本地定时器时，不会阻塞等待建议锁的任务所在的工作者
+*/
+struct AsyncLockFile<O: Default + 'static> {
+    runtime:    MultiTaskRuntime<O>,        //异步运行时
+    file:       AsyncFile<O>,               //异步文件
+    kind:       FileLockKind,               //建议锁类型
+    range:      Option<(u64, u64)>,         //建议锁范围，为空表示整个文件
+    result:     LockFileResult,             //获取建议锁结果
+}
+
+unsafe impl<O: Default + 'static> Send for AsyncLockFile<O> {}
+unsafe impl<O: Default + 'static> Sync for AsyncLockFile<O> {}
+
+impl<O: Default + 'static> Future for AsyncLockFile<O> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.as_ref().result.0.borrow_mut().take() {
+            //已获取建议锁，则返回
+            return Poll::Ready(result);
+        }
+
+        //复制文件句柄，复制的文件句柄与原文件句柄共享建议锁，阻塞时不会持有原文件句柄的读锁
+        let file = match self.as_ref().file.0.inner.read().try_clone() {
+            Err(e) => return Poll::Ready(Err(e)),
+            Ok(file) => file,
+        };
+
+        //异步阻塞的获取建议锁
+        let task_id = self.as_ref().runtime.alloc();
+        let task_id_copy = task_id.clone();
+        let runtime = self.as_ref().runtime.clone();
+        let kind = self.as_ref().kind;
+        let range = self.as_ref().range;
+        let result = self.as_ref().result.clone();
+        let task = async move {
+            //设置等待获取建议锁的任务的值，并唤醒等待获取建议锁的任务
+            *result.0.borrow_mut() = Some(lock_file_wait(&file, kind, range));
+            runtime.wakeup(&task_id_copy);
+
+            //返回当前异步任务的默认值
+            Default::default()
+        };
+
+        //挂起当前任务，并返回值未就绪
+        self.as_ref().runtime.pending::<()>(&task_id, cx.waker().clone());
+        if let Err(e) = self.as_ref().runtime.spawn(task_id, task) {
+            //派发获取建议锁的任务失败，则立即返回错误原因
+            return Poll::Ready(Err(Error::new(ErrorKind::Other, format!("Async Lock File Error, kind: {:?}, range: {:?}, reason: {:?}", self.as_ref().kind, self.as_ref().range, e))));
+        }
+        Poll::Pending
+    }
+}
+
+impl<O: Default + 'static> AsyncLockFile<O> {
+    //构建在文件运行时上阻塞的获取建议锁的方法
+    pub fn new(runtime: MultiTaskRuntime<O>, file: AsyncFile<O>, kind: FileLockKind, range: Option<(u64, u64)>) -> Self {
+        AsyncLockFile {
+            runtime,
+            file,
+            kind,
+            range,
+            result: LockFileResult(Arc::new(RefCell::new(None))), //设置初始值
+        }
     }
 }
 
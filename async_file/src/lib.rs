@@ -1,4 +1,9 @@
+extern crate futures;
 extern crate parking_lot;
+extern crate log;
 extern crate r#async;
+#[cfg(any(unix))]
+extern crate libc;
 
-pub mod file;
\ No newline at end of file
+pub mod file;
+pub mod buf;
\ No newline at end of file
@@ -1,15 +1,20 @@
 use std::thread;
 use std::sync::Arc;
 use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
+use futures::stream::StreamExt;
+
 use r#async::rt::multi_thread::{MultiTaskPool, MultiTaskRuntime};
-use async_file::file::{create_dir, rename, remove_file, remove_dir, AsyncFileOptions, WriteOptions, AsyncFile};
+use async_file::file::{create_dir, rename, remove_file, remove_dir, AsyncFileOptions, WriteOptions, AsyncFile, FileLockKind,
+                       metadata, symlink, hard_link, set_permissions, remove_dir_all, copy_dir, read_dir, walk_dir};
+use async_file::buf::{AsyncBufReader, AsyncBufWriter};
 
 #[test]
 fn test_async_file() {
     //初始化异步运行时
-    let pool = MultiTaskPool::new("TestAsyncRuntime".to_string(), 8, 1024 * 1024, 10);
+    let pool = MultiTaskPool::new("TestAsyncRuntime".to_string(), 8, 1024 * 1024, 10, None);
     let rt = pool.startup(true);
 
     let rt_copy = rt.clone();
@@ -72,3 +77,280 @@ fn test_async_file() {
 
     thread::sleep(Duration::from_millis(10000));
 }
+
+#[test]
+fn test_async_dir() {
+    //初始化异步运行时
+    let pool = MultiTaskPool::new("TestAsyncDir".to_string(), 4, 1024 * 1024, 10, None);
+    let rt = pool.startup(true);
+
+    let is_finish = Arc::new(AtomicBool::new(false));
+    let is_finish_copy = is_finish.clone();
+    let rt_copy = rt.clone();
+    let future = async move {
+        if let Err(e) = remove_dir_all(rt_copy.clone(), "./test_async_dir".to_string()).await {
+            if e.kind() != ErrorKind::NotFound {
+                panic!("remove dir all failed, dir: {:?}, reason: {:?}", "./test_async_dir", e);
+            }
+        }
+
+        //构建测试目录树
+        if let Err(e) = create_dir(rt_copy.clone(), "./test_async_dir/src/a/b".to_string()).await {
+            panic!("create dir failed, dir: {:?}, reason: {:?}", "./test_async_dir/src/a/b", e);
+        }
+        for path in &["./test_async_dir/src/1.txt", "./test_async_dir/src/a/2.txt", "./test_async_dir/src/a/b/3.txt"] {
+            let file = AsyncFile::open(rt_copy.clone(), path.to_string(), AsyncFileOptions::TruncateWrite).await.unwrap();
+            file.write(0, Arc::new(Vec::from("Hello Async Dir")), WriteOptions::Flush).await.unwrap();
+        }
+
+        let meta = metadata(rt_copy.clone(), "./test_async_dir/src/1.txt".to_string()).await.unwrap();
+        assert!(meta.is_file());
+        assert_eq!(meta.len(), 15);
+
+        //读取目录
+        let mut dir = read_dir(rt_copy.clone(), "./test_async_dir/src".to_string()).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = dir.next().await {
+            names.push(entry.unwrap().file_name().into_string().unwrap());
+        }
+        names.sort();
+        assert_eq!(names, vec!["1.txt".to_string(), "a".to_string()]);
+
+        //遍历目录
+        let mut dir = walk_dir(rt_copy.clone(), "./test_async_dir/src".to_string()).await.unwrap();
+        let mut count = 0;
+        while let Some(entry) = dir.next().await {
+            println!("walk dir, entry: {:?}", entry.unwrap().path());
+            count += 1;
+        }
+        assert_eq!(count, 5);
+        assert_eq!(read_dir(rt_copy.clone(), "./test_async_dir/none".to_string()).await.err().unwrap().kind(), ErrorKind::NotFound);
+
+        //复制目录
+        assert_eq!(copy_dir(rt_copy.clone(), "./test_async_dir/src".to_string(), "./test_async_dir/dst".to_string()).await.unwrap(), 45);
+        let mut dir = walk_dir(rt_copy.clone(), "./test_async_dir/dst".to_string()).await.unwrap();
+        let mut count = 0;
+        while let Some(entry) = dir.next().await {
+            assert!(entry.is_ok());
+            count += 1;
+        }
+        assert_eq!(count, 5);
+        assert_eq!(copy_dir(rt_copy.clone(), "./test_async_dir/src".to_string(), "./test_async_dir/src/a".to_string()).await.err().unwrap().kind(), ErrorKind::InvalidInput);
+        //复制到源目录下不存在的子目录，则在创建目标目录前返回错误
+        assert_eq!(copy_dir(rt_copy.clone(), "./test_async_dir/src".to_string(), "./test_async_dir/src/c/d".to_string()).await.err().unwrap().kind(), ErrorKind::InvalidInput);
+        assert_eq!(metadata(rt_copy.clone(), "./test_async_dir/src/c".to_string()).await.err().unwrap().kind(), ErrorKind::NotFound);
+        //目录的符号链接被复制为符号链接，不会跟随复制
+        symlink(rt_copy.clone(), "./a".to_string(), "./test_async_dir/src/a.link".to_string()).await.unwrap();
+        assert_eq!(copy_dir(rt_copy.clone(), "./test_async_dir/src".to_string(), "./test_async_dir/dst2".to_string()).await.unwrap(), 45);
+        assert!(std::fs::symlink_metadata("./test_async_dir/dst2/a.link").unwrap().file_type().is_symlink());
+        assert!(metadata(rt_copy.clone(), "./test_async_dir/dst2/a.link".to_string()).await.unwrap().is_dir());
+
+        //链接
+        hard_link(rt_copy.clone(), "./test_async_dir/src/1.txt".to_string(), "./test_async_dir/1.hard".to_string()).await.unwrap();
+        assert_eq!(metadata(rt_copy.clone(), "./test_async_dir/1.hard".to_string()).await.unwrap().len(), 15);
+        symlink(rt_copy.clone(), "./src/a".to_string(), "./test_async_dir/a.link".to_string()).await.unwrap();
+        assert!(metadata(rt_copy.clone(), "./test_async_dir/a.link".to_string()).await.unwrap().is_dir());
+
+        //设置权限
+        let mut perm = meta.permissions();
+        perm.set_readonly(true);
+        set_permissions(rt_copy.clone(), "./test_async_dir/src/1.txt".to_string(), perm.clone()).await.unwrap();
+        assert!(metadata(rt_copy.clone(), "./test_async_dir/src/1.txt".to_string()).await.unwrap().permissions().readonly());
+        perm.set_readonly(false);
+        set_permissions(rt_copy.clone(), "./test_async_dir/src/1.txt".to_string(), perm).await.unwrap();
+
+        //递归移除目录，不会跟随符号链接
+        remove_dir_all(rt_copy.clone(), "./test_async_dir".to_string()).await.unwrap();
+        assert_eq!(metadata(rt_copy.clone(), "./test_async_dir".to_string()).await.err().unwrap().kind(), ErrorKind::NotFound);
+
+        is_finish_copy.store(true, Ordering::Relaxed);
+    };
+    if let Err(e) = rt.spawn(rt.alloc(), future) {
+        panic!("spawn test dir task failed, reason: {:?}", e);
+    }
+
+    thread::sleep(Duration::from_millis(3000));
+    assert!(is_finish.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_async_buf_file() {
+    //初始化异步运行时
+    let pool = MultiTaskPool::new("TestAsyncBufFile".to_string(), 4, 1024 * 1024, 10, None);
+    let rt = pool.startup(true);
+
+    let is_finish = Arc::new(AtomicBool::new(false));
+    let is_finish_copy = is_finish.clone();
+    let rt_copy = rt.clone();
+    let future = async move {
+        if let Err(e) = create_dir(rt_copy.clone(), "./test_async_buf_file".to_string()).await {
+            panic!("create dir failed, dir: {:?}, reason: {:?}", "./test_async_buf_file", e);
+        }
+
+        //带缓冲的写入，会合并多次小的写入
+        let path = "./test_async_buf_file/test.csv".to_string();
+        let file = AsyncFile::open(rt_copy.clone(), path.clone(), AsyncFileOptions::TruncateWrite).await.unwrap();
+        let mut writer = AsyncBufWriter::with_capacity(64, file, 0, WriteOptions::SyncAll(true));
+        let mut len = 0;
+        for index in 0..100 {
+            len += writer.write(format!("{},name_{},什么是异步文件\r\n", index, index).as_bytes()).await.unwrap();
+        }
+        len += writer.write(&[b'x'; 100]).await.unwrap();
+        assert!(writer.buffer().len() < 64);
+        writer.flush().await.unwrap();
+        assert!(writer.buffer().is_empty());
+        assert_eq!(writer.pos(), len as u64);
+        assert_eq!(metadata(rt_copy.clone(), path.clone()).await.unwrap().len(), len as u64);
+
+        //按行读取
+        let file = AsyncFile::open(rt_copy.clone(), path.clone(), AsyncFileOptions::OnlyRead).await.unwrap();
+        let mut reader = AsyncBufReader::with_capacity(16, file.clone());
+        let mut line = String::new();
+        assert_eq!(reader.read_line(&mut line).await.unwrap(), 32);
+        assert_eq!(line, "0,name_0,什么是异步文件\r\n");
+        let mut buf = Vec::new();
+        reader.read_until(b',', &mut buf).await.unwrap();
+        assert_eq!(buf, b"1,".to_vec());
+        assert_eq!(reader.pos(), 34);
+
+        let mut lines = Box::pin(AsyncBufReader::with_capacity(16, file.clone()).lines());
+        let mut count = 0;
+        while let Some(line) = lines.next().await {
+            let line = line.unwrap();
+            if count < 100 {
+                assert_eq!(line, format!("{},name_{},什么是异步文件", count, count));
+            } else {
+                assert_eq!(line, String::from_utf8(vec![b'x'; 100]).unwrap());
+            }
+            count += 1;
+        }
+        assert_eq!(count, 101);
+
+        //按块读取
+        let mut chunks = Box::pin(AsyncBufReader::with_capacity(1000, file).chunks());
+        let mut total = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.unwrap();
+            assert!(chunk.len() <= 1000);
+            total += chunk.len();
+        }
+        assert_eq!(total, len);
+
+        remove_dir_all(rt_copy.clone(), "./test_async_buf_file".to_string()).await.unwrap();
+        is_finish_copy.store(true, Ordering::Relaxed);
+    };
+    if let Err(e) = rt.spawn(rt.alloc(), future) {
+        panic!("spawn test buf file task failed, reason: {:?}", e);
+    }
+
+    thread::sleep(Duration::from_millis(3000));
+    assert!(is_finish.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_async_file_atomic_write_and_lock() {
+    //初始化异步运行时
+    let pool = MultiTaskPool::new("TestAsyncFileLock".to_string(), 4, 1024 * 1024, 10, Some(1));
+    let rt = pool.startup(true);
+
+    let is_finish = Arc::new(AtomicBool::new(false));
+    let is_finish_copy = is_finish.clone();
+    let rt_copy = rt.clone();
+    let future = async move {
+        if let Err(e) = create_dir(rt_copy.clone(), "./test_async_file_lock".to_string()).await {
+            panic!("create dir failed, dir: {:?}, reason: {:?}", "./test_async_file_lock", e);
+        }
+
+        //原子写，替换已存在的文件，且不会遗留临时文件
+        let path = "./test_async_file_lock/config.json".to_string();
+        AsyncFile::atomic_write(rt_copy.clone(), path.clone(), Arc::new(Vec::from("{\"version\": 1}"))).await.unwrap();
+        AsyncFile::atomic_write(rt_copy.clone(), path.clone(), Arc::new(Vec::from("{\"version\": 2}"))).await.unwrap();
+        let file = AsyncFile::open(rt_copy.clone(), path.clone(), AsyncFileOptions::OnlyRead).await.unwrap();
+        assert_eq!(file.read(0, 1000).await.unwrap(), Vec::from("{\"version\": 2}"));
+        let mut dir = read_dir(rt_copy.clone(), "./test_async_file_lock".to_string()).await.unwrap();
+        let mut count = 0;
+        while let Some(_) = dir.next().await {
+            count += 1;
+        }
+        assert_eq!(count, 1);
+        assert_eq!(AsyncFile::atomic_write(rt_copy.clone(), "./test_async_file_lock/none/config.json".to_string(), Arc::new(Vec::new())).await.err().unwrap().kind(), ErrorKind::NotFound);
+
+        //整个文件的建议锁，不同的文件句柄之间互斥
+        let file0 = AsyncFile::open(rt_copy.clone(), path.clone(), AsyncFileOptions::ReadWrite).await.unwrap();
+        let file1 = AsyncFile::open(rt_copy.clone(), path.clone(), AsyncFileOptions::ReadWrite).await.unwrap();
+        file0.lock(FileLockKind::Exclusive).await.unwrap();
+        assert!(!file1.try_lock(FileLockKind::Shared).unwrap());
+        file0.unlock().unwrap();
+        assert!(file1.try_lock(FileLockKind::Shared).unwrap());
+        assert!(file0.try_lock(FileLockKind::Shared).unwrap());
+        assert!(!file0.try_lock(FileLockKind::Exclusive).unwrap());
+        file1.unlock().unwrap();
+        file0.unlock().unwrap();
+
+        //已被其它文件句柄锁住时，异步等待锁释放，且等待时不占用运行时的工作者
+        file1.lock(FileLockKind::Exclusive).await.unwrap();
+        let file1_copy = file1.clone();
+        let rt_copy_ = rt_copy.clone();
+        rt_copy.spawn(rt_copy.alloc(), async move {
+            rt_copy_.wait_timeout(100).await;
+            file1_copy.unlock().unwrap();
+        }).unwrap();
+        file0.lock(FileLockKind::Exclusive).await.unwrap();
+        assert!(!file1.try_lock(FileLockKind::Shared).unwrap());
+        file0.unlock().unwrap();
+
+        //文件范围的建议锁
+        file0.lock_range(FileLockKind::Exclusive, 0, 8).await.unwrap();
+        assert!(file0.try_lock_range(FileLockKind::Shared, 8, 0).unwrap());
+        file0.unlock_range(0, 0).unwrap();
+
+        remove_dir_all(rt_copy.clone(), "./test_async_file_lock".to_string()).await.unwrap();
+        is_finish_copy.store(true, Ordering::Relaxed);
+    };
+    if let Err(e) = rt.spawn(rt.alloc(), future) {
+        panic!("spawn test file lock task failed, reason: {:?}", e);
+    }
+
+    thread::sleep(Duration::from_millis(3000));
+    assert!(is_finish.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_async_file_lock_without_timer() {
+    //初始化没有本地定时器的异步运行时
+    let pool = MultiTaskPool::new("TestAsyncFileLockNoTimer".to_string(), 2, 1024 * 1024, 10, None);
+    let rt = pool.startup(true);
+
+    let is_finish = Arc::new(AtomicBool::new(false));
+    let is_finish_copy = is_finish.clone();
+    let rt_copy = rt.clone();
+    let future = async move {
+        if let Err(e) = create_dir(rt_copy.clone(), "./test_async_file_lock_no_timer".to_string()).await {
+            panic!("create dir failed, dir: {:?}, reason: {:?}", "./test_async_file_lock_no_timer", e);
+        }
+
+        //已被其它文件句柄锁住时，在文件运行时上阻塞等待锁释放
+        let path = "./test_async_file_lock_no_timer/config.json".to_string();
+        AsyncFile::atomic_write(rt_copy.clone(), path.clone(), Arc::new(Vec::from("{\"version\": 1}"))).await.unwrap();
+        let file0 = AsyncFile::open(rt_copy.clone(), path.clone(), AsyncFileOptions::ReadWrite).await.unwrap();
+        let file1 = AsyncFile::open(rt_copy.clone(), path.clone(), AsyncFileOptions::ReadWrite).await.unwrap();
+        file1.lock(FileLockKind::Exclusive).await.unwrap();
+        let file1_copy = file1.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            file1_copy.unlock().unwrap();
+        });
+        file0.lock(FileLockKind::Exclusive).await.unwrap();
+        assert!(!file1.try_lock(FileLockKind::Shared).unwrap());
+        file0.unlock().unwrap();
+
+        remove_dir_all(rt_copy.clone(), "./test_async_file_lock_no_timer".to_string()).await.unwrap();
+        is_finish_copy.store(true, Ordering::Relaxed);
+    };
+    if let Err(e) = rt.spawn(rt.alloc(), future) {
+        panic!("spawn test file lock task failed, reason: {:?}", e);
+    }
+
+    thread::sleep(Duration::from_millis(3000));
+    assert!(is_finish.load(Ordering::Relaxed));
+}
\ No newline at end of file
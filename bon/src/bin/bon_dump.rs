@@ -0,0 +1,94 @@
+/**
+ * bon数据的查看和编辑工具
+ * 默认从文件或标准输入读取bon二进制数据，并以文本格式输出所有值
+ * 使用-e参数时，从文件或标准输入读取文本格式的值，并将编码后的二进制数据写入标准输出
+ *
+ * 用法: bon_dump [-e|--encode] [-c|--compact] [file]
+ */
+extern crate bon;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+use bon::text::{from_text_seq, to_text};
+use bon::{Encode, ReadBuffer, WriteBuffer};
+
+const USAGE: &str = "usage: bon_dump [-e|--encode] [-c|--compact] [file]";
+
+fn main() {
+    let mut is_encode = false;
+    let mut is_pretty = true;
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-e" | "--encode" => is_encode = true,
+            "-c" | "--compact" => is_pretty = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(arg),
+            _ => exit(USAGE),
+        }
+    }
+
+    let mut input = Vec::new();
+    let r = match path {
+        Some(ref path) => File::open(path).and_then(|mut file| file.read_to_end(&mut input)),
+        None => io::stdin().read_to_end(&mut input),
+    };
+    if let Err(e) = r {
+        exit(&format!("read input failed, reason: {}", e));
+    }
+
+    if is_encode {
+        encode(&input);
+    } else {
+        decode(&input, is_pretty);
+    }
+}
+
+// 将文本格式的值编码为二进制数据
+fn encode(input: &[u8]) {
+    let text = match std::str::from_utf8(input) {
+        Ok(text) => text,
+        Err(e) => exit(&format!("invalid utf8 text, reason: {}", e)),
+    };
+    let values = match from_text_seq(text) {
+        Ok(values) => values,
+        Err(e) => exit(&format!("{:?}", e)),
+    };
+
+    let mut bb = WriteBuffer::new();
+    for value in values.iter() {
+        value.encode(&mut bb);
+    }
+    if let Err(e) = io::stdout().write_all(&bb.unwrap()) {
+        exit(&format!("write output failed, reason: {}", e));
+    }
+}
+
+// 将二进制数据中的所有值以文本格式输出
+fn decode(input: &[u8], is_pretty: bool) {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut rb = ReadBuffer::new(input, 0);
+    while rb.head() < input.len() {
+        let head = rb.head();
+        match rb.read() {
+            Ok(value) => {
+                if let Err(e) = writeln!(out, "{}", to_text(&value, is_pretty)) {
+                    exit(&format!("write output failed, reason: {}", e));
+                }
+            }
+            Err(e) => exit(&format!("decode failed, head: {}, reason: {:?}", head, e)),
+        }
+    }
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
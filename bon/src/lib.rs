@@ -35,19 +35,67 @@
 // 容器，由于有总大小的描述，从而可以只对感兴趣的部分作反序列化
 // TODO 定义一个全类型的枚举 enum BonType<T>， ReadNext WriteNext 的 T 应该为BonType。提供一个 read(&self) -> BonType<T>
 extern crate data_view;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
+
+pub mod stream;
+pub mod text;
+#[cfg(feature = "serde")]
+pub mod serde_impl;
 
 use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io;
 use std::marker::Sized;
 use std::ops::Deref;
 use std::ops::Range;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use data_view::{GetView, SetView};
 
+// 通用对象，内容为字段数量，和字段名称hash与字段值的序列
+pub const CONTAINER_OBJECT: u32 = 1;
+// 通用数组，内容为元素数量，和元素的序列
+pub const CONTAINER_ARRAY: u32 = 2;
+// 通用map，内容为键值对数量，和键与值的序列
+pub const CONTAINER_MAP: u32 = 3;
+// 可选值，内容为一个值，serde用于区分值本身为null的Some和None
+pub const CONTAINER_SOME: u32 = 4;
+// 值序列，内容为多个值的序列，流式读写器用于将编码为多个值的类型作为一个值读写
+pub const CONTAINER_VALUES: u32 = 5;
+
+/**
+ * @description 计算通用对象的字段名称hash，使用32位的FNV-1a
+ * @example
+ */
+pub fn name_hash(name: &str) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for b in name.as_bytes() {
+        hash ^= *b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    hash
+}
+
+/**
+ * @description 将指定的值以规范模式编码为bon格式，相等的值总是编码为相同的字节，值中有无法规范编码的值，如NaN，则返回错误，见WriteBuffer::with_canonical
+ * @example
+ */
+pub fn encode_canonical<T: Encode>(value: &T) -> Result<Vec<u8>, ReadBonErr> {
+    let mut bb = WriteBuffer::with_canonical(true);
+    value.encode(&mut bb);
+    bb.into_canonical()
+}
+
 pub enum EnumType {
     Void,
     Bool,
@@ -89,16 +137,75 @@ pub enum EnumValue {
     Struct(Arc<StructValue>),
 }
 
+#[derive(PartialEq, Eq, Hash)]
 pub struct StructValue {
     pub hash: u32,
     pub fields: Vec<FieldValue>,
 }
 
+#[derive(PartialEq, Eq, Hash)]
 pub struct FieldValue {
     pub name: String,
     pub fvalue: EnumValue,
 }
 
+// 浮点数按位比较，保证可以作为map的键
+impl PartialEq for EnumValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (EnumValue::Void, EnumValue::Void) => true,
+            (EnumValue::Bool(a), EnumValue::Bool(b)) => a == b,
+            (EnumValue::U8(a), EnumValue::U8(b)) => a == b,
+            (EnumValue::U16(a), EnumValue::U16(b)) => a == b,
+            (EnumValue::U32(a), EnumValue::U32(b)) => a == b,
+            (EnumValue::U64(a), EnumValue::U64(b)) => a == b,
+            (EnumValue::U128(a), EnumValue::U128(b)) => a == b,
+            (EnumValue::I8(a), EnumValue::I8(b)) => a == b,
+            (EnumValue::I16(a), EnumValue::I16(b)) => a == b,
+            (EnumValue::I32(a), EnumValue::I32(b)) => a == b,
+            (EnumValue::I64(a), EnumValue::I64(b)) => a == b,
+            (EnumValue::I128(a), EnumValue::I128(b)) => a == b,
+            (EnumValue::F32(a), EnumValue::F32(b)) => a.to_bits() == b.to_bits(),
+            (EnumValue::F64(a), EnumValue::F64(b)) => a.to_bits() == b.to_bits(),
+            (EnumValue::Str(a), EnumValue::Str(b)) => a == b,
+            (EnumValue::Bin(a), EnumValue::Bin(b)) => a == b,
+            (EnumValue::Arr(a), EnumValue::Arr(b)) => a == b,
+            (EnumValue::Map(a), EnumValue::Map(b)) => a == b,
+            (EnumValue::Struct(a), EnumValue::Struct(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for EnumValue {}
+
+impl Hash for EnumValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            EnumValue::Void => (),
+            EnumValue::Bool(v) => v.hash(state),
+            EnumValue::U8(v) => v.hash(state),
+            EnumValue::U16(v) => v.hash(state),
+            EnumValue::U32(v) => v.hash(state),
+            EnumValue::U64(v) => v.hash(state),
+            EnumValue::U128(v) => v.hash(state),
+            EnumValue::I8(v) => v.hash(state),
+            EnumValue::I16(v) => v.hash(state),
+            EnumValue::I32(v) => v.hash(state),
+            EnumValue::I64(v) => v.hash(state),
+            EnumValue::I128(v) => v.hash(state),
+            EnumValue::F32(v) => v.to_bits().hash(state),
+            EnumValue::F64(v) => v.to_bits().hash(state),
+            EnumValue::Str(v) => v.hash(state),
+            EnumValue::Bin(v) => v.hash(state),
+            EnumValue::Arr(v) => v.hash(state),
+            EnumValue::Map(v) => v.len().hash(state), // map无序，只计算长度
+            EnumValue::Struct(v) => v.hash(state),
+        }
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct ReadBuffer<'a> {
     // u8数组
@@ -118,6 +225,7 @@ pub enum ReadBonErr {
         act_type: (String, u8),
         head: usize,
     },
+    Io(Arc<io::Error>),
     Other(String),
 }
 
@@ -138,12 +246,26 @@ impl fmt::Display for ReadBonErr {
                 "ReadBonError TypeNoMatch try_read = {:?}, act_type = {:?}, head = {:?}",
                 try_read, act_type, head
             ),
+            ReadBonErr::Io(e) => write!(f, "ReadBonError Io error = {:?}", e),
             ReadBonErr::Other(s) => write!(f, "ReadBonError Other other = {:?}", s),
         }
     }
 }
 
-impl Error for ReadBonErr {}
+impl Error for ReadBonErr {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReadBonErr::Io(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadBonErr {
+    fn from(e: io::Error) -> Self {
+        ReadBonErr::Io(Arc::new(e))
+    }
+}
 
 impl ReadBonErr {
     fn overflow(try_index: usize, len: usize) -> ReadBonErr {
@@ -424,95 +546,34 @@ impl<'a> ReadBuffer<'a> {
     }
 
     pub fn read_bin(&mut self) -> Result<Vec<u8>, ReadBonErr> {
-        self.probe_border(1)?;
-        let t = self.bytes.get_u8(self.head);
-        self.head += 1;
-        let len: usize;
-        if t >= 111 && t <= 175 {
-            len = (t as usize) - 111;
-            self.head += len;
-        } else {
-            match t {
-                176 => {
-                    len = self.bytes.get_u8(self.head) as usize as usize;
-                    self.head += len + 1;
-                }
-                177 => {
-                    len = self.bytes.get_lu16(self.head) as usize;
-                    self.head += len + 2;
-                }
-                178 => {
-                    len = self.bytes.get_lu32(self.head) as usize;
-                    self.head += len + 4;
-                }
-                179 => {
-                    len = self.bytes.get_lu16(self.head) as usize
-                        + (self.bytes.get_lu32(self.head + 2) * 0x10000) as usize;
-                    self.head += len + 6;
-                }
-                _ => {
-                    return Err(ReadBonErr::type_no_match(
-                        "bin".to_string(),
-                        t,
-                        self.head - 1,
-                    ));
-                }
-            };
-        }
-
-        let mut dst = Vec::with_capacity(len);
-        unsafe {
-            dst.set_len(len);
-        }
-        (&mut dst).clone_from_slice(&self.bytes[self.head - len..self.head]);
-        Ok(dst)
+        Ok(self.read_bin_borrowed()?.to_vec())
     }
 
     pub fn read_utf8(&mut self) -> Result<String, ReadBonErr> {
-        self.probe_border(1)?;
-        let t = self.bytes.get_u8(self.head);
-        self.head += 1;
-        let len: usize;
-        if t >= 42 && t <= 106 {
-            len = t as usize - 42;
-            self.head += len;
-        } else {
-            match t {
-                107 => {
-                    len = self.bytes.get_u8(self.head) as usize as usize;
-                    self.head += len + 1;
-                }
-                108 => {
-                    len = self.bytes.get_lu16(self.head) as usize;
-                    self.head += len + 2;
-                }
-                109 => {
-                    len = self.bytes.get_lu32(self.head) as usize;
-                    self.head += len + 4;
-                }
-                110 => {
-                    len = self.bytes.get_lu16(self.head) as usize
-                        + (self.bytes.get_lu32(self.head + 2) * 0x10000) as usize;
-                    self.head += len + 6;
-                }
-                _ => {
-                    return Err(ReadBonErr::type_no_match(
-                        "string".to_string(),
-                        t,
-                        self.head - 1,
-                    ));
-                }
-            }
-        }
+        Ok(self.read_utf8_borrowed()?.to_string())
+    }
 
-        let mut dst = Vec::with_capacity(len);
-        unsafe {
-            dst.set_len(len);
-        }
-        (&mut dst).clone_from_slice(&self.bytes[self.head - len..self.head]);
-        match String::from_utf8(dst) {
+    /**
+     * @description 读出二进制数据，返回的切片直接引用读缓冲区，不分配内存
+     * @example
+     */
+    pub fn read_bin_borrowed(&mut self) -> Result<&'a [u8], ReadBonErr> {
+        self.read_data(111, "bin")
+    }
+
+    /**
+     * @description 读出UTF8字符串，返回的字符串直接引用读缓冲区，不分配内存
+     * @example
+     */
+    pub fn read_utf8_borrowed(&mut self) -> Result<&'a str, ReadBonErr> {
+        let head = self.head;
+        let bin = self.read_data(42, "string")?;
+        match std::str::from_utf8(bin) {
             Ok(s) => Ok(s),
-            Err(e) => Err(ReadBonErr::other(e.to_string())),
+            Err(e) => {
+                self.head = head;
+                Err(ReadBonErr::other(e.to_string()))
+            }
         }
     }
 
@@ -526,7 +587,7 @@ impl<'a> ReadBuffer<'a> {
         let len: u64;
         if t >= 180 && t <= 244 {
             len = t as u64 - 180;
-            self.head += len as usize;
+            self.head += 4;
         } else {
             match t {
                 245 => {
@@ -581,7 +642,11 @@ impl<'a> ReadBuffer<'a> {
             3 => Ok(EnumValue::F32(0.0)),
             4 => Ok(EnumValue::F32(1.0)),
             5 => {
-                panic!("16 bit floating-point number temporarily unsupported");
+                self.head -= 1;
+                Err(ReadBonErr::other(format!(
+                    "16 bit floating-point number temporarily unsupported, head: {}",
+                    self.head
+                )))
             }
             6 => {
                 self.head += 4;
@@ -592,10 +657,14 @@ impl<'a> ReadBuffer<'a> {
                 Ok(EnumValue::F64(self.bytes.get_lf64(self.head - 8)))
             }
             8 => {
-                panic!("128 bit floating-point number temporarily unsupported");
+                self.head -= 1;
+                Err(ReadBonErr::other(format!(
+                    "128 bit floating-point number temporarily unsupported, head: {}",
+                    self.head
+                )))
             }
             15 => Ok(EnumValue::I8(-1)),
-            16..36 => Ok(EnumValue::U8(first - 10)),
+            16..36 => Ok(EnumValue::U8(first - 16)),
             36 => {
                 self.head += 1;
                 Ok(EnumValue::U8(self.bytes.get_u8(self.head - 1)))
@@ -621,7 +690,7 @@ impl<'a> ReadBuffer<'a> {
             }
             41 => {
                 self.head += 16;
-                Ok(EnumValue::U128(self.bytes.get_lu128(self.head - 8) as u128))
+                Ok(EnumValue::U128(self.bytes.get_lu128(self.head - 16) as u128))
             }
             9 => {
                 self.head += 1;
@@ -660,8 +729,55 @@ impl<'a> ReadBuffer<'a> {
                 self.head -= 1;
                 Ok(EnumValue::Bin(self.read_bin()?))
             }
+            180..249 => {
+                self.head -= 1;
+                self.read_container_value()
+            }
+            _ => Err(ReadBonErr::type_no_match(
+                "value".to_string(),
+                first,
+                self.head - 1,
+            )),
+        }
+    }
+
+    // 读出容器值，通用数组为数组，通用map为map，Some包装容器和值序列容器返回错误，其它容器为结构体，字段名称为"#"加字段名称hash的16进制
+    fn read_container_value(&mut self) -> Result<EnumValue, ReadBonErr> {
+        let (t, mut body) = self.read_container_buffer()?;
+        if t == CONTAINER_SOME || t == CONTAINER_VALUES {
+            // Some包装容器和值序列容器的内容不是元素序列，没有对应的EnumValue形式
+            return Err(ReadBonErr::other(format!(
+                "container type has no value form, type: {}",
+                t
+            )));
+        }
+        let count = body.read_lengthen()?;
+        match t {
+            CONTAINER_ARRAY => {
+                let mut arr = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    arr.push(body.read()?);
+                }
+                Ok(EnumValue::Arr(Arc::new(arr)))
+            }
+            CONTAINER_MAP => {
+                let mut map = HashMap::with_capacity(count as usize);
+                for _ in 0..count {
+                    let key = body.read()?;
+                    map.insert(Arc::new(key), Arc::new(body.read()?));
+                }
+                Ok(EnumValue::Map(map))
+            }
             _ => {
-                panic!("other type TODO");
+                let mut fields = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let name = format!("#{:08x}", body.read_u32()?);
+                    fields.push(FieldValue {
+                        name,
+                        fvalue: body.read()?,
+                    });
+                }
+                Ok(EnumValue::Struct(Arc::new(StructValue { hash: t, fields })))
             }
         }
     }
@@ -729,8 +845,8 @@ impl<'a> ReadBuffer<'a> {
                     Ok(T::from(self.bytes.get_lu64(self.head - 8) as u64))
                 }
                 41 => {
-                    self.head += 8;
-                    Ok(T::from(self.bytes.get_lu128(self.head - 8) as u128))
+                    self.head += 16;
+                    Ok(T::from(self.bytes.get_lu128(self.head - 16) as u128))
                 }
                 _ => {
                     println!("read integer error, act_type: {}, bin: {:?}", t, self.bytes);
@@ -744,6 +860,172 @@ impl<'a> ReadBuffer<'a> {
         }
     }
 
+    /**
+     * @description 跳过下一个值，不解码
+     * @example
+     */
+    pub fn skip(&mut self) -> Result<(), ReadBonErr> {
+        self.head = self.value_end()?;
+        Ok(())
+    }
+
+    /**
+     * @description 读出只包含下一个值的子读缓冲区，不解码
+     * @example
+     */
+    pub fn read_value_buffer(&mut self) -> Result<ReadBuffer<'a>, ReadBonErr> {
+        let end = self.value_end()?;
+        let bytes = self.bytes;
+        let start = self.head;
+        self.head = end;
+        Ok(ReadBuffer::new(&bytes[start..end], 0))
+    }
+
+    /**
+     * @description 读出下一个容器的类型，和不包括容器类型的容器内容的子读缓冲区，不解码
+     * @example
+     */
+    pub fn read_container_buffer(&mut self) -> Result<(u32, ReadBuffer<'a>), ReadBonErr> {
+        let head = self.head;
+        let body = self.read_data(180, "container")?;
+        if body.len() < 4 {
+            self.head = head;
+            return Err(ReadBonErr::overflow(head + 4, self.bytes.len()));
+        }
+        Ok((body.get_lu32(0), ReadBuffer::new(&body[4..], 0)))
+    }
+
+    /**
+     * @description 获取当前位置的通用数组中指定序号的元素的子读缓冲区，序号越界则返回None，不移动头部指针
+     * @example
+     */
+    pub fn get_element(&self, index: usize) -> Result<Option<ReadBuffer<'a>>, ReadBonErr> {
+        let mut body = self.clone().read_typed_container(CONTAINER_ARRAY)?;
+        let count = body.read_lengthen()? as usize;
+        if index >= count {
+            return Ok(None);
+        }
+
+        for _ in 0..index {
+            body.skip()?;
+        }
+        Ok(Some(body.read_value_buffer()?))
+    }
+
+    /**
+     * @description 获取当前位置的通用对象中指定名称hash的字段值的子读缓冲区，没有此字段则返回None，不移动头部指针
+     * @example
+     */
+    pub fn get_field(&self, hash: u32) -> Result<Option<ReadBuffer<'a>>, ReadBonErr> {
+        let mut body = self.clone().read_typed_container(CONTAINER_OBJECT)?;
+        let count = body.read_lengthen()?;
+        for _ in 0..count {
+            if body.read_u32()? == hash {
+                return Ok(Some(body.read_value_buffer()?));
+            }
+            body.skip()?;
+        }
+        Ok(None)
+    }
+
+    /**
+     * @description 读出指定类型的容器内容的子读缓冲区，容器类型不匹配则返回错误，且不移动头部指针
+     * @example
+     */
+    pub fn read_typed_container(&mut self, t: u32) -> Result<ReadBuffer<'a>, ReadBonErr> {
+        let head = self.head;
+        let (tt, body) = self.read_container_buffer()?;
+        if tt != t {
+            self.head = head;
+            return Err(ReadBonErr::other(format!(
+                "container type no match, try_read = {:?}, act_type = {:?}, head = {:?}",
+                t, tt, head
+            )));
+        }
+        Ok(body)
+    }
+
+    // 获取下一个值的结束位置
+    fn value_end(&self) -> Result<usize, ReadBonErr> {
+        self.probe_border(1)?;
+        let t = self.bytes.get_u8(self.head);
+        let len = match t {
+            0..5 | 15..36 => 1,
+            6 => 5,
+            7 => 9,
+            9 | 36 => 2,
+            10 | 37 => 3,
+            11 | 38 => 5,
+            12 | 39 => 7,
+            13 | 40 => 9,
+            14 | 41 => 17,
+            5 => 3,
+            8 => 17,
+            42..111 => return Ok(self.data_range(42, "string")?.end),
+            111..180 => return Ok(self.data_range(111, "bin")?.end),
+            180..249 => return Ok(self.data_range(180, "container")?.end),
+            _ => {
+                return Err(ReadBonErr::type_no_match(
+                    "value".to_string(),
+                    t,
+                    self.head,
+                ));
+            }
+        };
+        self.probe_border(len)?;
+        Ok(self.head + len)
+    }
+
+    // 读出指定首字节的变长数据，即字符串、二进制数据或容器，与WriteBuffer::write_data对应，失败则不移动头部指针
+    fn read_data(&mut self, t: u8, try_read: &str) -> Result<&'a [u8], ReadBonErr> {
+        let range = self.data_range(t, try_read)?;
+        self.head = range.end;
+        let bytes = self.bytes;
+        Ok(&bytes[range])
+    }
+
+    // 获取指定首字节的变长数据的数据范围
+    fn data_range(&self, t: u8, try_read: &str) -> Result<Range<usize>, ReadBonErr> {
+        self.probe_border(1)?;
+        let first = self.bytes.get_u8(self.head);
+        if first < t || first > t + 68 {
+            return Err(ReadBonErr::type_no_match(
+                try_read.to_string(),
+                first,
+                self.head,
+            ));
+        }
+
+        let (len, len_bytes) = match first - t {
+            // 长度小于等于64， 本字节直接表达
+            l @ 0..65 => (l as usize, 0),
+            65 => {
+                self.probe_border(2)?;
+                (self.bytes.get_u8(self.head + 1) as usize, 1)
+            }
+            66 => {
+                self.probe_border(3)?;
+                (self.bytes.get_lu16(self.head + 1) as usize, 2)
+            }
+            67 => {
+                self.probe_border(5)?;
+                (self.bytes.get_lu32(self.head + 1) as usize, 4)
+            }
+            _ => {
+                self.probe_border(7)?;
+                (
+                    self.bytes.get_lu16(self.head + 1) as usize
+                        + ((self.bytes.get_lu32(self.head + 3) as usize) << 16),
+                    6,
+                )
+            }
+        };
+
+        let start = self.head + 1 + len_bytes;
+        self.probe_border(1 + len_bytes + len)?;
+        Ok(start..start + len)
+    }
+
     //探测边界， 如果越界， 返回错误
     #[inline]
     fn probe_border(&self, len: usize) -> Result<(), ReadBonErr> {
@@ -764,6 +1046,10 @@ pub struct WriteBuffer {
     pub bytes: Vec<u8>,
     // 尾部指针
     tail: usize,
+    // 是否为规范模式
+    is_canonical: bool,
+    // 规范模式下是否写入了无法规范编码的值
+    canonical_err: bool,
 }
 
 impl Deref for WriteBuffer {
@@ -805,12 +1091,16 @@ impl WriteBuffer {
         WriteBuffer {
             bytes: Vec::new(),
             tail: 0,
+            is_canonical: false,
+            canonical_err: false,
         }
     }
     pub fn with_bytes(buf: Vec<u8>, tail: usize) -> WriteBuffer {
         WriteBuffer {
             bytes: buf,
             tail: tail,
+            is_canonical: false,
+            canonical_err: false,
         }
     }
 
@@ -818,7 +1108,40 @@ impl WriteBuffer {
         WriteBuffer {
             bytes: Vec::with_capacity(size),
             tail: 0,
+            is_canonical: false,
+            canonical_err: false,
+        }
+    }
+
+    /**
+     * @description 创建指定模式的写缓冲区，规范模式下相等的值总是编码为相同的字节，可以用于计算hash和签名
+     * 规范模式下，map的键值对按键的编码排序；整数按数值选择最小的宽度，与整数的类型无关；
+     * 可以无损表示为32位浮点数的64位浮点数使用32位浮点数，-0.0编码为0.0；容器的长度使用最小的宽度
+     * NaN没有规范的编码，规范模式下拒绝写入NaN，try_write_f32和try_write_f64会返回错误；
+     * 通过Encode写入时无法返回错误，则不写入并记录错误，into_canonical会返回此错误，所以规范模式的数据应该通过into_canonical或encode_canonical获取
+     * @example
+     */
+    pub fn with_canonical(is_canonical: bool) -> WriteBuffer {
+        WriteBuffer {
+            bytes: Vec::new(),
+            tail: 0,
+            is_canonical: is_canonical,
+            canonical_err: false,
+        }
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        self.is_canonical
+    }
+
+    //获取规范模式下写入的数据，写入过无法规范编码的值，如NaN，则返回错误，此时的数据不是规范的，不能用于计算hash和签名
+    pub fn into_canonical(mut self) -> Result<Vec<u8>, ReadBonErr> {
+        if self.canonical_err {
+            return Err(ReadBonErr::other("write canonical failed, it's NaN".to_string()));
         }
+
+        self.bytes.truncate(self.tail);
+        Ok(self.bytes)
     }
 
     pub fn tail(&self) -> usize {
@@ -834,6 +1157,7 @@ impl WriteBuffer {
 
     pub fn clear(&mut self) {
         self.tail = 0;
+        self.canonical_err = false;
     }
 
     pub fn write_u8(&mut self, v: u8) {
@@ -895,41 +1219,67 @@ impl WriteBuffer {
     }
 
     pub fn write_f32(&mut self, v: f32) {
+        if self.try_write_f32(v).is_err() {
+            self.canonical_err = true;
+        }
+    }
+
+    //写入32位浮点数，规范模式下拒绝写入NaN，并返回错误
+    pub fn try_write_f32(&mut self, v: f32) -> Result<(), ReadBonErr> {
+        if self.is_canonical && v.is_nan() {
+            return Err(ReadBonErr::other("write canonical f32 failed, it's NaN".to_string()));
+        }
         if v == 0.0 {
             self.try_extend_capity(1);
             self.bytes.set_u8(3, self.tail);
             self.tail += 1;
-            return;
+            return Ok(());
         }
         if v == 1.0 {
             self.try_extend_capity(1);
             self.bytes.set_u8(4, self.tail);
             self.tail += 1;
-            return;
+            return Ok(());
         }
         self.try_extend_capity(5);
         self.bytes.set_u8(6, self.tail);
         self.bytes.set_lf32(v, self.tail + 1);
         self.tail += 5;
+        Ok(())
     }
 
     pub fn write_f64(&mut self, v: f64) {
+        if self.try_write_f64(v).is_err() {
+            self.canonical_err = true;
+        }
+    }
+
+    //写入64位浮点数，规范模式下拒绝写入NaN，并返回错误
+    pub fn try_write_f64(&mut self, v: f64) -> Result<(), ReadBonErr> {
+        if self.is_canonical {
+            if v.is_nan() {
+                return Err(ReadBonErr::other("write canonical f64 failed, it's NaN".to_string()));
+            } else if v as f32 as f64 == v {
+                return self.try_write_f32(v as f32);
+            }
+        }
         if v == 0.0 {
             self.try_extend_capity(1);
             self.bytes.set_u8(3, self.tail);
             self.tail += 1;
-            return;
+            return Ok(());
         }
         if v == 1.0 {
             self.try_extend_capity(1);
             self.bytes.set_u8(4, self.tail);
             self.tail += 1;
-            return;
+            return Ok(());
         }
         self.try_extend_capity(9);
         self.bytes.set_u8(7, self.tail);
         self.bytes.set_lf64(v, self.tail + 1);
         self.tail += 9;
+        Ok(())
     }
     /**
      * @description 写入一个动态长度，正整数，不允许大于0x20000000。
@@ -956,6 +1306,19 @@ impl WriteBuffer {
         }
     }
 
+    //写入指定类型的容器，先写入容器内容，再写入容器头，可以在任意位置写入嵌套的容器
+    pub fn write_typed_container<F>(&mut self, t: u32, write_next: F)
+    where
+        F: FnOnce(&mut WriteBuffer),
+    {
+        let mut body = WriteBuffer::with_canonical(self.is_canonical);
+        body.bytes.extend_from_slice(&t.to_le_bytes());
+        body.tail += 4;
+        write_next(&mut body);
+        self.canonical_err |= body.canonical_err;
+        self.write_data(&body.bytes[..body.tail], 180);
+    }
+
     //写字符串
     pub fn write_utf8(&mut self, s: &str) {
         self.write_data(s.as_bytes(), 42);
@@ -971,6 +1334,15 @@ impl WriteBuffer {
     where
         F: Fn(&mut WriteBuffer, &T),
     {
+        if self.is_canonical {
+            //规范模式下容器的长度使用最小的宽度，与预估大小无关
+            let mut body = WriteBuffer::with_canonical(true);
+            write_next(&mut body, o);
+            self.canonical_err |= body.canonical_err;
+            self.write_data(&body.bytes[..body.tail], 180);
+            return;
+        }
+
         let t = self.bytes.len();
         let len_bytes: usize; //描述容器长度的值的字节数
         let capacity = self.bytes.capacity();
@@ -1129,6 +1501,9 @@ impl WriteBuffer {
     }
 
     fn write_int32(&mut self, mut v: i32) {
+        if self.is_canonical {
+            return self.write_canonical_int(v < 0, (v as i64).abs() as u128);
+        }
         if v >= -1 && v < 20 {
             self.write_common(v as i8);
             return;
@@ -1142,6 +1517,9 @@ impl WriteBuffer {
     }
 
     fn write_int64(&mut self, mut v: i64) {
+        if self.is_canonical {
+            return self.write_canonical_int(v < 0, (v as i128).abs() as u128);
+        }
         if v >= -1 && v < 20 {
             self.write_common(v as i8);
             return;
@@ -1159,6 +1537,9 @@ impl WriteBuffer {
     }
 
     fn write_int128(&mut self, mut v: i128) {
+        if self.is_canonical {
+            return self.write_canonical_int(v < 0, v.wrapping_abs() as u128);
+        }
         if v >= -1 && v < 20 {
             self.write_common(v as i8);
             return;
@@ -1178,6 +1559,9 @@ impl WriteBuffer {
     }
 
     fn write_uint32(&mut self, v: u32) {
+        if self.is_canonical {
+            return self.write_canonical_int(false, v as u128);
+        }
         if v < 20 {
             self.write_common(v as i8);
         } else {
@@ -1186,6 +1570,9 @@ impl WriteBuffer {
     }
 
     fn write_uint64(&mut self, v: u64) {
+        if self.is_canonical {
+            return self.write_canonical_int(false, v as u128);
+        }
         if v < 20 {
             self.write_common(v as i8);
         } else if v <= 0xFFFFFFFF {
@@ -1196,6 +1583,9 @@ impl WriteBuffer {
     }
 
     fn write_uint128(&mut self, v: u128) {
+        if self.is_canonical {
+            return self.write_canonical_int(false, v as u128);
+        }
         if v < 20 {
             self.write_common(v as i8);
         } else if v <= 0xFFFFFFFF {
@@ -1251,6 +1641,102 @@ impl WriteBuffer {
         }
     }
 
+    //规范模式下写整数，按符号和绝对值选择最小的宽度
+    fn write_canonical_int(&mut self, is_negative: bool, v: u128) {
+        if is_negative && v == 1 {
+            self.write_common(-1);
+            return;
+        }
+        if !is_negative && v < 20 {
+            self.write_common(v as i8);
+            return;
+        }
+        let t = if is_negative { 9 } else { 36 };
+        if v <= 0xFF {
+            self.write_8(v as u8, t);
+        } else if v <= 0xFFFF {
+            self.write_16(v as u16, t + 1);
+        } else if v <= 0xFFFFFFFF {
+            self.write_32(v as u32, t + 2);
+        } else if v <= 0xFFFFFFFFFFFF {
+            self.write_48(v as u64, t + 3);
+        } else if v <= 0xFFFFFFFFFFFFFFFF {
+            self.write_64(v as u64, t + 4);
+        } else {
+            self.write_128(v, t + 5);
+        }
+    }
+
+    //写入map的所有键值对，规范模式下按键的编码排序
+    fn write_entries<'a, K, V, I>(&mut self, entries: I)
+    where
+        K: Encode + 'a,
+        V: Encode + 'a,
+        I: Iterator<Item = (&'a K, &'a V)>,
+    {
+        if !self.is_canonical {
+            for (k, v) in entries {
+                k.encode(self);
+                v.encode(self);
+            }
+            return;
+        }
+
+        let mut canonical_err = false;
+        let mut sorted: Vec<(Vec<u8>, &V)> = entries
+            .map(|(k, v)| {
+                let mut bb = WriteBuffer::with_canonical(true);
+                k.encode(&mut bb);
+                canonical_err |= bb.canonical_err;
+                bb.bytes.truncate(bb.tail);
+                (bb.bytes, v)
+            })
+            .collect();
+        self.canonical_err |= canonical_err;
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        for (k, v) in sorted {
+            self.write_raw(&k);
+            v.encode(self);
+        }
+    }
+
+    //写入集合的所有元素，规范模式下按元素的编码排序
+    fn write_elements<'a, T, I>(&mut self, elements: I)
+    where
+        T: Encode + 'a,
+        I: Iterator<Item = &'a T>,
+    {
+        if !self.is_canonical {
+            for v in elements {
+                v.encode(self);
+            }
+            return;
+        }
+
+        let mut canonical_err = false;
+        let mut sorted: Vec<Vec<u8>> = elements
+            .map(|v| {
+                let mut bb = WriteBuffer::with_canonical(true);
+                v.encode(&mut bb);
+                canonical_err |= bb.canonical_err;
+                bb.bytes.truncate(bb.tail);
+                bb.bytes
+            })
+            .collect();
+        self.canonical_err |= canonical_err;
+        sorted.sort();
+        for v in sorted {
+            self.write_raw(&v);
+        }
+    }
+
+    //写入已编码的数据
+    fn write_raw(&mut self, arr: &[u8]) {
+        self.try_extend_capity(arr.len());
+        self.bytes.set(arr, self.tail);
+        self.tail += arr.len();
+    }
+
     //写常用数字-1~19
     #[inline]
     fn write_common(&mut self, v: i8) {
@@ -1535,6 +2021,11 @@ pub trait Decode: Sized {
     fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr>;
 }
 
+// 借用解码，解码结果可以直接引用读缓冲区中的字符串和二进制数据，避免分配内存
+pub trait DecodeBorrowed<'a>: Sized {
+    fn decode_borrowed(bb: &mut ReadBuffer<'a>) -> Result<Self, ReadBonErr>;
+}
+
 impl Encode for u8 {
     fn encode(&self, bb: &mut WriteBuffer) {
         bb.write_u8(self.clone());
@@ -1727,20 +2218,17 @@ impl Decode for String {
     }
 }
 
-impl<K: Encode + Eq + Hash, V: Encode> Encode for HashMap<K, V> {
+impl<K: Encode + Eq + Hash, V: Encode, S: BuildHasher> Encode for HashMap<K, V, S> {
     fn encode(&self, bb: &mut WriteBuffer) {
         //self.typeid().encode(bb);
         self.len().encode(bb);
-        for (k, v) in self.iter() {
-            k.encode(bb);
-            v.encode(bb);
-        }
+        bb.write_entries(self.iter());
     }
 }
 
-impl<K: Decode + Eq + Hash, V: Decode> Decode for HashMap<K, V> {
+impl<K: Decode + Eq + Hash, V: Decode, S: BuildHasher + Default> Decode for HashMap<K, V, S> {
     fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
-        let mut map = HashMap::new();
+        let mut map = HashMap::default();
         let count = usize::decode(bb)?;
         for _ in 0..count {
             map.insert(K::decode(bb)?, V::decode(bb)?);
@@ -1769,11 +2257,18 @@ impl<T: Decode> Decode for Vec<T> {
     }
 }
 
+// None为null，Some为值本身，值为null或可选值容器时写入可选值容器，避免与None或外层的Some混淆，与serde的格式相同
 impl<T: Encode> Encode for Option<T> {
     fn encode(&self, bb: &mut WriteBuffer) {
         match self {
             &Some(ref v) => {
+                let start = bb.tail;
                 v.encode(bb);
+                if is_some_wrapped(&bb.bytes[start..bb.tail]) {
+                    let bin = bb.bytes[start..bb.tail].to_vec();
+                    bb.tail = start;
+                    bb.write_typed_container(CONTAINER_SOME, |bb| bb.write_raw(&bin));
+                }
             }
             &None => {
                 bb.write_nil();
@@ -1784,9 +2279,419 @@ impl<T: Encode> Encode for Option<T> {
 
 impl<T: Decode> Decode for Option<T> {
     fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        if bb.is_nil()? {
+            return Ok(None);
+        }
+
+        // 可选值容器的内容为Some的值
+        let head = bb.head;
+        if let Ok(mut body) = bb.read_typed_container(CONTAINER_SOME) {
+            return Ok(Some(T::decode(&mut body)?));
+        }
+        bb.head = head;
+        Ok(Some(T::decode(bb)?))
+    }
+}
+
+// 判断Some的值是否需要写入可选值容器，值为null或可选值容器时需要写入
+fn is_some_wrapped(bin: &[u8]) -> bool {
+    match bin.first() {
+        None | Some(0) => true,
+        Some(_) => match ReadBuffer::new(bin, 0).read_container_buffer() {
+            Ok((t, _)) => t == CONTAINER_SOME,
+            Err(_) => false,
+        },
+    }
+}
+
+// 集合和队列与Vec的格式相同，先写入元素数量，再写入所有元素
+impl<T: Encode + Eq + Hash, S: BuildHasher> Encode for HashSet<T, S> {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        self.len().encode(bb);
+        bb.write_elements(self.iter());
+    }
+}
+
+impl<T: Decode + Eq + Hash, S: BuildHasher + Default> Decode for HashSet<T, S> {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        let count = usize::decode(bb)?;
+        let mut set = HashSet::default();
+        for _ in 0..count {
+            set.insert(T::decode(bb)?);
+        }
+        Ok(set)
+    }
+}
+
+impl<T: Encode + Ord> Encode for BTreeSet<T> {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        self.len().encode(bb);
+        for v in self.iter() {
+            v.encode(bb);
+        }
+    }
+}
+
+impl<T: Decode + Ord> Decode for BTreeSet<T> {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        let count = usize::decode(bb)?;
+        let mut set = BTreeSet::new();
+        for _ in 0..count {
+            set.insert(T::decode(bb)?);
+        }
+        Ok(set)
+    }
+}
+
+impl<K: Encode + Ord, V: Encode> Encode for BTreeMap<K, V> {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        self.len().encode(bb);
+        for (k, v) in self.iter() {
+            k.encode(bb);
+            v.encode(bb);
+        }
+    }
+}
+
+impl<K: Decode + Ord, V: Decode> Decode for BTreeMap<K, V> {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        let count = usize::decode(bb)?;
+        let mut map = BTreeMap::new();
+        for _ in 0..count {
+            map.insert(K::decode(bb)?, V::decode(bb)?);
+        }
+        Ok(map)
+    }
+}
+
+impl<T: Encode> Encode for VecDeque<T> {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        self.len().encode(bb);
+        for v in self.iter() {
+            v.encode(bb);
+        }
+    }
+}
+
+impl<T: Decode> Decode for VecDeque<T> {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        let count = usize::decode(bb)?;
+        let mut deque = VecDeque::new();
+        for _ in 0..count {
+            deque.push_back(T::decode(bb)?);
+        }
+        Ok(deque)
+    }
+}
+
+// 定长数组与Vec的格式相同，解码时元素数量必须与数组长度相同
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        N.encode(bb);
+        for v in self.iter() {
+            v.encode(bb);
+        }
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        let head = bb.head();
+        let vec = Vec::<T>::decode(bb)?;
+        let len = vec.len();
+        <[T; N]>::try_from(vec).map_err(|_| {
+            ReadBonErr::other(format!(
+                "decode array failed, head: {}, len: {}, expect len: {}",
+                head, len, N
+            ))
+        })
+    }
+}
+
+// 智能指针与被指向的值的格式相同
+macro_rules! impl_pointer {
+    ($($p: ident),*) => {
+        $(
+            impl<T: Encode> Encode for $p<T> {
+                fn encode(&self, bb: &mut WriteBuffer) {
+                    self.as_ref().encode(bb);
+                }
+            }
+
+            impl<T: Decode> Decode for $p<T> {
+                fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+                    Ok($p::new(T::decode(bb)?))
+                }
+            }
+        )*
+    };
+}
+
+impl_pointer!(Box, Arc, Rc);
+
+// 与派生的枚举和serde的格式相同，即只有一个字段的通用对象，字段名称hash为变体名称Ok或Err的name_hash，字段值为变体的值
+impl<T: Encode, E: Encode> Encode for Result<T, E> {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        bb.write_typed_container(CONTAINER_OBJECT, |bb| {
+            bb.write_lengthen(1);
+            match self {
+                Ok(v) => {
+                    bb.write_u32(name_hash("Ok"));
+                    v.encode(bb);
+                }
+                Err(e) => {
+                    bb.write_u32(name_hash("Err"));
+                    e.encode(bb);
+                }
+            }
+        });
+    }
+}
+
+impl<T: Decode, E: Decode> Decode for Result<T, E> {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        let head = bb.head();
+        let mut body = bb.read_typed_container(CONTAINER_OBJECT)?;
+        let count = body.read_lengthen()?;
+        let tag = body.read_u32()?;
+        if count == 1 && tag == name_hash("Ok") {
+            Ok(Ok(T::decode(&mut body)?))
+        } else if count == 1 && tag == name_hash("Err") {
+            Ok(Err(E::decode(&mut body)?))
+        } else {
+            bb.head = head;
+            Err(ReadBonErr::other(format!(
+                "decode Result failed, head: {}, tag: {}, field count: {}",
+                head, tag, count
+            )))
+        }
+    }
+}
+
+// 秒数和纳秒数
+impl Encode for Duration {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        bb.write_u64(self.as_secs());
+        bb.write_u32(self.subsec_nanos());
+    }
+}
+
+impl Decode for Duration {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        let secs = bb.read_u64()?;
+        let nanos = bb.read_u32()?;
+        if nanos >= 1_000_000_000 {
+            return Err(ReadBonErr::other(format!(
+                "decode Duration failed, invalid nanos: {}",
+                nanos
+            )));
+        }
+        Ok(Duration::new(secs, nanos))
+    }
+}
+
+// 字符编码为只有一个字符的utf8字符串，与serde的格式相同
+impl Encode for char {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        bb.write_utf8(self.encode_utf8(&mut [0; 4]));
+    }
+}
+
+impl Decode for char {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        let head = bb.head;
+        let s = bb.read_utf8_borrowed()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => {
+                bb.head = head;
+                Err(ReadBonErr::other(format!("decode char failed, invalid string: {:?}, head: {:?}", s, head)))
+            }
+        }
+    }
+}
+
+impl Encode for () {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        bb.write_nil();
+    }
+}
+
+impl Decode for () {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        match bb.is_nil()? {
+            true => Ok(()),
+            false => Err(ReadBonErr::type_no_match(
+                "null".to_string(),
+                bb.get_type()?,
+                bb.head(),
+            )),
+        }
+    }
+}
+
+// 元组依次写入所有元素，不写入元素数量
+macro_rules! impl_tuple {
+    ($($name: ident)+) => {
+        impl<$($name: Encode),+> Encode for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode(&self, bb: &mut WriteBuffer) {
+                let ($(ref $name,)+) = *self;
+                $($name.encode(bb);)+
+            }
+        }
+
+        impl<$($name: Decode),+> Decode for ($($name,)+) {
+            fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+                Ok(($($name::decode(bb)?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple!(A);
+impl_tuple!(A B);
+impl_tuple!(A B C);
+impl_tuple!(A B C D);
+impl_tuple!(A B C D E);
+impl_tuple!(A B C D E F);
+impl_tuple!(A B C D E F G);
+impl_tuple!(A B C D E F G H);
+impl_tuple!(A B C D E F G H I);
+impl_tuple!(A B C D E F G H I J);
+impl_tuple!(A B C D E F G H I J K);
+impl_tuple!(A B C D E F G H I J K L);
+
+impl Encode for EnumValue {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        match self {
+            EnumValue::Void => bb.write_nil(),
+            EnumValue::Bool(v) => bb.write_bool(*v),
+            EnumValue::U8(v) => bb.write_u8(*v),
+            EnumValue::U16(v) => bb.write_u16(*v),
+            EnumValue::U32(v) => bb.write_u32(*v),
+            EnumValue::U64(v) => bb.write_u64(*v),
+            EnumValue::U128(v) => bb.write_u128(*v),
+            EnumValue::I8(v) => bb.write_i8(*v),
+            EnumValue::I16(v) => bb.write_i16(*v),
+            EnumValue::I32(v) => bb.write_i32(*v),
+            EnumValue::I64(v) => bb.write_i64(*v),
+            EnumValue::I128(v) => bb.write_i128(*v),
+            EnumValue::F32(v) => bb.write_f32(*v),
+            EnumValue::F64(v) => bb.write_f64(*v),
+            EnumValue::Str(v) => bb.write_utf8(v),
+            EnumValue::Bin(v) => bb.write_bin(v, 0..v.len()),
+            EnumValue::Arr(arr) => bb.write_typed_container(CONTAINER_ARRAY, |bb| {
+                bb.write_lengthen(arr.len() as u32);
+                for v in arr.iter() {
+                    v.encode(bb);
+                }
+            }),
+            EnumValue::Map(map) => bb.write_typed_container(CONTAINER_MAP, |bb| {
+                bb.write_lengthen(map.len() as u32);
+                bb.write_entries(map.iter().map(|(k, v)| (k.as_ref(), v.as_ref())));
+            }),
+            EnumValue::Struct(v) => bb.write_typed_container(v.hash, |bb| {
+                bb.write_lengthen(v.fields.len() as u32);
+                for field in v.fields.iter() {
+                    bb.write_u32(field_name_hash(&field.name));
+                    field.fvalue.encode(bb);
+                }
+            }),
+        }
+    }
+}
+
+impl Decode for EnumValue {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        bb.read()
+    }
+}
+
+// 获取结构体值的字段名称hash，"#"加16进制的字段名称直接使用此hash
+fn field_name_hash(name: &str) -> u32 {
+    if name.len() == 9 && name.starts_with('#') {
+        if let Ok(hash) = u32::from_str_radix(&name[1..], 16) {
+            return hash;
+        }
+    }
+    name_hash(name)
+}
+
+impl<'a> Encode for &'a str {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        bb.write_utf8(self);
+    }
+}
+
+impl<'a> Encode for &'a [u8] {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        bb.write_bin(self, 0..self.len());
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for &'a str {
+    fn decode_borrowed(bb: &mut ReadBuffer<'a>) -> Result<Self, ReadBonErr> {
+        bb.read_utf8_borrowed()
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for &'a [u8] {
+    fn decode_borrowed(bb: &mut ReadBuffer<'a>) -> Result<Self, ReadBonErr> {
+        bb.read_bin_borrowed()
+    }
+}
+
+// 不包含引用的类型，借用解码与解码相同
+macro_rules! impl_decode_borrowed {
+    ($($t: ty),*) => {
+        $(
+            impl<'a> DecodeBorrowed<'a> for $t {
+                fn decode_borrowed(bb: &mut ReadBuffer<'a>) -> Result<Self, ReadBonErr> {
+                    <$t as Decode>::decode(bb)
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_borrowed!(
+    bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, String
+);
+
+impl<'a, K, V, S> DecodeBorrowed<'a> for HashMap<K, V, S>
+where
+    K: DecodeBorrowed<'a> + Eq + Hash,
+    V: DecodeBorrowed<'a>,
+    S: BuildHasher + Default,
+{
+    fn decode_borrowed(bb: &mut ReadBuffer<'a>) -> Result<Self, ReadBonErr> {
+        let mut map = HashMap::default();
+        let count = usize::decode(bb)?;
+        for _ in 0..count {
+            map.insert(K::decode_borrowed(bb)?, V::decode_borrowed(bb)?);
+        }
+        Ok(map)
+    }
+}
+
+impl<'a, T: DecodeBorrowed<'a>> DecodeBorrowed<'a> for Vec<T> {
+    fn decode_borrowed(bb: &mut ReadBuffer<'a>) -> Result<Self, ReadBonErr> {
+        let count = usize::decode(bb)?;
+        let mut vec = Vec::new();
+        for _ in 0..count {
+            vec.push(T::decode_borrowed(bb)?);
+        }
+        Ok(vec)
+    }
+}
+
+impl<'a, T: DecodeBorrowed<'a>> DecodeBorrowed<'a> for Option<T> {
+    fn decode_borrowed(bb: &mut ReadBuffer<'a>) -> Result<Self, ReadBonErr> {
         match bb.is_nil()? {
             true => Ok(None),
-            false => Ok(Some(T::decode(bb)?)),
+            false => Ok(Some(T::decode_borrowed(bb)?)),
         }
     }
 }
@@ -2271,6 +3176,306 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_borrowed() -> Result<(), Box<dyn Error>> {
+        let arr: Vec<u8> = (0..1000).map(|_| thread_rng().gen::<u8>()).collect();
+        let mut buf = WriteBuffer::new();
+        "abc".encode(&mut buf);
+        (&arr[..]).encode(&mut buf);
+        vec![Some("x"), None, Some("yz")].encode(&mut buf);
+        buf.write_u32(7);
+
+        let bytes = buf.unwrap();
+        let mut read_buf = ReadBuffer::new(&bytes, 0);
+        let s = <&str>::decode_borrowed(&mut read_buf)?;
+        assert_eq!(s, "abc");
+        assert_eq!(s.as_ptr(), bytes[1..].as_ptr());
+        assert_eq!(<&[u8]>::decode_borrowed(&mut read_buf)?, &arr[..]);
+        assert_eq!(Vec::<Option<&str>>::decode_borrowed(&mut read_buf)?, vec![Some("x"), None, Some("yz")]);
+        assert_eq!(u32::decode_borrowed(&mut read_buf)?, 7);
+
+        // 类型不匹配或越界时，不移动头部指针
+        let mut read_buf = ReadBuffer::new(&bytes, 0);
+        assert!(read_buf.read_bin_borrowed().is_err());
+        assert_eq!(read_buf.head(), 0);
+        let mut read_buf = ReadBuffer::new(&bytes[..10], 4);
+        assert!(read_buf.read_bin_borrowed().is_err());
+        assert_eq!(read_buf.head(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collections() -> Result<(), Box<dyn Error>> {
+        let mut hash_set = HashSet::new();
+        hash_set.insert("a".to_string());
+        hash_set.insert("b".to_string());
+        let mut btree_map = BTreeMap::new();
+        btree_map.insert(3u32, vec![(1u8, 'x')]);
+        btree_map.insert(1u32, vec![]);
+        let btree_set: BTreeSet<i64> = [-300i64, 5, 70000].iter().cloned().collect();
+        let deque: VecDeque<u16> = (0..10).collect();
+        let tuple = (1u8, -2i16, "c".to_string(), true, 5u32, 6u64, 7i8, 8i32, 9i64, 10usize, 11isize, Some(12u8));
+        let ok: Result<u32, String> = Ok(7);
+        let err: Result<u32, String> = Err("e".to_string());
+
+        let mut buf = WriteBuffer::new();
+        hash_set.encode(&mut buf);
+        btree_map.encode(&mut buf);
+        btree_set.encode(&mut buf);
+        deque.encode(&mut buf);
+        tuple.encode(&mut buf);
+        [1u32, 2, 3].encode(&mut buf);
+        Box::new(1u8).encode(&mut buf);
+        Arc::new("arc".to_string()).encode(&mut buf);
+        Rc::new(-1i32).encode(&mut buf);
+        ok.encode(&mut buf);
+        err.encode(&mut buf);
+        Duration::new(5, 999_999_999).encode(&mut buf);
+        '中'.encode(&mut buf);
+        ().encode(&mut buf);
+
+        let mut read_buf = ReadBuffer::new(buf.get_byte(), 0);
+        assert_eq!(HashSet::<String>::decode(&mut read_buf)?, hash_set);
+        assert_eq!(BTreeMap::<u32, Vec<(u8, char)>>::decode(&mut read_buf)?, btree_map);
+        assert_eq!(BTreeSet::<i64>::decode(&mut read_buf)?, btree_set);
+        assert_eq!(VecDeque::<u16>::decode(&mut read_buf)?, deque);
+        assert_eq!(<(u8, i16, String, bool, u32, u64, i8, i32, i64, usize, isize, Option<u8>)>::decode(&mut read_buf)?, tuple);
+        assert_eq!(<[u32; 3]>::decode(&mut read_buf)?, [1, 2, 3]);
+        assert_eq!(*Box::<u8>::decode(&mut read_buf)?, 1);
+        assert_eq!(*Arc::<String>::decode(&mut read_buf)?, "arc");
+        assert_eq!(*Rc::<i32>::decode(&mut read_buf)?, -1);
+        assert_eq!(Result::<u32, String>::decode(&mut read_buf)?, ok);
+        assert_eq!(Result::<u32, String>::decode(&mut read_buf)?, err);
+        assert_eq!(Duration::decode(&mut read_buf)?, Duration::new(5, 999_999_999));
+        assert_eq!(char::decode(&mut read_buf)?, '中');
+        <()>::decode(&mut read_buf)?;
+        assert_eq!(read_buf.head(), buf.tail());
+
+        // 数组长度不匹配
+        let mut buf = WriteBuffer::new();
+        vec![1u32, 2].encode(&mut buf);
+        assert!(<[u32; 3]>::decode(&mut ReadBuffer::new(buf.get_byte(), 0)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_option() -> Result<(), Box<dyn Error>> {
+        // 值为null或可选值容器的Some写入可选值容器，不会与None或外层的Some混淆
+        let mut buf = WriteBuffer::new();
+        Some(()).encode(&mut buf);
+        None::<()>.encode(&mut buf);
+        Some(None::<u8>).encode(&mut buf);
+        Some(Some(None::<u8>)).encode(&mut buf);
+        Some(Some(5u8)).encode(&mut buf);
+        Some(vec![Some(()), None]).encode(&mut buf);
+
+        let mut read_buf = ReadBuffer::new(buf.get_byte(), 0);
+        assert_eq!(Option::<()>::decode(&mut read_buf)?, Some(()));
+        assert_eq!(Option::<()>::decode(&mut read_buf)?, None);
+        assert_eq!(Option::<Option<u8>>::decode(&mut read_buf)?, Some(None));
+        assert_eq!(Option::<Option<Option<u8>>>::decode(&mut read_buf)?, Some(Some(None)));
+        assert_eq!(Option::<Option<u8>>::decode(&mut read_buf)?, Some(Some(5)));
+        assert_eq!(Option::<Vec<Option<()>>>::decode(&mut read_buf)?, Some(vec![Some(()), None]));
+        assert_eq!(read_buf.head(), buf.tail());
+
+        // 不需要写入可选值容器的Some为值本身
+        let mut buf = WriteBuffer::new();
+        Some(5u8).encode(&mut buf);
+        assert_eq!(ReadBuffer::new(buf.get_byte(), 0).read_u8()?, 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonical() -> Result<(), Box<dyn Error>> {
+        // 相同数值的不同类型整数编码相同
+        let mut buf1 = WriteBuffer::with_canonical(true);
+        let mut buf2 = WriteBuffer::with_canonical(true);
+        buf1.write_i32(200);
+        buf1.write_i64(-200);
+        buf1.write_i64(0x80000000);
+        buf1.write_u128(0x1000000000000);
+        buf1.write_f64(1.5);
+        buf1.write_f64(-0.0);
+        buf2.write_u8(200);
+        buf2.write_i16(-200);
+        buf2.write_u32(0x80000000);
+        buf2.write_u64(0x1000000000000);
+        buf2.write_f32(1.5);
+        buf2.write_f32(0.0);
+        assert_eq!(buf1.get_byte(), buf2.get_byte());
+
+        let mut read_buf = ReadBuffer::new(buf1.get_byte(), 0);
+        assert_eq!(read_buf.read_i32()?, 200);
+        assert_eq!(read_buf.read_i64()?, -200);
+        assert_eq!(read_buf.read_i64()?, 0x80000000);
+        assert_eq!(read_buf.read_u64()?, 0x1000000000000);
+        assert_eq!(read_buf.read_f64()?, 1.5);
+        assert_eq!(read_buf.read_f64()?, 0.0);
+        for v in [std::i64::MIN, std::i64::MAX, -1, 19, 20, -0x80].iter() {
+            let mut buf = WriteBuffer::with_canonical(true);
+            buf.write_i64(*v);
+            assert_eq!(ReadBuffer::new(buf.get_byte(), 0).read_i64()?, *v);
+        }
+
+        // map按键的编码排序，与插入顺序无关
+        let mut map1 = HashMap::new();
+        let mut map2 = HashMap::new();
+        (0..100).for_each(|i| { map1.insert(i.to_string(), vec![i]); });
+        (0..100).rev().for_each(|i| { map2.insert(i.to_string(), vec![i]); });
+        let mut buf1 = WriteBuffer::with_canonical(true);
+        let mut buf2 = WriteBuffer::with_canonical(true);
+        map1.encode(&mut buf1);
+        map2.encode(&mut buf2);
+        assert_eq!(buf1.get_byte(), buf2.get_byte());
+        assert_eq!(HashMap::<String, Vec<u32>>::decode(&mut ReadBuffer::new(buf1.get_byte(), 0))?, map1);
+
+        // 容器中的值也使用规范模式
+        let mut buf = WriteBuffer::with_canonical(true);
+        buf.write_typed_container(CONTAINER_ARRAY, |bb| {
+            bb.write_lengthen(1);
+            bb.write_i32(200);
+        });
+        assert_eq!(buf.get_byte()[buf.tail() - 2..], [36, 200]);
+
+        // 规范模式下容器的长度使用最小的宽度，与预估大小无关
+        let mut buf1 = WriteBuffer::with_canonical(true);
+        let mut buf2 = WriteBuffer::with_canonical(true);
+        buf1.write_container(&200i64, |bb, v| bb.write_i64(*v), None);
+        buf2.write_container(&200u8, |bb, v| bb.write_u8(*v), Some(1));
+        assert_eq!(buf1.into_canonical()?, vec![182, 36, 200]);
+        assert_eq!(buf2.into_canonical()?, vec![182, 36, 200]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonical_nan() -> Result<(), ReadBonErr> {
+        // 规范模式下拒绝写入NaN
+        let mut buf = WriteBuffer::with_canonical(true);
+        buf.try_write_f32(1.5)?;
+        assert!(buf.try_write_f64(std::f64::NAN).is_err());
+        assert!(buf.try_write_f32(std::f32::NAN).is_err());
+        assert_eq!(buf.tail(), 5);
+        assert!(buf.clone().into_canonical()? == vec![6, 0, 0, 192, 63]);
+        buf.write_f64(std::f64::NAN);
+        assert_eq!(buf.tail(), 5);
+        assert!(buf.clone().into_canonical().is_err());
+        buf.clear();
+        assert!(buf.into_canonical()?.is_empty());
+
+        // 容器、map的键和集合元素中的NaN也会返回错误
+        assert!(encode_canonical(&vec![1.0f32, std::f32::NAN]).is_err());
+        let mut buf = WriteBuffer::with_canonical(true);
+        buf.write_typed_container(CONTAINER_ARRAY, |bb| {
+            bb.write_lengthen(1);
+            bb.write_f32(std::f32::NAN);
+        });
+        assert!(buf.into_canonical().is_err());
+        let mut buf = WriteBuffer::with_canonical(true);
+        buf.write_container(&std::f32::NAN, |bb, v| bb.write_f32(*v), None);
+        assert!(buf.into_canonical().is_err());
+        let mut map = HashMap::new();
+        map.insert(EnumValue::F64(std::f64::NAN), 1u8);
+        assert!(encode_canonical(&map).is_err());
+        let mut set = HashSet::new();
+        set.insert(EnumValue::F32(std::f32::NAN));
+        assert!(encode_canonical(&set).is_err());
+
+        // 非规范模式下正常写入NaN
+        let mut buf = WriteBuffer::new();
+        buf.try_write_f64(std::f64::NAN)?;
+        assert!(ReadBuffer::new(buf.get_byte(), 0).read_f64()?.is_nan());
+        assert!(buf.into_canonical().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor() -> Result<(), Box<dyn Error>> {
+        // { 1: "header", 2: [0, "a", [bin; 300], {}], 3: 99 }
+        let big: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let mut buf = WriteBuffer::new();
+        buf.write_bool(true);
+        buf.write_typed_container(CONTAINER_OBJECT, |bb| {
+            bb.write_lengthen(3);
+            bb.write_u32(1);
+            bb.write_utf8("header");
+            bb.write_u32(2);
+            bb.write_typed_container(CONTAINER_ARRAY, |bb| {
+                bb.write_lengthen(4);
+                bb.write_u8(0);
+                bb.write_utf8("a");
+                bb.write_bin(&big, 0..big.len());
+                bb.write_typed_container(CONTAINER_OBJECT, |bb| bb.write_lengthen(0));
+            });
+            bb.write_u32(3);
+            bb.write_i64(99);
+        });
+        buf.write_f64(0.5);
+
+        let bytes = buf.unwrap();
+        let mut read_buf = ReadBuffer::new(&bytes, 0);
+        read_buf.skip()?;
+        let head = read_buf.head();
+        assert_eq!(read_buf.get_field(1)?.unwrap().read_utf8_borrowed()?, "header");
+        assert_eq!(read_buf.get_field(3)?.unwrap().read_i64()?, 99);
+        assert!(read_buf.get_field(4)?.is_none());
+        assert!(read_buf.get_element(0).is_err());
+        assert_eq!(read_buf.head(), head);
+
+        let arr = read_buf.get_field(2)?.unwrap();
+        assert_eq!(arr.get_element(1)?.unwrap().read_utf8()?, "a");
+        assert_eq!(arr.get_element(2)?.unwrap().read_bin()?, big);
+        assert_eq!(arr.get_element(3)?.unwrap().get_field(1)?.is_none(), true);
+        assert!(arr.get_element(4)?.is_none());
+
+        // 跳过整个容器
+        let (t, _) = read_buf.clone().read_container_buffer()?;
+        assert_eq!(t, CONTAINER_OBJECT);
+        read_buf.skip()?;
+        assert_eq!(read_buf.read_f64()?, 0.5);
+        assert!(read_buf.skip().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_container_head() -> Result<(), Box<dyn Error>> {
+        // 短容器的首字节直接表达长度，读容器时头部指针只跳过首字节和容器类型，即从容器内容开始读
+        let mut buf = WriteBuffer::new();
+        buf.write_typed_container(CONTAINER_MAP, |bb| {
+            bb.write_lengthen(1);
+            bb.write_u8(7);
+            bb.write_utf8("abc");
+        });
+        buf.write_u8(9);
+        let bytes = buf.unwrap();
+        let mut read_buf = ReadBuffer::new(&bytes, 0);
+        let (t, count, value) = read_buf.read_container(|bb, t, len| {
+            assert_eq!(len as usize, bytes.len() - 2);
+            Ok((t, bb.read_lengthen()?, (bb.read_u8()?, bb.read_utf8()?)))
+        })?;
+        assert_eq!(t, CONTAINER_MAP);
+        assert_eq!(count, 1);
+        assert_eq!(value, (7, "abc".to_string()));
+        assert_eq!(read_buf.read_u8()?, 9);
+
+        // 跳过16位和128位浮点数
+        let mut bytes = vec![5, 0, 0x3c, 8];
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.push(2);
+        let mut read_buf = ReadBuffer::new(&bytes, 0);
+        read_buf.skip()?;
+        assert_eq!(read_buf.head(), 3);
+        read_buf.skip()?;
+        assert_eq!(read_buf.read_bool()?, true);
+        assert!(ReadBuffer::new(&bytes[0..2], 0).skip().is_err());
+
+        Ok(())
+    }
+
     //测试大小比较
     #[test]
     fn test_ord() {
@@ -0,0 +1,1027 @@
+/**
+ * bon格式的serde序列化和反序列化
+ * 结构体序列化为通用对象，字段名称使用name_hash；序列、元组和元组结构体序列化为通用数组；map序列化为通用map
+ * 枚举序列化为只有一个字段的通用对象，字段名称为变体名称，字段值为变体的值，单元变体的值为null
+ * None序列化为null，Some序列化为值本身，值本身为null或可选值容器时，序列化为包含值的可选值容器，以区分Some(None)、Some(())和None
+ * char序列化为只有一个字符的字符串，与Encode for char相同
+ * 反序列化结构体时，未知的字段名称转换为"#"加字段名称hash的16进制的字符串
+ */
+use std::fmt;
+
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+use serde::ser::{
+    self, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant,
+};
+use serde::{Deserialize, Serialize};
+
+use data_view::GetView;
+
+use super::{
+    name_hash, ReadBonErr, ReadBuffer, WriteBuffer, CONTAINER_ARRAY, CONTAINER_MAP,
+    CONTAINER_OBJECT, CONTAINER_SOME,
+};
+
+impl ser::Error for ReadBonErr {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ReadBonErr::other(msg.to_string())
+    }
+}
+
+impl de::Error for ReadBonErr {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ReadBonErr::other(msg.to_string())
+    }
+}
+
+/**
+ * @description 将指定的值序列化为bon格式
+ * @example
+ */
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, ReadBonErr> {
+    let mut bb = WriteBuffer::new();
+    value.serialize(&mut bb)?;
+    Ok(bb.unwrap())
+}
+
+/**
+ * @description 将指定的值以规范模式序列化为bon格式，相等的值总是序列化为相同的字节，见WriteBuffer::with_canonical
+ * @example
+ */
+pub fn to_canonical_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, ReadBonErr> {
+    let mut bb = WriteBuffer::with_canonical(true);
+    value.serialize(&mut bb)?;
+    bb.into_canonical()
+}
+
+/**
+ * @description 从bon格式反序列化指定的值，值可以引用指定的二进制数据
+ * @example
+ */
+pub fn from_bytes<'de, T: Deserialize<'de>>(bin: &'de [u8]) -> Result<T, ReadBonErr> {
+    T::deserialize(&mut ReadBuffer::new(bin, 0))
+}
+
+// 向写缓冲区追加原始字节
+fn write_raw(bb: &mut WriteBuffer, bin: &[u8]) {
+    bb.bytes.extend_from_slice(bin);
+    bb.tail += bin.len();
+}
+
+// 判断Some的值是否需要写入可选值容器，值为null或可选值容器时，直接写入会与None或外层的Some混淆
+fn is_some_wrapped(bin: &[u8]) -> bool {
+    match bin.first() {
+        None | Some(0) => true,
+        Some(_) => match ReadBuffer::new(bin, 0).read_container_buffer() {
+            Ok((t, _)) => t == CONTAINER_SOME,
+            Err(_) => false,
+        },
+    }
+}
+
+// 未知字段名称hash的字段名称，与文本表示相同
+fn unknown_field(hash: u32) -> String {
+    format!("#{:08x}", hash)
+}
+
+/**
+ * 序列化容器，先将容器元素序列化到内容缓冲区，结束时再写入容器头和元素数量
+ */
+pub struct Compound<'b> {
+    bb: &'b mut WriteBuffer, //写缓冲区
+    t: u32,                  //容器类型
+    count: u32,              //元素数量
+    body: WriteBuffer,       //容器内容缓冲区
+    variant: Option<u32>,    //非单元变体的变体名称hash，不是变体则为空
+    entries: Vec<(Vec<u8>, Vec<u8>)>, //规范模式下map的键值对，结束时按键排序后写入
+}
+
+impl<'b> Compound<'b> {
+    fn new(bb: &'b mut WriteBuffer, t: u32, variant: Option<u32>) -> Self {
+        let body = WriteBuffer::with_canonical(bb.is_canonical());
+        Compound {
+            bb,
+            t,
+            count: 0,
+            body,
+            variant,
+            entries: Vec::new(),
+        }
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ReadBonErr> {
+        self.count += 1;
+        value.serialize(&mut self.body)
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), ReadBonErr> {
+        self.body.write_u32(name_hash(key));
+        self.element(value)
+    }
+
+    // 规范模式下的map，键值对需要排序
+    fn is_sorted_map(&self) -> bool {
+        self.t == CONTAINER_MAP && self.body.is_canonical()
+    }
+
+    fn finish(self) -> Result<(), ReadBonErr> {
+        let Compound {
+            bb,
+            t,
+            count,
+            mut body,
+            variant,
+            mut entries,
+        } = self;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (k, v) in entries.iter() {
+            write_raw(&mut body, k);
+            write_raw(&mut body, v);
+        }
+        let write_next = |bb: &mut WriteBuffer| {
+            bb.write_lengthen(count);
+            write_raw(bb, &body.bytes[..body.tail]);
+        };
+        match variant {
+            None => bb.write_typed_container(t, write_next),
+            Some(hash) => bb.write_typed_container(CONTAINER_OBJECT, |bb| {
+                bb.write_lengthen(1);
+                bb.write_u32(hash);
+                bb.write_typed_container(t, write_next);
+            }),
+        }
+        Ok(())
+    }
+}
+
+impl<'b> ser::Serializer for &'b mut WriteBuffer {
+    type Ok = ();
+    type Error = ReadBonErr;
+    type SerializeSeq = Compound<'b>;
+    type SerializeTuple = Compound<'b>;
+    type SerializeTupleStruct = Compound<'b>;
+    type SerializeTupleVariant = Compound<'b>;
+    type SerializeMap = Compound<'b>;
+    type SerializeStruct = Compound<'b>;
+    type SerializeStructVariant = Compound<'b>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), ReadBonErr> {
+        self.write_bool(v);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), ReadBonErr> {
+        self.write_i8(v);
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), ReadBonErr> {
+        self.write_i16(v);
+        Ok(())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), ReadBonErr> {
+        self.write_i32(v);
+        Ok(())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), ReadBonErr> {
+        self.write_i64(v);
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), ReadBonErr> {
+        self.write_i128(v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), ReadBonErr> {
+        self.write_u8(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), ReadBonErr> {
+        self.write_u16(v);
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), ReadBonErr> {
+        self.write_u32(v);
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), ReadBonErr> {
+        self.write_u64(v);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), ReadBonErr> {
+        self.write_u128(v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), ReadBonErr> {
+        self.try_write_f32(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), ReadBonErr> {
+        self.try_write_f64(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), ReadBonErr> {
+        self.write_utf8(v.encode_utf8(&mut [0; 4]));
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), ReadBonErr> {
+        self.write_utf8(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), ReadBonErr> {
+        self.write_bin(v, 0..v.len());
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), ReadBonErr> {
+        self.write_nil();
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), ReadBonErr> {
+        let mut body = WriteBuffer::with_canonical(self.is_canonical());
+        value.serialize(&mut body)?;
+        let bin = &body.bytes[..body.tail];
+        if is_some_wrapped(bin) {
+            self.write_typed_container(CONTAINER_SOME, |bb| write_raw(bb, bin));
+        } else {
+            write_raw(self, bin);
+        }
+        Ok(())
+    }
+
+    fn serialize_unit(self) -> Result<(), ReadBonErr> {
+        self.write_nil();
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), ReadBonErr> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), ReadBonErr> {
+        self.write_typed_container(CONTAINER_OBJECT, |bb| {
+            bb.write_lengthen(1);
+            bb.write_u32(name_hash(variant));
+            bb.write_nil();
+        });
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), ReadBonErr> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), ReadBonErr> {
+        let mut compound = Compound::new(self, CONTAINER_OBJECT, None);
+        compound.field(variant, value)?;
+        compound.finish()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'b>, ReadBonErr> {
+        Ok(Compound::new(self, CONTAINER_ARRAY, None))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Compound<'b>, ReadBonErr> {
+        Ok(Compound::new(self, CONTAINER_ARRAY, None))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'b>, ReadBonErr> {
+        Ok(Compound::new(self, CONTAINER_ARRAY, None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'b>, ReadBonErr> {
+        Ok(Compound::new(self, CONTAINER_ARRAY, Some(name_hash(variant))))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'b>, ReadBonErr> {
+        Ok(Compound::new(self, CONTAINER_MAP, None))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'b>, ReadBonErr> {
+        Ok(Compound::new(self, CONTAINER_OBJECT, None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'b>, ReadBonErr> {
+        Ok(Compound::new(self, CONTAINER_OBJECT, Some(name_hash(variant))))
+    }
+}
+
+impl<'b> SerializeSeq for Compound<'b> {
+    type Ok = ();
+    type Error = ReadBonErr;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ReadBonErr> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), ReadBonErr> {
+        self.finish()
+    }
+}
+
+impl<'b> SerializeTuple for Compound<'b> {
+    type Ok = ();
+    type Error = ReadBonErr;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ReadBonErr> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), ReadBonErr> {
+        self.finish()
+    }
+}
+
+impl<'b> SerializeTupleStruct for Compound<'b> {
+    type Ok = ();
+    type Error = ReadBonErr;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ReadBonErr> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), ReadBonErr> {
+        self.finish()
+    }
+}
+
+impl<'b> SerializeTupleVariant for Compound<'b> {
+    type Ok = ();
+    type Error = ReadBonErr;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ReadBonErr> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), ReadBonErr> {
+        self.finish()
+    }
+}
+
+impl<'b> SerializeMap for Compound<'b> {
+    type Ok = ();
+    type Error = ReadBonErr;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ReadBonErr> {
+        // 键值对只计数一次
+        if self.is_sorted_map() {
+            self.entries.push((to_canonical_bytes(key)?, Vec::new()));
+            return Ok(());
+        }
+        key.serialize(&mut self.body)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ReadBonErr> {
+        if self.is_sorted_map() {
+            let value = to_canonical_bytes(value)?;
+            self.count += 1;
+            return match self.entries.last_mut() {
+                Some(entry) => {
+                    entry.1 = value;
+                    Ok(())
+                }
+                None => Err(ReadBonErr::other("serialize map value before key".to_string())),
+            };
+        }
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), ReadBonErr> {
+        self.finish()
+    }
+}
+
+impl<'b> SerializeStruct for Compound<'b> {
+    type Ok = ();
+    type Error = ReadBonErr;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ReadBonErr> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), ReadBonErr> {
+        self.finish()
+    }
+}
+
+impl<'b> SerializeStructVariant for Compound<'b> {
+    type Ok = ();
+    type Error = ReadBonErr;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ReadBonErr> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), ReadBonErr> {
+        self.finish()
+    }
+}
+
+impl<'de, 'b> de::Deserializer<'de> for &'b mut ReadBuffer<'de> {
+    type Error = ReadBonErr;
+
+    // 根据下一个值的类型反序列化，通用对象的键为字段名称hash
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        self.probe_border(1)?;
+        match self.bytes.get_u8(self.head) {
+            0 => {
+                self.head += 1;
+                visitor.visit_unit()
+            }
+            1 | 2 => visitor.visit_bool(self.read_bool()?),
+            3 | 4 | 6 => visitor.visit_f32(self.read_f32()?),
+            7 => visitor.visit_f64(self.read_f64()?),
+            14 => visitor.visit_i128(self.read_i128()?),
+            9..36 => visitor.visit_i64(self.read_i64()?),
+            41 => visitor.visit_u128(self.read_u128()?),
+            36..41 => visitor.visit_u64(self.read_u64()?),
+            42..111 => visitor.visit_borrowed_str(self.read_utf8_borrowed()?),
+            111..180 => visitor.visit_borrowed_bytes(self.read_bin_borrowed()?),
+            180..249 => {
+                let head = self.head;
+                let (t, mut body) = self.read_container_buffer()?;
+                if t == CONTAINER_SOME {
+                    return visitor.visit_some(&mut body);
+                }
+                let count = body.read_lengthen()?;
+                match t {
+                    CONTAINER_ARRAY => visitor.visit_seq(Elements { body, count }),
+                    CONTAINER_MAP => visitor.visit_map(Entries {
+                        body,
+                        count,
+                        fields: None,
+                    }),
+                    CONTAINER_OBJECT => visitor.visit_map(Entries {
+                        body,
+                        count,
+                        fields: Some(&[]),
+                    }),
+                    _ => {
+                        self.head = head;
+                        Err(ReadBonErr::other(format!(
+                            "deserialize container failed, invalid type: {:?}, head: {:?}",
+                            t, head
+                        )))
+                    }
+                }
+            }
+            t => Err(ReadBonErr::type_no_match(
+                "value".to_string(),
+                t,
+                self.head,
+            )),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_bool(self.read_bool()?)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_i8(self.read_i8()?)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_i16(self.read_i16()?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_i32(self.read_i32()?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_i64(self.read_i64()?)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_i128(self.read_i128()?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_u8(self.read_u8()?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_u16(self.read_u16()?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_u32(self.read_u32()?)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_u64(self.read_u64()?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_u128(self.read_u128()?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_f32(self.read_f32()?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_f64(self.read_f64()?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        let head = self.head;
+        let s = self.read_utf8_borrowed()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => {
+                self.head = head;
+                Err(ReadBonErr::other(format!(
+                    "deserialize char failed, invalid string: {:?}, head: {:?}",
+                    s, head
+                )))
+            }
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_borrowed_str(self.read_utf8_borrowed()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_borrowed_bytes(self.read_bin_borrowed()?)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        if self.is_nil()? {
+            return visitor.visit_none();
+        }
+
+        // 可选值容器的内容为Some的值
+        let head = self.head;
+        if let Ok(mut body) = self.read_typed_container(CONTAINER_SOME) {
+            return visitor.visit_some(&mut body);
+        }
+        self.head = head;
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        if self.is_nil()? {
+            visitor.visit_unit()
+        } else {
+            Err(ReadBonErr::type_no_match(
+                "null".to_string(),
+                self.bytes.get_u8(self.head),
+                self.head,
+            ))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        let mut body = self.read_typed_container(CONTAINER_ARRAY)?;
+        let count = body.read_lengthen()?;
+        visitor.visit_seq(Elements { body, count })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        let mut body = self.read_typed_container(CONTAINER_MAP)?;
+        let count = body.read_lengthen()?;
+        visitor.visit_map(Entries {
+            body,
+            count,
+            fields: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        let mut body = self.read_typed_container(CONTAINER_OBJECT)?;
+        let count = body.read_lengthen()?;
+        visitor.visit_map(Entries {
+            body,
+            count,
+            fields: Some(fields),
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        let head = self.head;
+        let mut body = self.read_typed_container(CONTAINER_OBJECT)?;
+        if body.read_lengthen()? != 1 {
+            self.head = head;
+            return Err(ReadBonErr::other(format!(
+                "deserialize enum {} failed, invalid variant count, head: {:?}",
+                name, head
+            )));
+        }
+
+        let hash = body.read_u32()?;
+        match variants.iter().find(|variant| name_hash(variant) == hash) {
+            Some(variant) => visitor.visit_enum(Variant {
+                body,
+                variant: *variant,
+            }),
+            None => {
+                self.head = head;
+                Err(ReadBonErr::other(format!(
+                    "deserialize enum {} failed, invalid variant hash: {:?}, head: {:?}",
+                    name, hash, head
+                )))
+            }
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ReadBonErr> {
+        self.skip()?;
+        visitor.visit_unit()
+    }
+}
+
+/*
+* 通用数组的元素访问器
+*/
+struct Elements<'de> {
+    body: ReadBuffer<'de>, //剩余的容器内容
+    count: u32,            //剩余的元素数量
+}
+
+impl<'de> SeqAccess<'de> for Elements<'de> {
+    type Error = ReadBonErr;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, ReadBonErr> {
+        if self.count == 0 {
+            return Ok(None);
+        }
+
+        self.count -= 1;
+        seed.deserialize(&mut self.body).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.count as usize)
+    }
+}
+
+/*
+* 通用map或通用对象的键值对访问器
+*/
+struct Entries<'de> {
+    body: ReadBuffer<'de>,                     //剩余的容器内容
+    count: u32,                                //剩余的键值对数量
+    fields: Option<&'static [&'static str]>,   //通用对象的所有字段名称，通用map则为空
+}
+
+impl<'de> MapAccess<'de> for Entries<'de> {
+    type Error = ReadBonErr;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ReadBonErr> {
+        if self.count == 0 {
+            return Ok(None);
+        }
+
+        self.count -= 1;
+        match self.fields {
+            None => seed.deserialize(&mut self.body).map(Some),
+            Some(fields) => {
+                // 通用对象的键为字段名称hash，已知字段转换为字段名称，未知字段转换为字段名称hash的字符串，不能作为序号匹配字段
+                let hash = self.body.read_u32()?;
+                match fields.iter().find(|field| name_hash(field) == hash) {
+                    Some(field) => seed.deserialize((*field).into_deserializer()).map(Some),
+                    None => seed.deserialize(unknown_field(hash).into_deserializer()).map(Some),
+                }
+            }
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        seed.deserialize(&mut self.body)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.count as usize)
+    }
+}
+
+/*
+* 枚举变体访问器
+*/
+struct Variant<'de> {
+    body: ReadBuffer<'de>, //变体值
+    variant: &'static str, //变体名称
+}
+
+impl<'de> EnumAccess<'de> for Variant<'de> {
+    type Error = ReadBonErr;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), ReadBonErr> {
+        let value = seed.deserialize(IntoDeserializer::<ReadBonErr>::into_deserializer(self.variant))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant<'de> {
+    type Error = ReadBonErr;
+
+    fn unit_variant(mut self) -> Result<(), ReadBonErr> {
+        Deserialize::deserialize(&mut self.body)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        mut self,
+        seed: T,
+    ) -> Result<T::Value, ReadBonErr> {
+        seed.deserialize(&mut self.body)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        mut self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        de::Deserializer::deserialize_seq(&mut self.body, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        mut self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ReadBonErr> {
+        de::Deserializer::deserialize_struct(&mut self.body, "", fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use super::super::{Decode, Encode};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Header<'a> {
+        id: u64,
+        route: &'a str,
+        tags: Vec<String>,
+        extra: Option<i32>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Body {
+        Ping,
+        Data(Vec<u8>),
+        Pos(i32, i32),
+        Move { x: i64, y: i64 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Msg<'a> {
+        #[serde(borrow)]
+        header: Header<'a>,
+        bodies: Vec<Body>,
+        attrs: HashMap<String, u32>,
+        pair: (bool, char),
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct OldHeader {
+        id: u64,
+        extra: Option<i32>,
+    }
+
+    #[test]
+    fn test_serde() -> Result<(), ReadBonErr> {
+        let mut attrs = HashMap::new();
+        attrs.insert("a".to_string(), 1);
+        attrs.insert("b".to_string(), 100000);
+        let msg = Msg {
+            header: Header {
+                id: 0xffffffffff,
+                route: "gateway/login",
+                tags: vec!["x".to_string(), "y".to_string()],
+                extra: None,
+            },
+            bodies: vec![
+                Body::Ping,
+                Body::Data(vec![1, 2, 3]),
+                Body::Pos(-1, 20),
+                Body::Move { x: -300, y: 300 },
+            ],
+            attrs,
+            pair: (true, '中'),
+        };
+
+        let bin = to_bytes(&msg)?;
+        let r: Msg = from_bytes(&bin)?;
+        assert_eq!(r, msg);
+
+        // 结构体为通用对象，可以按字段名称hash访问
+        let rb = ReadBuffer::new(&bin, 0);
+        let header = rb.get_field(name_hash("header"))?.unwrap();
+        let mut route = header.get_field(name_hash("route"))?.unwrap();
+        assert_eq!(route.read_utf8_borrowed()?, "gateway/login");
+
+        // 忽略未知字段
+        let old: OldHeader = from_bytes(&to_bytes(&msg.header)?)?;
+        assert_eq!(old, OldHeader { id: 0xffffffffff, extra: None });
+
+        // 类型不匹配
+        assert!(from_bytes::<Header>(&to_bytes(&msg.bodies)?).is_err());
+
+        // 规范模式下map按键排序，与插入顺序无关
+        let mut attrs = HashMap::new();
+        (0..100).rev().for_each(|i| { attrs.insert(i.to_string(), i); });
+        let bin = to_canonical_bytes(&attrs)?;
+        assert_eq!(from_bytes::<HashMap<String, i32>>(&bin)?, attrs);
+        let attrs: HashMap<String, i32> = attrs.into_iter().collect();
+        assert_eq!(to_canonical_bytes(&attrs)?, bin);
+        assert!(to_canonical_bytes(&std::f64::NAN).is_err());
+
+        Ok(())
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Pair {
+        a: u8,
+        b: Option<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct StrictPair {
+        a: u8,
+        b: Option<u8>,
+    }
+
+    #[test]
+    fn test_serde_option() -> Result<(), ReadBonErr> {
+        // Some的值本身为null时，使用可选值容器与None区分
+        let values: Vec<Option<Option<u8>>> = vec![None, Some(None), Some(Some(3))];
+        for value in values.iter() {
+            assert_eq!(from_bytes::<Option<Option<u8>>>(&to_bytes(value)?)?, *value);
+        }
+        let values: Vec<Option<Option<()>>> = vec![None, Some(None), Some(Some(()))];
+        for value in values.iter() {
+            assert_eq!(from_bytes::<Option<Option<()>>>(&to_bytes(value)?)?, *value);
+        }
+        assert_eq!(from_bytes::<Option<()>>(&to_bytes(&Some(()))?)?, Some(()));
+        assert_eq!(from_bytes::<Option<()>>(&to_bytes(&None::<()>)?)?, None);
+
+        // 其它的Some直接写入值，与Encode for Option相同
+        let mut bb = WriteBuffer::new();
+        Some(3u8).encode(&mut bb);
+        assert_eq!(to_bytes(&Some(3u8))?, bb.unwrap());
+        assert_eq!(from_bytes::<Option<u8>>(&to_bytes(&Some(3u8))?)?, Some(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serde_char_and_unknown_field() -> Result<(), ReadBonErr> {
+        // char与Encode for char的格式相同
+        let mut bb = WriteBuffer::new();
+        '中'.encode(&mut bb);
+        let bin = bb.unwrap();
+        assert_eq!(to_bytes(&'中')?, bin);
+        assert_eq!(from_bytes::<char>(&bin)?, '中');
+        assert_eq!(char::decode(&mut ReadBuffer::new(&to_bytes(&'a')?, 0))?, 'a');
+        assert!(char::decode(&mut ReadBuffer::new(&to_bytes(&"ab")?, 0)).is_err());
+
+        // 未知字段的名称hash不能作为序号匹配字段
+        let mut bb = WriteBuffer::new();
+        bb.write_typed_container(CONTAINER_OBJECT, |bb| {
+            bb.write_lengthen(2);
+            bb.write_u32(name_hash("a"));
+            bb.write_u8(1);
+            bb.write_u32(1);
+            bb.write_u8(9);
+        });
+        let bin = bb.unwrap();
+        assert_eq!(from_bytes::<Pair>(&bin)?, Pair { a: 1, b: None });
+        let e = from_bytes::<StrictPair>(&bin).err().unwrap();
+        assert!(e.to_string().contains("#00000001"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,740 @@
+/**
+ * 基于io::Read和io::Write的流式bon编码和解码
+ * 每次只缓冲一个值，容器可以只写入或读取容器头，再逐个写入或读取容器中的元素，从而用有限的内存处理很大的存档和回放数据
+ * 容器头中记录了容器的总长度，写入时需要预先知道容器内容的长度，读取时可以跳过不感兴趣的容器或容器的剩余部分
+ * 编码为多个值的类型，如Vec、元组和Result，写入时会包装为一个值序列容器，读取时再解开，保证每次写入的都是一个值
+ */
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+use super::{Decode, Encode, EnumValue, ReadBonErr, ReadBuffer, WriteBuffer, CONTAINER_VALUES};
+
+// 默认的单个值的最大长度，16M
+const DEFAULT_LIMIT: usize = 16 * 1024 * 1024;
+
+// 容器类型的字节数
+const CONTAINER_TYPE_LEN: u64 = 4;
+
+/**
+ * 流式bon写入器
+ */
+pub struct BonWriter<W: Write> {
+    inner: W,         //被写入的流
+    buf: Vec<u8>,     //编码单个值的缓冲区
+    pos: u64,         //已写入的字节数
+    frames: Vec<u64>, //正在写入的嵌套容器的结束位置
+}
+
+impl<W: Write> BonWriter<W> {
+    pub fn new(inner: W) -> Self {
+        BonWriter {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    //获取已写入的字节数
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    //获取被写入的流，还有未结束的容器则返回错误
+    pub fn into_inner(self) -> io::Result<W> {
+        if !self.frames.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("bon writer into inner failed, reason: {} unfinished container", self.frames.len()),
+            ));
+        }
+        Ok(self.inner)
+    }
+
+    /**
+     * @description 编码并写入一个值，编码结果不是单个值时，包装为值序列容器写入
+     * @example
+     */
+    pub fn write<T: Encode>(&mut self, value: &T) -> io::Result<()> {
+        let mut bb = WriteBuffer::with_bytes(std::mem::replace(&mut self.buf, Vec::new()), 0);
+        value.encode(&mut bb);
+        let tail = bb.tail();
+        let mut bytes = bb.unwrap();
+        let r = if is_single_value(&bytes[..tail]) {
+            self.write_raw(&bytes[..tail])
+        } else {
+            self.write_values(&bytes[..tail])
+        };
+        bytes.clear();
+        self.buf = bytes;
+        r
+    }
+
+    /**
+     * @description 写入已编码的数据
+     * @example
+     */
+    pub fn write_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.check_frame(bytes.len() as u64)?;
+        self.inner.write_all(bytes)?;
+        self.pos += bytes.len() as u64;
+        Ok(())
+    }
+
+    /**
+     * @description 写入一个动态长度，即通用容器的元素数量，格式见WriteBuffer::write_lengthen
+     * @example
+     */
+    pub fn write_lengthen(&mut self, t: u32) -> io::Result<()> {
+        if t < 0x80 {
+            self.write_raw(&[t as u8])
+        } else if t < 0x4000 {
+            self.write_raw(&((0x8000 + t) as u16).to_be_bytes())
+        } else if t < 0x20000000 {
+            self.write_raw(&(0xC0000000 + t).to_be_bytes())
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("bon writer write lengthen failed, reason: invalid lengthen {}", t),
+            ))
+        }
+    }
+
+    /**
+     * @description 从指定的流中读取指定长度的二进制数据并写入，不缓冲整个二进制数据
+     * @example
+     */
+    pub fn write_bin_from<R: Read>(&mut self, len: u64, r: &mut R) -> io::Result<()> {
+        self.write_data_head(len, 111)?;
+        self.check_frame(len)?;
+        let copied = io::copy(&mut r.take(len), &mut self.inner)?;
+        self.pos += copied;
+        if copied < len {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("bon writer write bin failed, len: {}, copied: {}", len, copied),
+            ));
+        }
+        Ok(())
+    }
+
+    /**
+     * @description 开始写入指定类型的容器，body_len为容器内容的字节数，不包括容器类型，之后写入的值都属于此容器
+     * @example
+     */
+    pub fn begin_container(&mut self, t: u32, body_len: u64) -> io::Result<()> {
+        self.write_data_head(body_len + CONTAINER_TYPE_LEN, 180)?;
+        self.write_raw(&t.to_le_bytes())?;
+        self.check_frame(body_len)?;
+        self.frames.push(self.pos + body_len);
+        Ok(())
+    }
+
+    /**
+     * @description 结束写入当前容器，写入的内容长度必须与开始时指定的长度相同
+     * @example
+     */
+    pub fn end_container(&mut self) -> io::Result<()> {
+        match self.frames.pop() {
+            Some(end) if end == self.pos => Ok(()),
+            Some(end) => {
+                let msg = format!(
+                    "bon writer end container failed, expect len: {}, act len: {}",
+                    end, self.pos
+                );
+                self.frames.push(end);
+                Err(Error::new(ErrorKind::InvalidData, msg))
+            }
+            None => Err(Error::new(
+                ErrorKind::InvalidData,
+                "bon writer end container failed, reason: no container",
+            )),
+        }
+    }
+
+    /**
+     * @description 写入指定类型的容器，容器内容先写入缓冲区，适用于较小的容器
+     * @example
+     */
+    pub fn write_container<F>(&mut self, t: u32, write_next: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut WriteBuffer),
+    {
+        let mut bb = WriteBuffer::new();
+        bb.write_typed_container(t, write_next);
+        let tail = bb.tail();
+        self.write_raw(&bb.get_byte()[..tail])
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    // 将已编码的多个值包装为值序列容器写入，先检查整个容器，避免写入一部分后失败
+    fn write_values(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let body_len = bytes.len() as u64 + CONTAINER_TYPE_LEN;
+        self.check_frame(data_head_len(body_len) + body_len)?;
+        self.write_data_head(body_len, 180)?;
+        self.write_raw(&CONTAINER_VALUES.to_le_bytes())?;
+        self.write_raw(bytes)
+    }
+
+    // 写入变长数据的头，与WriteBuffer::write_data对应
+    fn write_data_head(&mut self, len: u64, t: u8) -> io::Result<()> {
+        if len <= 64 {
+            self.write_raw(&[t + len as u8])
+        } else if len <= 0xff {
+            self.write_raw(&[t + 65, len as u8])
+        } else if len <= 0xffff {
+            let b = (len as u16).to_le_bytes();
+            self.write_raw(&[t + 66, b[0], b[1]])
+        } else if len <= 0xffffffff {
+            let b = (len as u32).to_le_bytes();
+            self.write_raw(&[t + 67, b[0], b[1], b[2], b[3]])
+        } else if len <= 0xffffffffffff {
+            let b = len.to_le_bytes();
+            self.write_raw(&[t + 68, b[0], b[1], b[2], b[3], b[4], b[5]])
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("bon writer write data failed, reason: too long {}", len),
+            ))
+        }
+    }
+
+    // 检查写入指定长度后是否超出当前容器
+    fn check_frame(&self, len: u64) -> io::Result<()> {
+        match self.frames.last() {
+            Some(end) if self.pos + len > *end => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "bon writer write failed, reason: container overflow, pos: {}, len: {}, end: {}",
+                    self.pos, len, end
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/**
+ * 流式bon读取器
+ */
+pub struct BonReader<R: Read> {
+    inner: R,                   //被读取的流
+    buf: Vec<u8>,               //当前值的缓冲区
+    peek: Option<u8>,           //预读的下一个值的首字节
+    head: Option<(usize, u64)>, //已读取但未消费的下一个值的头部字节数和数据字节数，头部在缓冲区中
+    pos: u64,                   //已读取的字节数，包括预读的字节和头部
+    limit: usize,               //单个值的最大字节数
+    frames: Vec<u64>,           //正在读取的嵌套容器的结束位置
+}
+
+impl<R: Read> BonReader<R> {
+    pub fn new(inner: R) -> Self {
+        BonReader::with_limit(inner, DEFAULT_LIMIT)
+    }
+
+    //指定单个值的最大字节数，读取或缓冲超过限制的值会返回错误，容器可以通过begin_container逐个读取元素
+    pub fn with_limit(inner: R, limit: usize) -> Self {
+        BonReader {
+            inner,
+            buf: Vec::new(),
+            peek: None,
+            head: None,
+            pos: 0,
+            limit,
+            frames: Vec::new(),
+        }
+    }
+
+    //获取已读取的字节数
+    pub fn position(&self) -> u64 {
+        match (self.head, self.peek) {
+            (Some((head_len, _)), _) => self.pos - head_len as u64,
+            (None, Some(_)) => self.pos - 1,
+            (None, None) => self.pos,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /**
+     * @description 判断是否还有下一个值，在容器中则判断容器是否还有剩余内容，否则判断流是否结束
+     * @example
+     */
+    pub fn has_next(&mut self) -> Result<bool, ReadBonErr> {
+        if let Some(end) = self.frames.last() {
+            return Ok(self.position() < *end);
+        }
+        if self.peek.is_some() || self.head.is_some() {
+            return Ok(true);
+        }
+        self.fill_peek()
+    }
+
+    /**
+     * @description 获取下一个值的类型，即首字节
+     * @example
+     */
+    pub fn get_type(&mut self) -> Result<u8, ReadBonErr> {
+        self.check_frame(1)?;
+        self.next_type()
+    }
+
+    /**
+     * @description 读取并解码下一个值，值序列容器会被解开，从其中的值解码
+     * @example
+     */
+    pub fn read<T: Decode>(&mut self) -> Result<T, ReadBonErr> {
+        let head = self.position();
+        let head_len = self.read_buf()?;
+        let len = self.buf.len();
+        let mut start = 0;
+        if self.buf[0] >= 180
+            && len >= head_len + CONTAINER_TYPE_LEN as usize
+            && self.buf[head_len..head_len + 4] == CONTAINER_VALUES.to_le_bytes()
+        {
+            start = head_len + CONTAINER_TYPE_LEN as usize;
+        }
+        let mut bb = ReadBuffer::new(&self.buf, start);
+        let r = T::decode(&mut bb)?;
+        if bb.head() != len {
+            return Err(ReadBonErr::other(format!(
+                "bon reader read failed, pos: {}, len: {}, decode len: {}",
+                head,
+                len,
+                bb.head()
+            )));
+        }
+        Ok(r)
+    }
+
+    pub fn read_value(&mut self) -> Result<EnumValue, ReadBonErr> {
+        self.read::<EnumValue>()
+    }
+
+    /**
+     * @description 读取下一个值的已编码数据，值超过限制时返回错误，且不消费该值，可以继续跳过或逐个读取容器中的元素
+     * @example
+     */
+    pub fn read_raw(&mut self) -> Result<&[u8], ReadBonErr> {
+        self.read_buf()?;
+        Ok(&self.buf)
+    }
+
+    /**
+     * @description 跳过下一个值，不缓冲值的内容
+     * @example
+     */
+    pub fn skip(&mut self) -> Result<(), ReadBonErr> {
+        let (_, data_len) = self.read_head()?;
+        self.check_frame(data_len)?;
+        self.head = None;
+        self.discard(data_len)
+    }
+
+    /**
+     * @description 读取一个动态长度，即通用容器的元素数量，格式见ReadBuffer::read_lengthen
+     * @example
+     */
+    pub fn read_lengthen(&mut self) -> Result<u32, ReadBonErr> {
+        self.check_head("lengthen")?;
+        self.check_frame(1)?;
+        let t = self.next_type()?;
+        let len = if t < 0x80 {
+            1
+        } else if t < 0xC0 {
+            2
+        } else if t < 0xE0 {
+            4
+        } else {
+            return Err(ReadBonErr::type_no_match(
+                "lengthen".to_string(),
+                t,
+                self.position() as usize,
+            ));
+        };
+        self.check_frame(len)?;
+        self.peek = None;
+        let mut bytes = [t, 0, 0, 0];
+        self.inner.read_exact(&mut bytes[1..len as usize])?;
+        self.pos += len - 1;
+        Ok(match len {
+            1 => t as u32,
+            2 => u16::from_be_bytes([bytes[0], bytes[1]]) as u32 - 0x8000,
+            _ => u32::from_be_bytes(bytes) - 0xC0000000,
+        })
+    }
+
+    /**
+     * @description 开始读取下一个容器，返回容器类型，之后读取的值都属于此容器，直到调用end_container
+     * @example
+     */
+    pub fn begin_container(&mut self) -> Result<u32, ReadBonErr> {
+        let t = self.get_type()?;
+        if t < 180 || t > 248 {
+            return Err(ReadBonErr::type_no_match(
+                "container".to_string(),
+                t,
+                self.position() as usize,
+            ));
+        }
+
+        let (_, data_len) = self.read_head()?;
+        if data_len < CONTAINER_TYPE_LEN {
+            return Err(ReadBonErr::other(format!(
+                "bon reader begin container failed, reason: invalid container len {}",
+                data_len
+            )));
+        }
+        self.check_frame(data_len)?;
+        self.head = None;
+        let mut tt = [0u8; 4];
+        self.inner.read_exact(&mut tt)?;
+        self.pos += CONTAINER_TYPE_LEN;
+        self.frames.push(self.pos + data_len - CONTAINER_TYPE_LEN);
+        Ok(u32::from_le_bytes(tt))
+    }
+
+    /**
+     * @description 结束读取当前容器，跳过容器中未读取的内容
+     * @example
+     */
+    pub fn end_container(&mut self) -> Result<(), ReadBonErr> {
+        let end = match self.frames.last() {
+            Some(end) => *end,
+            None => {
+                return Err(ReadBonErr::other(
+                    "bon reader end container failed, reason: no container".to_string(),
+                ))
+            }
+        };
+        // 预读的字节和头部已从流中读出，只需丢弃之后的数据
+        self.peek = None;
+        self.head = None;
+        let pos = self.pos;
+        self.discard(end - pos)?;
+        self.frames.pop();
+        Ok(())
+    }
+
+    // 读取下一个值到缓冲区，先检查长度限制和容器边界，通过后才消费该值，返回头部字节数
+    fn read_buf(&mut self) -> Result<usize, ReadBonErr> {
+        let (head_len, data_len) = self.read_head()?;
+        let len = head_len as u64 + data_len;
+        if len > self.limit as u64 {
+            return Err(ReadBonErr::other(format!(
+                "bon reader read failed, reason: value too large, pos: {}, len: {}, limit: {}",
+                self.position(),
+                len,
+                self.limit
+            )));
+        }
+        self.check_frame(data_len)?;
+        self.head = None;
+        self.buf.resize(len as usize, 0);
+        self.inner.read_exact(&mut self.buf[head_len..])?;
+        self.pos += data_len;
+        Ok(head_len)
+    }
+
+    // 已读取了值的头部时，不能按其它格式读取
+    fn check_head(&self, try_read: &str) -> Result<(), ReadBonErr> {
+        match self.head {
+            Some(_) => Err(ReadBonErr::type_no_match(
+                try_read.to_string(),
+                self.buf[0],
+                self.position() as usize,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // 读取下一个值的首字节
+    fn next_type(&mut self) -> Result<u8, ReadBonErr> {
+        if self.head.is_some() {
+            return Ok(self.buf[0]);
+        }
+        if self.peek.is_none() && !self.fill_peek()? {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "bon reader read failed, reason: end of stream",
+            )
+            .into());
+        }
+        Ok(self.peek.unwrap())
+    }
+
+    // 预读一个字节，流已结束则返回false
+    fn fill_peek(&mut self) -> Result<bool, ReadBonErr> {
+        let mut first = [0u8];
+        loop {
+            match self.inner.read(&mut first) {
+                Ok(0) => return Ok(false),
+                Ok(_) => {
+                    self.peek = Some(first[0]);
+                    self.pos += 1;
+                    return Ok(true);
+                }
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    // 读取下一个值的头部到缓冲区，返回头部字节数和之后的数据字节数，与ReadBuffer::value_end对应
+    // 头部在值被消费前保留，重复调用返回相同的结果
+    fn read_head(&mut self) -> Result<(usize, u64), ReadBonErr> {
+        if let Some(head) = self.head {
+            return Ok(head);
+        }
+        self.check_frame(1)?;
+        let t = self.next_type()?;
+        self.peek = None;
+        self.buf.clear();
+        self.buf.push(t);
+        let head = self.parse_head(t)?;
+        self.head = Some(head);
+        Ok(head)
+    }
+
+    // 解析值的头部，首字节已读取
+    fn parse_head(&mut self, t: u8) -> Result<(usize, u64), ReadBonErr> {
+        let (len_bytes, base) = match t {
+            0..=4 | 15..36 => return Ok((1, 0)),
+            5 => return Ok((1, 2)),
+            6 => return Ok((1, 4)),
+            7 => return Ok((1, 8)),
+            8 => return Ok((1, 16)),
+            9 | 36 => return Ok((1, 1)),
+            10 | 37 => return Ok((1, 2)),
+            11 | 38 => return Ok((1, 4)),
+            12 | 39 => return Ok((1, 6)),
+            13 | 40 => return Ok((1, 8)),
+            14 | 41 => return Ok((1, 16)),
+            42..111 => (data_len_bytes(t - 42), 42),
+            111..180 => (data_len_bytes(t - 111), 111),
+            180..249 => (data_len_bytes(t - 180), 180),
+            _ => {
+                return Err(ReadBonErr::type_no_match(
+                    "value".to_string(),
+                    t,
+                    self.position() as usize - 1,
+                ))
+            }
+        };
+
+        if len_bytes == 0 {
+            return Ok((1, (t - base) as u64));
+        }
+        self.check_frame(len_bytes as u64)?;
+        let mut bytes = [0u8; 8];
+        self.inner.read_exact(&mut bytes[..len_bytes])?;
+        self.pos += len_bytes as u64;
+        self.buf.extend_from_slice(&bytes[..len_bytes]);
+        Ok((1 + len_bytes, u64::from_le_bytes(bytes)))
+    }
+
+    // 丢弃指定长度的数据
+    fn discard(&mut self, len: u64) -> Result<(), ReadBonErr> {
+        let copied = io::copy(&mut (&mut self.inner).take(len), &mut io::sink())?;
+        self.pos += copied;
+        if copied < len {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("bon reader skip failed, len: {}, skipped: {}", len, copied),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    // 检查读取指定长度后是否超出当前容器
+    fn check_frame(&self, len: u64) -> Result<(), ReadBonErr> {
+        match self.frames.last() {
+            Some(end) if self.position() + len > *end => Err(ReadBonErr::overflow(
+                (self.position() + len) as usize,
+                *end as usize,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// 判断已编码的数据是否正好是一个值
+fn is_single_value(bytes: &[u8]) -> bool {
+    let mut rb = ReadBuffer::new(bytes, 0);
+    rb.skip().is_ok() && rb.head() == bytes.len()
+}
+
+// 获取指定长度的变长数据头的字节数，与BonWriter::write_data_head对应
+fn data_head_len(len: u64) -> u64 {
+    if len <= 64 {
+        1
+    } else if len <= 0xff {
+        2
+    } else if len <= 0xffff {
+        3
+    } else if len <= 0xffffffff {
+        5
+    } else {
+        7
+    }
+}
+
+// 获取变长数据头中长度的字节数
+fn data_len_bytes(offset: u8) -> usize {
+    match offset {
+        0..65 => 0,
+        65 => 1,
+        66 => 2,
+        67 => 4,
+        _ => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use super::super::{CONTAINER_ARRAY, CONTAINER_OBJECT};
+    use super::*;
+
+    #[test]
+    fn test_stream() -> Result<(), ReadBonErr> {
+        let big = vec![7u8; 70000];
+        let mut w = BonWriter::new(Vec::new());
+        w.write(&"hello".to_string())?;
+        w.write(&-300i32)?;
+        w.write_bin_from(big.len() as u64, &mut &big[..])?;
+        // 3个u8元素的数组，每个元素1字节
+        w.begin_container(CONTAINER_ARRAY, 4)?;
+        w.write_lengthen(3)?;
+        w.write(&1u8)?;
+        w.write(&2u8)?;
+        assert!(w.end_container().is_err());
+        assert!(w.write(&300u32).is_err());
+        w.write(&3u8)?;
+        w.end_container()?;
+        w.write_container(CONTAINER_OBJECT, |bb| {
+            bb.write_lengthen(1);
+            bb.write_u32(1);
+            bb.write_utf8("x");
+        })?;
+        w.write(&true)?;
+        let bytes = w.into_inner()?;
+
+        // 流式写入的数据和内存中编码的数据一致
+        let mut bb = WriteBuffer::new();
+        bb.write_utf8("hello");
+        bb.write_i32(-300);
+        bb.write_bin(&big, 0..big.len());
+        bb.write_typed_container(CONTAINER_ARRAY, |bb| {
+            bb.write_lengthen(3);
+            bb.write_u8(1);
+            bb.write_u8(2);
+            bb.write_u8(3);
+        });
+        bb.write_typed_container(CONTAINER_OBJECT, |bb| {
+            bb.write_lengthen(1);
+            bb.write_u32(1);
+            bb.write_utf8("x");
+        });
+        bb.write_bool(true);
+        assert_eq!(&bytes[..], &bb.get_byte()[..bb.tail()][..]);
+
+        let mut r = BonReader::with_limit(&bytes[..], 1024);
+        assert_eq!(r.read::<String>()?, "hello");
+        assert_eq!(r.read::<i32>()?, -300);
+        // 超过限制的值不会被消费，可以继续跳过
+        let pos = r.position();
+        assert!(r.read_raw().is_err());
+        assert!(r.read::<Vec<u8>>().is_err());
+        assert_eq!(r.position(), pos);
+        assert!(r.has_next()?);
+        assert!(r.read_lengthen().is_err());
+        r.skip()?;
+        assert_eq!(r.begin_container()?, CONTAINER_ARRAY);
+        r.end_container()?;
+
+        let mut r = BonReader::with_limit(&bytes[..], 1024);
+        r.skip()?;
+        r.skip()?;
+        r.skip()?;
+        assert_eq!(r.begin_container()?, CONTAINER_ARRAY);
+        assert_eq!(r.read_lengthen()?, 3);
+        assert_eq!(r.read::<u8>()?, 1);
+        r.end_container()?;
+        assert_eq!(r.begin_container()?, CONTAINER_OBJECT);
+        assert_eq!(r.read_lengthen()?, 1);
+        assert_eq!(r.read::<u32>()?, 1);
+        assert_eq!(r.get_type()?, 43);
+        assert!(r.has_next()?);
+        assert_eq!(r.read::<String>()?, "x");
+        assert!(!r.has_next()?);
+        assert!(r.read::<u8>().is_err());
+        r.end_container()?;
+        assert_eq!(r.read_value()?, EnumValue::Bool(true));
+        assert!(!r.has_next()?);
+        assert_eq!(r.position(), bytes.len() as u64);
+        match r.read_value() {
+            Err(ReadBonErr::Io(_)) => (),
+            _ => panic!("expect io error"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_values() -> Result<(), ReadBonErr> {
+        let mut map = HashMap::new();
+        map.insert(1u32, "a".to_string());
+        let mut w = BonWriter::new(Vec::new());
+        w.write(&vec![1u32, 2, 3])?;
+        w.write(&Vec::<u32>::new())?;
+        w.write(&(7u8, "x".to_string()))?;
+        w.write(&Ok::<u8, String>(9))?;
+        w.write(&Duration::new(3, 500))?;
+        w.write(&map)?;
+        w.write(&5u8)?;
+        // 容器中的值序列也要在容器的长度内
+        w.begin_container(CONTAINER_ARRAY, 3)?;
+        assert!(w.write(&vec![1u8, 2]).is_err());
+        w.write(&1u8)?;
+        w.write(&2u8)?;
+        w.write(&3u8)?;
+        w.end_container()?;
+        let bytes = w.into_inner()?;
+
+        let mut r = BonReader::new(&bytes[..]);
+        assert_eq!(r.read::<Vec<u32>>()?, vec![1, 2, 3]);
+        assert_eq!(r.read::<Vec<u32>>()?, Vec::<u32>::new());
+        assert_eq!(r.read::<(u8, String)>()?, (7, "x".to_string()));
+        assert_eq!(r.read::<Result<u8, String>>()?, Ok(9));
+        assert_eq!(r.read::<Duration>()?, Duration::new(3, 500));
+        assert_eq!(r.read::<HashMap<u32, String>>()?, map);
+        assert_eq!(r.read::<u8>()?, 5);
+        assert_eq!(r.begin_container()?, CONTAINER_ARRAY);
+        r.end_container()?;
+        assert!(!r.has_next()?);
+
+        // 值序列不能作为单个值读取
+        assert!(BonReader::new(&bytes[..]).read::<u32>().is_err());
+        assert!(BonReader::new(&bytes[..]).read_value().is_err());
+        Ok(())
+    }
+}
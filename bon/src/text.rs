@@ -0,0 +1,515 @@
+/**
+ * bon值的文本表示，格式类似JSON，数值带有类型后缀，可以无损的相互转换
+ * null、true、false
+ * 整数和浮点数：1u8、-300i16、0.5f32、1e100f64，没有后缀的整数为u64或i64，没有后缀的浮点数为f64
+ * 字符串："abc\n"，转义规则与JSON相同
+ * 二进制数据：b"0a1bff"，内容为16进制
+ * 数组：[1u8, "a"]
+ * map：{"a": 1u8, 2u8: [true]}，键可以为任意值
+ * 结构体：@0000abcd{"name": "x", "#12345678": 1u8}，@后为结构体类型的16进制，字段名称为"#"加16进制时表示字段名称hash
+ * 支持//开始的单行注释
+ * 注意：bon编码整数时会选择最小的类型，所以二进制转换为文本再转换回二进制是无损的，但文本中整数的类型在编码后可能变小
+ */
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use super::{
+    EnumValue, FieldValue, ReadBonErr, StructValue, CONTAINER_ARRAY, CONTAINER_MAP, CONTAINER_SOME,
+    CONTAINER_VALUES,
+};
+
+// 缩进
+const INDENT: &str = "  ";
+
+impl fmt::Display for EnumValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_text(self, false))
+    }
+}
+
+impl fmt::Debug for EnumValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_text(self, false))
+    }
+}
+
+/**
+ * @description 将bon值转换为文本，is_pretty为真则换行并缩进
+ * @example
+ */
+pub fn to_text(value: &EnumValue, is_pretty: bool) -> String {
+    let mut text = String::new();
+    write_value(&mut text, value, is_pretty, 0);
+    text
+}
+
+/**
+ * @description 从文本解析一个bon值
+ * @example
+ */
+pub fn from_text(text: &str) -> Result<EnumValue, ReadBonErr> {
+    let mut parser = Parser::new(text);
+    let value = parser.parse_value()?;
+    parser.skip_blank();
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+    Ok(value)
+}
+
+/**
+ * @description 从文本解析以空白分隔的多个bon值
+ * @example
+ */
+pub fn from_text_seq(text: &str) -> Result<Vec<EnumValue>, ReadBonErr> {
+    let mut parser = Parser::new(text);
+    let mut values = Vec::new();
+    loop {
+        parser.skip_blank();
+        if parser.peek().is_none() {
+            return Ok(values);
+        }
+        values.push(parser.parse_value()?);
+    }
+}
+
+// 写入换行和指定层次的缩进
+fn write_indent(text: &mut String, is_pretty: bool, depth: usize) {
+    if is_pretty {
+        text.push('\n');
+        for _ in 0..depth {
+            text.push_str(INDENT);
+        }
+    }
+}
+
+// 写入容器的所有元素
+fn write_elements<I, F>(text: &mut String, iter: I, is_pretty: bool, depth: usize, mut write_next: F)
+where
+    I: ExactSizeIterator,
+    F: FnMut(&mut String, I::Item),
+{
+    let len = iter.len();
+    for (index, item) in iter.enumerate() {
+        write_indent(text, is_pretty, depth + 1);
+        write_next(text, item);
+        if index + 1 < len {
+            text.push(',');
+            if !is_pretty {
+                text.push(' ');
+            }
+        }
+    }
+    if len > 0 {
+        write_indent(text, is_pretty, depth);
+    }
+}
+
+fn write_value(text: &mut String, value: &EnumValue, is_pretty: bool, depth: usize) {
+    let _ = match value {
+        EnumValue::Void => write!(text, "null"),
+        EnumValue::Bool(v) => write!(text, "{}", v),
+        EnumValue::U8(v) => write!(text, "{}u8", v),
+        EnumValue::U16(v) => write!(text, "{}u16", v),
+        EnumValue::U32(v) => write!(text, "{}u32", v),
+        EnumValue::U64(v) => write!(text, "{}u64", v),
+        EnumValue::U128(v) => write!(text, "{}u128", v),
+        EnumValue::I8(v) => write!(text, "{}i8", v),
+        EnumValue::I16(v) => write!(text, "{}i16", v),
+        EnumValue::I32(v) => write!(text, "{}i32", v),
+        EnumValue::I64(v) => write!(text, "{}i64", v),
+        EnumValue::I128(v) => write!(text, "{}i128", v),
+        EnumValue::F32(v) => write!(text, "{:?}f32", v),
+        EnumValue::F64(v) => write!(text, "{:?}f64", v),
+        EnumValue::Str(v) => {
+            write_str(text, v);
+            Ok(())
+        }
+        EnumValue::Bin(v) => {
+            text.push_str("b\"");
+            for b in v.iter() {
+                let _ = write!(text, "{:02x}", b);
+            }
+            text.push('"');
+            Ok(())
+        }
+        EnumValue::Arr(arr) => {
+            text.push('[');
+            write_elements(text, arr.iter(), is_pretty, depth, |text, v| {
+                write_value(text, v, is_pretty, depth + 1)
+            });
+            text.push(']');
+            Ok(())
+        }
+        EnumValue::Map(map) => {
+            text.push('{');
+            write_elements(text, map.iter(), is_pretty, depth, |text, (k, v)| {
+                write_value(text, k, is_pretty, depth + 1);
+                text.push_str(": ");
+                write_value(text, v, is_pretty, depth + 1);
+            });
+            text.push('}');
+            Ok(())
+        }
+        EnumValue::Struct(v) => {
+            let _ = write!(text, "@{:08x}{{", v.hash);
+            write_elements(text, v.fields.iter(), is_pretty, depth, |text, field| {
+                write_str(text, &field.name);
+                text.push_str(": ");
+                write_value(text, &field.fvalue, is_pretty, depth + 1);
+            });
+            text.push('}');
+            Ok(())
+        }
+    };
+}
+
+// 写入带引号和转义的字符串
+fn write_str(text: &mut String, s: &str) {
+    text.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '\t' => text.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                let _ = write!(text, "\\u{:04x}", c as u32);
+            }
+            c => text.push(c),
+        }
+    }
+    text.push('"');
+}
+
+/*
+* 文本解析器
+*/
+struct Parser<'a> {
+    text: &'a str, //文本
+    pos: usize,    //当前解析的字节位置
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser { text, pos: 0 }
+    }
+
+    fn error(&self, reason: &str) -> ReadBonErr {
+        ReadBonErr::other(format!(
+            "parse text failed, pos = {:?}, reason = {}",
+            self.pos, reason
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expect: char) -> Result<(), ReadBonErr> {
+        self.skip_blank();
+        match self.next() {
+            Some(c) if c == expect => Ok(()),
+            _ => Err(self.error(&format!("expect {:?}", expect))),
+        }
+    }
+
+    // 跳过空白和注释
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.next();
+                }
+                Some('/') if self.text[self.pos..].starts_with("//") => {
+                    match self.text[self.pos..].find('\n') {
+                        Some(index) => self.pos += index + 1,
+                        None => self.pos = self.text.len(),
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<EnumValue, ReadBonErr> {
+        self.skip_blank();
+        match self.peek() {
+            Some('"') => Ok(EnumValue::Str(self.parse_str()?)),
+            Some('[') => {
+                self.next();
+                let mut arr = Vec::new();
+                self.parse_elements(']', |parser| {
+                    arr.push(parser.parse_value()?);
+                    Ok(())
+                })?;
+                Ok(EnumValue::Arr(Arc::new(arr)))
+            }
+            Some('{') => {
+                self.next();
+                let mut map = HashMap::new();
+                self.parse_elements('}', |parser| {
+                    let key = parser.parse_value()?;
+                    parser.expect(':')?;
+                    map.insert(Arc::new(key), Arc::new(parser.parse_value()?));
+                    Ok(())
+                })?;
+                Ok(EnumValue::Map(map))
+            }
+            Some('@') => {
+                self.next();
+                let word = self.parse_word();
+                let hash = u32::from_str_radix(word, 16)
+                    .map_err(|_| self.error("invalid struct hash"))?;
+                // 数组、map、Some包装和值序列的容器类型不能作为结构体hash，否则编码后无法还原
+                if hash == CONTAINER_ARRAY
+                    || hash == CONTAINER_MAP
+                    || hash == CONTAINER_SOME
+                    || hash == CONTAINER_VALUES
+                {
+                    return Err(self.error("reserved struct hash"));
+                }
+                self.expect('{')?;
+                let mut fields = Vec::new();
+                self.parse_elements('}', |parser| {
+                    parser.skip_blank();
+                    let name = parser.parse_str()?;
+                    parser.expect(':')?;
+                    fields.push(FieldValue {
+                        name,
+                        fvalue: parser.parse_value()?,
+                    });
+                    Ok(())
+                })?;
+                Ok(EnumValue::Struct(Arc::new(StructValue { hash, fields })))
+            }
+            Some('b') if self.text[self.pos..].starts_with("b\"") => {
+                self.next();
+                let hex = self.parse_str()?;
+                if hex.len() % 2 != 0 {
+                    return Err(self.error("invalid bin length"));
+                }
+                let mut bin = Vec::with_capacity(hex.len() / 2);
+                for index in (0..hex.len()).step_by(2) {
+                    match hex.get(index..index + 2).map(|h| u8::from_str_radix(h, 16)) {
+                        Some(Ok(b)) => bin.push(b),
+                        _ => return Err(self.error("invalid bin")),
+                    }
+                }
+                Ok(EnumValue::Bin(bin))
+            }
+            Some(_) => self.parse_scalar(),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    // 解析以逗号分隔的容器元素，直到指定的结束字符，允许最后一个元素后有逗号
+    fn parse_elements<F>(&mut self, end: char, mut parse_next: F) -> Result<(), ReadBonErr>
+    where
+        F: FnMut(&mut Self) -> Result<(), ReadBonErr>,
+    {
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(end) {
+                self.next();
+                return Ok(());
+            }
+
+            parse_next(self)?;
+            self.skip_blank();
+            match self.next() {
+                Some(',') => continue,
+                Some(c) if c == end => return Ok(()),
+                _ => return Err(self.error(&format!("expect ',' or {:?}", end))),
+            }
+        }
+    }
+
+    // 解析由字母、数字和数值符号组成的单词
+    fn parse_word(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+' || c == '_' {
+                self.next();
+            } else {
+                break;
+            }
+        }
+        &self.text[start..self.pos]
+    }
+
+    // 解析null、布尔值、整数和浮点数
+    fn parse_scalar(&mut self) -> Result<EnumValue, ReadBonErr> {
+        let start = self.pos;
+        let word = self.parse_word();
+        let value = match word {
+            "" => None,
+            "null" => Some(EnumValue::Void),
+            "true" => Some(EnumValue::Bool(true)),
+            "false" => Some(EnumValue::Bool(false)),
+            _ => parse_number(word),
+        };
+        value.ok_or_else(|| {
+            self.pos = start;
+            self.error(&format!("invalid value {:?}", word))
+        })
+    }
+
+    // 解析带引号和转义的字符串
+    fn parse_str(&mut self) -> Result<String, ReadBonErr> {
+        if self.next() != Some('"') {
+            return Err(self.error("expect '\"'"));
+        }
+
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('/') => s.push('/'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('u') => {
+                        let mut code = self.parse_unicode()?;
+                        if code >= 0xd800 && code < 0xdc00 {
+                            // utf16代理对
+                            if !self.text[self.pos..].starts_with("\\u") {
+                                return Err(self.error("invalid unicode surrogate"));
+                            }
+                            self.pos += 2;
+                            let low = self.parse_unicode()?;
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                        }
+                        match std::char::from_u32(code) {
+                            Some(c) => s.push(c),
+                            None => return Err(self.error("invalid unicode")),
+                        }
+                    }
+                    _ => return Err(self.error("invalid escape")),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn parse_unicode(&mut self) -> Result<u32, ReadBonErr> {
+        match self.text.get(self.pos..self.pos + 4).map(|h| u32::from_str_radix(h, 16)) {
+            Some(Ok(code)) => {
+                self.pos += 4;
+                Ok(code)
+            }
+            _ => Err(self.error("invalid unicode")),
+        }
+    }
+}
+
+// 解析带类型后缀的数值
+fn parse_number(word: &str) -> Option<EnumValue> {
+    const SUFFIXES: [&str; 12] = [
+        "u128", "i128", "u16", "u32", "u64", "i16", "i32", "i64", "f32", "f64", "u8", "i8",
+    ];
+    let suffix = SUFFIXES.iter().find(|suffix| word.ends_with(*suffix));
+    let (num, suffix) = match suffix {
+        Some(suffix) => (&word[..word.len() - suffix.len()], *suffix),
+        None => {
+            let is_float = word.contains('.')
+                || word.contains("inf")
+                || word.contains("NaN")
+                || word.contains('e')
+                || word.contains('E');
+            if is_float {
+                (word, "f64")
+            } else if word.starts_with('-') {
+                (word, "i64")
+            } else {
+                (word, "u64")
+            }
+        }
+    };
+
+    match suffix {
+        "u8" => num.parse().ok().map(EnumValue::U8),
+        "u16" => num.parse().ok().map(EnumValue::U16),
+        "u32" => num.parse().ok().map(EnumValue::U32),
+        "u64" => num.parse().ok().map(EnumValue::U64),
+        "u128" => num.parse().ok().map(EnumValue::U128),
+        "i8" => num.parse().ok().map(EnumValue::I8),
+        "i16" => num.parse().ok().map(EnumValue::I16),
+        "i32" => num.parse().ok().map(EnumValue::I32),
+        "i64" => num.parse().ok().map(EnumValue::I64),
+        "i128" => num.parse().ok().map(EnumValue::I128),
+        "f32" => num.parse().ok().map(EnumValue::F32),
+        _ => num.parse().ok().map(EnumValue::F64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Decode, Encode, ReadBuffer, WriteBuffer};
+    use super::*;
+
+    #[test]
+    fn test_text() -> Result<(), ReadBonErr> {
+        let text = r##"
+            // 测试
+            @0000abcd{
+                "name": "a\"b\\c\n中\u0001",
+                "#12345678": [null, true, false, 7u8, 300u16, -1i8, -70000i32, 18446744073709551615u64,],
+                "pos": {"x": 0.5f32, 1.0e100f64: -2.25f64, [1u8]: b"00ff10"},
+                "empty": @00000001{},
+            }
+        "##;
+        let value = from_text(text)?;
+        assert_eq!(from_text(&to_text(&value, true))?, value);
+        assert_eq!(from_text(&to_text(&value, false))?, value);
+        assert_eq!(to_text(&from_text("[1, -2, 3.5, inf, \"\\ud83d\\ude00\"]")?, false), "[1u64, -2i64, 3.5f64, inff64, \"😀\"]");
+        assert!(from_text("@00000002{}").is_err());
+        assert!(from_text("@00000003{}").is_err());
+        assert!(from_text("@00000004{}").is_err());
+        assert!(from_text("@00000005{}").is_err());
+        assert_eq!(to_text(&from_text("@00000006{}")?, true), "@00000006{}");
+        assert!(from_text("[1u8 2u8]").is_err());
+        assert!(from_text("300u8").is_err());
+        assert!(from_text("b\"0\"").is_err());
+        assert!(from_text("1u8 2u8").is_err());
+        assert_eq!(from_text_seq("1u8 // x\n 2u8")?.len(), 2);
+
+        // 与二进制相互转换
+        let value = from_text(r##"@00000001{"#0000abcd": [1u8, "x", b"ff"], "#00000001": {"k": null}}"##)?;
+        let mut bb = WriteBuffer::new();
+        value.encode(&mut bb);
+        let bin = bb.unwrap();
+        let mut rb = ReadBuffer::new(&bin, 0);
+        assert_eq!(EnumValue::decode(&mut rb)?, value);
+        assert_eq!(rb.head(), bin.len());
+
+        // 小整数和暂不支持的浮点数
+        let value = from_text("[0u8, 5u8, 19u8, 20u8, 255u8]")?;
+        let mut bb = WriteBuffer::new();
+        value.encode(&mut bb);
+        let bin = bb.unwrap();
+        assert_eq!(ReadBuffer::new(&bin, 0).read()?, value);
+        for bin in [&[5u8, 0, 0x3c][..], &[8u8; 17][..]] {
+            let mut rb = ReadBuffer::new(bin, 0);
+            assert!(rb.read().is_err());
+            assert_eq!(rb.head(), 0);
+        }
+
+        Ok(())
+    }
+}
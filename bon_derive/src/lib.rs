@@ -0,0 +1,481 @@
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use syn::{Data, DeriveInput, Fields, Generics, Ident, Lit, Meta, NestedMeta, WherePredicate};
+
+/// 为结构体、元组结构体和枚举派生`bon::Encode`
+///
+/// 编码格式与bon的serde后端相同：命名字段的结构体为通用对象，字段名称使用name_hash；单字段的元组结构体为字段值；
+/// 其它元组结构体为通用数组；单元结构体为null；枚举为只有一个字段的通用对象，字段名称hash为变体标签，字段值为按结构体的方式写入的变体的值
+///
+/// 变体标签默认为变体名称的name_hash，可以用#[bon(tag = N)]指定；反序列化通用对象时，未知的字段名称hash会返回错误
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// extern crate bon;
+/// #[macro_use]
+/// extern crate bon_derive;
+///
+/// #[derive(BonEncode, BonDecode)]
+/// struct Player {
+///     name: String,
+///     level: u32,
+///     #[bon(skip)]        // 不序列化，反序列化时为默认值
+///     cache: Vec<u8>,
+///     #[bon(default)]     // 反序列化时缺少此字段，则为默认值
+///     score: u64,
+/// }
+///
+/// #[derive(BonEncode, BonDecode)]
+/// enum Msg {
+///     Ping,                   // 变体标签默认为变体名称的name_hash，即name_hash("Ping")
+///     #[bon(tag = 10)]        // 指定变体标签
+///     Chat(String, u32),
+/// }
+/// ```
+#[proc_macro_derive(BonEncode, attributes(bon))]
+pub fn encode_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse(input).unwrap();
+    let gen = impl_encode(&ast);
+    gen.into()
+}
+
+/// 为结构体、元组结构体和枚举派生`bon::Decode`，编码格式见`BonEncode`
+#[proc_macro_derive(BonDecode, attributes(bon))]
+pub fn decode_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse(input).unwrap();
+    let gen = impl_decode(&ast);
+    gen.into()
+}
+
+/*
+* 字段的序列化属性
+*/
+struct FieldAttr {
+    is_skip:    bool,   //是否跳过此字段，跳过的字段不序列化，反序列化时为默认值
+    is_default: bool,   //反序列化时缺少此字段，是否使用默认值
+}
+
+//获取指定属性列表中所有#[bon(...)]的属性项
+fn bon_metas(attrs: &[syn::Attribute]) -> Vec<Meta> {
+    let mut metas = Vec::new();
+    for attr in attrs {
+        if attr.path.segments.len() != 1 || attr.path.segments[0].ident != "bon" {
+            continue;
+        }
+
+        match attr.parse_meta() {
+            Ok(Meta::List(list)) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => metas.push(meta),
+                        _ => panic!("invalid bon attribute, expect #[bon(skip)], #[bon(default)] or #[bon(tag = N)]"),
+                    }
+                }
+            },
+            _ => panic!("invalid bon attribute, expect #[bon(...)]"),
+        }
+    }
+
+    metas
+}
+
+//解析字段的序列化属性
+fn field_attr(field: &syn::Field) -> FieldAttr {
+    let mut attr = FieldAttr {
+        is_skip: false,
+        is_default: false,
+    };
+
+    for meta in bon_metas(&field.attrs) {
+        match meta {
+            Meta::Word(ref ident) if ident == "skip" => attr.is_skip = true,
+            Meta::Word(ref ident) if ident == "default" => attr.is_default = true,
+            _ => panic!("invalid bon field attribute, expect #[bon(skip)] or #[bon(default)]"),
+        }
+    }
+
+    attr
+}
+
+//计算通用对象的字段名称hash，与bon::name_hash相同，使用32位的FNV-1a
+fn name_hash(name: &str) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for b in name.as_bytes() {
+        hash ^= *b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    hash
+}
+
+//解析所有变体的标签，未指定标签的变体使用变体名称的name_hash
+fn variant_tags(variants: &syn::punctuated::Punctuated<syn::Variant, syn::token::Comma>) -> Vec<u32> {
+    let mut tags: Vec<u32> = Vec::with_capacity(variants.len());
+    for variant in variants.iter() {
+        let mut tag = name_hash(&variant.ident.to_string());
+        for meta in bon_metas(&variant.attrs) {
+            match meta {
+                Meta::NameValue(ref nv) if nv.ident == "tag" => {
+                    match nv.lit {
+                        Lit::Int(ref i) if i.value() <= u32::max_value() as u64 => tag = i.value() as u32,
+                        _ => panic!("invalid bon variant tag, expect u32"),
+                    }
+                },
+                _ => panic!("invalid bon variant attribute, expect #[bon(tag = N)]"),
+            }
+        }
+
+        if tags.contains(&tag) {
+            panic!("duplicate bon variant tag {} of {}", tag, variant.ident);
+        }
+        tags.push(tag);
+    }
+
+    tags
+}
+
+//获取所有命名字段的名称hash，不同字段的名称hash冲突时编译失败
+fn field_hashes(fields: &Fields) -> Vec<u32> {
+    let mut hashes: Vec<u32> = Vec::with_capacity(fields.iter().len());
+    for field in fields.iter() {
+        let hash = match field.ident {
+            Some(ref ident) => name_hash(&ident.to_string()),
+            None => 0,
+        };
+        if field.ident.is_some() && hashes.contains(&hash) {
+            panic!("duplicate bon field name hash {} of {}", hash, field.ident.as_ref().unwrap());
+        }
+        hashes.push(hash);
+    }
+
+    hashes
+}
+
+//判断是否为只有一个未跳过字段的元组结构体或元组变体，这种字段与serde的newtype相同，直接写入字段值
+fn is_newtype(fields: &Fields) -> bool {
+    match fields {
+        Fields::Unnamed(ref unnamed) => unnamed.unnamed.len() == 1 && !field_attr(&unnamed.unnamed[0]).is_skip,
+        _ => false,
+    }
+}
+
+//为所有类型参数增加指定的约束
+fn add_bound(generics: &Generics, bound: syn::TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(bound.clone());
+    }
+    generics
+}
+
+//为所有跳过或缺少时使用默认值的字段的类型增加Default约束，字段类型使用了类型参数时需要此约束
+fn add_default_bound(generics: &mut Generics, fields: &Fields) {
+    for field in fields.iter() {
+        let attr = field_attr(field);
+        if attr.is_skip || attr.is_default {
+            let ty = &field.ty;
+            let predicate: WherePredicate = syn::parse2(quote! { #ty: ::std::default::Default }).unwrap();
+            generics.make_where_clause().predicates.push(predicate);
+        }
+    }
+}
+
+//获取所有字段的绑定名，命名字段为字段名，匿名字段为_序号
+fn field_bindings(fields: &Fields) -> Vec<Ident> {
+    fields.iter().enumerate().map(|(index, field)| {
+        match field.ident {
+            Some(ref ident) => ident.clone(),
+            None => Ident::new(&format!("_{}", index), Span::call_site()),
+        }
+    }).collect()
+}
+
+//生成按绑定名序列化所有未跳过字段的代码，命名字段写入通用对象，匿名字段写入通用数组
+fn encode_fields(fields: &Fields, bindings: &[Ident]) -> proc_macro2::TokenStream {
+    if let Fields::Unit = fields {
+        return quote! {
+            bb.write_nil();
+        };
+    }
+    if is_newtype(fields) {
+        let binding = &bindings[0];
+        return quote! {
+            ::bon::Encode::encode(#binding, bb);
+        };
+    }
+
+    let hashes = field_hashes(fields);
+    let mut count = 0u32;
+    let mut writes = Vec::new();
+    for ((field, binding), hash) in fields.iter().zip(bindings.iter()).zip(hashes.iter()) {
+        if field_attr(field).is_skip {
+            continue;
+        }
+
+        count += 1;
+        match field.ident {
+            Some(_) => writes.push(quote! {
+                bb.write_u32(#hash);
+                ::bon::Encode::encode(#binding, bb);
+            }),
+            None => writes.push(quote! {
+                ::bon::Encode::encode(#binding, bb);
+            }),
+        }
+    }
+
+    let container = match fields {
+        Fields::Named(_) => quote! { ::bon::CONTAINER_OBJECT },
+        _ => quote! { ::bon::CONTAINER_ARRAY },
+    };
+    quote! {
+        bb.write_typed_container(#container, |bb| {
+            bb.write_lengthen(#count);
+            #(#writes)*
+        });
+    }
+}
+
+//生成反序列化所有字段，并构建指定路径的结构体或变体的代码
+fn decode_fields(path: proc_macro2::TokenStream, fields: &Fields, name: &str) -> proc_macro2::TokenStream {
+    match fields {
+        Fields::Named(_) => decode_named_fields(path, fields, name),
+        Fields::Unnamed(_) if is_newtype(fields) => quote! {
+            Ok(#path(::bon::Decode::decode(bb)?))
+        },
+        Fields::Unnamed(_) => decode_unnamed_fields(path, fields, name),
+        Fields::Unit => quote! {
+            <() as ::bon::Decode>::decode(bb)?;
+            Ok(#path)
+        },
+    }
+}
+
+//生成从通用对象中按字段名称hash反序列化所有命名字段的代码，字段可以是任意顺序
+fn decode_named_fields(path: proc_macro2::TokenStream, fields: &Fields, name: &str) -> proc_macro2::TokenStream {
+    let hashes = field_hashes(fields);
+    let mut lets = Vec::new();
+    let mut arms = Vec::new();
+    let mut values = Vec::new();
+    for (field, hash) in fields.iter().zip(hashes.iter()) {
+        let attr = field_attr(field);
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        if attr.is_skip {
+            values.push(quote! { #ident: <#ty as ::std::default::Default>::default() });
+            continue;
+        }
+
+        let field_name = ident.to_string();
+        let slot = Ident::new(&format!("__{}", ident), Span::call_site());
+        lets.push(quote! {
+            let mut #slot: Option<#ty> = None;
+        });
+        arms.push(quote! {
+            #hash => {
+                if #slot.is_some() {
+                    return Err(::bon::ReadBonErr::Other(format!("decode {} failed, duplicate field: {}", #name, #field_name)));
+                }
+                #slot = Some(::bon::Decode::decode(&mut body)?);
+            },
+        });
+        let missing = if attr.is_default {
+            quote! { <#ty as ::std::default::Default>::default() }
+        } else {
+            quote! {
+                return Err(::bon::ReadBonErr::Other(format!("decode {} failed, missing field: {}", #name, #field_name)))
+            }
+        };
+        values.push(quote! {
+            #ident: match #slot {
+                Some(value) => value,
+                None => #missing,
+            }
+        });
+    }
+
+    quote! {
+        let mut body = bb.read_typed_container(::bon::CONTAINER_OBJECT)?;
+        let count = body.read_lengthen()?;
+        #(#lets)*
+        for _ in 0..count {
+            match body.read_u32()? {
+                #(#arms)*
+                hash => return Err(::bon::ReadBonErr::Other(format!("decode {} failed, unknown field hash: {}", #name, hash))),
+            }
+        }
+        Ok(#path { #(#values),* })
+    }
+}
+
+//生成从通用数组中按声明顺序反序列化所有匿名字段的代码
+fn decode_unnamed_fields(path: proc_macro2::TokenStream, fields: &Fields, name: &str) -> proc_macro2::TokenStream {
+    let mut index = 0u32;
+    let mut values = Vec::new();
+    for (pos, field) in fields.iter().enumerate() {
+        let attr = field_attr(field);
+        let ty = &field.ty;
+        if attr.is_skip {
+            values.push(quote! { <#ty as ::std::default::Default>::default() });
+            continue;
+        }
+
+        let missing = if attr.is_default {
+            quote! { <#ty as ::std::default::Default>::default() }
+        } else {
+            let field_name = pos.to_string();
+            quote! {
+                return Err(::bon::ReadBonErr::Other(format!("decode {} failed, missing field: {}", #name, #field_name)))
+            }
+        };
+        values.push(quote! {
+            if #index < count {
+                ::bon::Decode::decode(&mut body)?
+            } else {
+                #missing
+            }
+        });
+        index += 1;
+    }
+
+    quote! {
+        let mut body = bb.read_typed_container(::bon::CONTAINER_ARRAY)?;
+        let count = body.read_lengthen()?;
+        if count > #index {
+            return Err(::bon::ReadBonErr::Other(format!("decode {} failed, field count: {}, max: {}", #name, count, #index)));
+        }
+        Ok(#path ( #(#values),* ))
+    }
+}
+
+fn impl_encode(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let generics = add_bound(&ast.generics, parse_quote_bound(quote! { ::bon::Encode }));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match ast.data {
+        Data::Struct(ref s) => {
+            let bindings = field_bindings(&s.fields);
+            let lets = s.fields.iter().zip(bindings.iter()).enumerate().map(|(index, (field, binding))| {
+                match field.ident {
+                    Some(ref ident) => quote! { let #binding = &self.#ident; },
+                    None => {
+                        let index = syn::Index::from(index);
+                        quote! { let #binding = &self.#index; }
+                    },
+                }
+            });
+            let writes = encode_fields(&s.fields, &bindings);
+            quote! {
+                #(#lets)*
+                #writes
+            }
+        },
+        Data::Enum(ref e) => {
+            let tags = variant_tags(&e.variants);
+            let arms = e.variants.iter().zip(tags.iter()).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let bindings = field_bindings(&variant.fields);
+                let refs = &bindings;
+                let pattern = match variant.fields {
+                    Fields::Named(_) => quote! { #name::#ident { #(ref #refs),* } },
+                    Fields::Unnamed(_) => quote! { #name::#ident ( #(ref #refs),* ) },
+                    Fields::Unit => quote! { #name::#ident },
+                };
+                let writes = encode_fields(&variant.fields, &bindings);
+                quote! {
+                    #pattern => {
+                        bb.write_typed_container(::bon::CONTAINER_OBJECT, |bb| {
+                            bb.write_lengthen(1);
+                            bb.write_u32(#tag);
+                            #writes
+                        });
+                    }
+                }
+            });
+            quote! {
+                #[allow(unused_variables)]
+                match *self {
+                    #(#arms)*
+                }
+            }
+        },
+        Data::Union(_) => panic!("BonEncode is not supported for union"),
+    };
+
+    quote! {
+        impl #impl_generics ::bon::Encode for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn encode(&self, bb: &mut ::bon::WriteBuffer) {
+                #body
+            }
+        }
+    }
+}
+
+fn impl_decode(ast: &DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let name_str = name.to_string();
+    let mut generics = add_bound(&ast.generics, parse_quote_bound(quote! { ::bon::Decode }));
+    match ast.data {
+        Data::Struct(ref s) => add_default_bound(&mut generics, &s.fields),
+        Data::Enum(ref e) => {
+            for variant in e.variants.iter() {
+                add_default_bound(&mut generics, &variant.fields);
+            }
+        },
+        Data::Union(_) => (),
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match ast.data {
+        Data::Struct(ref s) => decode_fields(quote! { #name }, &s.fields, &name_str),
+        Data::Enum(ref e) => {
+            let tags = variant_tags(&e.variants);
+            let arms = e.variants.iter().zip(tags.iter()).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let variant_str = format!("{}::{}", name_str, ident);
+                let decode = decode_fields(quote! { #name::#ident }, &variant.fields, &variant_str);
+                quote! {
+                    #tag => {
+                        #decode
+                    }
+                }
+            });
+            quote! {
+                let mut body = bb.read_typed_container(::bon::CONTAINER_OBJECT)?;
+                let count = body.read_lengthen()?;
+                if count != 1 {
+                    return Err(::bon::ReadBonErr::Other(format!("decode {} failed, invalid variant count: {}", #name_str, count)));
+                }
+                let tag = body.read_u32()?;
+                let bb = &mut body;
+                match tag {
+                    #(#arms)*
+                    _ => Err(::bon::ReadBonErr::Other(format!("decode {} failed, invalid variant tag: {}", #name_str, tag))),
+                }
+            }
+        },
+        Data::Union(_) => panic!("BonDecode is not supported for union"),
+    };
+
+    quote! {
+        impl #impl_generics ::bon::Decode for #name #ty_generics #where_clause {
+            #[allow(unused_comparisons, unused_mut)]
+            fn decode(bb: &mut ::bon::ReadBuffer) -> Result<Self, ::bon::ReadBonErr> {
+                #body
+            }
+        }
+    }
+}
+
+//解析类型参数的约束
+fn parse_quote_bound(tokens: proc_macro2::TokenStream) -> syn::TypeParamBound {
+    syn::parse2(tokens).unwrap()
+}
@@ -0,0 +1,241 @@
+extern crate bon;
+#[macro_use]
+extern crate bon_derive;
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::HashMap;
+
+use bon::{WriteBuffer, ReadBuffer, Encode, Decode, name_hash};
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode)]
+struct Player {
+    name:   String,
+    level:  u32,
+    #[bon(skip)]
+    cache:  Vec<u8>,
+    items:  HashMap<u32, String>,
+    #[bon(default)]
+    score:  u64,
+}
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode)]
+struct OldPlayer {
+    name:   String,
+    level:  u32,
+    items:  HashMap<u32, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode)]
+struct Pos(i32, #[bon(skip)] u8, i64);
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode)]
+struct Empty;
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode)]
+struct Wrapper<T> {
+    value:  T,
+    list:   Vec<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode)]
+struct Cached<T> {
+    id:     u32,
+    #[bon(skip)]
+    cache:  T,
+}
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode, Serialize, Deserialize)]
+struct Route {
+    id:     u64,
+    path:   String,
+    weight: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode, Serialize, Deserialize)]
+struct Score(u32);
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode, Serialize, Deserialize)]
+struct Point(i32, i32);
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode, Serialize, Deserialize)]
+enum Event {
+    Start,
+    Score(Score),
+    Move(Point, i8),
+    Jump { route: Route, height: u16 },
+}
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode)]
+enum Outcome {
+    Ok(u32),
+    Err(String),
+}
+
+#[derive(Debug, Clone, PartialEq, BonEncode, BonDecode)]
+enum Msg {
+    Ping,
+    Move(Pos),
+    #[bon(tag = 10)]
+    Chat {
+        from:   String,
+        text:   String,
+        #[bon(default)]
+        channel: Option<u8>,
+    },
+    Login(Player, #[bon(skip)] bool),
+}
+
+fn encode<T: Encode>(value: &T) -> Vec<u8> {
+    let mut bb = WriteBuffer::new();
+    value.encode(&mut bb);
+    bb.unwrap()
+}
+
+fn decode<T: Decode>(bin: &[u8]) -> T {
+    T::decode(&mut ReadBuffer::new(bin, 0)).unwrap()
+}
+
+#[test]
+fn test_derive_struct() {
+    let mut items = HashMap::new();
+    items.insert(1, "sword".to_string());
+    items.insert(2, "shield".to_string());
+    let player = Player {
+        name: "abc".to_string(),
+        level: 99,
+        cache: vec![1, 2, 3],
+        items,
+        score: 0xffffffff,
+    };
+
+    //跳过的字段反序列化为默认值
+    let p: Player = decode(&encode(&player));
+    assert_eq!(p, Player { cache: Vec::new(), ..player.clone() });
+
+    //缺少默认值字段时，反序列化为默认值
+    let old = OldPlayer {
+        name: player.name.clone(),
+        level: player.level,
+        items: player.items.clone(),
+    };
+    let p: Player = decode(&encode(&old));
+    assert_eq!(p, Player { cache: Vec::new(), score: 0, ..player.clone() });
+
+    //字段数量过多或缺少非默认值字段时，反序列化失败
+    let bin = encode(&player);
+    assert!(OldPlayer::decode(&mut ReadBuffer::new(&bin, 0)).is_err());
+    let bin = encode(&Empty);
+    assert!(Player::decode(&mut ReadBuffer::new(&bin, 0)).is_err());
+
+    let pos = Pos(15, 7, -100);
+    assert_eq!(decode::<Pos>(&encode(&pos)), Pos(15, 0, -100));
+    assert_eq!(decode::<Empty>(&encode(&Empty)), Empty);
+
+    let wrapper = Wrapper { value: "x".to_string(), list: vec!["y".to_string(), "z".to_string()] };
+    assert_eq!(decode::<Wrapper<String>>(&encode(&wrapper)), wrapper);
+}
+
+#[test]
+fn test_derive_enum() {
+    let msgs = vec![
+        Msg::Ping,
+        Msg::Move(Pos(0, 0, 1)),
+        Msg::Chat { from: "a".to_string(), text: "hello".to_string(), channel: Some(3) },
+        Msg::Login(Player {
+            name: "b".to_string(),
+            level: 1,
+            cache: Vec::new(),
+            items: HashMap::new(),
+            score: 10,
+        }, true),
+    ];
+    let bin = encode(&msgs);
+    let r: Vec<Msg> = decode(&bin);
+    assert_eq!(&r[0..3], &msgs[0..3]);
+    match r[3] {
+        Msg::Login(ref p, is_login) => {
+            assert_eq!(p.name, "b");
+            assert_eq!(p.score, 10);
+            assert!(!is_login);
+        },
+        _ => panic!("invalid msg"),
+    }
+
+    //变体标签默认为变体名称的name_hash，或为指定的变体标签
+    let bin = encode(&Msg::Ping);
+    assert!(ReadBuffer::new(&bin, 0).get_field(name_hash("Ping")).unwrap().is_some());
+    let bin = encode(&Msg::Chat { from: String::new(), text: String::new(), channel: None });
+    assert!(ReadBuffer::new(&bin, 0).get_field(10).unwrap().is_some());
+    assert!(ReadBuffer::new(&bin, 0).get_field(name_hash("Chat")).unwrap().is_none());
+
+    //无效的变体标签
+    let bin = encode(&(3u32));
+    assert!(Msg::decode(&mut ReadBuffer::new(&bin, 0)).is_err());
+    let mut bb = WriteBuffer::new();
+    bb.write_typed_container(bon::CONTAINER_OBJECT, |bb| {
+        bb.write_lengthen(1);
+        bb.write_u32(3);
+        bb.write_nil();
+    });
+    assert!(Msg::decode(&mut ReadBuffer::new(&bb.unwrap(), 0)).is_err());
+}
+
+#[test]
+fn test_derive_skip_generic() {
+    //跳过的泛型字段只需要实现Default
+    let cached = Cached { id: 7, cache: vec![1u8, 2, 3] };
+    assert_eq!(decode::<Cached<Vec<u8>>>(&encode(&cached)), Cached { id: 7, cache: Vec::new() });
+}
+
+#[test]
+fn test_derive_serde_layout() {
+    //派生的编码格式与serde后端相同，可以互相反序列化
+    let events = vec![
+        Event::Start,
+        Event::Score(Score(100)),
+        Event::Move(Point(-1, 2), 3),
+        Event::Jump { route: Route { id: 1, path: "a/b".to_string(), weight: 0.5 }, height: 20 },
+    ];
+    for event in events {
+        let bin = encode(&event);
+        assert_eq!(bin, bon::serde_impl::to_bytes(&event).unwrap());
+        assert_eq!(bon::serde_impl::from_bytes::<Event>(&bin).unwrap(), event);
+        assert_eq!(decode::<Event>(&bon::serde_impl::to_bytes(&event).unwrap()), event);
+    }
+
+    //字段可以是任意顺序
+    let mut bb = WriteBuffer::new();
+    bb.write_typed_container(bon::CONTAINER_OBJECT, |bb| {
+        bb.write_lengthen(3);
+        bb.write_u32(name_hash("path"));
+        "x".to_string().encode(bb);
+        bb.write_u32(name_hash("weight"));
+        bb.write_f32(1.0);
+        bb.write_u32(name_hash("id"));
+        bb.write_u64(9);
+    });
+    assert_eq!(decode::<Route>(&bb.unwrap()), Route { id: 9, path: "x".to_string(), weight: 1.0 });
+}
+
+#[test]
+fn test_derive_result_layout() {
+    //Result与变体名称相同的派生枚举和serde的格式相同，可以互相解码
+    let pairs = vec![(Ok(7u32), Outcome::Ok(7)), (Err("e".to_string()), Outcome::Err("e".to_string()))];
+    for (result, outcome) in pairs {
+        let bin = encode(&result);
+        assert_eq!(bin, encode(&outcome));
+        assert_eq!(bin, bon::serde_impl::to_bytes(&result).unwrap());
+        assert_eq!(decode::<Outcome>(&bin), outcome);
+        assert_eq!(decode::<Result<u32, String>>(&encode(&outcome)), result);
+        assert_eq!(bon::serde_impl::from_bytes::<Result<u32, String>>(&bin).unwrap(), result);
+    }
+
+    //Some(())和嵌套的可选值与serde的格式相同
+    for value in vec![Some(()), None] {
+        assert_eq!(encode(&value), bon::serde_impl::to_bytes(&value).unwrap());
+    }
+    for value in vec![Some(Some(None::<u8>)), Some(None), Some(Some(Some(5u8))), None] {
+        assert_eq!(encode(&value), bon::serde_impl::to_bytes(&value).unwrap());
+    }
+}
@@ -0,0 +1,535 @@
+/**
+ * bon消息信封，为bon格式的消息增加统一的消息头
+ * 信封依次为消息类型hash(u32)、协议版本(u16)、标志(u8)、原始长度(u32)、校验和(u32)和消息内容(bin)，都使用bon格式编码
+ * 标志的第0位表示消息内容是否被压缩，原始长度为消息内容压缩前的长度，解压时不会超过该长度
+ * 原始长度来自不可信的消息头，且校验和可以伪造，所以原始长度超过最大消息内容大小时在分配内存前拒绝
+ * 校验和为消息头(消息类型hash、协议版本、标志和原始长度，按小端字节序)和消息内容(压缩后)的adler32
+ * 编解码器根据消息类型hash，将消息内容分派给注册的消息类型解码
+ */
+extern crate adler32;
+extern crate bon;
+extern crate compress;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use adler32::RollingAdler32;
+use bon::{Decode, Encode, ReadBonErr, ReadBuffer, WriteBuffer};
+use compress::{compress, uncompress_limit, CompressLevel};
+
+// 消息内容已压缩的标志
+pub const FLAG_COMPRESS: u8 = 0x1;
+
+// 默认的最大消息内容大小，即原始长度的上限
+pub const DEFAULT_MAX_PAYLOAD: usize = 16 * 1024 * 1024;
+
+/**
+ * 信封中的消息，消息类型hash在同一个编解码器中必须唯一，一般使用消息名称的bon::name_hash
+ * 协议版本用于兼容旧的消息，只能解码不高于当前版本的消息，派生解码时可以用#[bon(default)]兼容旧版本缺少的字段
+ */
+pub trait Message: Encode + Decode {
+    // 消息类型hash
+    const HASH: u32;
+    // 当前的协议版本
+    const VERSION: u16;
+}
+
+/*
+* 信封错误
+*/
+#[derive(Debug)]
+pub enum EnvelopeErr {
+    //bon编解码错误
+    Bon(ReadBonErr),
+    //压缩或解压错误
+    Io(io::Error),
+    //校验和不匹配
+    Checksum { expect: u32, actual: u32 },
+    //消息内容的长度与原始长度不匹配
+    Length { expect: u32, actual: usize },
+    //原始长度超过最大消息内容大小
+    TooLarge { len: u32, max: usize },
+    //未注册的消息类型
+    UnknownType(u32),
+    //消息的协议版本高于注册的版本
+    Version { hash: u32, version: u16, max: u16 },
+}
+
+impl fmt::Display for EnvelopeErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeErr::Bon(e) => write!(f, "EnvelopeError Bon reason = {}", e),
+            EnvelopeErr::Io(e) => write!(f, "EnvelopeError Io reason = {}", e),
+            EnvelopeErr::Checksum { expect, actual } => write!(
+                f,
+                "EnvelopeError Checksum expect = {:?}, actual = {:?}",
+                expect, actual
+            ),
+            EnvelopeErr::Length { expect, actual } => write!(
+                f,
+                "EnvelopeError Length expect = {:?}, actual = {:?}",
+                expect, actual
+            ),
+            EnvelopeErr::TooLarge { len, max } => {
+                write!(f, "EnvelopeError TooLarge len = {:?}, max = {:?}", len, max)
+            }
+            EnvelopeErr::UnknownType(hash) => {
+                write!(f, "EnvelopeError UnknownType hash = {:?}", hash)
+            }
+            EnvelopeErr::Version { hash, version, max } => write!(
+                f,
+                "EnvelopeError Version hash = {:?}, version = {:?}, max = {:?}",
+                hash, version, max
+            ),
+        }
+    }
+}
+
+impl Error for EnvelopeErr {}
+
+impl From<ReadBonErr> for EnvelopeErr {
+    fn from(e: ReadBonErr) -> Self {
+        EnvelopeErr::Bon(e)
+    }
+}
+
+impl From<io::Error> for EnvelopeErr {
+    fn from(e: io::Error) -> Self {
+        EnvelopeErr::Io(e)
+    }
+}
+
+/**
+ * 信封
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub hash: u32,     //消息类型hash
+    pub version: u16,  //协议版本
+    pub flags: u8,     //标志
+    pub len: u32,      //消息内容压缩前的长度
+    pub checksum: u32, //消息头和消息内容的校验和
+    pub body: Vec<u8>, //消息内容，压缩时为压缩后的内容
+}
+
+impl Envelope {
+    /**
+     * @description 用bon格式的消息内容创建信封，消息内容不小于指定的压缩大小且压缩后更小时压缩，压缩大小为None则不压缩
+     * @example
+     */
+    pub fn new(
+        hash: u32,
+        version: u16,
+        body: Vec<u8>,
+        compress_size: Option<usize>,
+    ) -> Result<Self, EnvelopeErr> {
+        if body.len() > u32::max_value() as usize {
+            return Err(EnvelopeErr::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("new envelope failed, reason: body too long {}", body.len()),
+            )));
+        }
+        let len = body.len() as u32;
+        let mut flags = 0;
+        let mut body = body;
+        match compress_size {
+            Some(size) if body.len() >= size => {
+                let mut dst = Vec::new();
+                compress(&body, &mut dst, CompressLevel::Low)?;
+                if dst.len() < body.len() {
+                    flags |= FLAG_COMPRESS;
+                    body = dst;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(Envelope {
+            hash,
+            version,
+            flags,
+            len,
+            checksum: checksum(hash, version, flags, len, &body),
+            body,
+        })
+    }
+
+    pub fn is_compress(&self) -> bool {
+        self.flags & FLAG_COMPRESS != 0
+    }
+
+    /**
+     * @description 校验并获取bon格式的消息内容，原始长度不能超过默认的最大消息内容大小
+     * @example
+     */
+    pub fn payload<'a>(&'a self) -> Result<Cow<'a, [u8]>, EnvelopeErr> {
+        self.payload_limit(DEFAULT_MAX_PAYLOAD)
+    }
+
+    /**
+     * @description 校验并获取bon格式的消息内容，未压缩时直接借用消息内容，压缩时返回解压后的内容，原始长度超过指定的最大消息内容大小则返回错误
+     * @example
+     */
+    pub fn payload_limit<'a>(&'a self, max_payload: usize) -> Result<Cow<'a, [u8]>, EnvelopeErr> {
+        if self.len as usize > max_payload {
+            return Err(EnvelopeErr::TooLarge {
+                len: self.len,
+                max: max_payload,
+            });
+        }
+
+        let actual = checksum(self.hash, self.version, self.flags, self.len, &self.body);
+        if actual != self.checksum {
+            return Err(EnvelopeErr::Checksum {
+                expect: self.checksum,
+                actual,
+            });
+        }
+
+        let payload = if self.is_compress() {
+            let mut dst = Vec::new();
+            uncompress_limit(&self.body, &mut dst, self.len as usize)?;
+            Cow::Owned(dst)
+        } else {
+            Cow::Borrowed(&self.body[..])
+        };
+        if payload.len() != self.len as usize {
+            return Err(EnvelopeErr::Length {
+                expect: self.len,
+                actual: payload.len(),
+            });
+        }
+        Ok(payload)
+    }
+}
+
+// 计算消息头和消息内容的校验和
+fn checksum(hash: u32, version: u16, flags: u8, len: u32, body: &[u8]) -> u32 {
+    let mut adler = RollingAdler32::new();
+    adler.update_buffer(&hash.to_le_bytes());
+    adler.update_buffer(&version.to_le_bytes());
+    adler.update(flags);
+    adler.update_buffer(&len.to_le_bytes());
+    adler.update_buffer(body);
+    adler.hash()
+}
+
+impl Encode for Envelope {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        bb.write_u32(self.hash);
+        bb.write_u16(self.version);
+        bb.write_u8(self.flags);
+        bb.write_u32(self.len);
+        bb.write_u32(self.checksum);
+        bb.write_bin(&self.body, 0..self.body.len());
+    }
+}
+
+impl Decode for Envelope {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        Ok(Envelope {
+            hash: bb.read_u32()?,
+            version: bb.read_u16()?,
+            flags: bb.read_u8()?,
+            len: bb.read_u32()?,
+            checksum: bb.read_u32()?,
+            body: bb.read_bin()?,
+        })
+    }
+}
+
+// 消息处理器，解码消息内容并处理
+type Handler<R> = Box<dyn Fn(&mut ReadBuffer) -> Result<R, EnvelopeErr> + Send + Sync>;
+
+/**
+ * 信封编解码器，解码时将消息分派给注册的消息处理器，R为消息处理器的返回值
+ */
+pub struct EnvelopeCodec<R> {
+    compress_size: Option<usize>,             //压缩消息内容的最小大小，为None则不压缩
+    max_payload: usize,                       //解码时的最大消息内容大小
+    handlers: HashMap<u32, (u16, Handler<R>)>, //注册的消息处理器和消息的协议版本
+}
+
+impl<R> EnvelopeCodec<R> {
+    pub fn new() -> Self {
+        EnvelopeCodec {
+            compress_size: None,
+            max_payload: DEFAULT_MAX_PAYLOAD,
+            handlers: HashMap::new(),
+        }
+    }
+
+    //创建编码时压缩不小于指定大小的消息内容的编解码器
+    pub fn with_compress(compress_size: usize) -> Self {
+        EnvelopeCodec {
+            compress_size: Some(compress_size),
+            max_payload: DEFAULT_MAX_PAYLOAD,
+            handlers: HashMap::new(),
+        }
+    }
+
+    //设置解码时的最大消息内容大小，默认为DEFAULT_MAX_PAYLOAD
+    pub fn set_max_payload(&mut self, max_payload: usize) {
+        self.max_payload = max_payload;
+    }
+
+    /**
+     * @description 注册指定消息类型的处理器，消息类型hash已注册则返回false
+     * @example
+     */
+    pub fn register<T, F>(&mut self, handler: F) -> bool
+    where
+        T: Message,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        if self.handlers.contains_key(&T::HASH) {
+            return false;
+        }
+
+        let handler: Handler<R> = Box::new(move |bb: &mut ReadBuffer| {
+            let msg = T::decode(bb)?;
+            Ok(handler(msg))
+        });
+        self.handlers.insert(T::HASH, (T::VERSION, handler));
+        true
+    }
+
+    /**
+     * @description 将消息编码为信封
+     * @example
+     */
+    pub fn encode<T: Message>(&self, msg: &T) -> Result<Vec<u8>, EnvelopeErr> {
+        let mut bb = WriteBuffer::new();
+        msg.encode(&mut bb);
+        let envelope = Envelope::new(T::HASH, T::VERSION, bb.unwrap(), self.compress_size)?;
+
+        let mut bb = WriteBuffer::new();
+        envelope.encode(&mut bb);
+        Ok(bb.unwrap())
+    }
+
+    /**
+     * @description 解码信封，校验后将消息分派给注册的消息处理器，返回消息处理器的返回值
+     * @example
+     */
+    pub fn decode(&self, bin: &[u8]) -> Result<R, EnvelopeErr> {
+        let envelope = Envelope::decode(&mut ReadBuffer::new(bin, 0))?;
+        self.dispatch(&envelope)
+    }
+
+    /**
+     * @description 将已解码的信封中的消息分派给注册的消息处理器
+     * @example
+     */
+    pub fn dispatch(&self, envelope: &Envelope) -> Result<R, EnvelopeErr> {
+        let (max, handler) = match self.handlers.get(&envelope.hash) {
+            Some(h) => h,
+            None => return Err(EnvelopeErr::UnknownType(envelope.hash)),
+        };
+        if envelope.version > *max {
+            return Err(EnvelopeErr::Version {
+                hash: envelope.hash,
+                version: envelope.version,
+                max: *max,
+            });
+        }
+
+        let payload = envelope.payload_limit(self.max_payload)?;
+        let mut bb = ReadBuffer::new(&payload, 0);
+        let r = handler(&mut bb)?;
+        if bb.head() != payload.len() {
+            return Err(EnvelopeErr::Bon(ReadBonErr::Other(format!(
+                "decode message failed, hash: {}, len: {}, decode len: {}",
+                envelope.hash,
+                payload.len(),
+                bb.head()
+            ))));
+        }
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Login {
+        name: String,
+        token: Vec<u8>,
+    }
+
+    impl Encode for Login {
+        fn encode(&self, bb: &mut WriteBuffer) {
+            self.name.encode(bb);
+            self.token.encode(bb);
+        }
+    }
+
+    impl Decode for Login {
+        fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+            Ok(Login {
+                name: String::decode(bb)?,
+                token: Vec::decode(bb)?,
+            })
+        }
+    }
+
+    impl Message for Login {
+        const HASH: u32 = 1;
+        const VERSION: u16 = 2;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Ping(u32);
+
+    impl Encode for Ping {
+        fn encode(&self, bb: &mut WriteBuffer) {
+            self.0.encode(bb);
+        }
+    }
+
+    impl Decode for Ping {
+        fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+            Ok(Ping(u32::decode(bb)?))
+        }
+    }
+
+    impl Message for Ping {
+        const HASH: u32 = 2;
+        const VERSION: u16 = 1;
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Msg {
+        Login(Login),
+        Ping(Ping),
+    }
+
+    #[test]
+    fn test_envelope() -> Result<(), EnvelopeErr> {
+        let mut codec = EnvelopeCodec::with_compress(64);
+        assert!(codec.register(Msg::Login));
+        assert!(codec.register(Msg::Ping));
+        assert!(!codec.register(Msg::Ping));
+
+        let login = Login {
+            name: "player".to_string(),
+            token: vec![7; 1000],
+        };
+        let bin = codec.encode(&login)?;
+        let envelope = Envelope::decode(&mut ReadBuffer::new(&bin, 0))?;
+        assert_eq!(envelope.hash, Login::HASH);
+        assert_eq!(envelope.version, Login::VERSION);
+        assert!(envelope.is_compress());
+        assert_eq!(codec.decode(&bin)?, Msg::Login(login));
+
+        // 小消息不压缩
+        let bin = codec.encode(&Ping(3))?;
+        let envelope = Envelope::decode(&mut ReadBuffer::new(&bin, 0))?;
+        assert!(!envelope.is_compress());
+        assert_eq!(codec.decode(&bin)?, Msg::Ping(Ping(3)));
+
+        match envelope.payload()? {
+            Cow::Borrowed(body) => assert_eq!(body, &envelope.body[..]),
+            Cow::Owned(_) => panic!("expect borrowed payload"),
+        }
+
+        // 校验和覆盖消息内容和消息头
+        let mut changed = envelope.clone();
+        changed.body[0] ^= 0xff;
+        match codec.dispatch(&changed) {
+            Err(EnvelopeErr::Checksum { .. }) => (),
+            r => panic!("expect checksum error, {:?}", r),
+        }
+        for change in [
+            (|e: &mut Envelope| e.hash = Login::HASH) as fn(&mut Envelope),
+            |e: &mut Envelope| e.version = 0,
+            |e: &mut Envelope| e.flags = FLAG_COMPRESS,
+            |e: &mut Envelope| e.len += 1,
+        ]
+        .iter()
+        {
+            let mut changed = envelope.clone();
+            change(&mut changed);
+            match changed.payload() {
+                Err(EnvelopeErr::Checksum { .. }) => (),
+                r => panic!("expect checksum error, {:?}", r),
+            }
+        }
+
+        // 解压后的长度不能超过原始长度
+        let mut big = Envelope::new(Ping::HASH, 1, vec![16; 1000], Some(64))?;
+        assert!(big.is_compress());
+        big.len = 999;
+        big.checksum = checksum(big.hash, big.version, big.flags, big.len, &big.body);
+        assert!(big.payload().is_err());
+        big.len = 1001;
+        big.checksum = checksum(big.hash, big.version, big.flags, big.len, &big.body);
+        match big.payload() {
+            Err(EnvelopeErr::Length { expect: 1001, actual: 1000 }) => (),
+            r => panic!("expect length error, {:?}", r),
+        }
+
+        // 伪造的原始长度超过最大消息内容大小时，在解压前拒绝
+        let mut forged = Envelope::new(Ping::HASH, 1, vec![16; 1000], Some(64))?;
+        forged.len = u32::max_value();
+        forged.checksum = checksum(
+            forged.hash,
+            forged.version,
+            forged.flags,
+            forged.len,
+            &forged.body,
+        );
+        match forged.payload() {
+            Err(EnvelopeErr::TooLarge {
+                len,
+                max: DEFAULT_MAX_PAYLOAD,
+            }) if len == u32::max_value() => (),
+            r => panic!("expect too large error, {:?}", r),
+        }
+        let mut bb = WriteBuffer::new();
+        forged.encode(&mut bb);
+        match codec.decode(&bb.unwrap()) {
+            Err(EnvelopeErr::TooLarge { .. }) => (),
+            r => panic!("expect too large error, {:?}", r),
+        }
+        let mut small = EnvelopeCodec::new();
+        small.register(Msg::Login);
+        small.set_max_payload(100);
+        match small.decode(&codec.encode(&Login {
+            name: "a".to_string(),
+            token: vec![7; 1000],
+        })?) {
+            Err(EnvelopeErr::TooLarge { max: 100, .. }) => (),
+            r => panic!("expect too large error, {:?}", r),
+        }
+        assert_eq!(
+            small.decode(&codec.encode(&Login {
+                name: "a".to_string(),
+                token: vec![7; 10]
+            })?)?,
+            Msg::Login(Login {
+                name: "a".to_string(),
+                token: vec![7; 10]
+            })
+        );
+
+        // 未知消息类型和更高的协议版本
+        let unknown = Envelope::new(3, 1, vec![0], None)?;
+        match codec.dispatch(&unknown) {
+            Err(EnvelopeErr::UnknownType(3)) => (),
+            r => panic!("expect unknown type error, {:?}", r),
+        }
+        let newer = Envelope::new(Ping::HASH, 2, vec![16], None)?;
+        match codec.dispatch(&newer) {
+            Err(EnvelopeErr::Version { version: 2, max: 1, .. }) => (),
+            r => panic!("expect version error, {:?}", r),
+        }
+
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ extern crate libc;
 extern crate lz4;
 
 use std::vec::Vec;
-use std::io::{Result as IoResult, Read, Write, ErrorKind};
+use std::io::{Result as IoResult, Read, Write, Error, ErrorKind};
 
 use lz4::{BlockSize, BlockMode, ContentChecksum, EncoderBuilder, Decoder};
 
@@ -62,3 +62,34 @@ pub fn uncompress(src: &[u8], dst: &mut Vec<u8>) -> IoResult<()> {
                 }
             })
 }
+
+/*
+* 同步解压指定的二进制数据，解压后的数据超过指定的最大长度则返回错误，用于解压不可信的数据
+*/
+pub fn uncompress_limit(src: &[u8], dst: &mut Vec<u8>, limit: usize) -> IoResult<()> {
+    dst.truncate(0);
+    Decoder::new(src)
+            .and_then(|decoder| {
+                //多读一个字节，用于判断是否超过最大长度
+                let mut reader = decoder.take(limit as u64 + 1);
+                loop {
+                    match reader.read_to_end(dst) {
+                        Ok(_) => {
+                            ()
+                        },
+                        Err(ref e) if e.kind() == ErrorKind::Interrupted => {
+                            //重复读
+                            continue;
+                        },
+                        Err(e) => {
+                            return Err(e);
+                        },
+                    }
+                    if dst.len() > limit {
+                        return Err(Error::new(ErrorKind::InvalidData, format!("uncompress failed, reason: exceed limit {}", limit)));
+                    }
+                    let (_, result) = reader.into_inner().finish();
+                    return result;
+                }
+            })
+}
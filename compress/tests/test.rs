@@ -1,6 +1,6 @@
 extern crate compress;
 
-use compress::{CompressLevel, compress, uncompress};
+use compress::{CompressLevel, compress, uncompress, uncompress_limit};
 
 #[test]
 fn test_lz4() {
@@ -19,4 +19,17 @@ fn test_lz4() {
     assert!(uncompress(&vec[..], &mut vec_).is_ok());
     println!("!!!!!!!!!!!!!!!!!!!!!!!!!vec_ len: {}, vec_: {:?}", vec_.len(), vec_);
     assert!(String::from_utf8(vec_).ok().unwrap() == string);
+}
+
+#[test]
+fn test_lz4_limit() {
+    let buffer = vec![7u8; 1000];
+    let mut vec = Vec::new();
+    assert!(compress(&buffer[..], &mut vec, CompressLevel::Low).is_ok());
+
+    let mut vec_ = Vec::new();
+    assert!(uncompress_limit(&vec[..], &mut vec_, 1000).is_ok());
+    assert!(vec_ == buffer);
+    assert!(uncompress_limit(&vec[..], &mut vec_, 999).is_err());
+    assert!(vec_.len() <= 1000);
 }
\ No newline at end of file
@@ -131,7 +131,7 @@ fn main() {
     let position = Position {x: 5.0, y: 5.0};
     let positions = world.fetch_multi::<Node, Position>().unwrap();
     let positions = LendMut::lend_mut(&positions);
-    positions.insert(e, position);
+    positions.insert(e, position).unwrap();
 
     // modify component
     let write = unsafe { positions.get_unchecked_write(e) };
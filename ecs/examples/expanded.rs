@@ -775,7 +775,7 @@ fn main() {
     let position = Position{x: 5.0, y: 5.0,};
     let positions = world.fetch_multi::<Node, Position>().unwrap();
     let positions = LendMut::lend_mut(&positions);
-    positions.insert(e, position);
+    assert!(positions.insert(e, position).is_ok());
     let write = unsafe { positions.get_unchecked_write(e) };
     write.value.x = 10.0;
     write.notify.modify_event(write.id, "x", 0);
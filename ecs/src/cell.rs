@@ -1,25 +1,58 @@
-use std::cell::RefCell;
-use std::ops::{Deref, DerefMut};
+use std::cell::UnsafeCell;
 use std::default::Default;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
-pub struct StdCell<T>(RefCell<T>);
+// 被可变借用时的借用标志
+const WRITING: usize = usize::max_value();
+
+/// 使用原子借用标志的单元，并行派发器中的系统会在多个线程中同时借用同一个单元
+/// 共享借用可以同时存在，可变借用独占，借用冲突时等待其它线程释放借用，而不是panic
+/// 同一线程持有借用时不能再可变借用同一个单元，否则会一直等待
+pub struct StdCell<T> {
+    flag: AtomicUsize, // 共享借用的数量，被可变借用时为WRITING
+    value: UnsafeCell<T>,
+}
 
 impl<T> StdCell<T> {
     pub fn new(value: T) -> Self {
-        Self(RefCell::new(value))
+        StdCell {
+            flag: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
     }
-}
 
-impl<T> Deref for StdCell<T> {
-    type Target = RefCell<T>;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    /// 共享借用，被可变借用时等待
+    pub fn borrow(&self) -> Ref<'_, T> {
+        let mut flag = self.flag.load(Ordering::Relaxed);
+        loop {
+            if flag == WRITING || flag == WRITING - 1 {
+                thread::yield_now();
+                flag = self.flag.load(Ordering::Relaxed);
+                continue;
+            }
+            match self.flag.compare_exchange_weak(flag, flag + 1, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => return Ref { cell: self },
+                Err(f) => flag = f,
+            }
+        }
+    }
+
+    /// 可变借用，被借用时等待
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        while self
+            .flag
+            .compare_exchange_weak(0, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            thread::yield_now();
+        }
+        RefMut { cell: self }
     }
-}
 
-impl<T> DerefMut for StdCell<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.value.get() }
     }
 }
 
@@ -30,4 +63,75 @@ impl<T: Default> Default for StdCell<T> {
 }
 
 unsafe impl<T> Send for StdCell<T>{}
-unsafe impl<T> Sync for StdCell<T>{}
\ No newline at end of file
+unsafe impl<T> Sync for StdCell<T>{}
+
+/// 共享借用，释放时减少借用标志
+pub struct Ref<'a, T> {
+    cell: &'a StdCell<T>,
+}
+
+impl<'a, T> Deref for Ref<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<'a, T> Drop for Ref<'a, T> {
+    fn drop(&mut self) {
+        self.cell.flag.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// 可变借用，释放时清除借用标志
+pub struct RefMut<'a, T> {
+    cell: &'a StdCell<T>,
+}
+
+impl<'a, T> Deref for RefMut<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<'a, T> DerefMut for RefMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.cell.value.get() }
+    }
+}
+
+impl<'a, T> Drop for RefMut<'a, T> {
+    fn drop(&mut self) {
+        self.cell.flag.store(0, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_shared_read() {
+        // 多个线程同时共享借用同一个单元，借用标志不会冲突
+        let cell = Arc::new(StdCell::new(0usize));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let cell = cell.clone();
+                thread::spawn(move || {
+                    for _ in 0..10000 {
+                        let r = cell.borrow();
+                        assert_eq!(*r, 0);
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        *cell.borrow_mut() = 1;
+        assert_eq!(*cell.borrow(), 1);
+    }
+}
\ No newline at end of file
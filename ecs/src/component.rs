@@ -12,8 +12,9 @@ use map::{Map};
 use listener::Listener;
 
 use system::{SystemData, SystemMutData};
-use monitor::{Notify, NotifyImpl, CreateFn, DeleteFn, ModifyFn, Write, DeleteEvent};
-use entity::CellEntity;
+use monitor::{Notify, NotifyImpl, CreateFn, DeleteFn, ModifyFn, Write, DeleteEvent, ChangeTicks};
+use entity::{CellEntity, EntityKey};
+use query::{Query, Maybe, Without};
 use {Fetch, Lend, LendMut, TypeIds, World};
 use cell::StdCell;
 
@@ -22,6 +23,7 @@ pub trait Component: Sized + 'static {
 }
 
 pub trait MultiCase: Notify + ArcAny {
+    // 实体删除时调用，实体已从slab中移除，所以只能用原始id
     fn delete(&self, id: usize);
 }
 impl_downcast_arc!(MultiCase);
@@ -30,6 +32,7 @@ pub type CellMultiCase<E, C> = StdCell<MultiCaseImpl<E, C>>;
 
 impl<E: 'static, C: Component> MultiCase for CellMultiCase<E, C> {
     fn delete(&self, id: usize) {
+        self.borrow().notify.changes.delete(id);
         let notify = self.borrow_mut().notify.delete.clone();
         let e = DeleteEvent{
             id: id,
@@ -89,11 +92,19 @@ impl<E: 'static, C: Component> MultiCaseImpl<E, C> {
     pub fn mem_size(&self) -> usize {
         self.map.mem_size() + self.notify.mem_size()
     }
-    pub fn get(&self, id: usize) -> Option<&C> {
-        self.map.get(&id)
+    /// id可以是EntityId或原始id，EntityId的代数与实体不同时返回None
+    pub fn get<K: EntityKey>(&self, id: K) -> Option<&C> {
+        if !self.is_alive(id) {
+            return None;
+        }
+        self.map.get(&id.index())
     }
-    pub fn get_mut(&mut self, id: usize) -> Option<&mut C> {
-        self.map.get_mut(&id)
+    /// 直接修改组件，不发出修改事件，也不会被变化记录记下，需要时用get_write修改后发出事件
+    pub fn get_mut<K: EntityKey>(&mut self, id: K) -> Option<&mut C> {
+        if !self.is_alive(id) {
+            return None;
+        }
+        self.map.get_mut(&id.index())
     }
     pub unsafe fn get_unchecked(&self, id: usize) -> &C {
         self.map.get_unchecked(&id)
@@ -101,16 +112,26 @@ impl<E: 'static, C: Component> MultiCaseImpl<E, C> {
     pub unsafe fn get_unchecked_mut(&mut self, id: usize) -> &mut C {
         self.map.get_unchecked_mut(&id)
     }
-    pub fn get_write(&mut self, id: usize) -> Option<Write<C>> {
+    pub fn get_write<K: EntityKey>(&mut self, id: K) -> Option<Write<C>> {
+        if !self.is_alive(id) {
+            return None;
+        }
+        let id = id.index();
         match self.map.get_mut(&id) {
             Some(r) => Some(Write::new(id, r, &self.notify)),
             None => None,
         }
     }
+    /// 与get_write相同，Write只在调用notify.modify_event时发出修改事件并记录变化
     pub unsafe fn get_unchecked_write(&mut self, id: usize) -> Write<C> {
         Write::new(id, self.map.get_unchecked_mut(&id), &self.notify)
     }
-    pub fn insert(&mut self, id: usize, c: C) -> Option<C> {
+    /// 返回被替换的组件。实体不存在，或EntityId的代数与实体不同时不插入，返回Err(c)，原始id也会检查实体是否存在
+    pub fn insert<K: EntityKey>(&mut self, id: K, c: C) -> Result<Option<C>, C> {
+        if !self.entity.borrow().is_alive(id) {
+            return Err(c);
+        }
+        let id = id.index();
         let r = self.map.insert(id, c);
         match r {
             Some(_) => self.notify.modify_event(id, "", 0),
@@ -119,18 +140,27 @@ impl<E: 'static, C: Component> MultiCaseImpl<E, C> {
                 self.notify.create_event(id);
             },
         }
-        None
+        Ok(r)
     }
 
-    pub fn insert_no_notify(&mut self, id: usize, c: C) -> Option<C> {
+    /// 与insert相同，但不发出事件
+    pub fn insert_no_notify<K: EntityKey>(&mut self, id: K, c: C) -> Result<Option<C>, C> {
+        if !self.entity.borrow().is_alive(id) {
+            return Err(c);
+        }
+        let id = id.index();
         let r = self.map.insert(id, c);
         if let None = r {
             self.entity.borrow_mut().mark(id, self.bit_index)
         }
-        r
+        Ok(r)
     }
     
-    pub fn delete(&mut self, id: usize) -> Option<C> {
+    pub fn delete<K: EntityKey>(&mut self, id: K) -> Option<C> {
+        if !self.is_alive(id) {
+            return None;
+        }
+        let id = id.index();
         self.entity.borrow_mut().un_mark(id, self.bit_index);
         self.notify.delete_event(id);
         self.map.remove(&id)
@@ -144,12 +174,95 @@ impl<E: 'static, C: Component> MultiCaseImpl<E, C> {
         &self.notify
     }
 
+    pub fn get_changes(&self) -> &ChangeTicks {
+        self.notify.get_changes()
+    }
+
+    // 原始id不检查代数
+    fn is_alive<K: EntityKey>(&self, id: K) -> bool {
+        match id.generation() {
+            Some(_) => self.entity.borrow().is_alive(id),
+            None => true,
+        }
+    }
+
     // fn remove(&mut self, id: usize) -> DeleteListeners {
     //     self.map.remove(&id);
     //     self.notify.delete.clone()
     // }
 }
 
+impl<'a, E: 'static, C: Component> Query<E> for &'a MultiCaseImpl<E, C> {
+    type Item = &'a C;
+    fn require(&self) -> u64 {
+        1 << self.bit_index
+    }
+    fn exclude(&self) -> u64 {
+        0
+    }
+    unsafe fn fetch_item(&mut self, id: usize) -> Self::Item {
+        let r: &'a MultiCaseImpl<E, C> = *self;
+        r.get_unchecked(id)
+    }
+}
+
+impl<'a, E: 'static, C: Component> Query<E> for &'a mut MultiCaseImpl<E, C> {
+    type Item = &'a mut C;
+    fn require(&self) -> u64 {
+        1 << self.bit_index
+    }
+    fn exclude(&self) -> u64 {
+        0
+    }
+    // 每个实体只获取一次，所以返回的可变引用不会重叠
+    unsafe fn fetch_item(&mut self, id: usize) -> Self::Item {
+        &mut *(self.get_unchecked_mut(id) as *mut C)
+    }
+}
+
+impl<'a, E: 'static, C: Component> Query<E> for Maybe<&'a MultiCaseImpl<E, C>> {
+    type Item = Option<&'a C>;
+    fn require(&self) -> u64 {
+        0
+    }
+    fn exclude(&self) -> u64 {
+        0
+    }
+    unsafe fn fetch_item(&mut self, id: usize) -> Self::Item {
+        let r: &'a MultiCaseImpl<E, C> = self.0;
+        r.get(id)
+    }
+}
+
+impl<'a, E: 'static, C: Component> Query<E> for Maybe<&'a mut MultiCaseImpl<E, C>> {
+    type Item = Option<&'a mut C>;
+    fn require(&self) -> u64 {
+        0
+    }
+    fn exclude(&self) -> u64 {
+        0
+    }
+    unsafe fn fetch_item(&mut self, id: usize) -> Self::Item {
+        match self.0.get_mut(id) {
+            Some(r) => Some(&mut *(r as *mut C)),
+            None => None,
+        }
+    }
+}
+
+impl<'a, E: 'static, C: Component> Query<E> for Without<&'a MultiCaseImpl<E, C>> {
+    type Item = ();
+    fn require(&self) -> u64 {
+        0
+    }
+    fn exclude(&self) -> u64 {
+        1 << self.0.bit_index
+    }
+    unsafe fn fetch_item(&mut self, _id: usize) -> Self::Item {
+        ()
+    }
+}
+
 impl<'a, E: 'static, C: Component> SystemData<'a> for &'a MultiCaseImpl<E, C> {
     type FetchTarget = ShareMultiCase<E, C>;
 }
@@ -1,7 +1,15 @@
+use std::any::{Any, TypeId};
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
 use atom::Atom;
 use cell::StdCell;
 use listener::{FnListeners, Listener};
 
+use system::{ListenerDepends, RunnerFn};
 use world::World;
 
 pub trait Dispatcher {
@@ -35,33 +43,6 @@ impl Dispatcher for SeqDispatcher {
                 None => (),
             }
         }
-
-        // 根据系统的读写数据，计算依赖关系。 如果一个数据被读写，则读会依赖写。写会先执行，读后执行
-        // let mut system_map = FxHashMap32::default();
-        // let mut component_map = FxHashMap32::default();
-        //let mut vec = &mut self.vec;
-        // for k in names.iter() {
-        //     depend(world, k, &mut system_map, &mut component_map)
-        // }
-        // let mut len = names.len();
-        // loop {
-        //     for i in 0..len {
-        //         if calc(&names[len - i - 1], &mut system_map, &mut component_map) {
-        //             let key = names.swap_remove(len - i- 1);
-        //             system_map.remove(&key);
-        //             let sys = world.get_system(&key).unwrap();
-        //             let run = sys.fetch_run(sys.clone(), world).unwrap();
-        //             self.vec.push_back(run);
-        //         }
-        //     }
-        //     if len == names.len() {
-        //         panic!("cycle depend, {:?}", names);
-        //     }
-        //     len = names.len();
-        //     if len == 0 {
-        //         break;
-        //     }
-        // }
     }
     fn run(&self) {
         // println!("dispatch===========================");
@@ -69,44 +50,405 @@ impl Dispatcher for SeqDispatcher {
     }
 }
 
-//====================================
-// // 根据系统的读写数据，计算依赖关系
-// fn depend(world: &World, key: &Atom, system_map: &mut FxHashMap32<Atom, (Vec<(TypeId, TypeId)>, Vec<(TypeId, TypeId)>)>, component_map: &mut FxHashMap32<(TypeId, TypeId), (Vec<Atom>, Vec<Atom>)>) {
-//     match world.get_system(key) {
-//         Some(arc_sys) => {
-//             let (read, write) = arc_sys.get_depends();
-//             for r in read.iter() {
-//                 match component_map.entry((r.0, r.1)) {
-//                     Entry::Occupied(mut e) =>e.get_mut().0.push(key.clone()),
-//                     Entry::Vacant(e) => {e.insert((vec![key.clone()], Vec::new()));}
-//                 }
-//             }
-//             for r in write.iter() {
-//                 match component_map.entry((r.0, r.1)) {
-//                     Entry::Occupied(mut e) =>e.get_mut().1.push(key.clone()),
-//                     Entry::Vacant(e) => {e.insert((Vec::new(), vec![key.clone()]));}
-//                 }
-//             }
-//             system_map.insert(key.clone(), (read, write));
-//         },
-//         _ => ()
-//     }
-// }
-
-// // 根据依赖关系，计算先后次序
-// fn calc(key: &Atom, system_map: &FxHashMap32<Atom, (Vec<(TypeId, TypeId)>, Vec<(TypeId, TypeId)>)>, component_map: &FxHashMap32<(TypeId, TypeId), (Vec<Atom>, Vec<Atom>)>) -> bool {
-//     let (read_components, _) = system_map.get(key).unwrap();
-//     for k in read_components.iter() {
-//         match component_map.get(k) {
-//             Some((_, write_systems)) => {
-//                 for w in write_systems.iter() {
-//                     if system_map.get(w) != None {
-//                         return false
-//                     }
-//                 }
-//             },
-//             _ => ()
-//         }
-//     }
-//     true
-// }
+/// 并行派发器，根据系统读写的数据计算依赖关系，没有冲突的系统在派发器的工作线程中并行执行
+/// 读写同一数据的系统按列出的顺序执行，只读同一数据的系统可以并行执行
+/// 并行执行的系统通过StdCell的原子借用标志同时借用数据
+/// 监听器在触发事件的系统所在的线程中执行，所以系统写的数据上的监听器读写的数据，也计入该系统读写的数据
+pub struct ParallelDispatcher {
+    pool: Arc<Pool>,
+    threads: Vec<JoinHandle<()>>,
+    graph: Arc<Vec<SysNode>>,
+}
+
+// 依赖图中的系统
+struct SysNode {
+    run: RunnerFn,
+    depend: usize,     // 依赖的系统数量
+    nexts: Vec<usize>, // 依赖本系统的系统
+}
+
+// 工作线程共享的任务队列
+struct Pool {
+    queue: Mutex<(VecDeque<(Arc<RunState>, usize)>, bool)>, // 待执行的系统，和是否已关闭
+    cond: Condvar,
+}
+
+impl ParallelDispatcher {
+    /// 创建指定工作线程数量的并行派发器，工作线程在派发器销毁时退出
+    pub fn new(thread_count: usize) -> Self {
+        if thread_count == 0 {
+            panic!("invalid thread count, thread_count: {}", thread_count);
+        }
+        let pool = Arc::new(Pool {
+            queue: Mutex::new((VecDeque::new(), false)),
+            cond: Condvar::new(),
+        });
+        let threads = (0..thread_count)
+            .map(|i| {
+                let pool = pool.clone();
+                thread::Builder::new()
+                    .name(format!("Ecs-Dispatch-{}", i))
+                    .spawn(move || work(&pool))
+                    .unwrap()
+            })
+            .collect();
+        ParallelDispatcher {
+            pool,
+            threads,
+            graph: Arc::new(Vec::new()),
+        }
+    }
+}
+
+impl Drop for ParallelDispatcher {
+    fn drop(&mut self) {
+        self.pool.queue.lock().unwrap().1 = true;
+        self.pool.cond.notify_all();
+        for t in self.threads.drain(..) {
+            let _ = t.join();
+        }
+    }
+}
+
+impl Dispatcher for ParallelDispatcher {
+    fn build(&mut self, names: String, world: &World) {
+        let mut v = Vec::new();
+        for s in names.split(',') {
+            v.push(Atom::from(s.trim_start().trim_end()))
+        }
+        self.init(v, world);
+    }
+    fn init(&mut self, names: Vec<Atom>, world: &World) {
+        // 所有系统的监听器，包括不在本派发器中的系统
+        let listeners: Vec<ListenerDepends> = world
+            .iter_system()
+            .flat_map(|(_, sys)| sys.get_listener_depends())
+            .collect();
+        let mut nodes = Vec::new();
+        let mut depends = Vec::new();
+        for k in names.iter() {
+            let sys = match world.get_system(&k) {
+                Some(r) => r,
+                None => panic!("system is not exist:{}", **k),
+            };
+            match sys.fetch_run() {
+                Some(run) => {
+                    let mut depend = sys.get_depends();
+                    add_listener_depends(&mut depend, &listeners);
+                    depends.push(depend);
+                    nodes.push(SysNode {
+                        run,
+                        depend: 0,
+                        nexts: Vec::new(),
+                    });
+                }
+                None => (),
+            }
+        }
+
+        // 根据系统的读写数据，计算依赖关系。 如果两个系统读写同一数据，则先列出的系统先执行，依赖只从前向后，所以不会成环
+        for i in 0..nodes.len() {
+            for j in i + 1..nodes.len() {
+                let (read_i, write_i) = &depends[i];
+                let (read_j, write_j) = &depends[j];
+                if is_conflict(write_i, write_j)
+                    || is_conflict(write_i, read_j)
+                    || is_conflict(write_j, read_i)
+                {
+                    link(&mut nodes, i, j);
+                }
+            }
+        }
+        self.graph = Arc::new(nodes);
+    }
+    fn run(&self) {
+        let len = self.graph.len();
+        if len == 0 {
+            return;
+        }
+
+        let state = Arc::new(RunState {
+            pool: self.pool.clone(),
+            graph: self.graph.clone(),
+            depends: self.graph.iter().map(|n| AtomicUsize::new(n.depend)).collect(),
+            count: AtomicUsize::new(len),
+            panic: Mutex::new(None),
+            finish: (Mutex::new(false), Condvar::new()),
+        });
+        for i in 0..len {
+            if self.graph[i].depend == 0 {
+                spawn(&state, i);
+            }
+        }
+
+        let mut finish = state.finish.0.lock().unwrap();
+        while !*finish {
+            finish = state.finish.1.wait(finish).unwrap();
+        }
+        let e = state.panic.lock().unwrap().take();
+        if let Some(e) = e {
+            // 在调用线程中重新抛出系统执行时的panic
+            panic::resume_unwind(e);
+        }
+    }
+}
+
+// 一次派发的执行状态
+struct RunState {
+    pool: Arc<Pool>,
+    graph: Arc<Vec<SysNode>>,
+    depends: Vec<AtomicUsize>,                  // 每个系统未执行完的依赖数量
+    count: AtomicUsize,                         // 未执行完的系统数量
+    panic: Mutex<Option<Box<dyn Any + Send>>>,  // 第一个panic的系统的panic信息
+    finish: (Mutex<bool>, Condvar),             // 所有系统是否已执行完
+}
+
+// 工作线程，从任务队列中取出系统执行，队列为空且已关闭则退出
+fn work(pool: &Pool) {
+    loop {
+        let (state, index) = {
+            let mut queue = pool.queue.lock().unwrap();
+            loop {
+                if let Some(task) = queue.0.pop_front() {
+                    break task;
+                }
+                if queue.1 {
+                    return;
+                }
+                queue = pool.cond.wait(queue).unwrap();
+            }
+        };
+        exec(&state, index);
+    }
+}
+
+// 派发系统到工作线程
+fn spawn(state: &Arc<RunState>, index: usize) {
+    state.pool.queue.lock().unwrap().0.push_back((state.clone(), index));
+    state.pool.cond.notify_one();
+}
+
+// 执行系统，并派发依赖已全部执行完的后续系统
+fn exec(state: &Arc<RunState>, index: usize) {
+    let node = &state.graph[index];
+    if let Err(e) = panic::catch_unwind(AssertUnwindSafe(|| node.run.listen(&()))) {
+        let mut panic = state.panic.lock().unwrap();
+        if panic.is_none() {
+            *panic = Some(e);
+        }
+    }
+    for next in node.nexts.iter() {
+        if state.depends[*next].fetch_sub(1, Ordering::AcqRel) == 1 {
+            spawn(state, *next);
+        }
+    }
+    if state.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+        *state.finish.0.lock().unwrap() = true;
+        state.finish.1.notify_one();
+    }
+}
+
+// 将系统写的数据上的监听器读写的数据加入系统的读写数据，监听器写的数据可能继续触发其它监听器
+fn add_listener_depends(
+    depend: &mut (Vec<(TypeId, TypeId)>, Vec<(TypeId, TypeId)>),
+    listeners: &[ListenerDepends],
+) {
+    let mut added = vec![false; listeners.len()];
+    loop {
+        let mut changed = false;
+        for (i, (target, read, write)) in listeners.iter().enumerate() {
+            if !added[i] && is_conflict(&depend.1, std::slice::from_ref(target)) {
+                added[i] = true;
+                changed = true;
+                depend.0.extend_from_slice(read);
+                depend.1.extend_from_slice(write);
+            }
+        }
+        if !changed {
+            return;
+        }
+    }
+}
+
+// 判断两组数据是否冲突，删除实体时会删除实体上的所有组件，所以实体与它的组件也冲突
+fn is_conflict(a: &[(TypeId, TypeId)], b: &[(TypeId, TypeId)]) -> bool {
+    let unit = TypeId::of::<()>();
+    for x in a.iter() {
+        for y in b.iter() {
+            if x == y || (x.0 == y.0 && x.0 != unit && (x.1 == unit || y.1 == unit)) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// 增加依赖，to在from执行完后执行
+fn link(nodes: &mut Vec<SysNode>, from: usize, to: usize) {
+    nodes[from].nexts.push(to);
+    nodes[to].depend += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::time::{Duration, Instant};
+
+    use atom;
+    use atom::Atom;
+    use share;
+
+    use super::*;
+    use monitor::ModifyEvent;
+    use single::SingleCaseImpl;
+    use system::{Runner, SingleCaseListener};
+    use Lend;
+
+    // impl_system!展开后通过ecs::路径访问监听器trait
+    mod ecs {
+        pub use system::SingleCaseListener;
+    }
+
+    pub struct A(usize);
+    pub struct B(usize);
+    pub struct C(usize);
+
+    // 正在执行的write_a和write_b的数量，和两者是否同时执行过
+    static RUNNING: AtomicUsize = AtomicUsize::new(0);
+    static OVERLAP: AtomicBool = AtomicBool::new(false);
+
+    // 等待另一个系统也开始执行，超时则认为没有并行执行
+    fn wait_other() {
+        RUNNING.fetch_add(1, Ordering::AcqRel);
+        let time = Instant::now();
+        while RUNNING.load(Ordering::Acquire) < 2 {
+            if Instant::now() - time > Duration::from_secs(5) {
+                return;
+            }
+            thread::yield_now();
+        }
+        OVERLAP.store(true, Ordering::Release);
+    }
+
+    pub struct WriteA;
+    impl<'a> Runner<'a> for WriteA {
+        type ReadData = ();
+        type WriteData = &'a mut SingleCaseImpl<A>;
+        fn run(&mut self, _read: Self::ReadData, write: Self::WriteData) {
+            wait_other();
+            write.0 = 1;
+        }
+    }
+    impl_system! {WriteA, true, {}}
+
+    pub struct WriteB;
+    impl<'a> Runner<'a> for WriteB {
+        type ReadData = ();
+        type WriteData = &'a mut SingleCaseImpl<B>;
+        fn run(&mut self, _read: Self::ReadData, write: Self::WriteData) {
+            wait_other();
+            write.0 = 2;
+        }
+    }
+    impl_system! {WriteB, true, {}}
+
+    pub struct WriteC;
+    impl<'a> Runner<'a> for WriteC {
+        type ReadData = (&'a SingleCaseImpl<A>, &'a SingleCaseImpl<B>);
+        type WriteData = &'a mut SingleCaseImpl<C>;
+        fn run(&mut self, read: Self::ReadData, write: Self::WriteData) {
+            write.0 = read.0 .0 + read.1 .0;
+        }
+    }
+    impl_system! {WriteC, true, {}}
+
+    pub struct ReadC;
+    impl<'a> Runner<'a> for ReadC {
+        type ReadData = &'a SingleCaseImpl<C>;
+        type WriteData = &'a mut SingleCaseImpl<B>;
+        fn run(&mut self, _read: Self::ReadData, _write: Self::WriteData) {}
+    }
+    impl_system! {ReadC, true, {}}
+
+    // 修改a并发出事件
+    pub struct NotifyA;
+    impl<'a> Runner<'a> for NotifyA {
+        type ReadData = ();
+        type WriteData = &'a mut SingleCaseImpl<A>;
+        fn run(&mut self, _read: Self::ReadData, write: Self::WriteData) {
+            write.0 += 1;
+            write.get_notify_ref().modify_event(0, "", 0);
+        }
+    }
+    impl_system! {NotifyA, true, {}}
+
+    // 监听a的修改，写c
+    pub struct ListenA;
+    impl<'a> SingleCaseListener<'a, A, ModifyEvent> for ListenA {
+        type ReadData = &'a SingleCaseImpl<A>;
+        type WriteData = &'a mut SingleCaseImpl<C>;
+        fn listen(&mut self, _event: &ModifyEvent, read: Self::ReadData, write: Self::WriteData) {
+            write.0 = read.0 * 10;
+        }
+    }
+    impl_system! {ListenA, false, {SingleCaseListener<A, ModifyEvent>}}
+
+    fn world() -> World {
+        let mut world = World::default();
+        world.register_single::<A>(A(0));
+        world.register_single::<B>(B(0));
+        world.register_single::<C>(C(0));
+        world.register_system(Atom::from("write_a"), CellWriteA::new(WriteA));
+        world.register_system(Atom::from("write_b"), CellWriteB::new(WriteB));
+        world.register_system(Atom::from("write_c"), CellWriteC::new(WriteC));
+        world.register_system(Atom::from("read_c"), CellReadC::new(ReadC));
+        world
+    }
+
+    #[test]
+    fn test_parallel() {
+        let world = world();
+        // write_c读a和b，按列出的顺序在write_a和write_b之后执行
+        let mut dispatch = ParallelDispatcher::new(4);
+        dispatch.build("write_c, write_a".to_string(), &world);
+        assert_eq!(dispatch.graph[0].nexts, vec![1]);
+
+        // write_a和write_b互相等待对方开始执行，只有并行执行才能同时执行
+        dispatch.build("write_a, write_b, write_c".to_string(), &world);
+        dispatch.run();
+        assert!(OVERLAP.load(Ordering::Acquire));
+        let c = world.fetch_single::<C>().unwrap();
+        assert_eq!(Lend::lend(&c).0, 3);
+    }
+
+    #[test]
+    fn test_listener() {
+        let mut world = world();
+        world.register_system(Atom::from("notify_a"), CellNotifyA::new(NotifyA));
+        world.register_system(Atom::from("listen_a"), CellListenA::new(ListenA));
+        // notify_a写a时，listen_a的监听器在notify_a中执行并写c，所以read_c要在notify_a之后执行
+        let mut dispatch = ParallelDispatcher::new(2);
+        dispatch.build("notify_a, read_c".to_string(), &world);
+        assert_eq!(dispatch.graph[0].nexts, vec![1]);
+        dispatch.build("read_c, notify_a".to_string(), &world);
+        assert_eq!(dispatch.graph[0].nexts, vec![1]);
+
+        dispatch.run();
+        let c = world.fetch_single::<C>().unwrap();
+        assert_eq!(Lend::lend(&c).0, 10);
+    }
+
+    #[test]
+    fn test_mutual() {
+        let world = world();
+        let mut dispatch = ParallelDispatcher::new(2);
+        // read_c写b读c，write_c写c读b，互相读写的系统按列出的顺序执行
+        dispatch.build("write_c, read_c".to_string(), &world);
+        assert_eq!(dispatch.graph[0].nexts, vec![1]);
+        assert_eq!(dispatch.graph[1].depend, 1);
+        dispatch.build("read_c, write_c".to_string(), &world);
+        assert_eq!(dispatch.graph[0].nexts, vec![1]);
+        assert_eq!(dispatch.graph[1].depend, 1);
+        dispatch.run();
+    }
+}
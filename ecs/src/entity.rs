@@ -15,6 +15,7 @@ use {Fetch, Lend, LendMut, TypeIds, World};
 use system::{SystemData, SystemMutData};
 use monitor::{Notify, NotifyImpl, CreateFn, DeleteFn, ModifyFn};
 use component::MultiCase;
+use query::{Query, QueryIter};
 use cell::StdCell;
 
 
@@ -73,8 +74,54 @@ impl<T: 'static> Entity for CellEntity<T> {
 }
 
 
+/// 带代数的实体id，实体被删除后代数加1，之前的EntityId就会失效，不会访问到复用了该id的新实体
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct EntityId {
+    index: usize,
+    generation: u32,
+}
+
+impl EntityId {
+    pub fn new(index: usize, generation: u32) -> Self {
+        EntityId { index, generation }
+    }
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// 可以用来访问实体和组件的id，EntityId会检查代数，usize为不检查代数的原始id，用于兼容使用原始id的代码
+/// 事件、Write、QueryIter和MultiCase::delete仍然使用原始id，迁移时用EntityImpl::get_id转换为EntityId后再保存
+pub trait EntityKey: Copy {
+    fn index(&self) -> usize;
+    // 需要匹配的代数，None表示不检查
+    fn generation(&self) -> Option<u32>;
+}
+
+impl EntityKey for usize {
+    fn index(&self) -> usize {
+        *self
+    }
+    fn generation(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl EntityKey for EntityId {
+    fn index(&self) -> usize {
+        self.index
+    }
+    fn generation(&self) -> Option<u32> {
+        Some(self.generation)
+    }
+}
+
 pub struct EntityImpl<T>{
     slab: Slab<u64>, // 值usize 记录每个id所关联的component的掩码位
+    generations: Vec<u32>, // 每个id的代数，id被删除时加1
     components: Vec<Arc<dyn MultiCase>>, // 组件
     notify: NotifyImpl,
     marker: PhantomData<T>,
@@ -83,6 +130,7 @@ impl<T> EntityImpl<T> {
     pub fn new() -> EntityImpl<T> {
         EntityImpl{
             slab: Slab::default(),
+            generations: Vec::new(),
             components: Vec::new(),
             notify: NotifyImpl::default(),
             marker: PhantomData,
@@ -92,6 +140,7 @@ impl<T> EntityImpl<T> {
     pub fn mem_size(&self) -> usize {
         let mut r = 0;
         r += self.slab.mem_size();
+        r += self.generations.capacity() * std::mem::size_of::<u32>();
         r += self.components.capacity() * std::mem::size_of::<Arc<dyn MultiCase>>();
         r += self.notify.mem_size();
         r
@@ -103,6 +152,36 @@ impl<T> EntityImpl<T> {
             None => false
         }
     }
+    /// 实体是否存在，EntityId的代数也必须相同
+    pub fn is_alive<K: EntityKey>(&self, id: K) -> bool {
+        if !self.is_exist(id.index()) {
+            return false;
+        }
+        match id.generation() {
+            Some(g) => self.get_generation(id.index()) == g,
+            None => true,
+        }
+    }
+    /// 将原始id转换为带当前代数的EntityId，实体不存在则返回None
+    pub fn get_id(&self, id: usize) -> Option<EntityId> {
+        if self.is_exist(id) {
+            Some(EntityId::new(id, self.get_generation(id)))
+        } else {
+            None
+        }
+    }
+    pub fn get_generation(&self, id: usize) -> u32 {
+        match self.generations.get(id) {
+            Some(g) => *g,
+            None => 0,
+        }
+    }
+    pub fn get_generations(&self) -> &[u32] {
+        &self.generations
+    }
+    pub fn len(&self) -> usize {
+        self.slab.len()
+    }
     pub fn get_mask(&self) -> usize {
         self.components.len()
     }
@@ -117,6 +196,10 @@ impl<T> EntityImpl<T> {
         self.notify.create_event(id);
         id
     }
+    pub fn create_id(&mut self) -> EntityId {
+        let id = self.create();
+        EntityId::new(id, self.get_generation(id))
+    }
     pub fn mark(&mut self, id: usize, bit_index: usize) {
         let mask = self.slab.get_mut(id).unwrap();
         *mask |= 1<<bit_index;
@@ -129,6 +212,10 @@ impl<T> EntityImpl<T> {
     }
     pub fn delete(&mut self, id: usize) {
         let mask = self.slab.remove(id);
+        if self.generations.len() <= id {
+            self.generations.resize(id + 1, 0);
+        }
+        self.generations[id] = self.generations[id].wrapping_add(1);
         self.notify.modify_event(id, "", 0);
         if mask == 0 {
             return
@@ -145,6 +232,28 @@ impl<T> EntityImpl<T> {
     pub fn iter(&self) -> EntityIter {
         EntityIter(self.slab.iter())
     }
+
+    /// 按分配的先后顺序，返回所有空闲的id
+    pub fn free_ids(&self) -> Vec<usize> {
+        self.slab.vacancy_keys()
+    }
+
+    /// 恢复id的分配状态，必须在没有分配过id时调用，不会发出创建事件
+    /// count为分配过的id的数量，free_ids为按分配的先后顺序排列的空闲id，之后创建的实体会按相同的顺序分配id
+    pub fn restore(&mut self, count: usize, free_ids: &[usize], generations: Vec<u32>) {
+        for _ in 0..count {
+            self.slab.insert(0);
+        }
+        for id in free_ids.iter().rev() {
+            self.slab.remove(*id);
+        }
+        self.generations = generations;
+    }
+
+    /// 查询同时拥有所需组件的实体，返回实体id和查询结果
+    pub fn query<Q: Query<T>>(&self, query: Q) -> QueryIter<T, Q> {
+        QueryIter::new(self.slab.iter(), query)
+    }
 }
 
 pub struct EntityIter<'a>(SlabIter<'a, u64>);
@@ -214,3 +323,72 @@ impl<'a, T: 'static> LendMut<'a> for ShareEntity<T> {
         unsafe {&mut * (&mut *self.deref().borrow_mut() as *mut EntityImpl<T>)}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use map::vecmap::VecMap;
+
+    use {Component, Lend, LendMut, World};
+
+    pub struct Node;
+
+    #[derive(Debug, PartialEq)]
+    pub struct Pos(usize);
+    impl Component for Pos {
+        type Storage = VecMap<Self>;
+    }
+
+    #[test]
+    fn test_generation() {
+        let mut world = World::default();
+        world.register_entity::<Node>();
+        world.register_multi::<Node, Pos>();
+        let entity = world.fetch_entity::<Node>().unwrap();
+        let pos = world.fetch_multi::<Node, Pos>().unwrap();
+        let pos = LendMut::lend_mut(&pos);
+
+        let old = world.create_entity_id::<Node>();
+        assert_eq!(pos.insert(old, Pos(1)), Ok(None));
+        assert_eq!(pos.get(old), Some(&Pos(1)));
+        world.free_entity_id::<Node>(old);
+
+        // 复用了id的新实体，旧的EntityId失效
+        let new = world.create_entity_id::<Node>();
+        assert_eq!(new.index(), old.index());
+        assert_eq!(new.generation(), old.generation() + 1);
+        assert!(!Lend::lend(&entity).is_alive(old));
+        assert_eq!(pos.insert(old, Pos(2)), Err(Pos(2)));
+        assert_eq!(pos.insert(new, Pos(3)), Ok(None));
+        assert_eq!(pos.insert_no_notify(old, Pos(2)), Err(Pos(2)));
+        assert_eq!(pos.get(old), None);
+        assert!(pos.get_mut(old).is_none());
+        assert!(pos.get_write(old).is_none());
+        assert_eq!(pos.delete(old), None);
+        world.free_entity_id::<Node>(old);
+        assert!(Lend::lend(&entity).is_alive(new));
+
+        // 原始id不检查代数，可以转换为EntityId
+        assert_eq!(pos.get(new.index()), Some(&Pos(3)));
+        assert_eq!(Lend::lend(&entity).get_id(new.index()), Some(new));
+        assert_eq!(pos.insert(new.index(), Pos(4)), Ok(Some(Pos(3))));
+        assert_eq!(pos.delete(new.index()), Some(Pos(4)));
+        assert_eq!(pos.insert(new.index(), Pos(5)), Ok(None));
+        world.free_entity::<Node>(new.index());
+        assert_eq!(Lend::lend(&entity).get_id(new.index()), None);
+
+        // 实体删除后，原始id取不到组件，也不能插入
+        let raw = new.index();
+        assert_eq!(pos.get(raw), None);
+        assert!(pos.get_write(raw).is_none());
+        assert_eq!(pos.insert(raw, Pos(6)), Err(Pos(6)));
+        assert_eq!(pos.insert_no_notify(raw, Pos(6)), Err(Pos(6)));
+        assert_eq!(pos.get(raw), None);
+
+        // 原始id不检查代数，会访问到复用了该id的新实体
+        let next = world.create_entity_id::<Node>();
+        assert_eq!(next.index(), raw);
+        assert_eq!(pos.insert(raw, Pos(7)), Ok(None));
+        assert_eq!(pos.get(next), Some(&Pos(7)));
+        assert_eq!(pos.get(new), None);
+    }
+}
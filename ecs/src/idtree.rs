@@ -1,3 +1,4 @@
+use bon::{Decode, Encode, ReadBonErr, ReadBuffer, WriteBuffer};
 use map::vecmap::VecMap;
 use monitor::NotifyImpl;
 
@@ -434,6 +435,159 @@ pub struct NodeList {
     pub len: usize,
 }
 
+// 依次写入节点数量，以及每个节点的id和节点
+impl Encode for IdTree {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        self.map.len().encode(bb);
+        for (i, n) in self.map.iter().enumerate() {
+            match n {
+                Some(n) => {
+                    (i + 1).encode(bb);
+                    n.encode(bb);
+                }
+                None => (),
+            }
+        }
+    }
+}
+
+// 解码时节点id的默认上限，不可信数据中过大的id会使VecMap分配过多的内存
+pub const DEFAULT_MAX_ID: usize = 1 << 20;
+
+// 每个节点编码后的最小长度，即id和节点的8个usize各占1字节
+const MIN_NODE_SIZE: usize = 9;
+
+impl Decode for IdTree {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        IdTree::decode_limit(bb, DEFAULT_MAX_ID)
+    }
+}
+
+impl IdTree {
+    /// 解码IdTree，节点id超过max_id，或节点数量超过剩余数据能容纳的数量，则返回错误
+    pub fn decode_limit(bb: &mut ReadBuffer, max_id: usize) -> Result<Self, ReadBonErr> {
+        let mut tree = IdTree::default();
+        let count = usize::decode(bb)?;
+        if count > (bb.len() - bb.head()) / MIN_NODE_SIZE {
+            return Err(ReadBonErr::Other(format!(
+                "decode IdTree failed, count: {}, remaining: {}",
+                count,
+                bb.len() - bb.head()
+            )));
+        }
+        for _ in 0..count {
+            let id = usize::decode(bb)?;
+            if id == 0 {
+                return Err(ReadBonErr::Other("decode IdTree failed, invalid id: 0".to_string()));
+            }
+            if id > max_id {
+                return Err(ReadBonErr::Other(format!("decode IdTree failed, id: {}, max: {}", id, max_id)));
+            }
+            if tree.map.get(id).is_some() {
+                return Err(ReadBonErr::Other(format!("decode IdTree failed, duplicate id: {}", id)));
+            }
+            tree.map.insert(id, Node::decode(bb)?);
+        }
+        match tree.check() {
+            Ok(_) => Ok(tree),
+            Err(s) => Err(ReadBonErr::Other(format!("decode IdTree failed, {}", s))),
+        }
+    }
+
+    // 检查节点之间的链接是否一致，解码不可信的数据时使用，否则之后的unsafe访问可能越界
+    // 每个节点的子节点链表必须首尾相连，子节点的parent指向该节点，数量与len和count一致
+    // 父节点的子节点数量等于parent指向它的节点数量，所以不会有链表外的节点，count一致也保证了没有环
+    fn check(&self) -> Result<(), String> {
+        let mut child_len = VecMap::with_capacity(self.map.len());
+        for (i, n) in self.map.iter().enumerate() {
+            let n = match n {
+                Some(n) => n,
+                None => continue,
+            };
+            let id = i + 1;
+            if n.parent == 0 {
+                if n.layer > 1 || n.prev > 0 || n.next > 0 {
+                    return Err(format!("invalid root, id: {}", id));
+                }
+            } else {
+                let p = match self.map.get(n.parent) {
+                    Some(p) => p,
+                    None => return Err(format!("parent is not exist, id: {}", id)),
+                };
+                if n.layer != if p.layer > 0 { p.layer + 1 } else { 0 } {
+                    return Err(format!("invalid layer, id: {}", id));
+                }
+                match child_len.get_mut(n.parent) {
+                    Some(len) => *len += 1,
+                    None => {
+                        child_len.insert(n.parent, 1usize);
+                    }
+                }
+            }
+            // 遍历子节点链表，最多走len步
+            let (mut prev, mut next, mut len, mut count) = (0, n.children.head, 0, 0);
+            while next > 0 {
+                len += 1;
+                if len > n.children.len {
+                    return Err(format!("children len mismatch, id: {}", id));
+                }
+                let c = match self.map.get(next) {
+                    Some(c) => c,
+                    None => return Err(format!("child is not exist, id: {}, child: {}", id, next)),
+                };
+                if c.parent != id || c.prev != prev {
+                    return Err(format!("invalid child link, id: {}, child: {}", id, next));
+                }
+                count += c.count + 1;
+                prev = next;
+                next = c.next;
+            }
+            if len != n.children.len || prev != n.children.tail || count != n.count {
+                return Err(format!("children mismatch, id: {}", id));
+            }
+        }
+        for (i, n) in self.map.iter().enumerate() {
+            if let Some(n) = n {
+                let len = child_len.get(i + 1).cloned().unwrap_or(0);
+                if len != n.children.len {
+                    return Err(format!("children len mismatch, id: {}", i + 1));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Encode for Node {
+    fn encode(&self, bb: &mut WriteBuffer) {
+        self.parent.encode(bb);
+        self.layer.encode(bb);
+        self.count.encode(bb);
+        self.prev.encode(bb);
+        self.next.encode(bb);
+        self.children.head.encode(bb);
+        self.children.tail.encode(bb);
+        self.children.len.encode(bb);
+    }
+}
+
+impl Decode for Node {
+    fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+        Ok(Node {
+            parent: usize::decode(bb)?,
+            layer: usize::decode(bb)?,
+            count: usize::decode(bb)?,
+            prev: usize::decode(bb)?,
+            next: usize::decode(bb)?,
+            children: NodeList {
+                head: usize::decode(bb)?,
+                tail: usize::decode(bb)?,
+                len: usize::decode(bb)?,
+            },
+        })
+    }
+}
+
 pub struct ChildrenIterator<'a> {
     inner: &'a VecMap<Node>,
     head: usize,
@@ -511,6 +665,66 @@ fn test11() {
         println!("i: {}", i);
     }
 }
+#[test]
+fn test_decode() {
+    let mut tree: IdTree = IdTree::default();
+    for id in 1..5 {
+        tree.create(id);
+    }
+    tree.insert_child(2, 1, 0, None);
+    tree.insert_child(3, 1, 1, None);
+    tree.insert_child(4, 3, 0, None);
+    tree.insert_child(1, 0, 0, None);
+    let mut bb = WriteBuffer::new();
+    tree.encode(&mut bb);
+    let bin = bb.unwrap();
+    let tree1 = IdTree::decode(&mut ReadBuffer::new(&bin, 0)).unwrap();
+    assert_eq!(tree1.get(1).unwrap().count, 3);
+    assert_eq!(tree1.get(4).unwrap().layer, 3);
+
+    // 修改链接后编码，解码时应返回错误
+    let corrupt = |f: &dyn Fn(&mut IdTree)| {
+        let mut tree = IdTree::decode(&mut ReadBuffer::new(&bin, 0)).unwrap();
+        f(&mut tree);
+        let mut bb = WriteBuffer::new();
+        tree.encode(&mut bb);
+        IdTree::decode(&mut ReadBuffer::new(&bb.unwrap(), 0)).is_err()
+    };
+    assert!(corrupt(&|t| t.map.get_mut(2).unwrap().next = 9));
+    assert!(corrupt(&|t| t.map.get_mut(3).unwrap().prev = 0));
+    assert!(corrupt(&|t| t.map.get_mut(4).unwrap().parent = 2));
+    assert!(corrupt(&|t| t.map.get_mut(1).unwrap().children.len = 3));
+    assert!(corrupt(&|t| t.map.get_mut(1).unwrap().count = 2));
+    assert!(corrupt(&|t| t.map.get_mut(4).unwrap().layer = 1));
+    // 3和4互为父子，形成环
+    assert!(corrupt(&|t| {
+        let n = t.map.get_mut(3).unwrap();
+        n.parent = 4;
+        n.prev = 0;
+    }));
+
+    // 截断的数据和超过剩余数据的节点数量
+    assert!(IdTree::decode(&mut ReadBuffer::new(&bin[..bin.len() - 1], 0)).is_err());
+    let mut bb = WriteBuffer::new();
+    usize::max_value().encode(&mut bb);
+    1usize.encode(&mut bb);
+    assert!(IdTree::decode(&mut ReadBuffer::new(&bb.unwrap(), 0)).is_err());
+    // 超过上限的节点id，不会按id分配内存
+    let mut bb = WriteBuffer::new();
+    1usize.encode(&mut bb);
+    (usize::max_value() >> 8).encode(&mut bb);
+    Node::default().encode(&mut bb);
+    let bin = bb.unwrap();
+    assert!(IdTree::decode(&mut ReadBuffer::new(&bin, 0)).is_err());
+    assert!(IdTree::decode_limit(&mut ReadBuffer::new(&bin, 0), 4).is_err());
+    let mut bb = WriteBuffer::new();
+    1usize.encode(&mut bb);
+    5usize.encode(&mut bb);
+    Node::default().encode(&mut bb);
+    let bin = bb.unwrap();
+    assert!(IdTree::decode_limit(&mut ReadBuffer::new(&bin, 0), 4).is_err());
+    assert!(IdTree::decode_limit(&mut ReadBuffer::new(&bin, 0), 5).is_ok());
+}
 #[cfg(test)]
 fn test_println(tree: &IdTree) {
     println!("--------------------------------");
@@ -10,6 +10,7 @@ extern crate pointer;
 extern crate any;
 extern crate hash;
 extern crate share;
+extern crate bon;
 
 // extern crate im;
 pub extern crate paste;
@@ -25,6 +26,8 @@ pub mod component;
 pub mod dispatch;
 pub mod single;
 pub mod monitor;
+pub mod query;
+pub mod snapshot;
 
 pub mod idtree;
 
@@ -32,9 +35,11 @@ pub use world::World;
 pub use system::{Runner, SingleCaseListener, MultiCaseListener, EntityListener, System};
 pub use component::{Component, MultiCaseImpl, CellMultiCase};
 pub use single::{SingleCaseImpl, CellSingleCase};
-pub use entity::{EntityImpl, CellEntity};
-pub use monitor::{CreateEvent, ModifyEvent, DeleteEvent, Write};
-pub use dispatch::{SeqDispatcher, Dispatcher};
+pub use entity::{EntityImpl, CellEntity, EntityId, EntityKey};
+pub use monitor::{CreateEvent, ModifyEvent, DeleteEvent, Write, ChangeTicks, ChangeIter};
+pub use dispatch::{SeqDispatcher, ParallelDispatcher, Dispatcher};
+pub use query::{Query, QueryIter, Maybe, Without};
+pub use snapshot::{Snapshot, SnapshotErr};
 
 use std::any::TypeId;
 
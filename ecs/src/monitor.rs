@@ -1,12 +1,17 @@
+use hash::XHashMap;
 use listener::{Listener as LibListener, FnListeners};
 use share::Share;
+use std::cell::UnsafeCell;
+use std::vec::IntoIter;
 use std::ops::{Deref};
 pub use listener::FnListener;
 
+/// 事件中的id都是原始id，需要在事件之后保存时，用EntityImpl::get_id转换为EntityId
 pub struct CreateEvent{
     pub id: usize,
 }
 
+/// 实体删除时发出的事件，实体已经不存在，get_id会返回None
 pub struct DeleteEvent{
     pub id: usize,
 }
@@ -47,6 +52,10 @@ impl NotifyImpl {
     pub fn remove_modify(&self, listener: &ModifyFn) {
         unsafe {&mut *(self.0.as_ref() as *const NotifyImpl1 as *mut NotifyImpl1)}.modify.delete(listener);
     }
+
+    pub fn get_changes(&self) -> &ChangeTicks {
+        &self.0.changes
+    }
 }
 
 impl Deref for NotifyImpl {
@@ -63,24 +72,28 @@ pub struct NotifyImpl1 {
     pub create: CreateListeners,
     pub delete: DeleteListeners,
     pub modify: ModifyListeners,
+    pub changes: ChangeTicks,
 }
 impl NotifyImpl1 {
     pub fn mem_size(&self) -> usize {
-        self.create.mem_size() + self.delete.mem_size() + self.modify.mem_size()
+        self.create.mem_size() + self.delete.mem_size() + self.modify.mem_size() + self.changes.mem_size()
     }
     pub fn create_event(&self, id: usize) {
+        self.changes.create(id);
         let e = CreateEvent{
             id: id,
         };
         self.create.listen(&e);
     }
     pub fn delete_event(&self, id: usize) {
+        self.changes.delete(id);
         let e = DeleteEvent{
             id: id,
         };
         self.delete.listen(&e);
     }
     pub fn modify_event(&self, id: usize, field: &'static str, index: usize) {
+        self.changes.modify(id);
         let e = ModifyEvent{
             id: id,
             field: field,
@@ -90,6 +103,114 @@ impl NotifyImpl1 {
     }
 }
 
+/// 变化记录，记录每个id最后一次被创建、修改和删除时的变化序号，每次变化序号加1
+/// 系统在运行结束时记下当前的变化序号，下次运行时就可以查询这之后发生变化的id，而不需要注册监听器
+/// 被删除的id不再出现在创建和修改的查询中
+/// 只记录通过事件发出的变化，如insert、delete和Write发出的事件；get_mut、get_unchecked_mut、get_unchecked_write
+/// 和单例组件的DerefMut直接修改数据，不发出修改事件时不会被记录
+/// 事件只在拥有写权限时触发，所以记录变化时不需要加锁
+/// 查询返回调用时的快照，迭代过程中记录新的变化不影响迭代，也不会出现在本次迭代中
+#[derive(Default)]
+pub struct ChangeTicks(UnsafeCell<ChangeTicks1>);
+
+unsafe impl Send for ChangeTicks {}
+unsafe impl Sync for ChangeTicks {}
+
+#[derive(Default, Clone)]
+struct ChangeTicks1 {
+    tick: usize,
+    create: XHashMap<usize, usize>,
+    modify: XHashMap<usize, usize>,
+    delete: XHashMap<usize, usize>,
+}
+
+impl Clone for ChangeTicks {
+    fn clone(&self) -> Self {
+        ChangeTicks(UnsafeCell::new(self.get().clone()))
+    }
+}
+
+impl ChangeTicks {
+    pub fn mem_size(&self) -> usize {
+        let r = self.get();
+        (r.create.capacity() + r.modify.capacity() + r.delete.capacity()) * std::mem::size_of::<(usize, usize)>()
+    }
+    /// 当前的变化序号
+    pub fn tick(&self) -> usize {
+        self.get().tick
+    }
+    /// 在指定的变化序号之后创建的id
+    pub fn created(&self, since: usize) -> ChangeIter {
+        ChangeIter::new(&self.get().create, since)
+    }
+    /// 在指定的变化序号之后修改的id，不包括只被创建的id
+    pub fn modified(&self, since: usize) -> ChangeIter {
+        ChangeIter::new(&self.get().modify, since)
+    }
+    /// 在指定的变化序号之后删除的id
+    pub fn deleted(&self, since: usize) -> ChangeIter {
+        ChangeIter::new(&self.get().delete, since)
+    }
+    /// 清除不晚于指定变化序号的记录，变化序号不会重置
+    /// 所有关心变化的系统都已处理到该序号后调用，避免记录随id的增删无限增长
+    pub fn clear_before(&self, tick: usize) {
+        let r = self.get_mut();
+        r.create.retain(|_, t| *t > tick);
+        r.modify.retain(|_, t| *t > tick);
+        r.delete.retain(|_, t| *t > tick);
+    }
+    pub fn create(&self, id: usize) {
+        let r = self.get_mut();
+        r.tick += 1;
+        r.create.insert(id, r.tick);
+        r.modify.remove(&id);
+    }
+    pub fn modify(&self, id: usize) {
+        let r = self.get_mut();
+        r.tick += 1;
+        r.modify.insert(id, r.tick);
+    }
+    pub fn delete(&self, id: usize) {
+        let r = self.get_mut();
+        r.tick += 1;
+        r.create.remove(&id);
+        r.modify.remove(&id);
+        r.delete.insert(id, r.tick);
+    }
+
+    fn get(&self) -> &ChangeTicks1 {
+        unsafe {&*self.0.get()}
+    }
+    fn get_mut(&self) -> &mut ChangeTicks1 {
+        unsafe {&mut *self.0.get()}
+    }
+}
+
+/// 变化记录的迭代器，返回id，顺序不确定
+/// 创建时复制符合条件的id，不借用变化记录
+pub struct ChangeIter {
+    iter: IntoIter<usize>,
+}
+
+impl ChangeIter {
+    fn new(map: &XHashMap<usize, usize>, since: usize) -> ChangeIter {
+        let ids: Vec<usize> = map.iter().filter(|(_, tick)| **tick > since).map(|(id, _)| *id).collect();
+        ChangeIter{iter: ids.into_iter()}
+    }
+}
+
+impl Iterator for ChangeIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
 pub trait Notify {
     fn add_create(&self, CreateFn);
     fn add_delete(&self, DeleteFn);
@@ -102,6 +223,7 @@ pub trait Notify {
     fn remove_modify(&self, &ModifyFn);
 }
 
+/// id为原始id，在组件存在时取得，可以用EntityImpl::get_id转换为EntityId
 pub struct Write<'a, T>{
     pub id: usize,
     pub value: &'a mut T,
@@ -114,4 +236,79 @@ impl<'a, T> Write<'a, T> {
             id, value, notify
         }
     }
+}
+#[cfg(test)]
+mod tests {
+    use map::vecmap::VecMap;
+
+    use {Component, LendMut, World};
+
+    pub struct Node;
+
+    pub struct Pos(usize);
+    impl Component for Pos {
+        type Storage = VecMap<Self>;
+    }
+
+    fn sorted<I: Iterator<Item = usize>>(iter: I) -> Vec<usize> {
+        let mut r: Vec<usize> = iter.collect();
+        r.sort();
+        r
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut world = World::default();
+        world.register_entity::<Node>();
+        world.register_multi::<Node, Pos>();
+        let pos = world.fetch_multi::<Node, Pos>().unwrap();
+        let pos = LendMut::lend_mut(&pos);
+
+        let ids: Vec<usize> = (0..4).map(|_| world.create_entity::<Node>()).collect();
+        for id in ids.iter() {
+            assert!(pos.insert(*id, Pos(0)).is_ok());
+        }
+        assert_eq!(sorted(pos.get_changes().created(0)), ids);
+
+        // 上次运行结束
+        let last = pos.get_changes().tick();
+        assert_eq!(pos.get_changes().created(last).count(), 0);
+
+        let write = pos.get_write(ids[0]).unwrap();
+        write.value.0 = 1;
+        write.notify.modify_event(write.id, "0", 0);
+        assert!(pos.insert(ids[1], Pos(2)).is_ok());
+        pos.delete(ids[2]);
+        world.free_entity::<Node>(ids[3]);
+
+        let changes = pos.get_changes();
+        assert_eq!(changes.created(last).count(), 0);
+        assert_eq!(sorted(changes.modified(last)), vec![ids[0], ids[1]]);
+        assert_eq!(sorted(changes.deleted(last)), vec![ids[2], ids[3]]);
+        assert_eq!(sorted(changes.created(0)), vec![ids[0], ids[1]]);
+        assert_eq!(changes.tick(), last + 4);
+
+        // 迭代时记录新的变化，不影响本次迭代
+        let mut modified = Vec::new();
+        for id in pos.get_changes().modified(last) {
+            assert!(pos.insert(id, Pos(3)).is_ok());
+            modified.push(id);
+        }
+        modified.sort();
+        assert_eq!(modified, vec![ids[0], ids[1]]);
+        assert_eq!(pos.get_changes().tick(), last + 6);
+
+        // 清除已处理的记录
+        pos.get_changes().clear_before(last + 4);
+        assert_eq!(pos.get_changes().created(0).count(), 0);
+        assert_eq!(pos.get_changes().deleted(0).count(), 0);
+        assert_eq!(sorted(pos.get_changes().modified(0)), vec![ids[0], ids[1]]);
+        pos.get_changes().clear_before(pos.get_changes().tick());
+        assert_eq!(pos.get_changes().modified(0).count(), 0);
+        assert_eq!(pos.get_changes().tick(), last + 6);
+
+        // 直接修改不发出事件，不会被记录
+        pos.get_mut(ids[0]).unwrap().0 = 4;
+        assert_eq!(pos.get_changes().modified(0).count(), 0);
+    }
 }
\ No newline at end of file
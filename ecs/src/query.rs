@@ -0,0 +1,189 @@
+//! 多组件联合查询，根据实体上记录的组件掩码，只遍历同时拥有所需组件的实体
+//!
+//! 查询项可以是&MultiCaseImpl、&mut MultiCaseImpl、Maybe和Without，以及它们组成的元组，例如：
+//! ```ignore
+//! for (id, (pos, vel, tag, _)) in entity.query((positions, velocitys, Maybe(tags), Without(hiddens))) {
+//!     ...
+//! }
+//! ```
+use std::marker::PhantomData;
+
+use slab::SlabIter;
+
+/// E是实体的类型，只能查询同一种实体上的组件
+pub trait Query<E> {
+    type Item;
+    // 实体必须拥有的组件的掩码
+    fn require(&self) -> u64;
+    // 实体不能拥有的组件的掩码
+    fn exclude(&self) -> u64;
+    // 获取实体上的查询结果，调用者需保证实体满足掩码，并且每个实体只获取一次
+    unsafe fn fetch_item(&mut self, id: usize) -> Self::Item;
+}
+
+/// 可选的查询项，实体没有该组件时查询结果为None
+pub struct Maybe<T>(pub T);
+
+/// 过滤掉拥有该组件的实体，查询结果为()
+pub struct Without<T>(pub T);
+
+/// 查询迭代器，返回实体id和查询结果，id为原始id，迭代时实体一定存在，需要保存时用EntityImpl::get_id转换
+pub struct QueryIter<'a, E, Q: Query<E>> {
+    iter: SlabIter<'a, u64>,
+    query: Q,
+    require: u64,
+    exclude: u64,
+    marker: PhantomData<E>,
+}
+
+impl<'a, E, Q: Query<E>> QueryIter<'a, E, Q> {
+    pub fn new(iter: SlabIter<'a, u64>, query: Q) -> Self {
+        QueryIter {
+            iter,
+            require: query.require(),
+            exclude: query.exclude(),
+            query,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, E, Q: Query<E>> Iterator for QueryIter<'a, E, Q> {
+    type Item = (usize, Q::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (id, mask) = self.iter.next()?;
+            if *mask & self.require == self.require && *mask & self.exclude == 0 {
+                return Some((id, unsafe { self.query.fetch_item(id) }));
+            }
+        }
+    }
+}
+
+impl<E> Query<E> for () {
+    type Item = ();
+    fn require(&self) -> u64 {
+        0
+    }
+    fn exclude(&self) -> u64 {
+        0
+    }
+    unsafe fn fetch_item(&mut self, _id: usize) -> Self::Item {
+        ()
+    }
+}
+
+macro_rules! impl_query {
+    ( $($ty:ident),* ) => {
+        #[allow(non_snake_case)]
+        impl<E, $($ty),*> Query<E> for ( $( $ty , )* ) where $( $ty: Query<E> ),*{
+            type Item = ( $($ty::Item,)* );
+            fn require(&self) -> u64 {
+                let ( $($ty,)* ) = self;
+                0 $(| $ty.require())*
+            }
+            fn exclude(&self) -> u64 {
+                let ( $($ty,)* ) = self;
+                0 $(| $ty.exclude())*
+            }
+            unsafe fn fetch_item(&mut self, id: usize) -> Self::Item {
+                let ( $($ty,)* ) = self;
+                ( $($ty.fetch_item(id),)* )
+            }
+        }
+    };
+}
+
+impl_query!(A);
+impl_query!(A, B);
+impl_query!(A, B, C);
+impl_query!(A, B, C, D);
+impl_query!(A, B, C, D, F);
+impl_query!(A, B, C, D, F, G);
+impl_query!(A, B, C, D, F, G, H);
+impl_query!(A, B, C, D, F, G, H, I);
+impl_query!(A, B, C, D, F, G, H, I, J);
+impl_query!(A, B, C, D, F, G, H, I, J, K);
+impl_query!(A, B, C, D, F, G, H, I, J, K, L);
+impl_query!(A, B, C, D, F, G, H, I, J, K, L, M);
+
+#[cfg(test)]
+mod tests {
+    use map::vecmap::VecMap;
+
+    use {Component, LendMut, World};
+
+    pub struct Node;
+
+    pub struct Pos(usize);
+    impl Component for Pos {
+        type Storage = VecMap<Self>;
+    }
+
+    pub struct Vel(usize);
+    impl Component for Vel {
+        type Storage = VecMap<Self>;
+    }
+
+    pub struct Tag;
+    impl Component for Tag {
+        type Storage = VecMap<Self>;
+    }
+
+    pub struct Hidden;
+    impl Component for Hidden {
+        type Storage = VecMap<Self>;
+    }
+
+    #[test]
+    fn test_query() {
+        use super::{Maybe, Without};
+
+        let mut world = World::default();
+        world.register_entity::<Node>();
+        world.register_multi::<Node, Pos>();
+        world.register_multi::<Node, Vel>();
+        world.register_multi::<Node, Tag>();
+        world.register_multi::<Node, Hidden>();
+
+        let entity = world.fetch_entity::<Node>().unwrap();
+        let pos = world.fetch_multi::<Node, Pos>().unwrap();
+        let vel = world.fetch_multi::<Node, Vel>().unwrap();
+        let tag = world.fetch_multi::<Node, Tag>().unwrap();
+        let hidden = world.fetch_multi::<Node, Hidden>().unwrap();
+        let (entity, pos, vel, tag, hidden) = (
+            LendMut::lend_mut(&entity),
+            LendMut::lend_mut(&pos),
+            LendMut::lend_mut(&vel),
+            LendMut::lend_mut(&tag),
+            LendMut::lend_mut(&hidden),
+        );
+
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let id = world.create_entity::<Node>();
+            assert!(pos.insert(id, Pos(i)).is_ok());
+            ids.push(id);
+        }
+        // ids[0]没有速度，ids[3]被隐藏，ids[4]的速度被删除
+        for id in ids[1..].iter() {
+            assert!(vel.insert(*id, Vel(1)).is_ok());
+        }
+        assert!(tag.insert(ids[2], Tag).is_ok());
+        assert!(hidden.insert(ids[3], Hidden).is_ok());
+        vel.delete(ids[4]);
+
+        let mut r = Vec::new();
+        for (id, (p, v, t, _)) in entity.query((&*pos, &mut *vel, Maybe(&*tag), Without(&*hidden))) {
+            v.0 += p.0;
+            r.push((id, t.is_some()));
+        }
+        assert_eq!(r, vec![(ids[1], false), (ids[2], true)]);
+        assert_eq!(vel.get(ids[2]).unwrap().0, 3);
+        assert_eq!(vel.get(ids[3]).unwrap().0, 1);
+
+        world.free_entity::<Node>(ids[1]);
+        assert_eq!(entity.query(&*vel).map(|(id, _)| id).collect::<Vec<usize>>(), vec![ids[2], ids[3]]);
+    }
+}
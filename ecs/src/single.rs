@@ -8,7 +8,7 @@ use any::ArcAny;
 // use pointer::cell::{StdCell};
 
 use cell::StdCell;
-use monitor::{CreateFn, DeleteFn, ModifyFn, Notify, NotifyImpl, Write};
+use monitor::{ChangeTicks, CreateFn, DeleteFn, ModifyFn, Notify, NotifyImpl, Write};
 use system::{SystemData, SystemMutData};
 use {Fetch, Lend, LendMut, TypeIds, World};
 
@@ -83,6 +83,10 @@ impl<T: 'static> SingleCaseImpl<T> {
         &self.notify
     }
 
+    pub fn get_changes(&self) -> &ChangeTicks {
+        self.notify.get_changes()
+    }
+
     pub fn get_write(&mut self) -> Write<T> {
         Write::new(0, &mut self.value, &self.notify)
     }
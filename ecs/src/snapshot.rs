@@ -0,0 +1,546 @@
+//! 世界快照，将注册的实体、组件和单例组件用bon格式保存为二进制数据，并恢复到新的世界中
+//!
+//! 快照依次为段的数量，以及每段的名称和二进制数据，恢复时根据名称找到注册的段，并按实体、组件、单例组件的顺序恢复
+//! 恢复的世界需要注册相同的实体、组件和单例组件，实体必须没有分配过id，恢复时不会发出事件
+//! 快照可能来自不可信的数据，实体分配过的id数量不能超过最大实体数量，组件数量不能超过剩余数据能容纳的数量
+use std::any::{Any, TypeId};
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use atom::Atom;
+use bon::{Decode, Encode, ReadBonErr, ReadBuffer, WriteBuffer};
+use hash::XHashMap;
+
+use component::{CellMultiCase, Component};
+use entity::CellEntity;
+use single::CellSingleCase;
+use world::World;
+use {Lend, LendMut};
+
+/// 默认的最大实体数量，即恢复时每种实体分配过的id数量的上限
+pub const DEFAULT_MAX_ENTITY: usize = 1 << 20;
+
+/*
+* 快照错误
+*/
+#[derive(Debug)]
+pub enum SnapshotErr {
+    //bon编解码错误
+    Bon(ReadBonErr),
+    //实体、组件或单例组件没有在世界中注册
+    NotRegistered(&'static str),
+    //快照中有未注册或重复的段
+    UnknownSection(Atom),
+    //快照的数据与世界不匹配
+    Invalid(String),
+}
+
+impl fmt::Display for SnapshotErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotErr::Bon(e) => write!(f, "SnapshotError Bon reason = {}", e),
+            SnapshotErr::NotRegistered(name) => {
+                write!(f, "SnapshotError NotRegistered type = {}", name)
+            }
+            SnapshotErr::UnknownSection(name) => {
+                write!(f, "SnapshotError UnknownSection name = {}", **name)
+            }
+            SnapshotErr::Invalid(reason) => write!(f, "SnapshotError Invalid reason = {}", reason),
+        }
+    }
+}
+
+impl Error for SnapshotErr {}
+
+impl From<ReadBonErr> for SnapshotErr {
+    fn from(e: ReadBonErr) -> Self {
+        SnapshotErr::Bon(e)
+    }
+}
+
+// 恢复时的暂存，记录快照中每种实体的id是否存活，用于校验组件的实体id
+struct Stage {
+    entitys: XHashMap<TypeId, Vec<bool>>,
+    max_entity: usize, // 最大实体数量
+}
+
+// 快照中的一段数据，恢复分为两步，decode只解码和校验，不修改世界，所有段都成功后才依次commit
+trait Section {
+    fn save(&self, world: &World, bb: &mut WriteBuffer) -> Result<(), SnapshotErr>;
+    fn decode(&self, world: &World, bb: &mut ReadBuffer, stage: &mut Stage) -> Result<Box<dyn Any>, SnapshotErr>;
+    fn commit(&self, value: Box<dyn Any>);
+}
+
+// 实体，依次为分配过的id的数量、按分配的先后顺序排列的空闲id和每个id的代数
+struct EntitySection<E>(PhantomData<E>);
+
+impl<E: 'static> Section for EntitySection<E> {
+    fn save(&self, world: &World, bb: &mut WriteBuffer) -> Result<(), SnapshotErr> {
+        let entity = fetch_entity::<E>(world)?;
+        let entity = Lend::lend(&entity);
+        let free_ids = entity.free_ids();
+        (entity.len() + free_ids.len()).encode(bb);
+        free_ids.encode(bb);
+        entity.get_generations().to_vec().encode(bb);
+        Ok(())
+    }
+
+    fn decode(&self, world: &World, bb: &mut ReadBuffer, stage: &mut Stage) -> Result<Box<dyn Any>, SnapshotErr> {
+        let entity = fetch_entity::<E>(world)?;
+        {
+            let entity = Lend::lend(&entity);
+            if entity.len() > 0 || entity.free_ids().len() > 0 {
+                return Err(SnapshotErr::Invalid("load entity failed, entity is not empty".to_string()));
+            }
+        }
+
+        let count = usize::decode(bb)?;
+        if count > stage.max_entity {
+            return Err(SnapshotErr::Invalid(format!(
+                "load entity failed, count: {}, max: {}",
+                count, stage.max_entity
+            )));
+        }
+        let free_ids = Vec::<usize>::decode(bb)?;
+        let mut alive = vec![true; count + 1];
+        alive[0] = false;
+        for id in free_ids.iter() {
+            if *id == 0 || *id > count || !alive[*id] {
+                return Err(SnapshotErr::Invalid(format!("load entity failed, invalid free id: {}", id)));
+            }
+            alive[*id] = false;
+        }
+        let generations = Vec::<u32>::decode(bb)?;
+        if generations.len() > count + 1 {
+            return Err(SnapshotErr::Invalid(format!(
+                "load entity failed, count: {}, generations len: {}",
+                count,
+                generations.len()
+            )));
+        }
+        stage.entitys.insert(TypeId::of::<E>(), alive);
+        Ok(Box::new((entity, count, free_ids, generations)))
+    }
+
+    fn commit(&self, value: Box<dyn Any>) {
+        let (entity, count, free_ids, generations) =
+            *value.downcast::<(Arc<CellEntity<E>>, usize, Vec<usize>, Vec<u32>)>().unwrap();
+        LendMut::lend_mut(&entity).restore(count, &free_ids, generations);
+    }
+}
+
+// 组件，依次为组件数量，以及每个组件的实体id和组件
+struct MultiSection<E, C>(PhantomData<(E, C)>);
+
+impl<E: 'static, C: Component + Encode + Decode> Section for MultiSection<E, C> {
+    fn save(&self, world: &World, bb: &mut WriteBuffer) -> Result<(), SnapshotErr> {
+        let (entity, multi) = (fetch_entity::<E>(world)?, fetch_multi::<E, C>(world)?);
+        let multi = Lend::lend(&multi);
+        let ids: Vec<usize> = Lend::lend(&entity).iter().filter(|id| multi.get(*id).is_some()).collect();
+        ids.len().encode(bb);
+        for id in ids {
+            id.encode(bb);
+            unsafe { multi.get_unchecked(id) }.encode(bb);
+        }
+        Ok(())
+    }
+
+    fn decode(&self, world: &World, bb: &mut ReadBuffer, stage: &mut Stage) -> Result<Box<dyn Any>, SnapshotErr> {
+        let (entity, multi) = (fetch_entity::<E>(world)?, fetch_multi::<E, C>(world)?);
+        // 实体在快照中，则按快照中的实体校验，否则按世界中已有的实体校验
+        let alive = stage.entitys.get(&TypeId::of::<E>());
+        let entity = Lend::lend(&entity);
+        let len = usize::decode(bb)?;
+        // 每个组件至少有1字节的实体id
+        if len > bb.len() - bb.head() {
+            return Err(SnapshotErr::Invalid(format!(
+                "load multi failed, len: {}, remaining: {}",
+                len,
+                bb.len() - bb.head()
+            )));
+        }
+        let mut values = Vec::new();
+        for _ in 0..len {
+            let id = usize::decode(bb)?;
+            let c = C::decode(bb)?;
+            let exist = match alive {
+                Some(alive) => id < alive.len() && alive[id],
+                None => entity.is_exist(id),
+            };
+            if !exist {
+                return Err(SnapshotErr::Invalid(format!("load multi failed, entity is not exist: {}", id)));
+            }
+            values.push((id, c));
+        }
+        Ok(Box::new((multi, values)))
+    }
+
+    fn commit(&self, value: Box<dyn Any>) {
+        let (multi, values) = *value.downcast::<(Arc<CellMultiCase<E, C>>, Vec<(usize, C)>)>().unwrap();
+        let multi = LendMut::lend_mut(&multi);
+        // decode时已经校验过实体存在，实体段先于组件段提交
+        for (id, c) in values {
+            let _ = multi.insert_no_notify(id, c);
+        }
+    }
+}
+
+// 单例组件
+struct SingleSection<T>(PhantomData<T>);
+
+impl<T: Encode + Decode + 'static> Section for SingleSection<T> {
+    fn save(&self, world: &World, bb: &mut WriteBuffer) -> Result<(), SnapshotErr> {
+        let single = fetch_single::<T>(world)?;
+        let single = Lend::lend(&single);
+        (**single).encode(bb);
+        Ok(())
+    }
+
+    fn decode(&self, world: &World, bb: &mut ReadBuffer, _stage: &mut Stage) -> Result<Box<dyn Any>, SnapshotErr> {
+        let single = fetch_single::<T>(world)?;
+        let value = T::decode(bb)?;
+        Ok(Box::new((single, value)))
+    }
+
+    fn commit(&self, value: Box<dyn Any>) {
+        let (single, value) = *value.downcast::<(Arc<CellSingleCase<T>>, T)>().unwrap();
+        **LendMut::lend_mut(&single) = value;
+    }
+}
+
+fn fetch_entity<E: 'static>(world: &World) -> Result<Arc<CellEntity<E>>, SnapshotErr> {
+    world.fetch_entity::<E>().ok_or(SnapshotErr::NotRegistered("entity"))
+}
+
+fn fetch_multi<E: 'static, C: Component>(world: &World) -> Result<Arc<CellMultiCase<E, C>>, SnapshotErr> {
+    world.fetch_multi::<E, C>().ok_or(SnapshotErr::NotRegistered("multi"))
+}
+
+fn fetch_single<T: 'static>(world: &World) -> Result<Arc<CellSingleCase<T>>, SnapshotErr> {
+    world.fetch_single::<T>().ok_or(SnapshotErr::NotRegistered("single"))
+}
+
+/// 快照的注册表，注册需要保存的实体、组件和单例组件，名称在快照中必须唯一，并且不能随版本改变
+pub struct Snapshot {
+    entitys: Vec<(Atom, Box<dyn Section>)>,
+    multis: Vec<(Atom, Box<dyn Section>)>,
+    singles: Vec<(Atom, Box<dyn Section>)>,
+    max_entity: usize,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Snapshot {
+            entitys: Vec::new(),
+            multis: Vec::new(),
+            singles: Vec::new(),
+            max_entity: DEFAULT_MAX_ENTITY,
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn new() -> Self {
+        Snapshot::default()
+    }
+
+    /// 设置恢复时的最大实体数量，默认为DEFAULT_MAX_ENTITY
+    pub fn set_max_entity(&mut self, max_entity: usize) {
+        self.max_entity = max_entity;
+    }
+
+    pub fn register_entity<E: 'static>(&mut self, name: Atom) {
+        self.check_name(&name);
+        self.entitys.push((name, Box::new(EntitySection::<E>(PhantomData))));
+    }
+
+    /// 组件所在的实体也需要注册，否则恢复时实体不存在
+    pub fn register_multi<E: 'static, C: Component + Encode + Decode>(&mut self, name: Atom) {
+        self.check_name(&name);
+        self.multis.push((name, Box::new(MultiSection::<E, C>(PhantomData))));
+    }
+
+    /// IdTree也是单例组件，可以用这个方法注册
+    pub fn register_single<T: Encode + Decode + 'static>(&mut self, name: Atom) {
+        self.check_name(&name);
+        self.singles.push((name, Box::new(SingleSection::<T>(PhantomData))));
+    }
+
+    /// 保存世界中注册的数据
+    pub fn save(&self, world: &World) -> Result<Vec<u8>, SnapshotErr> {
+        let mut bb = WriteBuffer::new();
+        (self.entitys.len() + self.multis.len() + self.singles.len()).encode(&mut bb);
+        for (name, section) in self.iter() {
+            let mut body = WriteBuffer::new();
+            section.save(world, &mut body)?;
+            let body = body.unwrap();
+            name.encode(&mut bb);
+            bb.write_bin(&body, 0..body.len());
+        }
+        Ok(bb.unwrap())
+    }
+
+    /// 将快照恢复到世界中，快照中没有的段会被忽略，快照中有未注册的段则返回错误
+    /// 先解码和校验所有的段，全部成功后才修改世界，返回错误时世界不会被修改
+    pub fn load(&self, world: &World, bin: &[u8]) -> Result<(), SnapshotErr> {
+        let mut bb = ReadBuffer::new(bin, 0);
+        let mut bodys: Vec<(Atom, Vec<u8>)> = Vec::new();
+        for _ in 0..usize::decode(&mut bb)? {
+            let name = Atom::decode(&mut bb)?;
+            if !self.iter().any(|(n, _)| n == &name) || bodys.iter().any(|(n, _)| n == &name) {
+                return Err(SnapshotErr::UnknownSection(name));
+            }
+            bodys.push((name, bb.read_bin()?));
+        }
+
+        let mut stage = Stage {
+            entitys: XHashMap::default(),
+            max_entity: self.max_entity,
+        };
+        let mut values = Vec::with_capacity(bodys.len());
+        for (name, section) in self.iter() {
+            let body = match bodys.iter().find(|(n, _)| n == name) {
+                Some(r) => &r.1,
+                None => continue,
+            };
+            let mut bb = ReadBuffer::new(body, 0);
+            let value = section.decode(world, &mut bb, &mut stage)?;
+            if bb.head() != body.len() {
+                return Err(SnapshotErr::Invalid(format!(
+                    "load snapshot failed, section: {}, len: {}, load len: {}",
+                    **name,
+                    body.len(),
+                    bb.head()
+                )));
+            }
+            values.push((section, value));
+        }
+
+        for (section, value) in values {
+            section.commit(value);
+        }
+        Ok(())
+    }
+
+    // 按实体、组件、单例组件的顺序遍历
+    fn iter(&self) -> impl Iterator<Item = &(Atom, Box<dyn Section>)> {
+        self.entitys.iter().chain(self.multis.iter()).chain(self.singles.iter())
+    }
+
+    fn check_name(&self, name: &Atom) {
+        if self.iter().any(|(n, _)| n == name) {
+            panic!("duplicate registration, snapshot: {:?}", **name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atom::Atom;
+    use bon::{Decode, Encode, ReadBonErr, ReadBuffer, WriteBuffer};
+    use map::vecmap::VecMap;
+
+    use super::{Snapshot, SnapshotErr, DEFAULT_MAX_ENTITY};
+    use idtree::IdTree;
+    use {Component, Lend, LendMut, World};
+
+    pub struct Node;
+
+    #[derive(Debug, PartialEq)]
+    pub struct Pos(u32);
+    impl Component for Pos {
+        type Storage = VecMap<Self>;
+    }
+    impl Encode for Pos {
+        fn encode(&self, bb: &mut WriteBuffer) {
+            self.0.encode(bb);
+        }
+    }
+    impl Decode for Pos {
+        fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+            Ok(Pos(u32::decode(bb)?))
+        }
+    }
+
+    fn new_world() -> World {
+        let mut world = World::default();
+        world.register_entity::<Node>();
+        world.register_multi::<Node, Pos>();
+        world.register_single::<IdTree>(IdTree::default());
+        world.register_single::<String>(String::new());
+        world
+    }
+
+    fn snapshot() -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        snapshot.register_entity::<Node>(Atom::from("node"));
+        snapshot.register_multi::<Node, Pos>(Atom::from("pos"));
+        snapshot.register_single::<IdTree>(Atom::from("idtree"));
+        snapshot.register_single::<String>(Atom::from("name"));
+        snapshot
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let world = new_world();
+        let pos = world.fetch_multi::<Node, Pos>().unwrap();
+        let tree = world.fetch_single::<IdTree>().unwrap();
+        let ids: Vec<usize> = (0..6).map(|_| world.create_entity::<Node>()).collect();
+        for id in ids.iter() {
+            LendMut::lend_mut(&pos).insert(*id, Pos(*id as u32 * 10)).unwrap();
+        }
+        world.free_entity::<Node>(ids[4]);
+        world.free_entity::<Node>(ids[1]);
+        {
+            let tree = LendMut::lend_mut(&tree);
+            for id in [ids[0], ids[2], ids[3]].iter() {
+                tree.create(*id);
+            }
+            tree.insert_child(ids[0], 0, 0, None);
+            tree.insert_child(ids[2], ids[0], 0, None);
+            tree.insert_child(ids[3], ids[0], 1, None);
+        }
+        **LendMut::lend_mut(&world.fetch_single::<String>().unwrap()) = "save".to_string();
+
+        let snapshot = snapshot();
+        let bin = snapshot.save(&world).unwrap();
+
+        let world1 = new_world();
+        snapshot.load(&world1, &bin).unwrap();
+        let pos1 = world1.fetch_multi::<Node, Pos>().unwrap();
+        let entity1 = world1.fetch_entity::<Node>().unwrap();
+        assert_eq!(Lend::lend(&entity1).iter().collect::<Vec<usize>>(), vec![ids[0], ids[2], ids[3], ids[5]]);
+        for id in ids.iter() {
+            assert_eq!(Lend::lend(&pos1).get(*id), Lend::lend(&pos).get(*id));
+        }
+        let tree1 = world1.fetch_single::<IdTree>().unwrap();
+        let root = Lend::lend(&tree1).get(ids[0]).unwrap();
+        assert_eq!((root.layer, root.count, root.children.head, root.children.tail), (1, 2, ids[2], ids[3]));
+        assert_eq!(Lend::lend(&tree1).get(ids[3]).unwrap().layer, 2);
+        assert_eq!(**Lend::lend(&world1.fetch_single::<String>().unwrap()), "save");
+
+        // 恢复后按相同的顺序分配id，代数也相同
+        for _ in 0..3 {
+            assert_eq!(world1.create_entity_id::<Node>(), world.create_entity_id::<Node>());
+        }
+
+        // 实体不为空，或快照中有未注册的段
+        assert!(snapshot.load(&world1, &bin).is_err());
+        let mut other = Snapshot::new();
+        other.register_entity::<Node>(Atom::from("node"));
+        match other.load(&new_world(), &bin) {
+            Err(SnapshotErr::UnknownSection(name)) => assert_eq!(*name, "pos"),
+            _ => panic!("load unknown section"),
+        }
+    }
+
+    #[test]
+    fn test_load_failed() {
+        // 实体段正确，组件段引用了不存在的实体
+        let mut bb = WriteBuffer::new();
+        2usize.encode(&mut bb);
+        let mut body = WriteBuffer::new();
+        2usize.encode(&mut body);
+        vec![1usize].encode(&mut body);
+        vec![0u32, 1, 0].encode(&mut body);
+        let body = body.unwrap();
+        Atom::from("node").encode(&mut bb);
+        bb.write_bin(&body, 0..body.len());
+        let mut body = WriteBuffer::new();
+        1usize.encode(&mut body);
+        1usize.encode(&mut body);
+        Pos(10).encode(&mut body);
+        let body = body.unwrap();
+        Atom::from("pos").encode(&mut bb);
+        bb.write_bin(&body, 0..body.len());
+        let bin = bb.unwrap();
+
+        let world = new_world();
+        match snapshot().load(&world, &bin) {
+            Err(SnapshotErr::Invalid(_)) => (),
+            _ => panic!("load invalid entity id"),
+        }
+        // 失败时世界不会被修改
+        let entity = world.fetch_entity::<Node>().unwrap();
+        assert_eq!(Lend::lend(&entity).len(), 0);
+        assert_eq!(Lend::lend(&entity).free_ids().len(), 0);
+        assert_eq!(world.create_entity::<Node>(), 1);
+    }
+
+    // 编码只有实体段的快照，实体段依次为分配过的id的数量、空闲id和代数
+    fn entity_snapshot(count: usize, free_ids: Vec<usize>) -> Vec<u8> {
+        let mut bb = WriteBuffer::new();
+        1usize.encode(&mut bb);
+        let mut body = WriteBuffer::new();
+        count.encode(&mut body);
+        free_ids.encode(&mut body);
+        Vec::<u32>::new().encode(&mut body);
+        let body = body.unwrap();
+        Atom::from("node").encode(&mut bb);
+        bb.write_bin(&body, 0..body.len());
+        bb.unwrap()
+    }
+
+    #[test]
+    fn test_load_oversize() {
+        // 超过最大实体数量的实体，包括会溢出的数量，在分配内存前返回错误
+        for count in [DEFAULT_MAX_ENTITY + 1, usize::max_value()].iter() {
+            match snapshot().load(&new_world(), &entity_snapshot(*count, vec![])) {
+                Err(SnapshotErr::Invalid(_)) => (),
+                r => panic!("load oversize entity count, {:?}", r),
+            }
+        }
+        let mut small = snapshot();
+        small.set_max_entity(2);
+        assert!(small.load(&new_world(), &entity_snapshot(2, vec![1])).is_ok());
+        match small.load(&new_world(), &entity_snapshot(3, vec![1])) {
+            Err(SnapshotErr::Invalid(_)) => (),
+            r => panic!("load oversize entity count, {:?}", r),
+        }
+
+        // 组件数量超过剩余数据能容纳的数量
+        let mut bb = WriteBuffer::new();
+        1usize.encode(&mut bb);
+        let mut body = WriteBuffer::new();
+        usize::max_value().encode(&mut body);
+        1usize.encode(&mut body);
+        let body = body.unwrap();
+        Atom::from("pos").encode(&mut bb);
+        bb.write_bin(&body, 0..body.len());
+        match snapshot().load(&new_world(), &bb.unwrap()) {
+            Err(SnapshotErr::Invalid(_)) => (),
+            r => panic!("load oversize multi len, {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_load_truncated() {
+        let world = new_world();
+        for _ in 0..3 {
+            world.create_entity::<Node>();
+        }
+        let pos = world.fetch_multi::<Node, Pos>().unwrap();
+        LendMut::lend_mut(&pos).insert(2, Pos(20)).unwrap();
+        let bin = snapshot().save(&world).unwrap();
+        for len in 0..bin.len() {
+            assert!(snapshot().load(&new_world(), &bin[..len]).is_err());
+        }
+
+        // 段内的数量声明了比实际更多的数据
+        let mut bb = WriteBuffer::new();
+        1usize.encode(&mut bb);
+        let mut body = WriteBuffer::new();
+        2usize.encode(&mut body);
+        1usize.encode(&mut body);
+        Pos(10).encode(&mut body);
+        let body = body.unwrap();
+        Atom::from("pos").encode(&mut bb);
+        bb.write_bin(&body, 0..body.len());
+        match snapshot().load(&world, &bb.unwrap()) {
+            Err(SnapshotErr::Bon(_)) => (),
+            r => panic!("load truncated multi, {:?}", r),
+        }
+    }
+}
@@ -51,12 +51,18 @@ pub trait SingleCaseListener<'a, C, EV> {
 
 pub type RunnerFn = FnListener<()>;
 pub type DisposeFn = FnListener<World>;
+/// 监听器监听的数据，和监听器读、写的数据
+pub type ListenerDepends = ((TypeId, TypeId), Vec<(TypeId, TypeId)>, Vec<(TypeId, TypeId)>);
 
 pub trait System: any::ArcAny {
     fn setup(&mut self, me: Arc<dyn System>, world: &World, name: &Atom);
     fn dispose(&self, world: &World);
     fn fetch_run(&self) -> Option<RunnerFn>;
     fn get_depends(&self) -> (Vec<(TypeId, TypeId)>, Vec<(TypeId, TypeId)>);
+    /// 获取每个监听器监听的数据和读写的数据，监听器在触发事件的系统中执行，并行派发时需要计入触发事件的系统
+    fn get_listener_depends(&self) -> Vec<ListenerDepends> {
+        Vec::new()
+    }
 }
 impl_downcast_arc!(System);
 
@@ -165,6 +171,26 @@ macro_rules! impl_system {
     };
     (@listener_get_depends $read_ids:ident $write_ids:ident $system: tt <$($sg:ty),*>,) => {};
 
+    //每一个listenner 监听的数据
+    (@listener_target SingleCaseListener, $c:ty, $ev:ty) => {
+        (std::any::TypeId::of::<()>(), std::any::TypeId::of::<$c>())
+    };
+    (@listener_target MultiCaseListener, $e:ty, $c:ty, $ev:ty) => {
+        (std::any::TypeId::of::<$e>(), std::any::TypeId::of::<$c>())
+    };
+    (@listener_target EntityListener, $e:ty, $ev:ty) => {
+        (std::any::TypeId::of::<$e>(), std::any::TypeId::of::<()>())
+    };
+
+    //每一个listenner get_listener_depends
+    (@listener_depends $depends:ident $system: tt <$($sg:ty),*>, $sign:tt <$($gen:ty),*> $($t:tt)*) => {
+        let r_ids = <<<$system <$($sg),*> as $crate::system::$sign<'_, $($gen),*>>::ReadData as $crate::system::SystemData>::FetchTarget as $crate::TypeIds>::type_ids();
+        let w_ids = <<<$system <$($sg),*> as $crate::system::$sign<'_, $($gen),*>>::WriteData as $crate::system::SystemMutData>::FetchTarget as $crate::TypeIds>::type_ids();
+        $depends.push((impl_system!(@listener_target $sign, $($gen),*), r_ids, w_ids));
+        impl_system!(@listener_depends $depends $system <$($sg),*>, $($t)*);
+    };
+    (@listener_depends $depends:ident $system: tt <$($sg:ty),*>,) => {};
+
     //每一个runner get_depends
     (@runner_get_depends $read_ids:ident $write_ids:ident $system: tt <$($sg:ty),*>, true) => {
         let r_ids = <<<$system <$($sg),*> as Runner>::ReadData as $crate::system::SystemData>::FetchTarget as $crate::TypeIds>::type_ids();
@@ -273,6 +299,12 @@ macro_rules! impl_system {
                     (read_ids, write_ids)
                 }
 
+                fn get_listener_depends(&self) -> std::vec::Vec<$crate::system::ListenerDepends> {
+                    let mut depends = std::vec::Vec::new();
+                    impl_system!(@listener_depends depends $system <$($sg),*>, $($t)*);
+                    depends
+                }
+
                 fn setup(&mut self, me: std::sync::Arc<$crate::system::System>, world: &$crate::world::World, name: &atom::Atom){
                     let me: std::sync::Arc<Self> = match $crate::system::System::downcast(me) {
                         Ok(r) => r,
@@ -12,7 +12,7 @@ use atom::Atom;
 // use pointer::cell::{TrustCell};
 
 use system::{System};
-use entity::{Entity, EntityImpl, CellEntity};
+use entity::{Entity, EntityImpl, CellEntity, EntityId};
 use component::{MultiCase, CellMultiCase, MultiCaseImpl, Component};
 use single::{SingleCase, CellSingleCase, SingleCaseImpl};
 use dispatch::Dispatcher;
@@ -82,6 +82,10 @@ impl World {
     pub fn get_system(&self, name: &Atom) -> Option<&Arc<dyn System>> {
         self.system.get(name)
     }
+    /// 遍历所有注册的系统
+    pub fn iter_system(&self) -> impl Iterator<Item = (&Atom, &Arc<dyn System>)> {
+        self.system.iter()
+    }
     pub fn unregister_system(&mut self, name: &Atom) {
         // 如果该system在dispatcher中，需要自己去释放
         // 用dispose方法， 取消所有的监听器
@@ -103,6 +107,20 @@ impl World {
             _ => panic!("not registration, entity: {:?}, id: {:?}", type_name::<E>(), id),
         }
     }
+    /// 创建实体，返回带代数的EntityId
+    pub fn create_entity_id<E: 'static>(&self) -> EntityId {
+        let id = TypeId::of::<E>();
+        match self.entity.get(&id) {
+            Some(v) => match v.clone().downcast() {
+                Ok(r) => {
+                    let rc: Arc<CellEntity<E>> = r;
+                    LendMut::lend_mut(&rc).create_id()
+                },
+                Err(_) => panic!("downcast err")
+            }
+            _ => panic!("not registration, entity: {:?}, id: {:?}", type_name::<E>(), id),
+        }
+    }
     pub fn free_entity<E: 'static>(&self, id: usize) {
         let eid = TypeId::of::<E>();
         match self.entity.get(&eid) {
@@ -116,6 +134,17 @@ impl World {
             _ => panic!("not registration, entity: {:?}, id: {:?}", type_name::<E>(), eid),
         }
     }
+    /// EntityId已失效时忽略
+    pub fn free_entity_id<E: 'static>(&self, id: EntityId) {
+        match self.fetch_entity::<E>() {
+            Some(r) => {
+                if LendMut::lend_mut(&r).is_alive(id) {
+                    LendMut::lend_mut(&r).delete(id.index());
+                }
+            },
+            _ => panic!("not registration, entity: {:?}, id: {:?}", type_name::<E>(), TypeId::of::<E>()),
+        }
+    }
     pub fn add_dispatcher<D: Dispatcher + 'static>(&mut self, name: Atom, dispatcher: D) {
         self.runner.insert(name, Arc::new(dispatcher));
     }
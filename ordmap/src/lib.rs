@@ -9,6 +9,9 @@
 #![feature(trait_alias)]
 #![feature(const_fn)]
 #![feature(nll)]
+
+extern crate bon;
+
 #[warn(unreachable_patterns)]
 
 #[allow(dead_code,unused_variables,non_snake_case,unused_parens,unused_assignments,unused_unsafe,unused_imports)]
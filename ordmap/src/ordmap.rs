@@ -469,6 +469,8 @@
 use std::intrinsics;
 use std::mem;
 use std::cmp::Ordering;
+
+use bon::{WriteBuffer, ReadBuffer, Encode, Decode, ReadBonErr};
 //use std::ops::{Generator, GeneratorState};
 
 
@@ -907,4 +909,36 @@ impl<'a, T: ImOrdMap + Clone + Iter<'a>> OrdMap<T> {
 	}
 }
 
+// 序列化为键值对数量和按键升序排列的键值对，与HashMap的序列化格式相同
+impl<T: ImOrdMap + Clone> Encode for OrdMap<T> where T::Key: Encode, T::Val: Encode {
+	fn encode(&self, bb: &mut WriteBuffer) {
+		let size = self.root.size();
+		size.encode(bb);
+		for i in 1..size + 1 {
+			if let Some(&Entry(ref k, ref v)) = self.root.index(i) {
+				k.encode(bb);
+				v.encode(bb);
+			}
+		}
+	}
+}
+
+impl<T: ImOrdMap + Clone> Decode for OrdMap<T> where T::Key: Decode, T::Val: Decode {
+	fn decode(bb: &mut ReadBuffer) -> Result<Self, ReadBonErr> {
+		let count = usize::decode(bb)?;
+		let mut root = T::new();
+		for _ in 0..count {
+			let key = <T::Key as Decode>::decode(bb)?;
+			let value = <T::Val as Decode>::decode(bb)?;
+			root = match root.insert(key, value) {
+				Some(r) => r,
+				None => return Err(ReadBonErr::Other("decode OrdMap failed, reason: duplicate key".to_string())),
+			};
+		}
+		Ok(OrdMap {
+			root: root,
+		})
+	}
+}
+
 //====================================
@@ -4,6 +4,13 @@
 
 extern crate atom;
 extern crate bon;
+#[cfg(test)]
+extern crate serde;
+#[cfg(test)]
+#[macro_use]
+extern crate serde_derive;
+
+pub mod validate;
 
 use std::vec::Vec;
 use std::collections::HashMap;
@@ -203,6 +210,7 @@ pub struct EnumInfo {
 	pub name_hash: u32,
 	pub notes: Option<HashMap<Atom, Atom>>,
 	pub members: Vec<Option<EnumType>>,
+	pub tags: Vec<u32>, //成员的变体标签，与成员一一对应，为空表示变体标签为成员序号
 }
 
 impl EnumInfo {
@@ -212,6 +220,26 @@ impl EnumInfo {
 			name_hash: name_hash,
 			notes: None,
 			members: Vec::new(),
+			tags: Vec::new(),
+		}
+	}
+
+	/**
+	* 增加指定名称的成员，变体标签为成员名称的name_hash，与bon_derive和bon的serde后端默认写入的变体标签相同
+	*/
+	pub fn add_member(&mut self, name: &str, member: Option<EnumType>) {
+		self.members.push(member);
+		self.tags.push(bon::name_hash(name));
+	}
+
+	/**
+	* 获取指定变体标签的成员序号
+	*/
+	pub fn member_index(&self, tag: u32) -> Option<usize> {
+		if self.tags.is_empty() {
+			Some(tag as usize)
+		} else {
+			self.tags.iter().position(|t| *t == tag)
 		}
 	}
 }
@@ -222,6 +250,7 @@ impl Encode for EnumInfo{
 		self.name_hash.encode(bb);
         self.notes.encode(bb);
         self.members.encode(bb);
+        self.tags.encode(bb);
 	}
 }
 
@@ -233,6 +262,7 @@ impl Decode for EnumInfo{
 			name_hash: u32::decode(bb)?,
 			notes: Option::decode(bb)?,
 			members: Vec::decode(bb)?,
+			tags: Vec::decode(bb)?,
 		})
 	}
 }
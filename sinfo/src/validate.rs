@@ -0,0 +1,584 @@
+/**
+ * 根据结构体信息校验bon数据
+ * 结构体为通用对象或类型为结构体名称hash的容器，字段名称hash为bon::name_hash；数组为通用数组；map为通用map
+ * 枚举为只有一个字段的通用对象，字段名称hash为成员的变体标签，字段值为成员的值，成员没有类型时值为null
+ * 变体标签默认为成员名称的name_hash，与bon_derive和bon的serde后端写入的变体标签相同；枚举信息没有变体标签时为成员序号
+ * 可选值为null、值本身或包含值的可选值容器，与bon的serde后端相同
+ */
+
+use std::fmt;
+use std::error::Error;
+
+use bon::{ReadBuffer, ReadBonErr, name_hash, CONTAINER_OBJECT, CONTAINER_ARRAY, CONTAINER_MAP, CONTAINER_SOME};
+
+use super::{StructInfo, EnumInfo, EnumType};
+
+/**
+* 校验错误
+*/
+#[derive(Debug, Clone)]
+pub enum SchemaErr {
+	//类型不匹配
+	TypeNoMatch {
+		path: String,
+		expect: String,
+		head: usize,
+	},
+	//缺少字段
+	MissingField {
+		path: String,
+	},
+	//未知字段
+	UnknownField {
+		path: String,
+		hash: u32,
+	},
+	//重复的字段
+	DuplicateField {
+		path: String,
+		hash: u32,
+	},
+	//数据错误，无法继续校验
+	Invalid {
+		path: String,
+		reason: ReadBonErr,
+	},
+}
+
+impl fmt::Display for SchemaErr {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SchemaErr::TypeNoMatch { path, expect, head } => write!(f, "SchemaError TypeNoMatch path = {}, expect = {}, head = {:?}", path, expect, head),
+			SchemaErr::MissingField { path } => write!(f, "SchemaError MissingField path = {}", path),
+			SchemaErr::UnknownField { path, hash } => write!(f, "SchemaError UnknownField path = {}, hash = {:?}", path, hash),
+			SchemaErr::DuplicateField { path, hash } => write!(f, "SchemaError DuplicateField path = {}, hash = {:?}", path, hash),
+			SchemaErr::Invalid { path, reason } => write!(f, "SchemaError Invalid path = {}, reason = {}", path, reason),
+		}
+	}
+}
+
+impl Error for SchemaErr {}
+
+/**
+* 结构体信息校验器
+*/
+pub struct Validator {
+	is_evolution: bool, //是否兼容结构体的演进，兼容则允许缺少可选字段，并忽略未知字段
+}
+
+impl Validator {
+	/**
+	* 构建严格的校验器，数据必须包含结构体的所有字段，且不允许未知字段
+	* @returns 返回校验器
+	*/
+	pub fn new() -> Self {
+		Validator::with_evolution(false)
+	}
+
+	/**
+	* 构建指定是否兼容结构体演进的校验器
+	* @param is_evolution 是否兼容结构体的演进，兼容则允许缺少新增的可选字段，并忽略已删除的字段
+	* @returns 返回校验器
+	*/
+	pub fn with_evolution(is_evolution: bool) -> Self {
+		Validator {
+			is_evolution,
+		}
+	}
+
+	/**
+	* 根据结构体信息校验读缓冲区中的下一个结构体，校验成功则读缓冲区移动到结构体之后
+	* @param info 结构体信息
+	* @param bb 读缓冲区
+	* @returns 返回所有的校验错误
+	*/
+	pub fn validate(&self, info: &StructInfo, bb: &mut ReadBuffer) -> Result<(), Vec<SchemaErr>> {
+		let mut errs = Vec::new();
+		let path = info.name.to_string();
+		let head = bb.head;
+		if let Err(reason) = self.check_struct(info, bb, &path, &mut errs) {
+			errs.push(SchemaErr::Invalid {
+				path,
+				reason,
+			});
+		}
+
+		if errs.is_empty() {
+			Ok(())
+		} else {
+			bb.head = head;
+			Err(errs)
+		}
+	}
+
+	//校验结构体，只有数据错误时返回错误
+	fn check_struct(&self, info: &StructInfo, bb: &mut ReadBuffer, path: &str, errs: &mut Vec<SchemaErr>) -> Result<(), ReadBonErr> {
+		let head = bb.head;
+		let mut body = match self.read_container(bb, &[CONTAINER_OBJECT, info.name_hash])? {
+			Some(body) => body,
+			None => {
+				errs.push(type_no_match(path, &info.name, head));
+				return Ok(());
+			},
+		};
+
+		let count = body.read_lengthen()?;
+		let mut founds = vec![false; info.fields.len()];
+		let mut unknowns = Vec::new();
+		for _ in 0..count {
+			let hash = body.read_u32()?;
+			match info.fields.iter().position(|field| name_hash(&field.name) == hash) {
+				Some(index) if founds[index] => {
+					//重复的已知字段，无论是否兼容演进都不允许
+					errs.push(SchemaErr::DuplicateField {
+						path: format!("{}.{}", path, *info.fields[index].name),
+						hash,
+					});
+					body.skip()?;
+				},
+				Some(index) => {
+					founds[index] = true;
+					let field = &info.fields[index];
+					self.check_value(&field.ftype, &mut body, &format!("{}.{}", path, *field.name), errs)?;
+				},
+				None if unknowns.contains(&hash) => {
+					//重复的未知字段
+					errs.push(SchemaErr::DuplicateField {
+						path: path.to_string(),
+						hash,
+					});
+					body.skip()?;
+				},
+				None => {
+					unknowns.push(hash);
+					if !self.is_evolution {
+						errs.push(SchemaErr::UnknownField {
+							path: path.to_string(),
+							hash,
+						});
+					}
+					body.skip()?;
+				},
+			}
+		}
+
+		for (field, is_found) in info.fields.iter().zip(founds) {
+			if is_found {
+				continue;
+			}
+
+			match field.ftype {
+				EnumType::Option(_) if self.is_evolution => (), //新增的可选字段
+				_ => errs.push(SchemaErr::MissingField {
+					path: format!("{}.{}", path, *field.name),
+				}),
+			}
+		}
+
+		Ok(())
+	}
+
+	//校验枚举，只有数据错误时返回错误
+	fn check_enum(&self, info: &EnumInfo, bb: &mut ReadBuffer, path: &str, errs: &mut Vec<SchemaErr>) -> Result<(), ReadBonErr> {
+		let head = bb.head;
+		let mut body = match self.read_container(bb, &[CONTAINER_OBJECT])? {
+			Some(body) => body,
+			None => {
+				errs.push(type_no_match(path, &info.name, head));
+				return Ok(());
+			},
+		};
+
+		if body.read_lengthen()? != 1 {
+			errs.push(type_no_match(path, &info.name, head));
+			return Ok(());
+		}
+		let tag = body.read_u32()?;
+		let path = format!("{}({})", path, tag);
+		match info.member_index(tag).and_then(|index| info.members.get(index)) {
+			Some(None) => {
+				//没有类型的成员，值必须为null
+				let head = body.head;
+				if !body.is_nil()? {
+					errs.push(type_no_match(&path, "null", head));
+				}
+				Ok(())
+			},
+			Some(Some(t)) => self.check_value(t, &mut body, &path, errs),
+			None => {
+				errs.push(type_no_match(&path, &info.name, head));
+				Ok(())
+			},
+		}
+	}
+
+	//校验指定类型的值，只有数据错误时返回错误
+	fn check_value(&self, t: &EnumType, bb: &mut ReadBuffer, path: &str, errs: &mut Vec<SchemaErr>) -> Result<(), ReadBonErr> {
+		let head = bb.head;
+		let code = bb.get_type()?;
+		let is_match = match t {
+			EnumType::Bool => code == 1 || code == 2,
+			EnumType::F32 | EnumType::F64 => is_float(code) || is_integer(code),
+			EnumType::Str => code >= 42 && code < 111,
+			EnumType::Bin => code >= 111 && code < 180,
+			EnumType::U256 | EnumType::I256 | EnumType::BigI => is_integer(code) || (code >= 111 && code < 180),
+			EnumType::U128 | EnumType::I128 => is_integer(code),
+			EnumType::Option(t) => {
+				if code == 0 {
+					bb.head += 1;
+					return Ok(());
+				}
+				if code >= 180 && code <= 248 {
+					//可选值容器的内容为值本身
+					let mut some = bb.clone();
+					if let (CONTAINER_SOME, mut body) = some.read_container_buffer()? {
+						bb.head = some.head;
+						return self.check_value(t, &mut body, path, errs);
+					}
+				}
+				return self.check_value(t, bb, path, errs);
+			},
+			EnumType::Struct(info) => return self.check_struct(info, bb, path, errs),
+			EnumType::Enum(info) => return self.check_enum(info, bb, path, errs),
+			EnumType::Arr(e) => {
+				let mut body = match self.read_container(bb, &[CONTAINER_ARRAY])? {
+					Some(body) => body,
+					None => {
+						errs.push(type_no_match(path, &type_name(t), head));
+						return Ok(());
+					},
+				};
+
+				let count = body.read_lengthen()?;
+				for index in 0..count {
+					self.check_value(e, &mut body, &format!("{}[{}]", path, index), errs)?;
+				}
+				return Ok(());
+			},
+			EnumType::Map(k, v) => {
+				let mut body = match self.read_container(bb, &[CONTAINER_MAP])? {
+					Some(body) => body,
+					None => {
+						errs.push(type_no_match(path, &type_name(t), head));
+						return Ok(());
+					},
+				};
+
+				let count = body.read_lengthen()?;
+				for index in 0..count {
+					self.check_value(k, &mut body, &format!("{}{{{}}}.key", path, index), errs)?;
+					self.check_value(v, &mut body, &format!("{}{{{}}}.value", path, index), errs)?;
+				}
+				return Ok(());
+			},
+			_ => {
+				//64位及以下的整数，需要检查取值范围
+				let (min, max) = int_range(t);
+				match code {
+					9..=13 | 15..=35 => {
+						let v = bb.read_i64()? as i128;
+						bb.head = head;
+						v >= min && v <= max
+					},
+					36..41 => {
+						let v = bb.read_u64()? as i128;
+						bb.head = head;
+						v >= min && v <= max
+					},
+					_ => false,
+				}
+			},
+		};
+
+		if !is_match {
+			errs.push(type_no_match(path, &type_name(t), head));
+		}
+		bb.skip()
+	}
+
+	//读取指定类型的容器，返回容器内容，不是指定类型的容器则跳过，并返回空
+	fn read_container<'a>(&self, bb: &mut ReadBuffer<'a>, types: &[u32]) -> Result<Option<ReadBuffer<'a>>, ReadBonErr> {
+		let code = bb.get_type()?;
+		if code < 180 || code > 248 {
+			bb.skip()?;
+			return Ok(None);
+		}
+
+		let (t, body) = bb.read_container_buffer()?;
+		if types.contains(&t) {
+			Ok(Some(body))
+		} else {
+			Ok(None)
+		}
+	}
+}
+
+//构建类型不匹配错误
+fn type_no_match(path: &str, expect: &str, head: usize) -> SchemaErr {
+	SchemaErr::TypeNoMatch {
+		path: path.to_string(),
+		expect: expect.to_string(),
+		head,
+	}
+}
+
+//是否是浮点数
+fn is_float(code: u8) -> bool {
+	code >= 3 && code <= 8
+}
+
+//是否是整数
+fn is_integer(code: u8) -> bool {
+	code >= 9 && code <= 41
+}
+
+//获取64位及以下的整数的取值范围
+fn int_range(t: &EnumType) -> (i128, i128) {
+	match t {
+		EnumType::U8 => (0, u8::max_value() as i128),
+		EnumType::U16 => (0, u16::max_value() as i128),
+		EnumType::U32 => (0, u32::max_value() as i128),
+		EnumType::U64 | EnumType::Usize => (0, u64::max_value() as i128),
+		EnumType::I8 => (i8::min_value() as i128, i8::max_value() as i128),
+		EnumType::I16 => (i16::min_value() as i128, i16::max_value() as i128),
+		EnumType::I32 => (i32::min_value() as i128, i32::max_value() as i128),
+		EnumType::I64 | EnumType::Isize => (i64::min_value() as i128, i64::max_value() as i128),
+		_ => (1, 0), //不是整数，则为空范围
+	}
+}
+
+//获取类型名称
+fn type_name(t: &EnumType) -> String {
+	match t {
+		EnumType::Arr(t) => format!("Arr<{}>", type_name(t)),
+		EnumType::Map(k, v) => format!("Map<{}, {}>", type_name(k), type_name(v)),
+		EnumType::Option(t) => format!("Option<{}>", type_name(t)),
+		EnumType::Struct(info) => info.name.to_string(),
+		EnumType::Enum(info) => info.name.to_string(),
+		_ => format!("{:?}", t),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use atom::Atom;
+	use bon::WriteBuffer;
+
+	use super::*;
+	use super::super::{FieldInfo, EnumInfo};
+
+	fn field(name: &str, ftype: EnumType) -> FieldInfo {
+		FieldInfo {
+			name: Atom::from(name),
+			ftype,
+			notes: None,
+		}
+	}
+
+	fn write_field(bb: &mut WriteBuffer, name: &str) {
+		bb.write_u32(name_hash(name));
+	}
+
+	#[test]
+	fn test_validate() {
+		let mut pos = StructInfo::new(Atom::from("Pos"), 1);
+		pos.fields.push(field("x", EnumType::I32));
+		pos.fields.push(field("y", EnumType::I32));
+		let mut info = StructInfo::new(Atom::from("Player"), 2);
+		info.fields.push(field("name", EnumType::Str));
+		info.fields.push(field("level", EnumType::U8));
+		info.fields.push(field("path", EnumType::Arr(Arc::new(EnumType::Struct(Arc::new(pos))))));
+		info.fields.push(field("guild", EnumType::Option(Arc::new(EnumType::Str))));
+
+		// { name: "abc", level: 300, path: [{ x: 1, y: "2" }], old: true }
+		let mut bb = WriteBuffer::new();
+		bb.write_typed_container(CONTAINER_OBJECT, |bb| {
+			bb.write_lengthen(4);
+			write_field(bb, "name");
+			bb.write_utf8("abc");
+			write_field(bb, "level");
+			bb.write_u16(300);
+			write_field(bb, "path");
+			bb.write_typed_container(CONTAINER_ARRAY, |bb| {
+				bb.write_lengthen(1);
+				bb.write_typed_container(1, |bb| {
+					bb.write_lengthen(2);
+					write_field(bb, "x");
+					bb.write_i32(1);
+					write_field(bb, "y");
+					bb.write_utf8("2");
+				});
+			});
+			write_field(bb, "old");
+			bb.write_bool(true);
+		});
+		bb.write_u8(7);
+		let bin = bb.unwrap();
+
+		let mut rb = ReadBuffer::new(&bin, 0);
+		let errs = Validator::new().validate(&info, &mut rb).unwrap_err();
+		assert_eq!(rb.head, 0);
+		let errs: Vec<String> = errs.iter().map(|e| match e {
+			SchemaErr::TypeNoMatch { path, .. } => format!("type {}", path),
+			SchemaErr::MissingField { path } => format!("missing {}", path),
+			SchemaErr::UnknownField { path, hash } => {
+				assert_eq!(*hash, name_hash("old"));
+				format!("unknown {}", path)
+			},
+			SchemaErr::DuplicateField { path, .. } => format!("duplicate {}", path),
+			SchemaErr::Invalid { path, .. } => format!("invalid {}", path),
+		}).collect();
+		assert_eq!(errs, vec![
+			"type Player.level",
+			"type Player.path[0].y",
+			"unknown Player",
+			"missing Player.guild",
+		]);
+
+		// 兼容结构体演进，只有类型不匹配
+		let errs = Validator::with_evolution(true).validate(&info, &mut rb).unwrap_err();
+		assert_eq!(errs.len(), 2);
+
+		// 数据错误
+		let mut rb = ReadBuffer::new(&bin[..bin.len() - 4], 0);
+		match Validator::with_evolution(true).validate(&info, &mut rb).unwrap_err().last() {
+			Some(SchemaErr::Invalid { .. }) => (),
+			e => panic!("invalid error: {:?}", e),
+		}
+	}
+
+	#[test]
+	fn test_validate_evolution() {
+		let mut info = StructInfo::new(Atom::from("Msg"), 3);
+		info.fields.push(field("id", EnumType::U64));
+		info.fields.push(field("tags", EnumType::Map(Arc::new(EnumType::Str), Arc::new(EnumType::I64))));
+		info.fields.push(field("ext", EnumType::Option(Arc::new(EnumType::Bin))));
+
+		// 类型为结构体名称hash的容器，缺少新增的可选字段ext，包含已删除的字段body
+		let mut bb = WriteBuffer::new();
+		bb.write_typed_container(3, |bb| {
+			bb.write_lengthen(3);
+			write_field(bb, "id");
+			bb.write_u64(0xffffffffff);
+			write_field(bb, "body");
+			bb.write_bin(&[1, 2, 3], 0..3);
+			write_field(bb, "tags");
+			bb.write_typed_container(CONTAINER_MAP, |bb| {
+				bb.write_lengthen(2);
+				bb.write_utf8("a");
+				bb.write_i8(-1);
+				bb.write_utf8("b");
+				bb.write_i64(-0xffffffffff);
+			});
+		});
+		bb.write_u8(7);
+		let bin = bb.unwrap();
+
+		let mut rb = ReadBuffer::new(&bin, 0);
+		assert_eq!(Validator::new().validate(&info, &mut rb).unwrap_err().len(), 2);
+		Validator::with_evolution(true).validate(&info, &mut rb).unwrap();
+		assert_eq!(rb.read_u8().unwrap(), 7);
+	}
+
+	// { s0: { tag: value }, tip: Some(None) }，重复时再写入tip和old字段
+	fn write_role(tag: u32, value: &dyn Fn(&mut WriteBuffer), is_duplicate: bool) -> Vec<u8> {
+		let mut bb = WriteBuffer::new();
+		bb.write_typed_container(CONTAINER_OBJECT, |bb| {
+			bb.write_lengthen(if is_duplicate { 4 } else { 2 });
+			write_field(bb, "s0");
+			bb.write_typed_container(CONTAINER_OBJECT, |bb| {
+				bb.write_lengthen(1);
+				bb.write_u32(tag);
+				value(bb);
+			});
+			//Some(None)为包含null的可选值容器
+			write_field(bb, "tip");
+			bb.write_typed_container(CONTAINER_SOME, |bb| bb.write_nil());
+			if is_duplicate {
+				write_field(bb, "tip");
+				bb.write_nil();
+				write_field(bb, "old");
+				bb.write_nil();
+			}
+		});
+		bb.unwrap()
+	}
+
+	#[test]
+	fn test_validate_enum_and_duplicate() {
+		let mut state = EnumInfo::new(Atom::from("State"), 5);
+		state.members.push(None);
+		state.members.push(Some(EnumType::Str));
+		let mut info = StructInfo::new(Atom::from("Role"), 4);
+		info.fields.push(field("s0", EnumType::Enum(Arc::new(state))));
+		info.fields.push(field("tip", EnumType::Option(Arc::new(EnumType::Option(Arc::new(EnumType::U8))))));
+
+		// 枚举为只有一个字段的通用对象，字段名称hash为成员序号
+		let bin = write_role(0, &|bb| bb.write_nil(), false);
+		Validator::new().validate(&info, &mut ReadBuffer::new(&bin, 0)).unwrap();
+		let bin = write_role(1, &|bb| bb.write_utf8("run"), false);
+		Validator::new().validate(&info, &mut ReadBuffer::new(&bin, 0)).unwrap();
+
+		// 成员的值类型不匹配，或成员序号无效
+		for (tag, path) in [(0, "Role.s0(0)"), (1, "Role.s0(1)"), (2, "Role.s0(2)")].iter() {
+			let bin = write_role(*tag, &|bb| bb.write_u8(1), false);
+			match &Validator::new().validate(&info, &mut ReadBuffer::new(&bin, 0)).unwrap_err()[..] {
+				[SchemaErr::TypeNoMatch { path: p, .. }] => assert_eq!(p, path),
+				e => panic!("invalid error: {:?}", e),
+			}
+		}
+
+		// 重复的字段，兼容演进时也会报告
+		let bin = write_role(0, &|bb| bb.write_nil(), true);
+		let errs = Validator::with_evolution(true).validate(&info, &mut ReadBuffer::new(&bin, 0)).unwrap_err();
+		match &errs[..] {
+			[SchemaErr::DuplicateField { path, hash }] => {
+				assert_eq!(path, "Role.tip");
+				assert_eq!(*hash, name_hash("tip"));
+			},
+			e => panic!("invalid error: {:?}", e),
+		}
+	}
+
+	#[derive(Serialize)]
+	enum SerdeState {
+		Idle,
+		Run(String),
+		Move { x: i32, y: i32 },
+	}
+
+	#[derive(Serialize)]
+	struct SerdeRole {
+		s0: SerdeState,
+		tip: Option<Option<u8>>,
+	}
+
+	#[test]
+	fn test_validate_serde_enum() {
+		let mut pos = StructInfo::new(Atom::from("Move"), 1);
+		pos.fields.push(field("x", EnumType::I32));
+		pos.fields.push(field("y", EnumType::I32));
+		let mut state = EnumInfo::new(Atom::from("SerdeState"), 5);
+		state.add_member("Idle", None);
+		state.add_member("Run", Some(EnumType::Str));
+		state.add_member("Move", Some(EnumType::Struct(Arc::new(pos))));
+		let mut info = StructInfo::new(Atom::from("SerdeRole"), 4);
+		info.fields.push(field("s0", EnumType::Enum(Arc::new(state))));
+		info.fields.push(field("tip", EnumType::Option(Arc::new(EnumType::Option(Arc::new(EnumType::U8))))));
+
+		// serde后端的变体标签为变体名称的name_hash
+		for s0 in vec![SerdeState::Idle, SerdeState::Run("run".to_string()), SerdeState::Move { x: 1, y: -1 }] {
+			let bin = bon::serde_impl::to_bytes(&SerdeRole { s0, tip: Some(None) }).unwrap();
+			Validator::new().validate(&info, &mut ReadBuffer::new(&bin, 0)).unwrap();
+		}
+
+		// 以成员序号作为变体标签的数据不匹配
+		let bin = write_role(1, &|bb| bb.write_utf8("run"), false);
+		match &Validator::new().validate(&info, &mut ReadBuffer::new(&bin, 0)).unwrap_err()[..] {
+			[SchemaErr::TypeNoMatch { path, .. }] => assert_eq!(path, "SerdeRole.s0(1)"),
+			e => panic!("invalid error: {:?}", e),
+		}
+	}
+}
\ No newline at end of file
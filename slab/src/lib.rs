@@ -257,6 +257,17 @@ impl<T> Slab<T> {
         }
     }
 
+    // 按分配的先后顺序，返回所有空位的键
+    pub fn vacancy_keys(&self) -> Vec<usize> {
+        let mut r = Vec::with_capacity(self.entries.len() - self.len);
+        let mut next = self.next;
+        while next < self.entries.len() {
+            r.push(next + 1);
+            next = unsafe{*(&self.entries[next] as *const T as usize as *const usize)};
+        }
+        r
+    }
+
     // pub fn retain<F>(&mut self, mut f: F) where F: FnMut(usize, &mut T) -> bool {
     //     for i in 0..self.entries.len() {
     //         let keep = match self.entries[i] {